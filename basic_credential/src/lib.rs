@@ -16,18 +16,30 @@ use p256::ecdsa::{signature::Signer as P256Signer, Signature, SigningKey};
 
 use rand::rngs::OsRng;
 use tls_codec::{TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize};
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 /// A signature key pair for the basic credential.
 ///
 /// This can be used as keys to implement the MLS basic credential. It is a simple
 /// private and public key pair with corresponding signature scheme.
+///
+/// The private key is zeroized when the key pair is dropped.
 #[derive(
-    TlsSerialize, TlsSize, TlsDeserialize, TlsDeserializeBytes, serde::Serialize, serde::Deserialize,
+    TlsSerialize,
+    TlsSize,
+    TlsDeserialize,
+    TlsDeserializeBytes,
+    serde::Serialize,
+    serde::Deserialize,
+    Zeroize,
+    ZeroizeOnDrop,
 )]
 #[cfg_attr(feature = "clonable", derive(Clone))]
 pub struct SignatureKeyPair {
     private: Vec<u8>,
+    #[zeroize(skip)]
     public: Vec<u8>,
+    #[zeroize(skip)]
     signature_scheme: SignatureScheme,
 }
 
@@ -1,8 +1,9 @@
+use reqwest::StatusCode;
 use tls_codec::{Deserialize, TlsVecU16, TlsVecU32};
 use url::Url;
 
 use super::{
-    networking::{get, post},
+    networking::{get, get_with_headers, post, post_with_status},
     user::User,
 };
 
@@ -26,7 +27,7 @@ impl Backend {
                 .map(|(b, kp)| (b, KeyPackageIn::from(kp)))
                 .collect(),
         );
-        let response = post(&url, &client_info)?;
+        let response = post(&url, &client_info, Some(&user.identity.borrow()))?;
 
         Ok(String::from_utf8(response).unwrap())
     }
@@ -37,7 +38,7 @@ impl Backend {
         let mut url = self.ds_url.clone();
         url.set_path("/clients/list");
 
-        let response = get(&url)?;
+        let response = get(&url, None)?;
         match TlsVecU32::<ClientInfo>::tls_deserialize(&mut response.as_slice()) {
             Ok(clients) => Ok(clients.into()),
             Err(e) => Err(format!("Error decoding server response: {e:?}")),
@@ -45,13 +46,17 @@ impl Backend {
     }
 
     /// Get and reserve a key package for a client.
-    pub fn consume_key_package(&self, client_id: &[u8]) -> Result<KeyPackageIn, String> {
+    pub fn consume_key_package(
+        &self,
+        user: &User,
+        client_id: &[u8],
+    ) -> Result<KeyPackageIn, String> {
         let mut url = self.ds_url.clone();
         let path = "/clients/key_package/".to_string()
             + &base64::encode_config(client_id, base64::URL_SAFE);
         url.set_path(&path);
 
-        let response = get(&url)?;
+        let response = get(&url, Some(&user.identity.borrow()))?;
         match KeyPackageIn::tls_deserialize(&mut response.as_slice()) {
             Ok(kp) => Ok(kp),
             Err(e) => Err(format!("Error decoding server response: {e:?}")),
@@ -59,56 +64,142 @@ impl Backend {
     }
 
     /// Publish client additional key packages
-    pub fn publish_key_packages(&self, user: &User, ckp: &ClientKeyPackages) -> Result<(), String> {
+    /// Returns the key package inventory of the client on the DS afterwards.
+    pub fn publish_key_packages(
+        &self,
+        user: &User,
+        ckp: &ClientKeyPackages,
+    ) -> Result<KeyPackageInventory, String> {
         let mut url = self.ds_url.clone();
         let path = "/clients/key_packages/".to_string()
             + &base64::encode_config(user.identity.borrow().identity(), base64::URL_SAFE);
         url.set_path(&path);
 
-        // The response should be empty.
-        let _response = post(&url, &ckp)?;
-        Ok(())
+        let response = post(&url, &ckp, Some(&user.identity.borrow()))?;
+        match KeyPackageInventory::tls_deserialize(&mut response.as_slice()) {
+            Ok(inventory) => Ok(inventory),
+            Err(e) => Err(format!("Error decoding server response: {e:?}")),
+        }
+    }
+
+    /// Get the number of key packages the DS holds for the user.
+    pub fn key_package_inventory(&self, user: &User) -> Result<KeyPackageInventory, String> {
+        let mut url = self.ds_url.clone();
+        let path = "/clients/key_packages/".to_string()
+            + &base64::encode_config(user.identity.borrow().identity(), base64::URL_SAFE)
+            + "/inventory";
+        url.set_path(&path);
+
+        let response = get(&url, Some(&user.identity.borrow()))?;
+        match KeyPackageInventory::tls_deserialize(&mut response.as_slice()) {
+            Ok(inventory) => Ok(inventory),
+            Err(e) => Err(format!("Error decoding server response: {e:?}")),
+        }
     }
 
     /// Send a welcome message.
-    pub fn send_welcome(&self, welcome_msg: &MlsMessageOut) -> Result<(), String> {
+    pub fn send_welcome(&self, user: &User, welcome_msg: &MlsMessageOut) -> Result<(), String> {
         let mut url = self.ds_url.clone();
         url.set_path("/send/welcome");
 
         // The response should be empty.
-        let _response = post(&url, welcome_msg)?;
+        let _response = post(&url, welcome_msg, Some(&user.identity.borrow()))?;
         Ok(())
     }
 
-    /// Send a group message.
-    pub fn send_msg(&self, group_msg: &GroupMessage) -> Result<(), String> {
+    /// Register a new group with the DS.
+    pub fn create_group(
+        &self,
+        user: &User,
+        group_info_and_tree: &GroupInfoAndTree,
+    ) -> Result<(), String> {
         let mut url = self.ds_url.clone();
-        url.set_path("/send/message");
+        url.set_path("/groups/create");
 
         // The response should be empty.
-        let _response = post(&url, group_msg)?;
+        let _response = post(&url, group_info_and_tree, Some(&user.identity.borrow()))?;
         Ok(())
     }
 
+    /// Upload the group info of a group for its current epoch.
+    pub fn publish_group_info(
+        &self,
+        user: &User,
+        group_id: &[u8],
+        group_info: &MlsMessageOut,
+    ) -> Result<(), String> {
+        let mut url = self.ds_url.clone();
+        let path = "/groups/".to_string()
+            + &base64::encode_config(group_id, base64::URL_SAFE)
+            + "/group_info";
+        url.set_path(&path);
+
+        // The response should be empty.
+        let _response = post(&url, group_info, Some(&user.identity.borrow()))?;
+        Ok(())
+    }
+
+    /// Get the latest group info and the ratchet tree of a group to join it
+    /// with an external commit.
+    pub fn group_info(&self, user: &User, group_id: &[u8]) -> Result<GroupInfoAndTree, String> {
+        let mut url = self.ds_url.clone();
+        let path = "/groups/".to_string()
+            + &base64::encode_config(group_id, base64::URL_SAFE)
+            + "/group_info";
+        url.set_path(&path);
+
+        let response = get(&url, Some(&user.identity.borrow()))?;
+        match GroupInfoAndTree::tls_deserialize(&mut response.as_slice()) {
+            Ok(group_info_and_tree) => Ok(group_info_and_tree),
+            Err(e) => Err(format!("Error decoding server response: {e:?}")),
+        }
+    }
+
+    /// Send a group message.
+    /// Returns the response of the DS, which is `GroupMessageResponse::StaleEpoch`
+    /// if the DS rejected a handshake message because it accepted another commit
+    /// for the epoch already.
+    pub fn send_msg(
+        &self,
+        user: &User,
+        group_msg: &GroupMessage,
+    ) -> Result<GroupMessageResponse, String> {
+        let mut url = self.ds_url.clone();
+        url.set_path("/send/message");
+
+        let response = post_with_status(
+            &url,
+            group_msg,
+            Some(&user.identity.borrow()),
+            &[StatusCode::CONFLICT],
+        )?;
+        match GroupMessageResponse::tls_deserialize(&mut response.as_slice()) {
+            Ok(r) => Ok(r),
+            Err(e) => Err(format!("Error decoding server response: {e:?}")),
+        }
+    }
+
     /// Get a list of all new messages for the user.
-    pub fn recv_msgs(&self, user: &User) -> Result<Vec<MlsMessageIn>, String> {
+    pub fn recv_msgs(&self, user: &User) -> Result<Vec<DeliveredMessage>, String> {
         let mut url = self.ds_url.clone();
         let path = "/recv/".to_string()
             + &base64::encode_config(user.identity.borrow().identity(), base64::URL_SAFE);
         url.set_path(&path);
 
-        let response = get(&url)?;
-        match TlsVecU16::<MlsMessageIn>::tls_deserialize(&mut response.as_slice()) {
+        let response = get(&url, Some(&user.identity.borrow()))?;
+        match TlsVecU16::<DeliveredMessage>::tls_deserialize(&mut response.as_slice()) {
             Ok(r) => Ok(r.into()),
             Err(e) => Err(format!("Invalid message list: {e:?}")),
         }
     }
 
     /// Reset the DS.
-    pub fn reset_server(&self) {
+    /// This requires the admin token the DS was started with.
+    pub fn reset_server(&self, admin_token: &str) -> Result<(), String> {
         let mut url = self.ds_url.clone();
         url.set_path("reset");
-        get(&url).unwrap();
+        get_with_headers(&url, None, &[(auth::ADMIN_TOKEN_HEADER, admin_token)])?;
+        Ok(())
     }
 }
 
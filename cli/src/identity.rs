@@ -31,7 +31,19 @@ impl Identity {
         };
         signature_keys.store(crypto.key_store()).unwrap();
 
+        // The first key package is a last-resort key package, such that the
+        // DS can always hand out a key package for this identity.
         let key_package = KeyPackage::builder()
+            .key_package_extensions(Extensions::single(Extension::LastResort(
+                LastResortExtension::default(),
+            )))
+            .leaf_node_capabilities(Capabilities::new(
+                None,
+                None,
+                Some(&[ExtensionType::LastResort]),
+                None,
+                None,
+            ))
             .build(
                 CryptoConfig {
                     ciphersuite,
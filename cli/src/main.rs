@@ -18,13 +18,14 @@ mod user;
 const HELP: &str = "
 >>> Available commands:
 >>>     - update                                update the client state
->>>     - reset                                 reset the server
+>>>     - reset                                 reset the server (requires the admin token in DS_ADMIN_TOKEN)
 >>>     - register {client name}                register a new client
 >>>     - save {client name}                    serialize and save the client state
 >>>     - load {client name}                    load and deserialize the client state as a new client
 >>>     - autosave                              enable automatic save of the current client state upon each update
 >>>     - create kp                             create a new key package
 >>>     - create group {group name}             create a new group
+>>>     - join group {group name}               join a group with an external commit
 >>>     - group {group name}                    group operations
 >>>         - send {message}                    send message to group
 >>>         - invite {client name}              invite a user to the group
@@ -157,6 +158,27 @@ fn main() {
             continue;
         }
 
+        // Join a group with an external commit.
+        if let Some(group_name) = op.strip_prefix("join group ") {
+            if let Some(client) = &mut client {
+                match client.join_group_by_external_commit(group_name.to_string()) {
+                    Ok(()) => stdout
+                        .write_all(format!(" >>> Joined group {group_name} :)\n\n").as_bytes())
+                        .unwrap(),
+                    Err(e) => stdout
+                        .write_all(
+                            format!(" >>> Error joining group {group_name}: {e} :(\n\n").as_bytes(),
+                        )
+                        .unwrap(),
+                }
+            } else {
+                stdout
+                    .write_all(b" >>> No client to join a group :(\n\n")
+                    .unwrap();
+            }
+            continue;
+        }
+
         // Group operations.
         if let Some(group_name) = op.strip_prefix("group ") {
             if let Some(client) = &mut client {
@@ -261,7 +283,21 @@ fn main() {
 
         // Reset the server and client.
         if op == "reset" {
-            backend::Backend::default().reset_server();
+            let admin_token = match std::env::var("DS_ADMIN_TOKEN") {
+                Ok(admin_token) => admin_token,
+                Err(_) => {
+                    stdout
+                        .write_all(b" >>> Set DS_ADMIN_TOKEN to reset the server :(\n\n")
+                        .unwrap();
+                    continue;
+                }
+            };
+            if let Err(e) = backend::Backend::default().reset_server(&admin_token) {
+                stdout
+                    .write_all(format!(" >>> Error resetting server: {e} :(\n\n").as_bytes())
+                    .unwrap();
+                continue;
+            }
             client = None;
             stdout.write_all(b" >>> Reset server :)\n\n").unwrap();
             continue;
@@ -283,11 +319,15 @@ fn main() {
 #[ignore]
 fn basic_test() {
     // Reset the server before doing anything for testing.
-    backend::Backend::default().reset_server();
+    let admin_token = std::env::var("DS_ADMIN_TOKEN").expect("DS_ADMIN_TOKEN is not set");
+    backend::Backend::default()
+        .reset_server(&admin_token)
+        .expect("Error resetting the server");
 
     const MESSAGE_1: &str = "Thanks for adding me Client1.";
     const MESSAGE_2: &str = "Welcome Client3.";
     const MESSAGE_3: &str = "Thanks so much for the warm welcome! 😊";
+    const MESSAGE_4: &str = "Hi all, I let myself in.";
 
     // Create one client
     let mut client_1 = user::User::new("Client1".to_string());
@@ -392,4 +432,35 @@ fn basic_test() {
             conversation::ConversationMessage::new(MESSAGE_3.to_owned(), "Client3".to_owned())
         ])
     );
+
+    // Client 4 joins the group with an external commit.
+    let mut client_4 = user::User::new("Client4".to_string());
+    client_4.update(None).unwrap();
+    client_4
+        .join_group_by_external_commit("MLS Discussions".to_string())
+        .unwrap();
+
+    // Everyone updates.
+    client_1.update(None).unwrap();
+    client_2.update(None).unwrap();
+    client_3.update(None).unwrap();
+
+    // Client 4 sends a message.
+    client_4
+        .send_msg(MESSAGE_4, "MLS Discussions".to_string())
+        .unwrap();
+    client_1.update(None).unwrap();
+
+    // Check that Client 1 received the message
+    assert_eq!(
+        client_1
+            .read_msgs("MLS Discussions".to_string())
+            .unwrap()
+            .unwrap()
+            .last(),
+        Some(&conversation::ConversationMessage::new(
+            MESSAGE_4.to_owned(),
+            "Client4".to_owned(),
+        ))
+    );
 }
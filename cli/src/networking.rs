@@ -1,18 +1,49 @@
-use reqwest::{self, blocking::Client, StatusCode};
+use ds_lib::auth::RequestSignature;
+use reqwest::{
+    self,
+    blocking::{Client, RequestBuilder},
+    StatusCode,
+};
 use url::Url;
 
 use tls_codec::Serialize;
 
+use super::identity::Identity;
+
 // TODO: return objects not bytes.
 
-pub fn post(url: &Url, msg: &impl Serialize) -> Result<Vec<u8>, String> {
+/// Post `msg` to `url`. If an `identity` is given, the request is signed with
+/// it to authenticate it to the DS.
+pub fn post(
+    url: &Url,
+    msg: &impl Serialize,
+    identity: Option<&Identity>,
+) -> Result<Vec<u8>, String> {
+    post_with_status(url, msg, identity, &[])
+}
+
+/// Like [`post`], but also returns the response body for the given non-OK
+/// status codes.
+pub fn post_with_status(
+    url: &Url,
+    msg: &impl Serialize,
+    identity: Option<&Identity>,
+    accepted_status: &[StatusCode],
+) -> Result<Vec<u8>, String> {
     let serialized_msg = msg.tls_serialize_detached().unwrap();
     log::debug!("Post {:?}", url);
     log::trace!("Payload: {:?}", serialized_msg);
     let client = Client::new();
-    let response = client.post(url.to_string()).body(serialized_msg).send();
+    let request = sign(
+        client.post(url.to_string()),
+        "POST",
+        url,
+        &serialized_msg,
+        identity,
+    )?;
+    let response = request.body(serialized_msg).send();
     if let Ok(r) = response {
-        if r.status() != StatusCode::OK {
+        if r.status() != StatusCode::OK && !accepted_status.contains(&r.status()) {
             return Err(format!("Error status code {:?}", r.status()));
         }
         match r.bytes() {
@@ -24,10 +55,27 @@ pub fn post(url: &Url, msg: &impl Serialize) -> Result<Vec<u8>, String> {
     }
 }
 
-pub fn get(url: &Url) -> Result<Vec<u8>, String> {
+/// Get `url`. If an `identity` is given, the request is signed with it to
+/// authenticate it to the DS.
+pub fn get(url: &Url, identity: Option<&Identity>) -> Result<Vec<u8>, String> {
+    get_with_headers(url, identity, &[])
+}
+
+/// Like [`get`], but sends the given additional headers.
+pub fn get_with_headers(
+    url: &Url,
+    identity: Option<&Identity>,
+    headers: &[(&str, &str)],
+) -> Result<Vec<u8>, String> {
     log::debug!("Get {:?}", url);
     let client = Client::new();
-    let response = client.get(url.to_string()).send();
+    let request = sign(client.get(url.to_string()), "GET", url, &[], identity)?;
+    let response = headers
+        .iter()
+        .fold(request, |request, (name, value)| {
+            request.header(*name, *value)
+        })
+        .send();
     if let Ok(r) = response {
         if r.status() != StatusCode::OK {
             return Err(format!("Error status code {:?}", r.status()));
@@ -40,3 +88,31 @@ pub fn get(url: &Url) -> Result<Vec<u8>, String> {
         Err(format!("ERROR: {:?}", response.err()))
     }
 }
+
+// Sign the request with the identity, if one is given.
+fn sign(
+    request: RequestBuilder,
+    method: &str,
+    url: &Url,
+    body: &[u8],
+    identity: Option<&Identity>,
+) -> Result<RequestBuilder, String> {
+    let identity = match identity {
+        Some(identity) => identity,
+        None => return Ok(request),
+    };
+    let signature = RequestSignature::sign(
+        &identity.signer,
+        identity.identity(),
+        method,
+        url.path(),
+        body,
+    )
+    .map_err(|e| format!("Error signing request: {e:?}"))?;
+    Ok(signature
+        .headers()
+        .into_iter()
+        .fold(request, |request, (name, value)| {
+            request.header(name, value)
+        }))
+}
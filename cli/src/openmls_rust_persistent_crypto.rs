@@ -4,19 +4,21 @@
 //! OpenMLS.
 
 use super::persistent_key_store::PersistentKeyStore;
-use openmls_rust_crypto::RustCrypto;
+use openmls_rust_crypto::{Clock, RustCrypto};
 use openmls_traits::OpenMlsProvider;
 
 #[derive(Default, Debug)]
 pub struct OpenMlsRustPersistentCrypto {
     crypto: RustCrypto,
     key_store: PersistentKeyStore,
+    clock: Clock,
 }
 
 impl OpenMlsProvider for OpenMlsRustPersistentCrypto {
     type CryptoProvider = RustCrypto;
     type RandProvider = RustCrypto;
     type KeyStoreProvider = PersistentKeyStore;
+    type TimeProvider = Clock;
 
     fn crypto(&self) -> &Self::CryptoProvider {
         &self.crypto
@@ -29,6 +31,10 @@ impl OpenMlsProvider for OpenMlsRustPersistentCrypto {
     fn key_store(&self) -> &Self::KeyStoreProvider {
         &self.key_store
     }
+
+    fn time(&self) -> &Self::TimeProvider {
+        &self.clock
+    }
 }
 
 impl OpenMlsRustPersistentCrypto {
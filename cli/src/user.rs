@@ -5,7 +5,7 @@ use std::io::{BufReader, BufWriter};
 use std::path::PathBuf;
 use std::{cell::RefCell, collections::HashMap, str};
 
-use ds_lib::{ClientKeyPackages, GroupMessage};
+use ds_lib::{ClientKeyPackages, GroupInfoAndTree, GroupMessage, GroupMessageResponse};
 use openmls::prelude::*;
 use openmls_traits::OpenMlsProvider;
 use tls_codec::TlsByteVecU8;
@@ -18,6 +18,14 @@ use super::{
 
 const CIPHERSUITE: Ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
 
+// How often a commit is re-based and sent again when the DS accepted another
+// commit for the epoch.
+const MAX_COMMIT_ATTEMPTS: usize = 3;
+
+// The number of key packages that are kept on the DS in addition to the
+// last-resort key package.
+const MIN_KEY_PACKAGES: u32 = 2;
+
 #[derive(serde::Serialize, serde::Deserialize)]
 pub struct Contact {
     username: String,
@@ -217,40 +225,6 @@ impl User {
         }
     }
 
-    /// Get a list of clients in the group to send messages to.
-    fn recipients(&self, group: &Group) -> Vec<Vec<u8>> {
-        let mut recipients = Vec::new();
-
-        let mls_group = group.mls_group.borrow();
-        for Member {
-            index: _,
-            encryption_key: _,
-            signature_key,
-            credential,
-        } in mls_group.members()
-        {
-            if self
-                .identity
-                .borrow()
-                .credential_with_key
-                .signature_key
-                .as_slice()
-                != signature_key.as_slice()
-            {
-                log::debug!(
-                    "Searching for contact {:?}",
-                    str::from_utf8(credential.identity()).unwrap()
-                );
-                let contact = match self.contacts.get(&credential.identity().to_vec()) {
-                    Some(c) => c.id.clone(),
-                    None => panic!("There's a member in the group we don't know."),
-                };
-                recipients.push(contact);
-            }
-        }
-        recipients
-    }
-
     /// Return the last 100 messages sent to the group.
     pub fn read_msgs(
         &self,
@@ -269,21 +243,39 @@ impl User {
 
     /// Create a new key package and publish it to the delivery server
     pub fn create_kp(&self) {
-        let kp = self.add_key_package();
+        self.publish_key_packages(1);
+    }
+
+    /// Create `count` new key packages and publish them to the delivery server
+    fn publish_key_packages(&self, count: u32) {
         let ckp = ClientKeyPackages(
-            vec![kp]
-                .into_iter()
+            (0..count)
+                .map(|_| self.add_key_package())
                 .map(|(b, kp)| (b.into(), KeyPackageIn::from(kp)))
                 .collect::<Vec<(TlsByteVecU8, KeyPackageIn)>>()
                 .into(),
         );
 
         match self.backend.publish_key_packages(self, &ckp) {
-            Ok(()) => (),
+            Ok(inventory) => log::debug!("Key package inventory: {:?}", inventory),
             Err(e) => println!("Error sending new key package: {e:?}"),
         };
     }
 
+    /// Publish new key packages if the delivery server runs low on them.
+    fn replenish_key_packages(&self) {
+        let inventory = match self.backend.key_package_inventory(self) {
+            Ok(inventory) => inventory,
+            Err(e) => {
+                log::error!("Error getting the key package inventory: {:?}", e);
+                return;
+            }
+        };
+        if inventory.key_packages < MIN_KEY_PACKAGES {
+            self.publish_key_packages(MIN_KEY_PACKAGES - inventory.key_packages);
+        }
+    }
+
     /// Send an application message to the group.
     pub fn send_msg(&self, msg: &str, group: String) -> Result<(), String> {
         let groups = self.groups.borrow();
@@ -298,10 +290,13 @@ impl User {
             .create_message(&self.crypto, &self.identity.borrow().signer, msg.as_bytes())
             .map_err(|e| format!("{e}"))?;
 
-        let msg = GroupMessage::new(message_out.into(), &self.recipients(group));
+        let msg = GroupMessage::new(message_out.into(), self.identity.borrow().identity());
         log::debug!(" >>> send: {:?}", msg);
-        match self.backend.send_msg(&msg) {
-            Ok(()) => (),
+        match self.backend.send_msg(self, &msg) {
+            Ok(GroupMessageResponse::Accepted(sequence_number)) => {
+                log::debug!(" >>> sequence number: {:?}", sequence_number)
+            }
+            Ok(response) => println!("Unexpected response to group message: {response:?}"),
             Err(e) => println!("Error sending group message: {e:?}"),
         }
 
@@ -471,8 +466,12 @@ impl User {
         log::debug!("update::Processing messages for {} ", self.username);
         // Go through the list of messages and process or store them.
         for message in self.backend.recv_msgs(self)?.drain(..) {
-            log::debug!("Reading message format {:#?} ...", message.wire_format());
-            match message.extract() {
+            log::debug!(
+                "Reading message format {:#?} (sequence number {:?}) ...",
+                message.msg.wire_format(),
+                message.sequence_number
+            );
+            match message.msg.extract() {
                 MlsMessageBodyIn::Welcome(welcome) => {
                     // Join the group. (Later we should ask the user to
                     // approve first ...)
@@ -510,6 +509,7 @@ impl User {
         }
         log::debug!("update::Processing messages done");
 
+        self.replenish_key_packages();
         self.update_clients();
 
         self.autosave();
@@ -526,8 +526,10 @@ impl User {
 
         // NOTE: Since the DS currently doesn't distribute copies of the group's ratchet
         // tree, we need to include the ratchet_tree_extension.
+        // The DS validates handshake messages, so they have to be sent in plaintext.
         let group_config = MlsGroupCreateConfig::builder()
             .use_ratchet_tree_extension(true)
+            .wire_format_policy(MIXED_PLAINTEXT_WIRE_FORMAT_POLICY)
             .build();
 
         let mut mls_group = MlsGroup::new_with_group_id(
@@ -540,6 +542,17 @@ impl User {
         .expect("Failed to create MlsGroup");
         mls_group.set_aad(group_aad.as_slice());
 
+        // Register the group with the DS. The group info is also handed out to
+        // clients joining the group with an external commit.
+        let group_info = mls_group
+            .export_group_info(self.crypto.crypto(), &self.identity.borrow().signer, false)
+            .expect("Failed to export the group info");
+        let group_info_and_tree =
+            GroupInfoAndTree::new(group_info.into(), mls_group.export_ratchet_tree().into());
+        if let Err(e) = self.backend.create_group(self, &group_info_and_tree) {
+            panic!("Failed to register group '{}' with the DS: {}", name, e);
+        }
+
         let group = Group {
             group_name: name.clone(),
             conversation: Conversation::default(),
@@ -555,108 +568,276 @@ impl User {
         self.autosave();
     }
 
+    /// Upload the group info of the group for its current epoch to the DS,
+    /// such that clients can join the group with an external commit.
+    fn publish_group_info(&self, group_name: &str) {
+        let group_info = match self.groups.borrow().get(group_name) {
+            Some(group) => group.mls_group.borrow().export_group_info(
+                self.crypto.crypto(),
+                &self.identity.borrow().signer,
+                false,
+            ),
+            None => return,
+        };
+        let published = group_info
+            .map_err(|e| e.to_string())
+            .and_then(|group_info| {
+                self.backend
+                    .publish_group_info(self, group_name.as_bytes(), &group_info)
+            });
+        if let Err(e) = published {
+            log::error!(
+                "Error publishing the group info of group {}: {:?}",
+                group_name,
+                e
+            );
+        }
+    }
+
     /// Invite user with the given name to the group.
     pub fn invite(&mut self, name: String, group_name: String) -> Result<(), String> {
         // First we need to get the key package for {id} from the DS.
-        let contact = match self.contacts.values().find(|c| c.username == name) {
-            Some(v) => v,
+        let contact_id = match self.contacts.values().find(|c| c.username == name) {
+            Some(v) => v.id.clone(),
             None => return Err(format!("No contact with name {name} known.")),
         };
 
         // Reclaim a key package from the server
-        let joiner_key_package = self.backend.consume_key_package(&contact.id).unwrap();
+        let joiner_key_package: KeyPackage = self
+            .backend
+            .consume_key_package(self, &contact_id)
+            .unwrap()
+            .into();
+
+        for _ in 0..MAX_COMMIT_ATTEMPTS {
+            // Build a proposal with this key package and do the MLS bits.
+            let groups = self.groups.borrow();
+            let group = match groups.get(&group_name) {
+                Some(g) => g,
+                None => return Err(format!("No group with name {group_name} known.")),
+            };
 
-        // Build a proposal with this key package and do the MLS bits.
-        let mut groups = self.groups.borrow_mut();
-        let group = match groups.get_mut(&group_name) {
-            Some(g) => g,
-            None => return Err(format!("No group with name {group_name} known.")),
-        };
+            let (out_messages, welcome, _group_info) = group
+                .mls_group
+                .borrow_mut()
+                .add_members(
+                    &self.crypto,
+                    &self.identity.borrow().signer,
+                    &[joiner_key_package.clone()],
+                )
+                .map_err(|e| format!("Failed to add member to group - {e}"))?;
+            drop(groups);
+
+            /* First, send the MlsMessage commit to the group.
+            This must be done before the member invitation is locally committed.
+            It avoids the invited member to receive the commit message (which is in the previous group epoch).*/
+            log::trace!("Sending commit");
+            if !self.send_commit(&group_name, out_messages)? {
+                continue;
+            }
 
-        let (out_messages, welcome, _group_info) = group
-            .mls_group
-            .borrow_mut()
-            .add_members(
-                &self.crypto,
-                &self.identity.borrow().signer,
-                &[joiner_key_package.into()],
-            )
-            .map_err(|e| format!("Failed to add member to group - {e}"))?;
+            // Second, process the invitation on our end.
+            self.groups.borrow()[&group_name]
+                .mls_group
+                .borrow_mut()
+                .merge_pending_commit(&self.crypto)
+                .expect("error merging pending commit");
+            self.publish_group_info(&group_name);
 
-        /* First, send the MlsMessage commit to the group.
-        This must be done before the member invitation is locally committed.
-        It avoids the invited member to receive the commit message (which is in the previous group epoch).*/
-        log::trace!("Sending commit");
-        let group = groups.get_mut(&group_name).unwrap(); // XXX: not cool.
-        let group_recipients = self.recipients(group);
+            // Finally, send Welcome to the joiner.
+            log::trace!("Sending welcome");
+            self.backend
+                .send_welcome(self, &welcome)
+                .expect("Error sending Welcome message");
 
-        let msg = GroupMessage::new(out_messages.into(), &group_recipients);
-        self.backend.send_msg(&msg)?;
+            self.autosave();
 
-        // Second, process the invitation on our end.
-        group
-            .mls_group
-            .borrow_mut()
-            .merge_pending_commit(&self.crypto)
-            .expect("error merging pending commit");
+            return Ok(());
+        }
 
-        // Finally, send Welcome to the joiner.
-        log::trace!("Sending welcome");
-        self.backend
-            .send_welcome(&welcome)
-            .expect("Error sending Welcome message");
+        Err(format!(
+            "Failed to add {name} to group {group_name} after {MAX_COMMIT_ATTEMPTS} attempts."
+        ))
+    }
 
-        drop(groups);
+    /// Remove user with the given name from the group.
+    pub fn remove(&mut self, name: String, group_name: String) -> Result<(), String> {
+        for _ in 0..MAX_COMMIT_ATTEMPTS {
+            // Get the group ID
+            let groups = self.groups.borrow();
+            let group = match groups.get(&group_name) {
+                Some(g) => g,
+                None => return Err(format!("No group with name {group_name} known.")),
+            };
 
-        self.autosave();
+            // Get the client leaf index
+            let leaf_index = self.find_member_index(name.clone(), group)?;
+
+            // Remove operation on the mls group
+            let (remove_message, _welcome, _group_info) = group
+                .mls_group
+                .borrow_mut()
+                .remove_members(&self.crypto, &self.identity.borrow().signer, &[leaf_index])
+                .map_err(|e| format!("Failed to remove member from group - {e}"))?;
+            drop(groups);
+
+            // First, send the MlsMessage remove commit to the group.
+            log::trace!("Sending commit");
+            if !self.send_commit(&group_name, remove_message)? {
+                continue;
+            }
 
-        Ok(())
+            // Second, process the removal on our end.
+            self.groups.borrow()[&group_name]
+                .mls_group
+                .borrow_mut()
+                .merge_pending_commit(&self.crypto)
+                .expect("error merging pending commit");
+            self.publish_group_info(&group_name);
+
+            self.autosave();
+
+            return Ok(());
+        }
+
+        Err(format!(
+            "Failed to remove {name} from group {group_name} after {MAX_COMMIT_ATTEMPTS} attempts."
+        ))
     }
 
-    /// Remove user with the given name from the group.
-    pub fn remove(&mut self, name: String, group_name: String) -> Result<(), String> {
-        // Get the group ID
+    /// Send a commit to the group and return whether the DS accepted it.
+    /// If the DS accepted another commit for the epoch already, the group is
+    /// re-based onto the winning commit (see [`Self::rebase`]) and the commit
+    /// has to be created again.
+    fn send_commit(&mut self, group_name: &str, commit: MlsMessageOut) -> Result<bool, String> {
+        let msg = GroupMessage::new(commit.into(), self.identity.borrow().identity());
+        match self.backend.send_msg(self, &msg)? {
+            GroupMessageResponse::Accepted(_) => Ok(true),
+            GroupMessageResponse::StaleEpoch(winning_commit) => {
+                log::debug!(
+                    "The DS accepted another commit for group {}. Re-basing ...",
+                    group_name
+                );
+                self.rebase(group_name, winning_commit)?;
+                Ok(false)
+            }
+        }
+    }
 
-        let mut groups = self.groups.borrow_mut();
-        let group = match groups.get_mut(&group_name) {
-            Some(g) => g,
-            None => return Err(format!("No group with name {group_name} known.")),
+    /// Re-base the group onto the commit the DS accepted instead of ours.
+    /// The pending commit is discarded and all messages queued on the DS are
+    /// processed, which includes the winning commit. The winning commit
+    /// returned by the DS is only processed here if it wasn't queued for us.
+    fn rebase(&mut self, group_name: &str, winning_commit: MlsMessageIn) -> Result<(), String> {
+        let epoch = {
+            let groups = self.groups.borrow();
+            let group = match groups.get(group_name) {
+                Some(g) => g,
+                None => return Err(format!("No group with name {group_name} known.")),
+            };
+            let mut mls_group = group.mls_group.borrow_mut();
+            mls_group.clear_pending_commit();
+            mls_group.epoch()
         };
 
-        // Get the client leaf index
+        self.update(None)?;
 
-        let leaf_index = match self.find_member_index(name, group) {
-            Ok(l) => l,
-            Err(e) => return Err(e),
+        let groups = self.groups.borrow();
+        let group = match groups.get(group_name) {
+            Some(g) => g,
+            None => return Err(format!("Removed from group {group_name}.")),
         };
+        let mut mls_group = group.mls_group.borrow_mut();
+        if mls_group.epoch() != epoch {
+            return Ok(());
+        }
 
-        // Remove operation on the mls group
-        let (remove_message, _welcome, _group_info) = group
-            .mls_group
-            .borrow_mut()
-            .remove_members(&self.crypto, &self.identity.borrow().signer, &[leaf_index])
-            .map_err(|e| format!("Failed to remove member from group - {e}"))?;
+        let protocol_message: ProtocolMessage = match winning_commit.extract() {
+            MlsMessageBodyIn::PublicMessage(message) => message.into(),
+            _ => return Err("The winning commit is not a public message.".to_string()),
+        };
+        let processed_message = mls_group
+            .process_message(&self.crypto, protocol_message)
+            .map_err(|e| format!("Failed to process the winning commit - {e}"))?;
+        match processed_message.into_content() {
+            ProcessedMessageContent::StagedCommitMessage(staged_commit) => mls_group
+                .merge_staged_commit(&self.crypto, *staged_commit)
+                .map_err(|e| format!("Failed to merge the winning commit - {e}")),
+            _ => Err("The winning commit is not a commit.".to_string()),
+        }
+    }
 
-        // First, send the MlsMessage remove commit to the group.
-        log::trace!("Sending commit");
-        let group = groups.get_mut(&group_name).unwrap(); // XXX: not cool.
-        let group_recipients = self.recipients(group);
+    /// Join the group with the given name with an external commit.
+    /// The group info and ratchet tree of the group are fetched from the DS,
+    /// which requires a member to have uploaded the group info for the current
+    /// epoch.
+    pub fn join_group_by_external_commit(&mut self, group_name: String) -> Result<(), String> {
+        if self.groups.borrow().contains_key(&group_name) {
+            return Err(format!("Group '{group_name}' exists already."));
+        }
+        let group_aad = group_name.clone() + " AAD";
+        // NOTE: The DS distributes the ratchet tree only with the group info,
+        // not with welcomes. The ratchet_tree_extension is included such that
+        // clients we add to the group can process our welcomes.
+        let group_config = MlsGroupJoinConfig::builder()
+            .use_ratchet_tree_extension(true)
+            .wire_format_policy(MIXED_PLAINTEXT_WIRE_FORMAT_POLICY)
+            .build();
 
-        let msg = GroupMessage::new(remove_message.into(), &group_recipients);
-        self.backend.send_msg(&msg)?;
+        for _ in 0..MAX_COMMIT_ATTEMPTS {
+            let group_info_and_tree = self.backend.group_info(self, group_name.as_bytes())?;
+            let verifiable_group_info = group_info_and_tree
+                .group_info
+                .try_into_verifiable_group_info()
+                .map_err(|e| format!("Invalid group info - {e}"))?;
+            let (mut mls_group, commit, _group_info) = MlsGroup::join_by_external_commit(
+                &self.crypto,
+                &self.identity.borrow().signer,
+                Some(group_info_and_tree.ratchet_tree),
+                verifiable_group_info,
+                &group_config,
+                group_aad.as_bytes(),
+                self.identity.borrow().credential_with_key.clone(),
+            )
+            .map_err(|e| format!("Failed to join group {group_name} - {e}"))?;
 
-        // Second, process the removal on our end.
-        group
-            .mls_group
-            .borrow_mut()
-            .merge_pending_commit(&self.crypto)
-            .expect("error merging pending commit");
+            // The external commit has to be accepted by the DS before it can be
+            // merged. If the DS accepted another commit for the epoch, the
+            // group info of the new epoch is fetched and the join is retried.
+            let msg = GroupMessage::new(commit.into(), self.identity.borrow().identity());
+            if let GroupMessageResponse::StaleEpoch(_) = self.backend.send_msg(self, &msg)? {
+                log::debug!(
+                    "The DS accepted another commit for group {}. Retrying ...",
+                    group_name
+                );
+                // The key material of the external commit is only written to
+                // the key store when the commit is merged. Until then it is
+                // kept in the pending commit of the group, so dropping the
+                // group discards it.
+                drop(mls_group);
+                continue;
+            }
+            mls_group
+                .merge_pending_commit(&self.crypto)
+                .expect("error merging pending commit");
+            mls_group.set_aad(group_aad.as_bytes());
+
+            let group = Group {
+                group_name: group_name.clone(),
+                conversation: Conversation::default(),
+                mls_group: RefCell::new(mls_group),
+            };
+            self.groups.borrow_mut().insert(group_name.clone(), group);
+            self.publish_group_info(&group_name);
 
-        drop(groups);
+            self.autosave();
 
-        self.autosave();
+            return Ok(());
+        }
 
-        Ok(())
+        Err(format!(
+            "Failed to join group {group_name} after {MAX_COMMIT_ATTEMPTS} attempts."
+        ))
     }
 
     /// Join a group with the provided welcome message.
@@ -674,6 +855,7 @@ impl User {
         // tree, we need to include the ratchet_tree_extension.
         let group_config = MlsGroupJoinConfig::builder()
             .use_ratchet_tree_extension(true)
+            .wire_format_policy(MIXED_PLAINTEXT_WIRE_FORMAT_POLICY)
             .build();
         let mut mls_group = MlsGroup::new_from_welcome(&self.crypto, &group_config, welcome, None)
             .expect("Failed to create MlsGroup");
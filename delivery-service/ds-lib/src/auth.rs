@@ -0,0 +1,168 @@
+//! # Authentication of client requests
+//!
+//! Clients authenticate their requests to the DS by signing them with the
+//! signature key of the key package they registered with. The DS stores this
+//! key with the client (see [`ClientInfo::signature_key()`]).
+//! The signature covers the HTTP method, the path, a timestamp and the body of
+//! the request and is sent in the [`CLIENT_ID_HEADER`], [`TIMESTAMP_HEADER`]
+//! and [`SIGNATURE_HEADER`] headers.
+//!
+//! The DS rejects requests with a timestamp that is more than
+//! [`MAX_CLOCK_SKEW`] seconds away from its own clock. A request can be
+//! replayed within this window.
+//!
+//! Administrative requests are authenticated with a token the DS is configured
+//! with, which is sent in the [`ADMIN_TOKEN_HEADER`] header.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use openmls_traits::{crypto::OpenMlsCrypto, signatures::Signer, types::SignatureScheme};
+use tls_codec::{Serialize, TlsByteSliceU16, TlsByteSliceU32};
+
+#[cfg(doc)]
+use crate::ClientInfo;
+
+/// The header holding the base64 encoded ID of the client signing a request.
+pub const CLIENT_ID_HEADER: &str = "x-ds-client-id";
+/// The header holding the time the request was signed at in seconds since the
+/// Unix epoch.
+pub const TIMESTAMP_HEADER: &str = "x-ds-timestamp";
+/// The header holding the base64 encoded signature of a request.
+pub const SIGNATURE_HEADER: &str = "x-ds-signature";
+/// The header holding the admin token for administrative requests.
+pub const ADMIN_TOKEN_HEADER: &str = "x-ds-admin-token";
+
+/// The maximum difference in seconds between the timestamp of a request and
+/// the clock of the DS.
+pub const MAX_CLOCK_SKEW: u64 = 60;
+
+const REQUEST_SIGNATURE_LABEL: &[u8] = b"OpenMLS DS Request";
+
+/// Errors when signing or verifying a request.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuthError {
+    /// A header is missing or malformed.
+    InvalidHeader(&'static str),
+    /// The timestamp of the request is too far away from the current time.
+    InvalidTimestamp,
+    /// The signature of the request is invalid.
+    InvalidSignature,
+    /// The request couldn't be encoded.
+    EncodingError,
+    /// The request couldn't be signed.
+    SigningError,
+}
+
+/// The signature of a request together with the ID of the signing client and
+/// the time the request was signed at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RequestSignature {
+    pub client_id: Vec<u8>,
+    pub timestamp: u64,
+    pub signature: Vec<u8>,
+}
+
+impl RequestSignature {
+    /// Sign a request with the given `method`, `path` and `body` at the
+    /// current time.
+    pub fn sign(
+        signer: &impl Signer,
+        client_id: &[u8],
+        method: &str,
+        path: &str,
+        body: &[u8],
+    ) -> Result<Self, AuthError> {
+        let timestamp = now();
+        let tbs = request_tbs(method, path, timestamp, body)?;
+        let signature = signer.sign(&tbs).map_err(|_| AuthError::SigningError)?;
+        Ok(Self {
+            client_id: client_id.to_vec(),
+            timestamp,
+            signature,
+        })
+    }
+
+    /// The headers to send with the request.
+    pub fn headers(&self) -> [(&'static str, String); 3] {
+        [
+            (
+                CLIENT_ID_HEADER,
+                base64::encode_config(&self.client_id, base64::URL_SAFE),
+            ),
+            (TIMESTAMP_HEADER, self.timestamp.to_string()),
+            (
+                SIGNATURE_HEADER,
+                base64::encode_config(&self.signature, base64::URL_SAFE),
+            ),
+        ]
+    }
+
+    /// Read the signature of a request from its headers, where `header`
+    /// returns the value of the header with the given name.
+    pub fn from_headers<'a>(
+        header: impl Fn(&'static str) -> Option<&'a str>,
+    ) -> Result<Self, AuthError> {
+        let decode = |name| {
+            header(name)
+                .and_then(|value| base64::decode_config(value, base64::URL_SAFE).ok())
+                .ok_or(AuthError::InvalidHeader(name))
+        };
+        let client_id = decode(CLIENT_ID_HEADER)?;
+        let signature = decode(SIGNATURE_HEADER)?;
+        let timestamp = header(TIMESTAMP_HEADER)
+            .and_then(|value| value.parse().ok())
+            .ok_or(AuthError::InvalidHeader(TIMESTAMP_HEADER))?;
+        Ok(Self {
+            client_id,
+            timestamp,
+            signature,
+        })
+    }
+
+    /// Verify the signature of a request with the given `method`, `path` and
+    /// `body` with the client's signature key.
+    pub fn verify(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        signature_scheme: SignatureScheme,
+        signature_key: &[u8],
+        method: &str,
+        path: &str,
+        body: &[u8],
+    ) -> Result<(), AuthError> {
+        if now().abs_diff(self.timestamp) > MAX_CLOCK_SKEW {
+            return Err(AuthError::InvalidTimestamp);
+        }
+        let tbs = request_tbs(method, path, self.timestamp, body)?;
+        crypto
+            .verify_signature(signature_scheme, &tbs, signature_key, &self.signature)
+            .map_err(|_| AuthError::InvalidSignature)
+    }
+}
+
+// The content of a request that is signed.
+fn request_tbs(
+    method: &str,
+    path: &str,
+    timestamp: u64,
+    body: &[u8],
+) -> Result<Vec<u8>, AuthError> {
+    let mut tbs = Vec::new();
+    let encoded = TlsByteSliceU16(REQUEST_SIGNATURE_LABEL)
+        .tls_serialize(&mut tbs)
+        .and_then(|_| TlsByteSliceU16(method.as_bytes()).tls_serialize(&mut tbs))
+        .and_then(|_| TlsByteSliceU16(path.as_bytes()).tls_serialize(&mut tbs))
+        .and_then(|_| timestamp.tls_serialize(&mut tbs))
+        .and_then(|_| TlsByteSliceU32(body).tls_serialize(&mut tbs));
+    match encoded {
+        Ok(_) => Ok(tbs),
+        Err(_) => Err(AuthError::EncodingError),
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
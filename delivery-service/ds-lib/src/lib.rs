@@ -3,7 +3,9 @@
 //! This library provides structs and necessary implementations to interact with
 //! the OpenMLS DS.
 //!
-//! Clients are represented by the `ClientInfo` struct.
+//! Clients are represented by the `ClientInfo` struct. Requests of clients are
+//! authenticated as described in [`auth`]. Queued messages can be pushed to
+//! clients as described in [`push`].
 
 use std::collections::HashSet;
 
@@ -13,6 +15,11 @@ use tls_codec::{
     TlsDeserializeBytes, TlsSerialize, TlsSize, TlsVecU32,
 };
 
+pub mod auth;
+pub mod push;
+
+use push::{DeliveryId, PushedMessage};
+
 /// Information about a client.
 /// To register a new client create a new `ClientInfo` and send it to
 /// `/clients/register`.
@@ -23,8 +30,17 @@ pub struct ClientInfo {
     /// map of reserved key_packages [group_id, key_package_hash]
     pub reserved_key_pkg_hash: HashSet<Vec<u8>>,
     pub id: Vec<u8>,
-    pub msgs: Vec<MlsMessageIn>,
+    /// The signature scheme and key the client registered with, i.e. the ones
+    /// of the first key package it registered.
+    pub signature_key: Option<(SignatureScheme, SignaturePublicKey)>,
+    pub msgs: Vec<DeliveredMessage>,
     pub welcome_queue: Vec<MlsMessageIn>,
+    /// The number of messages that were delivered from `msgs`, i.e. the
+    /// delivery ID of the first message in `msgs`.
+    pub delivered_msgs: u64,
+    /// The number of welcomes that were delivered from `welcome_queue`, i.e.
+    /// the delivery ID of the first welcome in `welcome_queue`.
+    pub delivered_welcomes: u64,
 }
 
 /// The DS returns a list of key packages for a client as `ClientKeyPackages`.
@@ -51,9 +67,14 @@ impl ClientInfo {
     pub fn new(client_name: String, mut key_packages: Vec<(Vec<u8>, KeyPackageIn)>) -> Self {
         let key_package: KeyPackage = KeyPackage::from(key_packages[0].1.clone());
         let id = key_package.leaf_node().credential().identity().to_vec();
+        let signature_key = Some((
+            key_package.ciphersuite().signature_algorithm(),
+            key_package.leaf_node().signature_key().clone(),
+        ));
         Self {
             client_name,
             id,
+            signature_key,
             key_packages: ClientKeyPackages(
                 key_packages
                     .drain(..)
@@ -64,6 +85,8 @@ impl ClientInfo {
             reserved_key_pkg_hash: HashSet::new(),
             msgs: Vec::new(),
             welcome_queue: Vec::new(),
+            delivered_msgs: 0,
+            delivered_welcomes: 0,
         }
     }
 
@@ -73,44 +96,222 @@ impl ClientInfo {
         self.id.as_slice()
     }
 
+    /// The signature scheme and key the client authenticates its requests
+    /// with, which are the ones it registered with.
+    /// They don't change when key packages are published or handed out.
+    pub fn signature_key(&self) -> Option<(SignatureScheme, SignaturePublicKey)> {
+        self.signature_key.clone()
+    }
+
     /// Acquire a key package from the client's key packages
     /// Mark the key package hash ref as "reserved key package"
     /// The reserved hash ref will be used in DS::send_welcome and removed once welcome is distributed
-    pub fn consume_kp(&mut self) -> Result<KeyPackageIn, String> {
-        if self.key_packages.0.len() <= 1 {
-            // We keep one keypackage to handle ClientInfo serialization/deserialization issues
+    ///
+    /// Key packages that aren't valid anymore, e.g. because they expired, are
+    /// dropped. Key packages are handed out in the order they were published.
+    /// A last-resort key package is only handed out if there is no other key
+    /// package left and is kept afterwards, such that it can be handed out
+    /// again.
+    /// We always keep one key package to handle ClientInfo
+    /// serialization/deserialization issues, i.e. the last key package is
+    /// never handed out if it isn't a last-resort key package.
+    pub fn consume_kp(&mut self, provider: &impl OpenMlsProvider) -> Result<KeyPackageIn, String> {
+        let (mut valid, invalid): (Vec<_>, Vec<_>) =
+            Vec::from(std::mem::take(&mut self.key_packages.0))
+                .into_iter()
+                .partition(|(_, key_package)| is_valid_key_package(key_package, provider));
+        if valid.is_empty() {
+            self.key_packages =
+                ClientKeyPackages(invalid.into_iter().take(1).collect::<Vec<_>>().into());
             return Err("No more keypackage available".to_string());
         }
-        match self.key_packages.0.pop() {
-            Some(c) => {
-                self.reserved_key_pkg_hash.insert(c.0.into_vec());
-                Ok(c.1)
+
+        let key_package = match valid.iter().position(|(_, kp)| !is_last_resort(kp)) {
+            Some(position) if valid.len() > 1 => Some(valid.remove(position)),
+            _ => valid.iter().find(|(_, kp)| is_last_resort(kp)).cloned(),
+        };
+        self.key_packages = ClientKeyPackages(valid.into());
+        match key_package {
+            Some((hash, key_package)) => {
+                self.reserved_key_pkg_hash.insert(hash.into_vec());
+                Ok(key_package)
             }
             None => Err("No more keypackage available".to_string()),
         }
     }
+
+    /// Count the key packages of the client that are valid at the current
+    /// time of the provider.
+    pub fn inventory(&self, provider: &impl OpenMlsProvider) -> KeyPackageInventory {
+        let mut inventory = KeyPackageInventory::default();
+        for (_, key_package) in self
+            .key_packages
+            .0
+            .iter()
+            .filter(|(_, key_package)| is_valid_key_package(key_package, provider))
+        {
+            if is_last_resort(key_package) {
+                inventory.last_resort_key_packages += 1;
+            } else {
+                inventory.key_packages += 1;
+            }
+        }
+        inventory
+    }
+
+    /// Take all queued welcomes and messages of the client, welcomes first.
+    pub fn take_queued_messages(&mut self) -> Vec<DeliveredMessage> {
+        self.delivered_welcomes += self.welcome_queue.len() as u64;
+        self.delivered_msgs += self.msgs.len() as u64;
+        self.welcome_queue
+            .drain(..)
+            .map(|welcome| DeliveredMessage::new(welcome, None))
+            .chain(self.msgs.drain(..))
+            .collect()
+    }
+
+    /// Get the queued welcomes and messages of the client with a delivery ID
+    /// of at least `next_welcome` and `next_msg` respectively, welcomes
+    /// first.
+    pub fn queued_messages(&self, next_welcome: u64, next_msg: u64) -> Vec<PushedMessage> {
+        let welcomes = (self.delivered_welcomes..)
+            .zip(self.welcome_queue.iter())
+            .filter(|(id, _)| *id >= next_welcome)
+            .map(|(id, welcome)| PushedMessage {
+                delivery_id: DeliveryId::Welcome(id),
+                message: DeliveredMessage::new(welcome.clone(), None),
+            });
+        let msgs = (self.delivered_msgs..)
+            .zip(self.msgs.iter())
+            .filter(|(id, _)| *id >= next_msg)
+            .map(|(id, msg)| PushedMessage {
+                delivery_id: DeliveryId::Message(id),
+                message: msg.clone(),
+            });
+        welcomes.chain(msgs).collect()
+    }
+
+    /// Remove the message with the given `delivery_id` and all messages that
+    /// were queued before it in the same queue.
+    pub fn acknowledge(&mut self, delivery_id: DeliveryId) {
+        fn acknowledge_in<T>(queue: &mut Vec<T>, delivered: &mut u64, id: u64) {
+            let acknowledged = usize::try_from(id.saturating_add(1).saturating_sub(*delivered))
+                .unwrap_or(usize::MAX)
+                .min(queue.len());
+            queue.drain(..acknowledged);
+            *delivered += acknowledged as u64;
+        }
+        match delivery_id {
+            DeliveryId::Welcome(id) => {
+                acknowledge_in(&mut self.welcome_queue, &mut self.delivered_welcomes, id)
+            }
+            DeliveryId::Message(id) => acknowledge_in(&mut self.msgs, &mut self.delivered_msgs, id),
+        }
+    }
+}
+
+fn is_valid_key_package(key_package: &KeyPackageIn, provider: &impl OpenMlsProvider) -> bool {
+    key_package
+        .clone()
+        .validate(
+            provider.crypto(),
+            provider.time(),
+            ProtocolVersion::default(),
+        )
+        .is_ok()
+}
+
+fn is_last_resort(key_package: &KeyPackageIn) -> bool {
+    KeyPackage::from(key_package.clone()).last_resort()
+}
+
+/// The number of valid key packages the DS holds for a client.
+/// Clients should publish new key packages when they run low.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsSize)]
+pub struct KeyPackageInventory {
+    /// The number of key packages that are handed out once.
+    pub key_packages: u32,
+    /// The number of last-resort key packages.
+    pub last_resort_key_packages: u32,
 }
 
 /// An core group message.
-/// This is an `MLSMessage` plus the list of recipients as a vector of client
-/// names.
+/// This is an `MLSMessage` plus the ID of the client sending it.
+/// The DS delivers the message to all members of the group except for the
+/// sender.
 #[derive(Debug)]
 pub struct GroupMessage {
     pub msg: MlsMessageIn,
-    pub recipients: TlsVecU32<TlsByteVecU32>,
+    pub sender: TlsByteVecU32,
 }
 
 impl GroupMessage {
-    /// Create a new `GroupMessage` taking an `MlsMessageIn` and slice of
-    /// recipient names.
-    pub fn new(msg: MlsMessageIn, recipients: &[Vec<u8>]) -> Self {
+    /// Create a new `GroupMessage` taking an `MlsMessageIn` and the ID of the
+    /// sending client.
+    pub fn new(msg: MlsMessageIn, sender: &[u8]) -> Self {
+        Self {
+            msg,
+            sender: sender.to_vec().into(),
+        }
+    }
+}
+
+/// The response of the DS to a `GroupMessage`.
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug, PartialEq, TlsSerialize, TlsDeserialize, TlsSize)]
+#[repr(u8)]
+pub enum GroupMessageResponse {
+    /// The message was accepted and queued for the members of the group.
+    /// For application messages this holds the sequence number the message
+    /// was stamped with.
+    #[tls_codec(discriminant = 1)]
+    Accepted(Option<u64>),
+    /// The handshake message was rejected because the DS accepted a commit for
+    /// the epoch of the message already. This holds the winning commit, which
+    /// the sender has to process before retrying on the new epoch.
+    StaleEpoch(MlsMessageIn),
+}
+
+/// A message the DS delivers to a client.
+/// Application messages are stamped with a sequence number that is
+/// incremented for every application message the DS accepts for the group.
+/// The sequence number is `None` for all other messages.
+#[derive(Debug, Clone, PartialEq, TlsSerialize, TlsDeserialize, TlsSize)]
+pub struct DeliveredMessage {
+    pub sequence_number: Option<u64>,
+    pub msg: MlsMessageIn,
+}
+
+impl DeliveredMessage {
+    /// Create a new `DeliveredMessage` taking an `MlsMessageIn` and its
+    /// sequence number.
+    pub fn new(msg: MlsMessageIn, sequence_number: Option<u64>) -> Self {
         Self {
+            sequence_number,
             msg,
-            recipients: recipients
-                .iter()
-                .map(|r| r.clone().into())
-                .collect::<Vec<TlsByteVecU32>>()
-                .into(),
+        }
+    }
+}
+
+/// A new group that is registered with the DS.
+/// This is an `MLSMessage` containing the `GroupInfo` of the group plus its
+/// ratchet tree. The DS uses it to track the group's state and members.
+/// The DS hands out the latest `GroupInfo` of a group together with its
+/// ratchet tree in the same format to clients joining the group with an
+/// external commit.
+#[derive(Debug, TlsSerialize, TlsDeserialize, TlsSize)]
+pub struct GroupInfoAndTree {
+    pub group_info: MlsMessageIn,
+    pub ratchet_tree: RatchetTreeIn,
+}
+
+impl GroupInfoAndTree {
+    /// Create a new `GroupInfoAndTree` taking an `MlsMessageIn` with a
+    /// `GroupInfo` and the ratchet tree of the group.
+    pub fn new(group_info: MlsMessageIn, ratchet_tree: RatchetTreeIn) -> Self {
+        Self {
+            group_info,
+            ratchet_tree,
         }
     }
 }
@@ -135,6 +336,11 @@ impl tls_codec::Deserialize for ClientInfo {
             String::from_utf8_lossy(TlsByteVecU16::tls_deserialize(bytes)?.as_slice()).into();
         let mut key_packages: Vec<(TlsByteVecU8, KeyPackageIn)> =
             TlsVecU32::<(TlsByteVecU8, KeyPackageIn)>::tls_deserialize(bytes)?.into();
+        if key_packages.is_empty() {
+            return Err(tls_codec::Error::DecodingError(
+                "A client needs at least one key package".to_string(),
+            ));
+        }
         let key_packages = key_packages
             .drain(..)
             .map(|(e1, e2)| (e1.into(), e2))
@@ -145,21 +351,21 @@ impl tls_codec::Deserialize for ClientInfo {
 
 impl tls_codec::Size for GroupMessage {
     fn tls_serialized_len(&self) -> usize {
-        self.msg.tls_serialized_len() + self.recipients.tls_serialized_len()
+        self.msg.tls_serialized_len() + self.sender.tls_serialized_len()
     }
 }
 
 impl tls_codec::Serialize for GroupMessage {
     fn tls_serialize<W: std::io::Write>(&self, writer: &mut W) -> Result<usize, tls_codec::Error> {
         let written = self.msg.tls_serialize(writer)?;
-        self.recipients.tls_serialize(writer).map(|l| l + written)
+        self.sender.tls_serialize(writer).map(|l| l + written)
     }
 }
 
 impl tls_codec::Deserialize for GroupMessage {
     fn tls_deserialize<R: std::io::Read>(bytes: &mut R) -> Result<Self, tls_codec::Error> {
         let msg = MlsMessageIn::tls_deserialize(bytes)?;
-        let recipients = TlsVecU32::<TlsByteVecU32>::tls_deserialize(bytes)?;
-        Ok(Self { msg, recipients })
+        let sender = TlsByteVecU32::tls_deserialize(bytes)?;
+        Ok(Self { msg, sender })
     }
 }
@@ -0,0 +1,137 @@
+//! # Push delivery of messages
+//!
+//! Instead of polling `/recv/{id}`, clients can connect to the WebSocket
+//! endpoint `/ws/{id}` of the DS. The DS pushes all welcomes and messages
+//! queued for the client as [`PushedMessage`]s as they arrive.
+//!
+//! A pushed message stays queued on the DS until the client acknowledges it by
+//! sending its [`DeliveryId`] back. An acknowledgement covers the message and
+//! all messages that were queued before it in the same queue. Unacknowledged
+//! messages are pushed again when the client reconnects.
+//!
+//! The request opening the WebSocket is authenticated like all other requests
+//! (see [`auth`](crate::auth)). [`PushClient`] implements the client side of
+//! the protocol.
+
+use std::net::TcpStream;
+
+use openmls_traits::signatures::Signer;
+use tls_codec::{Deserialize, Serialize, TlsDeserialize, TlsSerialize, TlsSize};
+use tungstenite::{
+    client::IntoClientRequest, http::HeaderValue, stream::MaybeTlsStream, Message, WebSocket,
+};
+
+use crate::{
+    auth::{AuthError, RequestSignature},
+    DeliveredMessage,
+};
+
+/// The ID of a message in one of the queues of a client.
+/// The messages of a queue are numbered consecutively in the order they are
+/// queued in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, TlsSerialize, TlsDeserialize, TlsSize)]
+#[repr(u8)]
+pub enum DeliveryId {
+    /// A message in the queue of welcome messages.
+    #[tls_codec(discriminant = 1)]
+    Welcome(u64),
+    /// A message in the queue of group messages.
+    Message(u64),
+}
+
+/// A message the DS pushes to a client.
+#[derive(Debug, Clone, PartialEq, TlsSerialize, TlsDeserialize, TlsSize)]
+pub struct PushedMessage {
+    pub delivery_id: DeliveryId,
+    pub message: DeliveredMessage,
+}
+
+/// Errors of the [`PushClient`].
+#[derive(Debug)]
+pub enum PushError {
+    /// The request opening the connection couldn't be signed.
+    Auth(AuthError),
+    /// The connection to the DS failed.
+    Connection(String),
+    /// A message couldn't be encoded or decoded.
+    Codec(String),
+    /// The DS closed the connection.
+    Closed,
+}
+
+fn connection_error(e: impl ToString) -> PushError {
+    PushError::Connection(e.to_string())
+}
+
+/// A client receiving the messages the DS pushes over a WebSocket.
+pub struct PushClient {
+    socket: WebSocket<MaybeTlsStream<TcpStream>>,
+}
+
+impl PushClient {
+    /// Connect to the DS at `ds_url`, e.g. `ws://localhost:8080`, as the
+    /// client with the given `client_id`.
+    /// The request is signed with the client's `signer`.
+    pub fn connect(
+        ds_url: &str,
+        client_id: &[u8],
+        signer: &impl Signer,
+    ) -> Result<Self, PushError> {
+        let path = "/ws/".to_string() + &base64::encode_config(client_id, base64::URL_SAFE);
+        let signature = RequestSignature::sign(signer, client_id, "GET", &path, &[])
+            .map_err(PushError::Auth)?;
+
+        let mut request = (ds_url.trim_end_matches('/').to_string() + &path)
+            .into_client_request()
+            .map_err(connection_error)?;
+        for (name, value) in signature.headers() {
+            let value = HeaderValue::from_str(&value).map_err(connection_error)?;
+            request.headers_mut().insert(name, value);
+        }
+        let (socket, _response) = tungstenite::connect(request).map_err(connection_error)?;
+
+        Ok(Self { socket })
+    }
+
+    /// Wait for the next message the DS pushes.
+    /// The message has to be acknowledged with [`PushClient::acknowledge()`]
+    /// once it's processed. Otherwise it's pushed again on the next
+    /// connection.
+    pub fn next_message(&mut self) -> Result<PushedMessage, PushError> {
+        loop {
+            match self.socket.read().map_err(connection_error)? {
+                Message::Binary(bytes) => {
+                    return PushedMessage::tls_deserialize(&mut bytes.as_slice())
+                        .map_err(|e| PushError::Codec(e.to_string()))
+                }
+                Message::Close(_) => return Err(PushError::Closed),
+                // Pings are answered when reading.
+                _ => continue,
+            }
+        }
+    }
+
+    /// Acknowledge the message with the given `delivery_id` and all messages
+    /// queued before it in the same queue, such that the DS deletes them.
+    pub fn acknowledge(&mut self, delivery_id: DeliveryId) -> Result<(), PushError> {
+        let ack = delivery_id
+            .tls_serialize_detached()
+            .map_err(|e| PushError::Codec(e.to_string()))?;
+        self.socket
+            .send(Message::Binary(ack))
+            .map_err(connection_error)
+    }
+
+    /// Close the connection.
+    pub fn close(mut self) -> Result<(), PushError> {
+        self.socket.close(None).map_err(connection_error)?;
+        // Wait for the DS to confirm the close.
+        loop {
+            match self.socket.read() {
+                Ok(_) => continue,
+                Err(tungstenite::Error::ConnectionClosed) => return Ok(()),
+                Err(e) => return Err(connection_error(e)),
+            }
+        }
+    }
+}
@@ -49,3 +49,78 @@ fn test_client_info() {
         .unwrap();
     assert_eq!(client_data.tls_serialize_detached().unwrap(), client_data2);
 }
+
+#[test]
+fn test_request_signature() {
+    let crypto = &OpenMlsRustCrypto::default();
+    let signature_scheme = SignatureScheme::ED25519;
+    let signature_keys = SignatureKeyPair::new(signature_scheme).unwrap();
+    let body = b"request body";
+
+    let signature =
+        auth::RequestSignature::sign(&signature_keys, b"Client1", "POST", "/send/message", body)
+            .unwrap();
+
+    // The signature survives the round trip through the headers.
+    let headers = signature.headers();
+    let decoded = auth::RequestSignature::from_headers(|name| {
+        headers
+            .iter()
+            .find(|(header, _)| *header == name)
+            .map(|(_, value)| value.as_str())
+    })
+    .unwrap();
+    assert_eq!(decoded, signature);
+
+    decoded
+        .verify(
+            crypto.crypto(),
+            signature_scheme,
+            signature_keys.public(),
+            "POST",
+            "/send/message",
+            body,
+        )
+        .unwrap();
+
+    // The signature doesn't cover other requests.
+    assert_eq!(
+        decoded.verify(
+            crypto.crypto(),
+            signature_scheme,
+            signature_keys.public(),
+            "POST",
+            "/send/welcome",
+            body,
+        ),
+        Err(auth::AuthError::InvalidSignature)
+    );
+    assert_eq!(
+        decoded.verify(
+            crypto.crypto(),
+            signature_scheme,
+            signature_keys.public(),
+            "POST",
+            "/send/message",
+            b"another body",
+        ),
+        Err(auth::AuthError::InvalidSignature)
+    );
+
+    // Old requests are rejected.
+    let old_signature = auth::RequestSignature {
+        timestamp: signature.timestamp - 2 * auth::MAX_CLOCK_SKEW,
+        ..signature
+    };
+    assert_eq!(
+        old_signature.verify(
+            crypto.crypto(),
+            signature_scheme,
+            signature_keys.public(),
+            "POST",
+            "/send/message",
+            body,
+        ),
+        Err(auth::AuthError::InvalidTimestamp)
+    );
+}
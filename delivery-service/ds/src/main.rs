@@ -8,19 +8,37 @@
 //!
 //! Because the infrastructure description doesn't give a lot of guidelines on
 //! the design of the DS we take a couple of deliberate design decisions here:
-//! * The DS does not know about groups.
-//! * Clients have to send a list of clients (group members) along with each
-//!   message for the DS to know where to send the message.
+//! * Groups have to be registered with the DS with their `GroupInfo` and
+//!   ratchet tree before messages can be sent to them.
+//! * The DS tracks the public state of each group in a `PublicGroup`. It
+//!   validates every handshake message and only accepts commits for the
+//!   current epoch. Handshake messages therefore have to be sent as
+//!   `PublicMessage`s.
+//! * Messages are delivered to all members of the group except for the
+//!   sender.
 //! * The DS stores and delivers key packages.
+//! * Members upload a signed `GroupInfo` after each commit, which the DS hands
+//!   out together with the ratchet tree to clients joining the group with an
+//!   external commit.
 //!
 //! This is a very basic delivery service that allows to register clients and
 //! send messages to MLS groups.
 //! Note that there are a lot of limitations to this service:
-//! * No persistence layer such that all information gets lost when the process
-//!   shuts down.
-//! * No authentication for clients.
-//! * Key packages can't be updated, changed or deleted at the moment.
-//! * Messages lost in transit are gone.
+//! * The state is kept in memory by default such that all information gets
+//!   lost when the process shuts down. Use `--db` to keep the state in an
+//!   embedded database instead (see [`storage`]).
+//! * Clients authenticate their requests by signing them with the signature
+//!   key of the key package they registered with (see `ds_lib::auth`). There
+//!   is no other way to prove the ownership of an identity, i.e. the first
+//!   client to register an identity owns it.
+//! * Resetting the server requires the admin token the DS is started with
+//!   (`--admin-token`).
+//! * Key packages are validated when they are published, but can't be updated,
+//!   changed or deleted at the moment.
+//! * Messages fetched from `/recv/{id}` are deleted right away, such that
+//!   messages lost in transit are gone. Messages pushed over a WebSocket
+//!   (`/ws/{id}`) are only deleted once the client acknowledges them (see
+//!   `ds_lib::push`).
 //!
 //! **⚠️ DON'T EXPECT ANY SECURITY OR PRIVACY FROM THIS!**
 //!
@@ -30,28 +48,141 @@
 //! The DS returns a list of messages queued for the client in all groups they
 //! are part of.
 
+// Request handlers return early with the HTTP response as the error.
+#![allow(clippy::result_large_err)]
+
 use actix_web::{get, post, web, web::Payload, App, HttpRequest, HttpServer, Responder};
 use clap::Command;
 use futures_util::StreamExt;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Mutex;
-use tls_codec::{Deserialize, Serialize, TlsSliceU16, TlsVecU32};
+use tokio::sync::watch;
+
+use storage::{MemoryStorage, SledStorage, Storage, StorageError};
+use tls_codec::{Deserialize, Serialize, TlsByteVecU8, TlsSliceU16, TlsVecU32};
 
-use ds_lib::*;
+use ds_lib::{
+    auth::{RequestSignature, ADMIN_TOKEN_HEADER},
+    push::DeliveryId,
+    *,
+};
 use openmls::prelude::*;
+use openmls_rust_crypto::OpenMlsRustCrypto;
 
+mod storage;
 #[cfg(test)]
 mod test;
 
 /// The DS state.
-/// It holds a list of clients and their information.
-#[derive(Default, Debug)]
+/// It holds a list of clients and their information as well as the groups in
+/// a [`Storage`].
+#[derive(Debug)]
 pub struct DsData {
-    // (ClientIdentity, ClientInfo)
-    clients: Mutex<HashMap<Vec<u8>, ClientInfo>>,
+    storage: Mutex<Box<dyn Storage>>,
+
+    // The provider used to validate group messages and request signatures.
+    provider: OpenMlsRustCrypto,
+
+    // The token that authorizes administrative requests. These are rejected
+    // if there is none.
+    admin_token: Option<String>,
+
+    // (ClientIdentity, notifier)
+    // Notifies the WebSocket sessions of a client when messages are queued for
+    // it. There is only an entry while the client has an open session.
+    notifications: Mutex<HashMap<Vec<u8>, watch::Sender<()>>>,
+}
+
+impl DsData {
+    /// Create a new `DsData` that keeps its state in the given `storage`.
+    pub fn new(storage: impl Storage + 'static) -> Self {
+        Self {
+            storage: Mutex::new(Box::new(storage)),
+            provider: OpenMlsRustCrypto::default(),
+            admin_token: None,
+            notifications: Mutex::default(),
+        }
+    }
+
+    /// Set the token that authorizes administrative requests.
+    pub fn with_admin_token(mut self, admin_token: String) -> Self {
+        self.admin_token = Some(admin_token);
+        self
+    }
+
+    // Notify the WebSocket sessions of a client that messages were queued for
+    // it.
+    fn notify(&self, client_id: &[u8]) {
+        if let Ok(notifications) = self.notifications.lock() {
+            if let Some(notifier) = notifications.get(client_id) {
+                notifier.send_replace(());
+            }
+        }
+    }
+
+    // Subscribe to the notifications for a client.
+    fn subscribe(&self, client_id: &[u8]) -> Option<watch::Receiver<()>> {
+        let mut notifications = self.notifications.lock().ok()?;
+        Some(
+            notifications
+                .entry(client_id.to_vec())
+                .or_insert_with(|| watch::channel(()).0)
+                .subscribe(),
+        )
+    }
+
+    // Drop the notifier of a client once its last WebSocket session is
+    // closed.
+    fn unsubscribe(&self, client_id: &[u8], receiver: watch::Receiver<()>) {
+        drop(receiver);
+        if let Ok(mut notifications) = self.notifications.lock() {
+            if notifications
+                .get(client_id)
+                .is_some_and(|notifier| notifier.receiver_count() == 0)
+            {
+                notifications.remove(client_id);
+            }
+        }
+    }
+}
 
-    // (group_id, epoch)
-    groups: Mutex<HashMap<Vec<u8>, u64>>,
+impl Default for DsData {
+    fn default() -> Self {
+        Self::new(MemoryStorage::default())
+    }
+}
+
+/// The number of past epochs the DS keeps the accepted commit of.
+/// Members that fell further behind can't catch up with the commits from the
+/// DS anymore.
+const PAST_EPOCH_COMMITS: u64 = 8;
+
+/// The state the DS keeps for a group.
+#[derive(Debug)]
+pub struct DsGroup {
+    // The public state of the group in its current epoch.
+    public_group: PublicGroup,
+
+    // (epoch, commit) for the commits the DS accepted for the group in the
+    // last `PAST_EPOCH_COMMITS` epochs.
+    commits: HashMap<u64, MlsMessageIn>,
+
+    // The sequence number of the next application message.
+    next_sequence_number: u64,
+
+    // The latest group info a member uploaded for the current epoch.
+    group_info: Option<MlsMessageIn>,
+}
+
+impl DsGroup {
+    fn new(public_group: PublicGroup, group_info: MlsMessageIn) -> Self {
+        Self {
+            public_group,
+            commits: HashMap::new(),
+            next_sequence_number: 0,
+            group_info: Some(group_info),
+        }
+    }
 }
 
 macro_rules! unwrap_item {
@@ -72,19 +203,138 @@ macro_rules! unwrap_data {
     };
 }
 
+// === Authentication ===
+
+/// Authenticate a request of a registered client (see `ds_lib::auth`).
+/// Returns the client on success. Otherwise a 401 is returned to the client.
+fn authenticate(
+    req: &HttpRequest,
+    body: &[u8],
+    storage: &dyn Storage,
+    provider: &impl OpenMlsProvider,
+) -> Result<ClientInfo, actix_web::HttpResponse> {
+    let signature = request_signature(req)?;
+    let client = storage
+        .client(&signature.client_id)
+        .map_err(|_| actix_web::HttpResponse::InternalServerError().finish())?
+        .ok_or_else(|| actix_web::HttpResponse::Unauthorized().body("Unknown client."))?;
+    verify_request(req, body, &signature, &client, provider)?;
+    Ok(client)
+}
+
+/// Read the signature of a request from its headers.
+fn request_signature(req: &HttpRequest) -> Result<RequestSignature, actix_web::HttpResponse> {
+    RequestSignature::from_headers(|name| {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+    })
+    .map_err(|e| actix_web::HttpResponse::Unauthorized().body(format!("Unsigned request: {e:?}")))
+}
+
+/// Verify that the request is signed by the given client.
+fn verify_request(
+    req: &HttpRequest,
+    body: &[u8],
+    signature: &RequestSignature,
+    client: &ClientInfo,
+    provider: &impl OpenMlsProvider,
+) -> Result<(), actix_web::HttpResponse> {
+    let (signature_scheme, signature_key) = client
+        .signature_key()
+        .filter(|_| signature.client_id == client.id)
+        .ok_or_else(|| actix_web::HttpResponse::Unauthorized().body("Unknown client."))?;
+    signature
+        .verify(
+            provider.crypto(),
+            signature_scheme,
+            signature_key.as_slice(),
+            req.method().as_str(),
+            req.path(),
+            body,
+        )
+        .map_err(|e| {
+            log::debug!("Rejecting request to {}: {:?}", req.path(), e);
+            actix_web::HttpResponse::Unauthorized().body(format!("Invalid signature: {e:?}"))
+        })
+}
+
+// === Key packages ===
+
+/// Validate the key packages a client publishes.
+/// Each key package has to be valid at the current time, use a ciphersuite
+/// the DS supports and belong to the client, i.e. use the client's identity
+/// and the `signature_key` the client authenticates with. Key packages with a
+/// hash in `known_hashes` are rejected as duplicates.
+/// Returns the key packages together with their hashes on success and the
+/// reason for rejecting them otherwise.
+fn validate_key_packages(
+    client: &ClientInfo,
+    signature_key: &SignaturePublicKey,
+    key_packages: ClientKeyPackages,
+    mut known_hashes: HashSet<Vec<u8>>,
+    provider: &impl OpenMlsProvider,
+) -> Result<Vec<(TlsByteVecU8, KeyPackageIn)>, String> {
+    let mut validated = Vec::new();
+    for (_, key_package_in) in Vec::from(key_packages.0) {
+        let key_package = key_package_in
+            .clone()
+            .validate(
+                provider.crypto(),
+                provider.time(),
+                ProtocolVersion::default(),
+            )
+            .map_err(|e| format!("Invalid key package: {e}"))?;
+        if provider
+            .crypto()
+            .supports(key_package.ciphersuite())
+            .is_err()
+        {
+            return Err(format!(
+                "Unsupported ciphersuite {:?}.",
+                key_package.ciphersuite()
+            ));
+        }
+        let leaf_node = key_package.leaf_node();
+        if leaf_node.credential().identity() != client.id()
+            || leaf_node.signature_key() != signature_key
+        {
+            return Err("The key package belongs to another client.".to_string());
+        }
+        let hash = key_package
+            .hash_ref(provider.crypto())
+            .map_err(|e| format!("Error hashing key package: {e}"))?
+            .as_slice()
+            .to_vec();
+        if !known_hashes.insert(hash.clone()) {
+            return Err("Duplicate key package.".to_string());
+        }
+        validated.push((hash.into(), key_package_in));
+    }
+    Ok(validated)
+}
+
 // === API ===
 
 /// Registering a new client takes a serialised `ClientInfo` object and returns
 /// a simple "Welcome {client name}" on success.
+/// The request has to be signed with the signature key of the first key
+/// package in the `ClientInfo`, otherwise a 401 is returned.
+/// A 400 is returned if a key package is invalid (see
+/// [`validate_key_packages`]).
 /// An HTTP conflict (409) is returned if a client with this name exists
 /// already.
 #[post("/clients/register")]
-async fn register_client(mut body: Payload, data: web::Data<DsData>) -> impl Responder {
+async fn register_client(
+    req: HttpRequest,
+    mut body: Payload,
+    data: web::Data<DsData>,
+) -> impl Responder {
     let mut bytes = web::BytesMut::new();
     while let Some(item) = body.next().await {
         bytes.extend_from_slice(&unwrap_item!(item));
     }
-    let info = match ClientInfo::tls_deserialize(&mut &bytes[..]) {
+    let mut info = match ClientInfo::tls_deserialize(&mut &bytes[..]) {
         Ok(i) => i,
         Err(_) => {
             log::error!("Invalid payload for /clients/register\n{:?}", bytes);
@@ -92,13 +342,33 @@ async fn register_client(mut body: Payload, data: web::Data<DsData>) -> impl Res
         }
     };
     log::debug!("Registering client: {:?}", info);
+    if let Err(response) = request_signature(&req)
+        .and_then(|signature| verify_request(&req, &bytes, &signature, &info, &data.provider))
+    {
+        return response;
+    }
+    // The request was verified with this key, so it exists.
+    let Some((_, signature_key)) = info.signature_key() else {
+        return actix_web::HttpResponse::BadRequest().body("The client has no key package.");
+    };
+    let key_packages = std::mem::take(&mut info.key_packages);
+    match validate_key_packages(
+        &info,
+        &signature_key,
+        key_packages,
+        HashSet::new(),
+        &data.provider,
+    ) {
+        Ok(key_packages) => info.key_packages = ClientKeyPackages(key_packages.into()),
+        Err(e) => return actix_web::HttpResponse::BadRequest().body(e),
+    }
 
-    let mut clients = unwrap_data!(data.clients.lock());
-    let client_name = info.client_name.clone();
-    let old = clients.insert(info.id.clone(), info);
-    if old.is_some() {
+    let mut storage = unwrap_data!(data.storage.lock());
+    if unwrap_data!(storage.client(&info.id)).is_some() {
         return actix_web::HttpResponse::Conflict().finish();
     }
+    unwrap_data!(storage.store_client(&info));
+    let client_name = info.client_name;
 
     actix_web::HttpResponse::Ok().body(format!("Welcome {client_name}!\n"))
 }
@@ -107,14 +377,10 @@ async fn register_client(mut body: Payload, data: web::Data<DsData>) -> impl Res
 #[get("/clients/list")]
 async fn list_clients(_req: HttpRequest, data: web::Data<DsData>) -> impl Responder {
     log::debug!("Listing clients");
-    let clients = unwrap_data!(data.clients.lock());
+    let storage = unwrap_data!(data.storage.lock());
 
     // XXX: we could encode while iterating to be less wasteful.
-    let clients: TlsVecU32<ClientInfo> = clients
-        .values()
-        .cloned()
-        .collect::<Vec<ClientInfo>>()
-        .into();
+    let clients: TlsVecU32<ClientInfo> = unwrap_data!(storage.clients()).into();
     let mut out_bytes = Vec::new();
     if clients.tls_serialize(&mut out_bytes).is_err() {
         return actix_web::HttpResponse::InternalServerError().finish();
@@ -122,23 +388,48 @@ async fn list_clients(_req: HttpRequest, data: web::Data<DsData>) -> impl Respon
     actix_web::HttpResponse::Ok().body(out_bytes)
 }
 
+/// Compares `a` and `b` in constant time, such that the admin token can't be
+/// guessed byte by byte.
+/// Only the lengths are compared in variable time.
+#[inline(never)]
+fn equal_ct(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    let diff = a.iter().zip(b).fold(0u8, |diff, (a, b)| diff | (a ^ b));
+    std::hint::black_box(diff) == 0
+}
+
 /// Resets the server state.
+/// This requires the admin token of the DS, otherwise a 403 is returned.
 #[get("/reset")]
-async fn reset(_req: HttpRequest, data: web::Data<DsData>) -> impl Responder {
+async fn reset(req: HttpRequest, data: web::Data<DsData>) -> impl Responder {
+    let admin_token = req
+        .headers()
+        .get(ADMIN_TOKEN_HEADER)
+        .and_then(|value| value.to_str().ok());
+    match (&data.admin_token, admin_token) {
+        (Some(expected), Some(admin_token))
+            if equal_ct(expected.as_bytes(), admin_token.as_bytes()) => {}
+        _ => return actix_web::HttpResponse::Forbidden().finish(),
+    }
     log::debug!("Resetting server");
-    let mut clients = unwrap_data!(data.clients.lock());
-    let mut groups = unwrap_data!(data.groups.lock());
-    clients.clear();
-    groups.clear();
+    let mut storage = unwrap_data!(data.storage.lock());
+    unwrap_data!(storage.clear());
     actix_web::HttpResponse::Ok().finish()
 }
 
 /// Get the list of key packages for a given client `{id}`.
 /// This returns a serialised vector of `ClientKeyPackages` (see the `ds-lib`
 /// for details).
+/// Only registered clients can get key packages, otherwise a 401 is returned.
 #[get("/clients/key_packages/{id}")]
-async fn get_key_packages(path: web::Path<String>, data: web::Data<DsData>) -> impl Responder {
-    let clients = unwrap_data!(data.clients.lock());
+async fn get_key_packages(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<DsData>,
+) -> impl Responder {
+    let storage = unwrap_data!(data.storage.lock());
 
     let id = match base64::decode_config(path.into_inner(), base64::URL_SAFE) {
         Ok(v) => v,
@@ -146,7 +437,11 @@ async fn get_key_packages(path: web::Path<String>, data: web::Data<DsData>) -> i
     };
     log::debug!("Getting key packages for {:?}", id);
 
-    let client = match clients.get(&id) {
+    if let Err(response) = authenticate(&req, &[], storage.as_ref(), &data.provider) {
+        return response;
+    }
+
+    let client = match unwrap_data!(storage.client(&id)) {
         Some(c) => c,
         None => return actix_web::HttpResponse::NoContent().finish(),
     };
@@ -154,8 +449,15 @@ async fn get_key_packages(path: web::Path<String>, data: web::Data<DsData>) -> i
 }
 
 /// Publish key packages for a given client `{id}`.
+/// This returns the serialised `KeyPackageInventory` of the client after
+/// adding the key packages (see the `ds-lib` for details).
+/// Only the client itself can publish key packages, otherwise a 401 or 403 is
+/// returned.
+/// A 400 is returned if a key package is invalid or was published before
+/// (see [`validate_key_packages`]).
 #[post("/clients/key_packages/{id}")]
 async fn publish_key_packages(
+    req: HttpRequest,
     path: web::Path<String>,
     mut body: Payload,
     data: web::Data<DsData>,
@@ -165,7 +467,7 @@ async fn publish_key_packages(
         bytes.extend_from_slice(&unwrap_item!(item));
     }
 
-    let mut clients = unwrap_data!(data.clients.lock());
+    let mut storage = unwrap_data!(data.storage.lock());
 
     let id = match base64::decode_config(path.into_inner(), base64::URL_SAFE) {
         Ok(v) => v,
@@ -173,10 +475,13 @@ async fn publish_key_packages(
     };
     log::debug!("Add key package for {:?}", id);
 
-    let client = match clients.get_mut(&id) {
-        Some(client) => client,
-        None => return actix_web::HttpResponse::NotFound().finish(),
+    let mut client = match authenticate(&req, &bytes, storage.as_ref(), &data.provider) {
+        Ok(client) => client,
+        Err(response) => return response,
     };
+    if client.id != id {
+        return actix_web::HttpResponse::Forbidden().finish();
+    }
 
     let key_packages = match ClientKeyPackages::tls_deserialize(&mut &bytes[..]) {
         Ok(ckp) => ckp,
@@ -190,21 +495,85 @@ async fn publish_key_packages(
         }
     };
 
-    key_packages
+    let known_hashes = client
+        .key_packages
         .0
         .iter()
-        .map(|(b, kp)| (b.clone(), kp.clone()))
+        .map(|(hash, _)| hash.as_slice().to_vec())
+        .chain(client.reserved_key_pkg_hash.iter().cloned())
+        .collect();
+    // The request was authenticated with this key, so it exists.
+    let Some((_, signature_key)) = client.signature_key() else {
+        return actix_web::HttpResponse::Unauthorized().finish();
+    };
+    let key_packages = match validate_key_packages(
+        &client,
+        &signature_key,
+        key_packages,
+        known_hashes,
+        &data.provider,
+    ) {
+        Ok(key_packages) => key_packages,
+        Err(e) => return actix_web::HttpResponse::BadRequest().body(e),
+    };
+    key_packages
+        .into_iter()
         .for_each(|value| client.key_packages.0.push(value));
+    unwrap_data!(storage.store_client(&client));
 
-    actix_web::HttpResponse::Ok().finish()
+    let inventory = client.inventory(&data.provider);
+    actix_web::HttpResponse::Ok().body(unwrap_data!(inventory.tls_serialize_detached()))
+}
+
+/// Get the number of valid key packages the DS holds for the client `{id}`.
+/// This returns a serialised `KeyPackageInventory` (see the `ds-lib` for
+/// details).
+/// Only the client itself can get its inventory, otherwise a 401 or 403 is
+/// returned.
+#[get("/clients/key_packages/{id}/inventory")]
+async fn key_package_inventory(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<DsData>,
+) -> impl Responder {
+    let storage = unwrap_data!(data.storage.lock());
+
+    let id = match base64::decode_config(path.into_inner(), base64::URL_SAFE) {
+        Ok(v) => v,
+        Err(_) => return actix_web::HttpResponse::BadRequest().finish(),
+    };
+    log::debug!("Getting key package inventory for {:?}", id);
+
+    let client = match authenticate(&req, &[], storage.as_ref(), &data.provider) {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+    if client.id != id {
+        return actix_web::HttpResponse::Forbidden().finish();
+    }
+
+    let inventory = client.inventory(&data.provider);
+    actix_web::HttpResponse::Ok().body(unwrap_data!(inventory.tls_serialize_detached()))
 }
 
 /// Consume a key package for a given client `{id}`.
 /// This returns a serialised `KeyPackage` (see the `ds-lib`
 /// for details).
+/// Key packages are handed out once, except for last-resort key packages,
+/// which are only handed out if the client has no other key package left (see
+/// `ClientInfo::consume_kp`).
+/// Only registered clients can consume key packages, otherwise a 401 is
+/// returned.
 #[get("/clients/key_package/{id}")]
-async fn consume_key_package(path: web::Path<String>, data: web::Data<DsData>) -> impl Responder {
-    let mut clients = unwrap_data!(data.clients.lock());
+async fn consume_key_package(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<DsData>,
+) -> impl Responder {
+    let mut storage = unwrap_data!(data.storage.lock());
+    if let Err(response) = authenticate(&req, &[], storage.as_ref(), &data.provider) {
+        return response;
+    }
 
     let id = match base64::decode_config(path.into_inner(), base64::URL_SAFE) {
         Ok(v) => v,
@@ -212,60 +581,338 @@ async fn consume_key_package(path: web::Path<String>, data: web::Data<DsData>) -
     };
     log::debug!("Consuming key package for {:?}", id);
 
-    let key_package = match clients.get_mut(&id) {
-        Some(c) => match c.consume_kp() {
-            Ok(kp) => kp,
-            Err(e) => {
-                log::debug!("Error consuming key package: {}", e);
-                return actix_web::HttpResponse::NoContent().finish();
-            }
-        },
+    let mut client = match unwrap_data!(storage.client(&id)) {
+        Some(c) => c,
         None => return actix_web::HttpResponse::NoContent().finish(),
     };
+    let key_package = match client.consume_kp(&data.provider) {
+        Ok(kp) => kp,
+        Err(e) => {
+            log::debug!("Error consuming key package: {}", e);
+            return actix_web::HttpResponse::NoContent().finish();
+        }
+    };
+    unwrap_data!(storage.store_client(&client));
 
     actix_web::HttpResponse::Ok().body(unwrap_data!(key_package.tls_serialize_detached()))
 }
 
-/// Send a welcome message to a client.
-/// This takes a serialised `Welcome` message and stores the message for all
-/// clients in the welcome message.
+/// Send a welcome message to the clients it invites.
+/// This takes a serialised `Welcome` message, splits it into one welcome per
+/// new member and stores each of them for the client with the matching
+/// reserved key package (see [`route_welcome`]).
+/// Only registered clients can send welcome messages, otherwise a 401 is
+/// returned.
 #[post("/send/welcome")]
-async fn send_welcome(mut body: Payload, data: web::Data<DsData>) -> impl Responder {
+async fn send_welcome(
+    req: HttpRequest,
+    mut body: Payload,
+    data: web::Data<DsData>,
+) -> impl Responder {
     let mut bytes = web::BytesMut::new();
     while let Some(item) = body.next().await {
         bytes.extend_from_slice(&unwrap_item!(item));
     }
     let welcome_msg = unwrap_data!(MlsMessageIn::tls_deserialize(&mut &bytes[..]));
-    let welcome = welcome_msg.clone().into_welcome().unwrap();
     log::debug!("Storing welcome message: {:?}", welcome_msg);
+    let version = welcome_msg.version();
+    let welcome = match welcome_msg.try_into_welcome() {
+        Ok(welcome) => welcome,
+        Err(_) => return actix_web::HttpResponse::BadRequest().finish(),
+    };
+
+    let mut storage = unwrap_data!(data.storage.lock());
+    if let Err(response) = authenticate(&req, &bytes, storage.as_ref(), &data.provider) {
+        return response;
+    }
+    let recipients = unwrap_data!(route_welcome(storage.as_mut(), welcome, version));
+    if recipients.is_empty() {
+        return actix_web::HttpResponse::NoContent().finish();
+    }
+    recipients
+        .iter()
+        .for_each(|recipient| data.notify(recipient));
+    actix_web::HttpResponse::Ok().finish()
+}
 
-    let mut clients = unwrap_data!(data.clients.lock());
-    for secret in welcome.secrets().iter() {
-        let key_package_hash = &secret.new_member();
-        for (_client_name, client) in clients.iter_mut() {
-            match client
+/// Queue a welcome message for every client it invites.
+/// The welcome is split into one welcome per new member, such that each client
+/// only receives its own encrypted group secrets and doesn't learn who else was
+/// invited. Each welcome is queued for the client that reserved the
+/// corresponding key package.
+/// Returns the IDs of the clients a welcome was queued for.
+fn route_welcome(
+    storage: &mut dyn Storage,
+    welcome: Welcome,
+    version: ProtocolVersion,
+) -> Result<Vec<Vec<u8>>, StorageError> {
+    let mut clients = storage.clients()?;
+    let mut routed = Vec::new();
+    for (key_package_ref, welcome) in welcome.split() {
+        let client = clients.iter_mut().find(|client| {
+            client
                 .reserved_key_pkg_hash
-                .take(key_package_hash.as_slice())
-            {
-                Some(_kp_hash) => {
-                    client.welcome_queue.push(welcome_msg);
-                    return actix_web::HttpResponse::Ok().finish();
+                .contains(key_package_ref.as_slice())
+        });
+        match client {
+            Some(client) => {
+                // A last-resort key package stays reserved as long as it can
+                // be handed out again.
+                if !client.key_packages.0.iter().any(|(hash, key_package)| {
+                    hash.as_slice() == key_package_ref.as_slice()
+                        && KeyPackage::from(key_package.clone()).last_resort()
+                }) {
+                    client
+                        .reserved_key_pkg_hash
+                        .remove(key_package_ref.as_slice());
                 }
-                None => continue,
-            };
+                client
+                    .welcome_queue
+                    .push(MlsMessageOut::from_welcome(welcome, version).into());
+                storage.store_client(client)?;
+                routed.push(client.id.clone());
+            }
+            None => log::debug!(
+                "No client reserved the key package {:?} of the welcome message.",
+                key_package_ref
+            ),
         }
     }
-    actix_web::HttpResponse::NoContent().finish()
+    Ok(routed)
 }
 
-/// Send an MLS message to a set of clients (group).
+/// Register a new group with the DS.
+/// This takes a serialised `GroupInfoAndTree` (see the `ds-lib` for details)
+/// and starts tracking the group's state from the signed `GroupInfo`.
+/// The request has to be signed by a member of the group, otherwise a 401 or
+/// 403 is returned.
+/// An HTTP conflict (409) is returned if a group with this ID exists already.
+#[post("/groups/create")]
+async fn create_group(
+    req: HttpRequest,
+    mut body: Payload,
+    data: web::Data<DsData>,
+) -> impl Responder {
+    let mut bytes = web::BytesMut::new();
+    while let Some(item) = body.next().await {
+        bytes.extend_from_slice(&unwrap_item!(item));
+    }
+    let group_info_and_tree = match GroupInfoAndTree::tls_deserialize(&mut &bytes[..]) {
+        Ok(g) => g,
+        Err(_) => {
+            log::error!("Invalid payload for /groups/create\n{:?}", bytes);
+            return actix_web::HttpResponse::BadRequest().finish();
+        }
+    };
+    let group_info = group_info_and_tree.group_info;
+    let verifiable_group_info = match group_info.clone().try_into_verifiable_group_info() {
+        Ok(group_info) => group_info,
+        Err(e) => {
+            return actix_web::HttpResponse::BadRequest().body(format!("Not a group info: {e}"))
+        }
+    };
+    let public_group = match PublicGroup::from_external(
+        data.provider.crypto(),
+        group_info_and_tree.ratchet_tree,
+        verifiable_group_info,
+        ProposalStore::new(),
+    ) {
+        Ok((public_group, _group_info)) => public_group,
+        Err(e) => {
+            return actix_web::HttpResponse::BadRequest().body(format!("Invalid group info: {e}"))
+        }
+    };
+    log::debug!("Creating group {:?}", public_group.group_id());
+
+    let mut storage = unwrap_data!(data.storage.lock());
+    let client = match authenticate(&req, &bytes, storage.as_ref(), &data.provider) {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+    if !public_group
+        .members()
+        .any(|member| member.credential.identity() == client.id.as_slice())
+    {
+        return actix_web::HttpResponse::Forbidden().body("Not a member of the group.");
+    }
+    if !unwrap_data!(storage.create_group(DsGroup::new(public_group, group_info))) {
+        return actix_web::HttpResponse::Conflict().finish();
+    }
+
+    actix_web::HttpResponse::Ok().finish()
+}
+
+/// Upload the group info of the group `{id}` for the current epoch.
+/// This takes a serialised `MLSMessage` containing a signed `GroupInfo`,
+/// which is handed out to clients joining the group with an external commit
+/// (see [`get_group_info`]). Members upload a new group info after each
+/// commit.
+/// * A 404 is returned if the group is unknown.
+/// * A 400 is returned if the group info is invalid.
+/// * A 409 is returned if the group info doesn't match the epoch of the group
+///   the DS tracks (see [`store_group_info`]).
+/// * A 401 is returned if the request isn't signed by a registered client.
+/// * A 403 is returned if the client is not a member of the group.
+#[post("/groups/{id}/group_info")]
+async fn publish_group_info(
+    req: HttpRequest,
+    path: web::Path<String>,
+    mut body: Payload,
+    data: web::Data<DsData>,
+) -> impl Responder {
+    let mut bytes = web::BytesMut::new();
+    while let Some(item) = body.next().await {
+        bytes.extend_from_slice(&unwrap_item!(item));
+    }
+
+    let group_id = match base64::decode_config(path.into_inner(), base64::URL_SAFE) {
+        Ok(v) => v,
+        Err(_) => return actix_web::HttpResponse::BadRequest().finish(),
+    };
+    log::debug!("Storing group info for group {:?}", group_id);
+    let group_info = match MlsMessageIn::tls_deserialize(&mut &bytes[..]) {
+        Ok(group_info) => group_info,
+        Err(_) => {
+            log::error!(
+                "Invalid payload for /groups/{:?}/group_info\n{:?}",
+                group_id,
+                bytes
+            );
+            return actix_web::HttpResponse::BadRequest().finish();
+        }
+    };
+
+    let mut storage = unwrap_data!(data.storage.lock());
+    let client = match authenticate(&req, &bytes, storage.as_ref(), &data.provider) {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+
+    let mut group_info = Some(group_info);
+    let mut result = None;
+    let known_group = unwrap_data!(storage.update_group(&group_id, &mut |group| {
+        if let Some(group_info) = group_info.take() {
+            result = Some(store_group_info(
+                group,
+                &data.provider,
+                group_info,
+                &client.id,
+            ));
+        }
+    }));
+    if !known_group {
+        return actix_web::HttpResponse::NotFound().body("Unknown group.");
+    }
+    match result {
+        Some(Ok(())) => actix_web::HttpResponse::Ok().finish(),
+        Some(Err(response)) => response,
+        None => actix_web::HttpResponse::InternalServerError().finish(),
+    }
+}
+
+/// Store the group info a member uploaded for the group.
+/// The group info has to be signed by a member of the group and has to match
+/// the state of the group in the epoch the DS tracks, i.e. its group context
+/// and confirmation tag.
+/// On error the HTTP response to return to the client is returned.
+fn store_group_info(
+    group: &mut DsGroup,
+    provider: &impl OpenMlsProvider,
+    group_info: MlsMessageIn,
+    sender: &[u8],
+) -> Result<(), actix_web::HttpResponse> {
+    if !group
+        .public_group
+        .members()
+        .any(|member| member.credential.identity() == sender)
+    {
+        return Err(actix_web::HttpResponse::Forbidden().body("Not a member of the group."));
+    }
+    let verifiable_group_info = group_info
+        .clone()
+        .try_into_verifiable_group_info()
+        .map_err(|e| {
+            actix_web::HttpResponse::BadRequest().body(format!("Not a group info: {e}"))
+        })?;
+    // Verifying the group info against the group's ratchet tree checks the
+    // signature and the tree hash.
+    let (uploaded_group, verified_group_info) = PublicGroup::from_external(
+        provider.crypto(),
+        group.public_group.export_ratchet_tree().into(),
+        verifiable_group_info,
+        ProposalStore::new(),
+    )
+    .map_err(|e| actix_web::HttpResponse::BadRequest().body(format!("Invalid group info: {e}")))?;
+    if verified_group_info.group_context() != group.public_group.group_context()
+        || uploaded_group.confirmation_tag() != group.public_group.confirmation_tag()
+    {
+        return Err(actix_web::HttpResponse::Conflict().body(format!(
+            "The group info doesn't match epoch {} of the group.",
+            group.public_group.group_context().epoch()
+        )));
+    }
+    group.group_info = Some(group_info);
+    Ok(())
+}
+
+/// Get the group info and ratchet tree of the group `{id}` to join it with an
+/// external commit.
+/// This returns a serialised `GroupInfoAndTree` (see the `ds-lib` for
+/// details) with the latest group info a member uploaded for the current
+/// epoch.
+/// Only registered clients can get group infos, otherwise a 401 is returned.
+/// A 404 is returned if the group is unknown or no member uploaded a group
+/// info for the current epoch yet.
+#[get("/groups/{id}/group_info")]
+async fn get_group_info(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<DsData>,
+) -> impl Responder {
+    let storage = unwrap_data!(data.storage.lock());
+    if let Err(response) = authenticate(&req, &[], storage.as_ref(), &data.provider) {
+        return response;
+    }
+
+    let group_id = match base64::decode_config(path.into_inner(), base64::URL_SAFE) {
+        Ok(v) => v,
+        Err(_) => return actix_web::HttpResponse::BadRequest().finish(),
+    };
+    log::debug!("Getting group info for group {:?}", group_id);
+
+    let mut group_info_and_tree = None;
+    let known_group = unwrap_data!(storage.read_group(&group_id, &mut |group| {
+        group_info_and_tree = group.group_info.clone().map(|group_info| {
+            GroupInfoAndTree::new(group_info, group.public_group.export_ratchet_tree().into())
+        });
+    }));
+    if !known_group {
+        return actix_web::HttpResponse::NotFound().body("Unknown group.");
+    }
+    match group_info_and_tree {
+        Some(group_info_and_tree) => actix_web::HttpResponse::Ok()
+            .body(unwrap_data!(group_info_and_tree.tls_serialize_detached())),
+        None => actix_web::HttpResponse::NotFound()
+            .body("There is no group info for the current epoch."),
+    }
+}
+
+/// Send an MLS message to a group.
 /// This takes a serialised `GroupMessage` and stores the message for each
-/// client in the recipient list.
-/// If a handshake message is sent with an epoch smaller or equal to another
-/// handshake message this DS has seen, a 409 is returned and the message is not
-/// processed.
+/// member of the group except for the sender (see [`route_group_message`]).
+/// On success a serialised `GroupMessageResponse::Accepted` is returned.
+/// Handshake messages are validated against the group's state before they are
+/// delivered:
+/// * A 404 is returned if the group is unknown.
+/// * A 409 with a serialised `GroupMessageResponse::StaleEpoch` is returned if
+///   the DS accepted a commit for the epoch of a handshake message already.
+/// * A 400 is returned if the message is invalid.
+/// * A 401 is returned if the request isn't signed by a registered client.
+/// * A 403 is returned if the sender is not a member of the group or doesn't
+///   match the client that signed the request or the authenticated sender of
+///   a handshake message.
 #[post("/send/message")]
-async fn msg_send(mut body: Payload, data: web::Data<DsData>) -> impl Responder {
+async fn msg_send(req: HttpRequest, mut body: Payload, data: web::Data<DsData>) -> impl Responder {
     let mut bytes = web::BytesMut::new();
     while let Some(item) = body.next().await {
         bytes.extend_from_slice(&unwrap_item!(item));
@@ -273,70 +920,209 @@ async fn msg_send(mut body: Payload, data: web::Data<DsData>) -> impl Responder
     let group_msg = unwrap_data!(GroupMessage::tls_deserialize(&mut &bytes[..]));
     log::debug!("Storing group message: {:?}", group_msg);
 
-    let mut clients = unwrap_data!(data.clients.lock());
-    let mut groups = unwrap_data!(data.groups.lock());
-
-    let protocol_msg: ProtocolMessage = group_msg.msg.clone().try_into().unwrap();
-
-    // Reject any handshake message that has an earlier epoch than the one we know
-    // about.
-    // XXX: There's no test for this block in here right now because it's pretty
-    //      painful to test in the current setting. This should get tested through
-    //      the client and maybe later with the MlsGroup API.
-    if protocol_msg.is_handshake_message() {
-        let epoch = protocol_msg.epoch().as_u64();
-        let group_id = protocol_msg.group_id().as_slice();
-        if let Some(&group_epoch) = groups.get(group_id) {
-            if group_epoch > epoch {
-                return actix_web::HttpResponse::Conflict().finish();
-            }
-            // Update server state to the latest epoch.
-            let old_value = groups.insert(group_id.to_vec(), epoch);
-            if old_value.is_none() {
-                return actix_web::HttpResponse::InternalServerError().finish();
-            }
-        } else {
-            // We haven't seen this group_id yet. Store it.
-            let old_value = groups.insert(group_id.to_vec(), epoch);
-            if old_value.is_some() {
-                return actix_web::HttpResponse::InternalServerError().finish();
+    let mut storage = unwrap_data!(data.storage.lock());
+    let client = match authenticate(&req, &bytes, storage.as_ref(), &data.provider) {
+        Ok(client) => client,
+        Err(response) => return response,
+    };
+    if client.id != group_msg.sender.as_slice() {
+        return actix_web::HttpResponse::Forbidden().body("Sender mismatch.");
+    }
+
+    let (recipients, sequence_number) = match route_group_message(
+        storage.as_mut(),
+        &data.provider,
+        group_msg.msg.clone(),
+        group_msg.sender.as_slice(),
+    ) {
+        Ok(routing) => routing,
+        Err(response) => return response,
+    };
+
+    for recipient in recipients {
+        match unwrap_data!(storage.client(&recipient)) {
+            Some(mut client) => {
+                client.msgs.push(DeliveredMessage::new(
+                    group_msg.msg.clone(),
+                    sequence_number,
+                ));
+                unwrap_data!(storage.store_client(&client));
+                data.notify(&client.id);
             }
+            None => log::debug!(
+                "Group member {:?} is not registered with the DS.",
+                recipient
+            ),
         }
     }
+    let response = GroupMessageResponse::Accepted(sequence_number);
+    actix_web::HttpResponse::Ok().body(unwrap_data!(response.tls_serialize_detached()))
+}
 
-    for recipient in group_msg.recipients.iter() {
-        let client = match clients.get_mut(recipient.as_slice()) {
-            Some(client) => client,
-            None => return actix_web::HttpResponse::NotFound().finish(),
-        };
-        client.msgs.push(group_msg.msg.clone());
+/// Validate a message for a group and return the IDs of the clients it has to
+/// be delivered to, together with the sequence number of an application
+/// message.
+/// Handshake messages are processed with the group's `PublicGroup`. Proposals
+/// are stored and commits are merged, such that the DS follows the group
+/// through its epochs. Only the first valid commit for an epoch is accepted;
+/// all later handshake messages for that epoch are rejected with the winning
+/// commit, as long as the epoch is one of the last `PAST_EPOCH_COMMITS`
+/// epochs. The message is delivered to all members of the group in the epoch
+/// the message was sent in, i.e. removed members still receive the commit
+/// removing them.
+/// The DS can't authenticate the sender of an application message and only
+/// checks that the sender is a member of the group.
+/// On error the HTTP response to return to the client is returned.
+fn route_group_message(
+    storage: &mut dyn Storage,
+    provider: &impl OpenMlsProvider,
+    message: MlsMessageIn,
+    sender: &[u8],
+) -> Result<(HashSet<Vec<u8>>, Option<u64>), actix_web::HttpResponse> {
+    let protocol_message: ProtocolMessage = match message.clone().extract() {
+        MlsMessageBodyIn::PublicMessage(m) => m.into(),
+        MlsMessageBodyIn::PrivateMessage(m) => m.into(),
+        _ => {
+            return Err(actix_web::HttpResponse::BadRequest()
+                .body("Only public and private messages can be sent to a group."))
+        }
+    };
+    let group_id = protocol_message.group_id().to_vec();
+
+    let mut protocol_message = Some(protocol_message);
+    let mut routing = None;
+    let known_group = storage
+        .update_group(&group_id, &mut |group| {
+            if let Some(protocol_message) = protocol_message.take() {
+                routing = Some(route_message_in_group(
+                    group,
+                    provider,
+                    message.clone(),
+                    protocol_message,
+                    sender,
+                ));
+            }
+        })
+        .map_err(|_| actix_web::HttpResponse::InternalServerError().finish())?;
+    if !known_group {
+        return Err(actix_web::HttpResponse::NotFound().body("Unknown group."));
     }
-    actix_web::HttpResponse::Ok().finish()
+    routing.unwrap_or_else(|| Err(actix_web::HttpResponse::InternalServerError().finish()))
+}
+
+/// See [`route_group_message`].
+fn route_message_in_group(
+    group: &mut DsGroup,
+    provider: &impl OpenMlsProvider,
+    message: MlsMessageIn,
+    protocol_message: ProtocolMessage,
+    sender: &[u8],
+) -> Result<(HashSet<Vec<u8>>, Option<u64>), actix_web::HttpResponse> {
+    // The members of the group in the epoch of the message.
+    let members: HashSet<Vec<u8>> = group
+        .public_group
+        .members()
+        .map(|member| member.credential.identity().to_vec())
+        .collect();
+
+    let sequence_number = if protocol_message.is_handshake_message() {
+        let epoch = protocol_message.epoch();
+        let group_epoch = group.public_group.group_context().epoch();
+        if epoch < group_epoch {
+            let winning_commit = group.commits.get(&epoch.as_u64()).ok_or_else(|| {
+                actix_web::HttpResponse::BadRequest().body(format!(
+                    "The message is for epoch {epoch}, which is too old or precedes the group's \
+                     registration with the DS."
+                ))
+            })?;
+            let response = GroupMessageResponse::StaleEpoch(winning_commit.clone())
+                .tls_serialize_detached()
+                .map_err(|_| actix_web::HttpResponse::InternalServerError().finish())?;
+            return Err(actix_web::HttpResponse::Conflict().body(response));
+        }
+        if epoch > group_epoch {
+            return Err(actix_web::HttpResponse::BadRequest().body(format!(
+                "The message is for epoch {epoch} but the group is in epoch {group_epoch}."
+            )));
+        }
+
+        let processed_message = group
+            .public_group
+            .process_message(provider, protocol_message)
+            .map_err(|e| {
+                actix_web::HttpResponse::BadRequest()
+                    .body(format!("Invalid handshake message: {e}"))
+            })?;
+        if processed_message.credential().identity() != sender {
+            return Err(actix_web::HttpResponse::Forbidden()
+                .body("The sender doesn't match the sender of the handshake message."));
+        }
+
+        match processed_message.into_content() {
+            ProcessedMessageContent::ProposalMessage(proposal)
+            | ProcessedMessageContent::ExternalJoinProposalMessage(proposal) => {
+                group.public_group.add_proposal(*proposal)
+            }
+            ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+                group.public_group.merge_commit(*staged_commit);
+                group.commits.insert(epoch.as_u64(), message);
+                group
+                    .commits
+                    .retain(|&commit_epoch, _| commit_epoch + PAST_EPOCH_COMMITS > epoch.as_u64());
+                // The group info of the previous epoch is stale now.
+                group.group_info = None;
+            }
+            ProcessedMessageContent::ApplicationMessage(_) => {
+                return Err(actix_web::HttpResponse::InternalServerError().finish())
+            }
+        }
+        None
+    } else {
+        if !members.contains(sender) {
+            return Err(actix_web::HttpResponse::Forbidden()
+                .body("The sender is not a member of the group."));
+        }
+        let sequence_number = group.next_sequence_number;
+        group.next_sequence_number += 1;
+        Some(sequence_number)
+    };
+
+    let recipients = members
+        .into_iter()
+        .filter(|member| member.as_slice() != sender)
+        .collect();
+    Ok((recipients, sequence_number))
 }
 
 /// Receive all messages stored for the client `{id}`.
-/// This returns a serialised vector of `Message`s (see the `ds-lib` for
-/// details) the DS has stored for the given client.
+/// This returns a serialised vector of `DeliveredMessage`s (see the `ds-lib`
+/// for details) the DS has stored for the given client.
 /// The messages are deleted on the DS when sent out.
+/// Only the client itself can receive its messages, otherwise a 401 or 403 is
+/// returned.
 #[get("/recv/{id}")]
-async fn msg_recv(path: web::Path<String>, data: web::Data<DsData>) -> impl Responder {
-    let mut clients = unwrap_data!(data.clients.lock());
+async fn msg_recv(
+    req: HttpRequest,
+    path: web::Path<String>,
+    data: web::Data<DsData>,
+) -> impl Responder {
+    let mut storage = unwrap_data!(data.storage.lock());
 
     let id = match base64::decode_config(path.into_inner(), base64::URL_SAFE) {
         Ok(v) => v,
         Err(_) => return actix_web::HttpResponse::BadRequest().finish(),
     };
     log::debug!("Getting messages for client {:?}", id);
-    let client = match clients.get_mut(&id) {
-        Some(client) => client,
-        None => return actix_web::HttpResponse::NotFound().finish(),
+    let mut client = match authenticate(&req, &[], storage.as_ref(), &data.provider) {
+        Ok(client) => client,
+        Err(response) => return response,
     };
+    if client.id != id {
+        return actix_web::HttpResponse::Forbidden().finish();
+    }
 
-    let mut out: Vec<MlsMessageIn> = Vec::new();
-    let mut welcomes: Vec<MlsMessageIn> = client.welcome_queue.drain(..).collect();
-    out.append(&mut welcomes);
-    let mut msgs: Vec<MlsMessageIn> = client.msgs.drain(..).collect();
-    out.append(&mut msgs);
+    let out = client.take_queued_messages();
+    unwrap_data!(storage.store_client(&client));
 
     match TlsSliceU16(&out).tls_serialize_detached() {
         Ok(out) => actix_web::HttpResponse::Ok().body(out),
@@ -344,6 +1130,132 @@ async fn msg_recv(path: web::Path<String>, data: web::Data<DsData>) -> impl Resp
     }
 }
 
+/// Push the messages queued for the client `{id}` over a WebSocket.
+/// All queued welcomes and messages are pushed as serialised `PushedMessage`s
+/// right away, new ones as they arrive (see the `ds-lib` for details).
+/// The client acknowledges messages by sending back their serialised
+/// `DeliveryId`, which deletes them on the DS. Unacknowledged messages are
+/// pushed again on the next connection.
+/// Only the client itself can open the WebSocket, otherwise a 401 or 403 is
+/// returned.
+#[get("/ws/{id}")]
+async fn push_messages(
+    req: HttpRequest,
+    path: web::Path<String>,
+    body: Payload,
+    data: web::Data<DsData>,
+) -> impl Responder {
+    let id = match base64::decode_config(path.into_inner(), base64::URL_SAFE) {
+        Ok(v) => v,
+        Err(_) => return actix_web::HttpResponse::BadRequest().finish(),
+    };
+    log::debug!("Opening WebSocket for client {:?}", id);
+    {
+        let storage = unwrap_data!(data.storage.lock());
+        let client = match authenticate(&req, &[], storage.as_ref(), &data.provider) {
+            Ok(client) => client,
+            Err(response) => return response,
+        };
+        if client.id != id {
+            return actix_web::HttpResponse::Forbidden().finish();
+        }
+    }
+
+    let (response, session, stream) = match actix_ws::handle(&req, body) {
+        Ok(handshake) => handshake,
+        Err(e) => {
+            return actix_web::HttpResponse::BadRequest().body(format!("Invalid handshake: {e}"))
+        }
+    };
+    actix_web::rt::spawn(push_session(data, id, session, stream));
+    response
+}
+
+/// Push the messages queued for a client until the WebSocket is closed.
+/// See [`push_messages`].
+async fn push_session(
+    data: web::Data<DsData>,
+    client_id: Vec<u8>,
+    mut session: actix_ws::Session,
+    mut stream: actix_ws::MessageStream,
+) {
+    // Subscribe before reading the queues to not miss any notification.
+    let Some(mut notifications) = data.subscribe(&client_id) else {
+        let _ = session.close(None).await;
+        return;
+    };
+    // The delivery IDs of the next welcome and message to push.
+    let mut next_welcome = 0;
+    let mut next_msg = 0;
+    'session: loop {
+        let pushed = match data
+            .storage
+            .lock()
+            .map(|storage| storage.client(&client_id))
+        {
+            Ok(Ok(Some(client))) => client.queued_messages(next_welcome, next_msg),
+            _ => break,
+        };
+        for message in pushed {
+            match message.delivery_id {
+                DeliveryId::Welcome(id) => next_welcome = id + 1,
+                DeliveryId::Message(id) => next_msg = id + 1,
+            }
+            let bytes = match message.tls_serialize_detached() {
+                Ok(bytes) => bytes,
+                Err(_) => break 'session,
+            };
+            if session.binary(bytes).await.is_err() {
+                break 'session;
+            }
+        }
+
+        tokio::select! {
+            notification = notifications.changed() => if notification.is_err() {
+                break;
+            },
+            message = stream.next() => match message {
+                Some(Ok(actix_ws::Message::Binary(bytes))) => {
+                    let delivery_id = match DeliveryId::tls_deserialize(&mut bytes.as_ref()) {
+                        Ok(delivery_id) => delivery_id,
+                        Err(_) => break,
+                    };
+                    if acknowledge(&data, &client_id, delivery_id).is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(actix_ws::Message::Ping(bytes))) => {
+                    if session.pong(&bytes).await.is_err() {
+                        break;
+                    }
+                }
+                Some(Ok(actix_ws::Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => (),
+            },
+        }
+    }
+    log::debug!("Closing WebSocket for client {:?}", client_id);
+    data.unsubscribe(&client_id, notifications);
+    let _ = session.close(None).await;
+}
+
+/// Delete the acknowledged messages of a client.
+fn acknowledge(
+    data: &DsData,
+    client_id: &[u8],
+    delivery_id: DeliveryId,
+) -> Result<(), StorageError> {
+    let mut storage = data
+        .storage
+        .lock()
+        .map_err(|e| StorageError::Backend(e.to_string()))?;
+    if let Some(mut client) = storage.client(client_id)? {
+        client.acknowledge(delivery_id);
+        storage.store_client(&client)?;
+    }
+    Ok(())
+}
+
 // === Main function driving the DS ===
 
 #[actix_web::main]
@@ -362,10 +1274,38 @@ async fn main() -> std::io::Result<()> {
                 .value_name("port")
                 .help("Sets a custom port number"),
         )
+        .arg(
+            clap::Arg::new("db")
+                .long("db")
+                .value_name("path")
+                .help("Keeps the state in a database at the given path"),
+        )
+        .arg(
+            clap::Arg::new("admin-token")
+                .long("admin-token")
+                .value_name("token")
+                .env("DS_ADMIN_TOKEN")
+                .help("Sets the token that authorizes resetting the server"),
+        )
         .get_matches();
 
     // The data this app operates on.
-    let data = web::Data::new(DsData::default());
+    let data = match matches.get_one::<String>("db") {
+        Some(path) => {
+            log::info!("Using database at {}", path);
+            let storage = SledStorage::open(path).map_err(std::io::Error::other)?;
+            DsData::new(storage)
+        }
+        None => DsData::default(),
+    };
+    let data = match matches.get_one::<String>("admin-token") {
+        Some(admin_token) => data.with_admin_token(admin_token.clone()),
+        None => {
+            log::info!("No admin token set, resetting the server is disabled");
+            data
+        }
+    };
+    let data = web::Data::new(data);
 
     // Set default port or use port provided on the command line.
     let port = matches.get_one("port").unwrap_or(&8080u16);
@@ -382,10 +1322,15 @@ async fn main() -> std::io::Result<()> {
             .service(list_clients)
             .service(publish_key_packages)
             .service(get_key_packages)
+            .service(key_package_inventory)
             .service(consume_key_package)
             .service(send_welcome)
+            .service(create_group)
+            .service(publish_group_info)
+            .service(get_group_info)
             .service(msg_recv)
             .service(msg_send)
+            .service(push_messages)
             .service(reset)
     })
     .bind(addr)?
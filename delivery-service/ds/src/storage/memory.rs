@@ -0,0 +1,76 @@
+//! In-memory storage of the DS state.
+
+use std::collections::HashMap;
+
+use ds_lib::ClientInfo;
+
+use super::{Storage, StorageError};
+use crate::DsGroup;
+
+/// A [`Storage`] that keeps the DS state in memory.
+#[derive(Debug, Default)]
+pub struct MemoryStorage {
+    // (ClientIdentity, ClientInfo)
+    clients: HashMap<Vec<u8>, ClientInfo>,
+
+    // (group_id, DsGroup)
+    groups: HashMap<Vec<u8>, DsGroup>,
+}
+
+impl Storage for MemoryStorage {
+    fn client(&self, id: &[u8]) -> Result<Option<ClientInfo>, StorageError> {
+        Ok(self.clients.get(id).cloned())
+    }
+
+    fn clients(&self) -> Result<Vec<ClientInfo>, StorageError> {
+        Ok(self.clients.values().cloned().collect())
+    }
+
+    fn store_client(&mut self, client: &ClientInfo) -> Result<(), StorageError> {
+        self.clients.insert(client.id.clone(), client.clone());
+        Ok(())
+    }
+
+    fn create_group(&mut self, group: DsGroup) -> Result<bool, StorageError> {
+        let group_id = group.public_group.group_id().to_vec();
+        if self.groups.contains_key(&group_id) {
+            return Ok(false);
+        }
+        self.groups.insert(group_id, group);
+        Ok(true)
+    }
+
+    fn read_group(
+        &self,
+        group_id: &[u8],
+        f: &mut dyn FnMut(&DsGroup),
+    ) -> Result<bool, StorageError> {
+        match self.groups.get(group_id) {
+            Some(group) => {
+                f(group);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn update_group(
+        &mut self,
+        group_id: &[u8],
+        f: &mut dyn FnMut(&mut DsGroup),
+    ) -> Result<bool, StorageError> {
+        match self.groups.get_mut(group_id) {
+            Some(group) => {
+                f(group);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn clear(&mut self) -> Result<(), StorageError> {
+        self.clients.clear();
+        self.groups.clear();
+        Ok(())
+    }
+}
@@ -0,0 +1,78 @@
+//! # Storage of the DS state
+//!
+//! The DS keeps its state in a [`Storage`] backend. There are two backends:
+//! * [`MemoryStorage`] keeps everything in memory such that all information
+//!   gets lost when the process shuts down.
+//! * [`SledStorage`] keeps everything in an embedded database on disk such that
+//!   the DS can be restarted without losing its state.
+//!
+//! The DS serialises all accesses to the storage, so backends don't have to
+//! synchronise concurrent changes.
+
+use std::fmt::Debug;
+
+use ds_lib::ClientInfo;
+use thiserror::Error;
+
+use crate::DsGroup;
+
+mod memory;
+mod sled_storage;
+
+pub use memory::MemoryStorage;
+pub use sled_storage::SledStorage;
+
+/// Storage error
+#[derive(Error, Debug)]
+pub enum StorageError {
+    /// A value couldn't be encoded or decoded.
+    #[error("Error encoding or decoding a value: {0}")]
+    Codec(String),
+    /// The storage backend failed.
+    #[error("Storage backend error: {0}")]
+    Backend(String),
+}
+
+/// The storage backend of the DS.
+///
+/// Clients are stored as [`ClientInfo`] records, which include the inventory of
+/// key packages of the client, the key packages it reserved and its queues of
+/// messages and welcomes.
+/// Groups are stored as [`DsGroup`]s, which include the public state of the
+/// group as well as the metadata the DS keeps about the group.
+pub trait Storage: Debug + Send {
+    /// Returns the client with the given `id`, if it's registered.
+    fn client(&self, id: &[u8]) -> Result<Option<ClientInfo>, StorageError>;
+
+    /// Returns all registered clients.
+    fn clients(&self) -> Result<Vec<ClientInfo>, StorageError>;
+
+    /// Stores the client, replacing a client with the same ID.
+    fn store_client(&mut self, client: &ClientInfo) -> Result<(), StorageError>;
+
+    /// Stores a new group.
+    /// Returns `false` and doesn't store the group if a group with the same ID
+    /// exists already.
+    fn create_group(&mut self, group: DsGroup) -> Result<bool, StorageError>;
+
+    /// Calls `f` with the group with the given `group_id` without storing it
+    /// again.
+    /// Returns `false` if there is no group with the given ID.
+    fn read_group(
+        &self,
+        group_id: &[u8],
+        f: &mut dyn FnMut(&DsGroup),
+    ) -> Result<bool, StorageError>;
+
+    /// Calls `f` with the group with the given `group_id` and stores the group
+    /// with all changes `f` made to it afterwards.
+    /// Returns `false` if there is no group with the given ID.
+    fn update_group(
+        &mut self,
+        group_id: &[u8],
+        f: &mut dyn FnMut(&mut DsGroup),
+    ) -> Result<bool, StorageError>;
+
+    /// Deletes all clients and groups.
+    fn clear(&mut self) -> Result<(), StorageError>;
+}
@@ -0,0 +1,260 @@
+//! Storage of the DS state in an embedded [`sled`] database.
+
+use std::{collections::HashSet, fmt::Display, path::Path};
+
+use ds_lib::{ClientInfo, ClientKeyPackages, DeliveredMessage};
+use openmls::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::{Storage, StorageError};
+use crate::DsGroup;
+
+/// A [`Storage`] that keeps the DS state in a [`sled`] database on disk.
+///
+/// Every change is flushed to disk before the call returns.
+#[derive(Debug)]
+pub struct SledStorage {
+    db: sled::Db,
+    // (ClientIdentity, StoredClient)
+    clients: sled::Tree,
+    // (group_id, StoredGroup)
+    groups: sled::Tree,
+}
+
+// The encoding of a `ClientInfo` in the database.
+// MLS messages don't implement serde and are stored TLS encoded.
+#[derive(Serialize, Deserialize)]
+struct StoredClient {
+    client_name: String,
+    id: Vec<u8>,
+    // Clients stored before the signature key was stored authenticate with
+    // the key of their first key package.
+    #[serde(default)]
+    signature_key: Option<(SignatureScheme, SignaturePublicKey)>,
+    key_packages: ClientKeyPackages,
+    reserved_key_pkg_hash: HashSet<Vec<u8>>,
+    msgs: Vec<Vec<u8>>,
+    welcome_queue: Vec<Vec<u8>>,
+    #[serde(default)]
+    delivered_msgs: u64,
+    #[serde(default)]
+    delivered_welcomes: u64,
+}
+
+// The encoding of a `DsGroup` in the database.
+// This is generic over the public group such that it can be encoded from a
+// reference.
+#[derive(Serialize, Deserialize)]
+struct StoredGroup<P> {
+    public_group: P,
+    // (epoch, TLS encoded commit)
+    commits: Vec<(u64, Vec<u8>)>,
+    next_sequence_number: u64,
+    // The TLS encoded group info.
+    #[serde(default)]
+    group_info: Option<Vec<u8>>,
+}
+
+fn codec_error(e: impl Display) -> StorageError {
+    StorageError::Codec(e.to_string())
+}
+
+fn backend_error(e: sled::Error) -> StorageError {
+    StorageError::Backend(e.to_string())
+}
+
+fn encode_client(client: &ClientInfo) -> Result<Vec<u8>, StorageError> {
+    let stored_client = StoredClient {
+        client_name: client.client_name.clone(),
+        id: client.id.clone(),
+        signature_key: client.signature_key(),
+        key_packages: client.key_packages.clone(),
+        reserved_key_pkg_hash: client.reserved_key_pkg_hash.clone(),
+        msgs: client
+            .msgs
+            .iter()
+            .map(|msg| msg.tls_serialize_detached().map_err(codec_error))
+            .collect::<Result<_, _>>()?,
+        welcome_queue: client
+            .welcome_queue
+            .iter()
+            .map(|welcome| welcome.tls_serialize_detached().map_err(codec_error))
+            .collect::<Result<_, _>>()?,
+        delivered_msgs: client.delivered_msgs,
+        delivered_welcomes: client.delivered_welcomes,
+    };
+    serde_json::to_vec(&stored_client).map_err(codec_error)
+}
+
+fn decode_client(bytes: &[u8]) -> Result<ClientInfo, StorageError> {
+    let stored_client: StoredClient = serde_json::from_slice(bytes).map_err(codec_error)?;
+    let signature_key = stored_client.signature_key.or_else(|| {
+        let (_, key_package) = stored_client.key_packages.0.as_slice().first()?;
+        let key_package = KeyPackage::from(key_package.clone());
+        Some((
+            key_package.ciphersuite().signature_algorithm(),
+            key_package.leaf_node().signature_key().clone(),
+        ))
+    });
+    Ok(ClientInfo {
+        client_name: stored_client.client_name,
+        key_packages: stored_client.key_packages,
+        reserved_key_pkg_hash: stored_client.reserved_key_pkg_hash,
+        id: stored_client.id,
+        signature_key,
+        msgs: stored_client
+            .msgs
+            .iter()
+            .map(|msg| DeliveredMessage::tls_deserialize(&mut msg.as_slice()).map_err(codec_error))
+            .collect::<Result<_, _>>()?,
+        welcome_queue: stored_client
+            .welcome_queue
+            .iter()
+            .map(|welcome| {
+                MlsMessageIn::tls_deserialize(&mut welcome.as_slice()).map_err(codec_error)
+            })
+            .collect::<Result<_, _>>()?,
+        delivered_msgs: stored_client.delivered_msgs,
+        delivered_welcomes: stored_client.delivered_welcomes,
+    })
+}
+
+fn encode_group(group: &DsGroup) -> Result<Vec<u8>, StorageError> {
+    let stored_group = StoredGroup {
+        public_group: &group.public_group,
+        commits: group
+            .commits
+            .iter()
+            .map(|(epoch, commit)| {
+                commit
+                    .tls_serialize_detached()
+                    .map(|commit| (*epoch, commit))
+                    .map_err(codec_error)
+            })
+            .collect::<Result<_, _>>()?,
+        next_sequence_number: group.next_sequence_number,
+        group_info: group
+            .group_info
+            .as_ref()
+            .map(|group_info| group_info.tls_serialize_detached().map_err(codec_error))
+            .transpose()?,
+    };
+    serde_json::to_vec(&stored_group).map_err(codec_error)
+}
+
+fn decode_group(bytes: &[u8]) -> Result<DsGroup, StorageError> {
+    let stored_group: StoredGroup<PublicGroup> =
+        serde_json::from_slice(bytes).map_err(codec_error)?;
+    Ok(DsGroup {
+        public_group: stored_group.public_group,
+        commits: stored_group
+            .commits
+            .iter()
+            .map(|(epoch, commit)| {
+                MlsMessageIn::tls_deserialize(&mut commit.as_slice())
+                    .map(|commit| (*epoch, commit))
+                    .map_err(codec_error)
+            })
+            .collect::<Result<_, _>>()?,
+        next_sequence_number: stored_group.next_sequence_number,
+        group_info: stored_group
+            .group_info
+            .map(|group_info| {
+                MlsMessageIn::tls_deserialize(&mut group_info.as_slice()).map_err(codec_error)
+            })
+            .transpose()?,
+    })
+}
+
+impl SledStorage {
+    /// Open the database at `path`. A new database is created if there is none
+    /// at `path` yet.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, StorageError> {
+        let db = sled::open(path).map_err(backend_error)?;
+        let clients = db.open_tree("clients").map_err(backend_error)?;
+        let groups = db.open_tree("groups").map_err(backend_error)?;
+        Ok(Self {
+            db,
+            clients,
+            groups,
+        })
+    }
+
+    fn flush(&self) -> Result<(), StorageError> {
+        self.db.flush().map_err(backend_error)?;
+        Ok(())
+    }
+}
+
+impl Storage for SledStorage {
+    fn client(&self, id: &[u8]) -> Result<Option<ClientInfo>, StorageError> {
+        match self.clients.get(id).map_err(backend_error)? {
+            Some(bytes) => decode_client(&bytes).map(Some),
+            None => Ok(None),
+        }
+    }
+
+    fn clients(&self) -> Result<Vec<ClientInfo>, StorageError> {
+        self.clients
+            .iter()
+            .values()
+            .map(|bytes| decode_client(&bytes.map_err(backend_error)?))
+            .collect()
+    }
+
+    fn store_client(&mut self, client: &ClientInfo) -> Result<(), StorageError> {
+        self.clients
+            .insert(client.id.as_slice(), encode_client(client)?)
+            .map_err(backend_error)?;
+        self.flush()
+    }
+
+    fn create_group(&mut self, group: DsGroup) -> Result<bool, StorageError> {
+        let group_id = group.public_group.group_id().as_slice();
+        if self.groups.contains_key(group_id).map_err(backend_error)? {
+            return Ok(false);
+        }
+        self.groups
+            .insert(group_id, encode_group(&group)?)
+            .map_err(backend_error)?;
+        self.flush()?;
+        Ok(true)
+    }
+
+    fn read_group(
+        &self,
+        group_id: &[u8],
+        f: &mut dyn FnMut(&DsGroup),
+    ) -> Result<bool, StorageError> {
+        match self.groups.get(group_id).map_err(backend_error)? {
+            Some(bytes) => {
+                f(&decode_group(&bytes)?);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    fn update_group(
+        &mut self,
+        group_id: &[u8],
+        f: &mut dyn FnMut(&mut DsGroup),
+    ) -> Result<bool, StorageError> {
+        let mut group = match self.groups.get(group_id).map_err(backend_error)? {
+            Some(bytes) => decode_group(&bytes)?,
+            None => return Ok(false),
+        };
+        f(&mut group);
+        self.groups
+            .insert(group_id, encode_group(&group)?)
+            .map_err(backend_error)?;
+        self.flush()?;
+        Ok(true)
+    }
+
+    fn clear(&mut self) -> Result<(), StorageError> {
+        self.clients.clear().map_err(backend_error)?;
+        self.groups.clear().map_err(backend_error)?;
+        self.flush()
+    }
+}
@@ -1,5 +1,7 @@
 use super::*;
-use actix_web::{body::MessageBody, http::StatusCode, test, web, web::Bytes, App};
+// Only the `test` module, such that `#[test]` isn't the actix test macro.
+use actix_web::test::{self};
+use actix_web::{body::MessageBody, http::StatusCode, web, web::Bytes, App};
 use openmls::prelude::config::CryptoConfig;
 use openmls_basic_credential::SignatureKeyPair;
 use openmls_rust_crypto::OpenMlsRustCrypto;
@@ -42,6 +44,52 @@ fn generate_key_package(
         .unwrap()
 }
 
+// Sign a request of the client with the given `client_id` (see `ds_lib::auth`).
+fn sign_request(
+    request: test::TestRequest,
+    method: &str,
+    path: &str,
+    body: &[u8],
+    client_id: &[u8],
+    signer: &SignatureKeyPair,
+) -> test::TestRequest {
+    let signature = RequestSignature::sign(signer, client_id, method, path, body).unwrap();
+    signature
+        .headers()
+        .into_iter()
+        .fold(request.uri(path), |request, header| {
+            request.insert_header(header)
+        })
+}
+
+fn signed_get(path: &str, client_id: &[u8], signer: &SignatureKeyPair) -> test::TestRequest {
+    sign_request(
+        test::TestRequest::get(),
+        "GET",
+        path,
+        &[],
+        client_id,
+        signer,
+    )
+}
+
+fn signed_post(
+    path: &str,
+    body: &[u8],
+    client_id: &[u8],
+    signer: &SignatureKeyPair,
+) -> test::TestRequest {
+    sign_request(
+        test::TestRequest::post(),
+        "POST",
+        path,
+        body,
+        client_id,
+        signer,
+    )
+    .set_payload(Bytes::copy_from_slice(body))
+}
+
 #[actix_rt::test]
 async fn test_list_clients() {
     let data = web::Data::new(DsData::default());
@@ -96,12 +144,13 @@ async fn test_list_clients() {
         KeyPackageIn::from(client_key_package.clone()),
     )];
     let client_data = ClientInfo::new(client_name.to_string(), client_key_package.clone());
-    let req = test::TestRequest::post()
-        .uri("/clients/register")
-        .set_payload(Bytes::copy_from_slice(
-            &client_data.tls_serialize_detached().unwrap(),
-        ))
-        .to_request();
+    let req = signed_post(
+        "/clients/register",
+        &client_data.tls_serialize_detached().unwrap(),
+        &client_id,
+        &signer,
+    )
+    .to_request();
 
     let response = test::call_service(&app, req).await;
     assert_eq!(response.status(), StatusCode::OK);
@@ -125,9 +174,14 @@ async fn test_list_clients() {
 
     // Get Client1 key packages.
     let path =
-        "/clients/key_packages/".to_owned() + &base64::encode_config(client_id, base64::URL_SAFE);
+        "/clients/key_packages/".to_owned() + &base64::encode_config(&client_id, base64::URL_SAFE);
     let req = test::TestRequest::with_uri(&path).to_request();
 
+    // The request has to be signed by a registered client.
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let req = signed_get(&path, &client_id, &signer).to_request();
     let response = test::call_service(&app, req).await;
     assert_eq!(response.status(), StatusCode::OK);
 
@@ -145,10 +199,99 @@ async fn test_list_clients() {
     assert_eq!(client_key_package, key_packages);
 }
 
+#[actix_rt::test]
+async fn test_register_client() {
+    let data = web::Data::new(DsData::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(data.clone())
+            .service(list_clients)
+            .service(register_client),
+    )
+    .await;
+
+    let client_name = "Client1";
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let crypto = &OpenMlsRustCrypto::default();
+    let (credential_with_key, signer) =
+        generate_credential(client_name.into(), SignatureScheme::from(ciphersuite));
+    let client_id = credential_with_key.credential.identity().to_vec();
+    let key_package = |credential_with_key: &CredentialWithKey, signer: &SignatureKeyPair| {
+        let key_package = generate_key_package(
+            ciphersuite,
+            credential_with_key.clone(),
+            Extensions::empty(),
+            crypto,
+            signer,
+        );
+        (
+            key_package
+                .hash_ref(crypto.crypto())
+                .unwrap()
+                .as_slice()
+                .to_vec(),
+            KeyPackageIn::from(key_package),
+        )
+    };
+    let register = |client_data: &ClientInfo| {
+        signed_post(
+            "/clients/register",
+            &client_data.tls_serialize_detached().unwrap(),
+            &client_id,
+            &signer,
+        )
+        .to_request()
+    };
+
+    // A key package with another signature key is rejected.
+    let (other_credential_with_key, other_signer) =
+        generate_credential(client_name.into(), SignatureScheme::from(ciphersuite));
+    let client_data = ClientInfo::new(
+        client_name.to_string(),
+        vec![
+            key_package(&credential_with_key, &signer),
+            key_package(&other_credential_with_key, &other_signer),
+        ],
+    );
+    let response = test::call_service(&app, register(&client_data)).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // All key packages of the client are registered.
+    let client_data = ClientInfo::new(
+        client_name.to_string(),
+        vec![
+            key_package(&credential_with_key, &signer),
+            key_package(&credential_with_key, &signer),
+        ],
+    );
+    let response = test::call_service(&app, register(&client_data)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let req = test::TestRequest::with_uri("/clients/list").to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let client_info =
+        TlsVecU32::<ClientInfo>::tls_deserialize(&mut bytes.as_ref()).expect("Invalid client list");
+    assert_eq!(
+        client_info.tls_serialize_detached().unwrap(),
+        TlsVecU32::<ClientInfo>::new(vec![client_data.clone()])
+            .tls_serialize_detached()
+            .unwrap()
+    );
+
+    // The client can't be registered twice.
+    let response = test::call_service(&app, register(&client_data)).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+}
+
 #[actix_rt::test]
 async fn test_group() {
     let crypto = &OpenMlsRustCrypto::default();
-    let mls_group_create_config = MlsGroupCreateConfig::default();
+    // The DS can only validate handshake messages that are sent in plaintext.
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(MIXED_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .build();
     let data = web::Data::new(DsData::default());
     let app = test::init_service(
         App::new()
@@ -159,6 +302,7 @@ async fn test_group() {
             .service(consume_key_package)
             .service(publish_key_packages)
             .service(send_welcome)
+            .service(create_group)
             .service(msg_recv)
             .service(msg_send),
     )
@@ -198,12 +342,13 @@ async fn test_group() {
         client_ids.push(credential_with_key.credential.identity().to_vec());
         credentials_with_key.push(credential_with_key);
         signers.push(signer);
-        let req = test::TestRequest::post()
-            .uri("/clients/register")
-            .set_payload(Bytes::copy_from_slice(
-                &client_data.tls_serialize_detached().unwrap(),
-            ))
-            .to_request();
+        let req = signed_post(
+            "/clients/register",
+            &client_data.tls_serialize_detached().unwrap(),
+            client_ids.last().unwrap(),
+            signers.last().unwrap(),
+        )
+        .to_request();
         let response = test::call_service(&app, req).await;
         assert_eq!(response.status(), StatusCode::OK);
     }
@@ -238,12 +383,13 @@ async fn test_group() {
     // Publish key package to the DS for Client2
     let path = "/clients/key_packages/".to_string()
         + &base64::encode_config(&client_ids[1], base64::URL_SAFE);
-    let req = test::TestRequest::post()
-        .uri(&path)
-        .set_payload(Bytes::copy_from_slice(
-            &ckp.tls_serialize_detached().unwrap(),
-        ))
-        .to_request();
+    let req = signed_post(
+        &path,
+        &ckp.tls_serialize_detached().unwrap(),
+        &client_ids[1],
+        &signers[1],
+    )
+    .to_request();
 
     // The response should be empty.
     let response = test::call_service(&app, req).await;
@@ -253,6 +399,7 @@ async fn test_group() {
     let group_id = GroupId::from_slice(b"MyFirstGroup");
 
     let credential_with_key_1 = credentials_with_key.remove(0);
+    let signer_2 = signers.remove(1);
     let signer_1 = signers.remove(0);
     let mut group = MlsGroup::new_with_group_id(
         crypto,
@@ -263,12 +410,30 @@ async fn test_group() {
     )
     .expect("An unexpected error occurred.");
 
+    // Register the group with the DS.
+    let group_info_and_tree = GroupInfoAndTree::new(
+        group
+            .export_group_info(crypto.crypto(), &signer_1, false)
+            .unwrap()
+            .into(),
+        group.export_ratchet_tree().into(),
+    );
+    let req = signed_post(
+        "/groups/create",
+        &group_info_and_tree.tls_serialize_detached().unwrap(),
+        &client_ids[0],
+        &signer_1,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
     // === Client1 invites Client2 ===
     // First we need to reserve the key package for Client2 from the DS.
     let path = "/clients/key_package/".to_owned()
         + &base64::encode_config(&client_ids[1], base64::URL_SAFE);
 
-    let req = test::TestRequest::with_uri(&path).to_request();
+    let req = signed_get(&path, &client_ids[0], &signer_1).to_request();
 
     let response = test::call_service(&app, req).await;
     assert_eq!(response.status(), StatusCode::OK);
@@ -279,40 +444,69 @@ async fn test_group() {
 
     // With the key package we can invite Client2 (create proposal and merge it
     // locally.)
-    let (_out_messages, welcome_msg, _group_info) = group
+    let (out_messages, welcome_msg, _group_info) = group
         .add_members(crypto, &signer_1, &[client2_key_package.into()])
         .expect("Could not add member to group.");
+
+    // Send the commit to the group. The DS validates it and moves to the next
+    // epoch.
+    let commit = GroupMessage::new(out_messages.clone().into(), &client_ids[0])
+        .tls_serialize_detached()
+        .unwrap();
+    let req = signed_post("/send/message", &commit, &client_ids[0], &signer_1).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let response = GroupMessageResponse::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(response, GroupMessageResponse::Accepted(None));
+
+    // Sending the commit again is rejected because the DS accepted a commit
+    // for the epoch already.
+    let req = signed_post("/send/message", &commit, &client_ids[0], &signer_1).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let response = GroupMessageResponse::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(
+        response,
+        GroupMessageResponse::StaleEpoch(out_messages.into())
+    );
+
     group
         .merge_pending_commit(crypto)
         .expect("error merging pending commit");
 
     // Send welcome message for Client2
-    let req = test::TestRequest::post()
-        .uri("/send/welcome")
-        .set_payload(Bytes::copy_from_slice(
-            &welcome_msg.tls_serialize_detached().unwrap(),
-        ))
-        .to_request();
+    let req = signed_post(
+        "/send/welcome",
+        &welcome_msg.tls_serialize_detached().unwrap(),
+        &client_ids[0],
+        &signer_1,
+    )
+    .to_request();
     let response = test::call_service(&app, req).await;
     assert_eq!(response.status(), StatusCode::OK);
 
     // There should be a welcome message now for Client2.
     let path = "/recv/".to_owned() + &base64::encode_config(clients[1], base64::URL_SAFE);
-    let req = test::TestRequest::with_uri(&path).to_request();
+    let req = signed_get(&path, &client_ids[1], &signer_2).to_request();
     let response = test::call_service(&app, req).await;
     assert_eq!(response.status(), StatusCode::OK);
 
     let bytes = response.into_body().try_into_bytes().unwrap();
-    let mut messages = TlsVecU16::<MlsMessageIn>::tls_deserialize(&mut bytes.as_ref())
+    let mut messages = TlsVecU16::<DeliveredMessage>::tls_deserialize(&mut bytes.as_ref())
         .expect("Invalid message list")
         .into_vec();
 
     let welcome_message = messages
         .iter()
-        .position(|m| matches!(m.wire_format(), WireFormat::Welcome))
+        .position(|m| matches!(m.msg.wire_format(), WireFormat::Welcome))
         .expect("Didn't get a welcome message from the server.");
     let welcome_message = messages.remove(welcome_message);
-    assert_eq!(welcome_msg, welcome_message.into());
+    assert_eq!(welcome_message.sequence_number, None);
+    assert_eq!(welcome_msg, welcome_message.msg.into());
     assert!(messages.is_empty());
 
     let mut group_on_client2 = MlsGroup::new_from_welcome(
@@ -332,42 +526,72 @@ async fn test_group() {
 
     // === Client2 sends a message to the group ===
     let client2_message = b"Thanks for adding me Client1.";
-    let signer_2 = signers.remove(0);
     let out_messages = group_on_client2
         .create_message(crypto, &signer_2, client2_message)
         .unwrap();
 
+    // A client can't send messages on behalf of another client.
+    let msg = GroupMessage::new(out_messages.clone().into(), b"Client3");
+    let req = signed_post(
+        "/send/message",
+        &msg.tls_serialize_detached().unwrap(),
+        &client_ids[1],
+        &signer_2,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
     // Send private_message to the group
-    let msg = GroupMessage::new(out_messages.into(), &client_ids);
-    let req = test::TestRequest::post()
-        .uri("/send/message")
-        .set_payload(Bytes::copy_from_slice(
-            &msg.tls_serialize_detached().unwrap(),
-        ))
-        .to_request();
+    let msg = GroupMessage::new(out_messages.into(), &client_ids[1]);
+    let req = signed_post(
+        "/send/message",
+        &msg.tls_serialize_detached().unwrap(),
+        &client_ids[1],
+        &signer_2,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // The application message is stamped with its sequence number.
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let response = GroupMessageResponse::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(response, GroupMessageResponse::Accepted(Some(0)));
+
+    // The message is not delivered back to Client2.
+    let path = "/recv/".to_owned() + &base64::encode_config(clients[1], base64::URL_SAFE);
+    let req = signed_get(&path, &client_ids[1], &signer_2).to_request();
     let response = test::call_service(&app, req).await;
     assert_eq!(response.status(), StatusCode::OK);
 
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let messages = TlsVecU16::<DeliveredMessage>::tls_deserialize(&mut bytes.as_ref())
+        .expect("Invalid message list");
+    assert!(messages.is_empty());
+
     // Client1 retrieves messages from the DS
     let path = "/recv/".to_owned() + &base64::encode_config(clients[0], base64::URL_SAFE);
-    let req = test::TestRequest::with_uri(&path).to_request();
+    let req = signed_get(&path, &client_ids[0], &signer_1).to_request();
     let response = test::call_service(&app, req).await;
     assert_eq!(response.status(), StatusCode::OK);
 
     let bytes = response.into_body().try_into_bytes().unwrap();
-    let mut messages = TlsVecU16::<MlsMessageIn>::tls_deserialize(&mut bytes.as_ref())
+    let mut messages = TlsVecU16::<DeliveredMessage>::tls_deserialize(&mut bytes.as_ref())
         .expect("Invalid message list");
 
     let mls_message = messages
         .iter()
         .position(|m| {
             matches!(
-                m.wire_format(),
+                m.msg.wire_format(),
                 WireFormat::PublicMessage | WireFormat::PrivateMessage
             )
         })
         .expect("Didn't get an MLS application message from the server.");
-    let protocol_message: ProtocolMessage = match messages.remove(mls_message).extract() {
+    let mls_message = messages.remove(mls_message);
+    assert_eq!(mls_message.sequence_number, Some(0));
+    let protocol_message: ProtocolMessage = match mls_message.msg.extract() {
         MlsMessageBodyIn::PrivateMessage(m) => m.into(),
         MlsMessageBodyIn::PublicMessage(m) => m.into(),
         _ => panic!("This is not an MLS message."),
@@ -385,4 +609,1044 @@ async fn test_group() {
     } else {
         panic!("Expected application message");
     }
+
+    // === Client1 and Client2 commit concurrently ===
+    let (client1_commit, _welcome, _group_info) = group
+        .self_update(crypto, &signer_1)
+        .expect("Could not update own key package.");
+    let (client2_commit, _welcome, _group_info) = group_on_client2
+        .self_update(crypto, &signer_2)
+        .expect("Could not update own key package.");
+
+    // The DS accepts the first commit for the epoch.
+    let msg = GroupMessage::new(client1_commit.clone().into(), &client_ids[0]);
+    let req = signed_post(
+        "/send/message",
+        &msg.tls_serialize_detached().unwrap(),
+        &client_ids[0],
+        &signer_1,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    group
+        .merge_pending_commit(crypto)
+        .expect("error merging pending commit");
+
+    // The second commit is rejected with the winning commit.
+    let msg = GroupMessage::new(client2_commit.into(), &client_ids[1]);
+    let req = signed_post(
+        "/send/message",
+        &msg.tls_serialize_detached().unwrap(),
+        &client_ids[1],
+        &signer_2,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let winning_commit = match GroupMessageResponse::tls_deserialize(&mut bytes.as_ref()).unwrap() {
+        GroupMessageResponse::StaleEpoch(commit) => commit,
+        response => panic!("Unexpected response {response:?}"),
+    };
+    assert_eq!(winning_commit, client1_commit.into());
+
+    // Client2 re-bases onto the winning commit.
+    group_on_client2.clear_pending_commit();
+    let protocol_message: ProtocolMessage = match winning_commit.extract() {
+        MlsMessageBodyIn::PublicMessage(m) => m.into(),
+        _ => panic!("This is not a public message."),
+    };
+    let processed_message = group_on_client2
+        .process_message(crypto, protocol_message)
+        .expect("Could not process the winning commit.");
+    let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+        processed_message.into_content()
+    else {
+        panic!("Expected a commit");
+    };
+    group_on_client2
+        .merge_staged_commit(crypto, *staged_commit)
+        .expect("error merging staged commit");
+    assert_eq!(group.epoch(), group_on_client2.epoch());
+}
+
+#[test]
+fn test_route_welcome() {
+    let crypto = &OpenMlsRustCrypto::default();
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let mls_group_create_config = MlsGroupCreateConfig::default();
+
+    // Register Client2 and Client3 with a reserved key package each.
+    let mut storage = MemoryStorage::default();
+    let mut key_packages = Vec::new();
+    for client_name in ["Client2", "Client3"] {
+        let (credential_with_key, signer) = generate_credential(
+            client_name.as_bytes().to_vec(),
+            SignatureScheme::from(ciphersuite),
+        );
+        let key_package = generate_key_package(
+            ciphersuite,
+            credential_with_key,
+            Extensions::empty(),
+            crypto,
+            &signer,
+        );
+        let key_package_ref = key_package.hash_ref(crypto.crypto()).unwrap();
+        let mut client_info = ClientInfo::new(
+            client_name.to_string(),
+            vec![(
+                key_package_ref.as_slice().to_vec(),
+                key_package.clone().into(),
+            )],
+        );
+        client_info
+            .reserved_key_pkg_hash
+            .insert(key_package_ref.as_slice().to_vec());
+        storage.store_client(&client_info).unwrap();
+        key_packages.push(key_package);
+    }
+
+    // Client1 invites both of them with a single commit.
+    let (credential_with_key, signer) =
+        generate_credential(b"Client1".to_vec(), SignatureScheme::from(ciphersuite));
+    let mut group = MlsGroup::new(
+        crypto,
+        &signer,
+        &mls_group_create_config,
+        credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+    let (_out_messages, welcome_msg, _group_info) = group
+        .add_members(crypto, &signer, &key_packages)
+        .expect("Could not add members to group.");
+    let welcome = welcome_msg
+        .into_welcome()
+        .expect("Unexpected message type.");
+
+    assert_eq!(
+        route_welcome(&mut storage, welcome, ProtocolVersion::default())
+            .unwrap()
+            .len(),
+        2
+    );
+
+    // Each client only receives the group secrets encrypted to its own key
+    // package.
+    for mut client in storage.clients().unwrap() {
+        assert!(client.reserved_key_pkg_hash.is_empty());
+        assert_eq!(client.welcome_queue.len(), 1);
+        let welcome = client
+            .welcome_queue
+            .remove(0)
+            .try_into_welcome()
+            .expect("Unexpected message type.");
+        assert_eq!(welcome.secrets().len(), 1);
+        assert_eq!(
+            welcome.secrets()[0].new_member().as_slice(),
+            client.key_packages.0[0].0.as_slice()
+        );
+    }
+}
+
+#[test]
+fn test_past_epoch_commits() {
+    let crypto = &OpenMlsRustCrypto::default();
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(MIXED_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .build();
+    let (credential_with_key, signer) =
+        generate_credential(b"Client1".to_vec(), SignatureScheme::from(ciphersuite));
+    let mut group = MlsGroup::new(
+        crypto,
+        &signer,
+        &mls_group_create_config,
+        credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    // Register the group with the DS.
+    let group_info: MlsMessageIn = group
+        .export_group_info(crypto.crypto(), &signer, false)
+        .unwrap()
+        .into();
+    let (public_group, _group_info) = PublicGroup::from_external(
+        crypto.crypto(),
+        group.export_ratchet_tree().into(),
+        group_info.clone().try_into_verifiable_group_info().unwrap(),
+        ProposalStore::new(),
+    )
+    .unwrap();
+    let mut storage = MemoryStorage::default();
+    assert!(storage
+        .create_group(DsGroup::new(public_group, group_info))
+        .unwrap());
+
+    // The DS accepts a commit for each epoch.
+    let mut commits = Vec::new();
+    for _ in 0..=PAST_EPOCH_COMMITS {
+        let (commit, _welcome, _group_info) = group
+            .self_update(crypto, &signer)
+            .expect("Could not update own key package.");
+        let commit: MlsMessageIn = commit.into();
+        route_group_message(&mut storage, crypto, commit.clone(), b"Client1")
+            .expect("The DS rejected the commit.");
+        group
+            .merge_pending_commit(crypto)
+            .expect("error merging pending commit");
+        commits.push(commit);
+    }
+
+    // Only the commits of the last epochs are kept.
+    let response = route_group_message(&mut storage, crypto, commits[0].clone(), b"Client1")
+        .expect_err("The DS accepted a stale commit.");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    let response = route_group_message(&mut storage, crypto, commits[1].clone(), b"Client1")
+        .expect_err("The DS accepted a stale commit.");
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let response = GroupMessageResponse::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(
+        response,
+        GroupMessageResponse::StaleEpoch(commits[1].clone())
+    );
+}
+
+#[actix_rt::test]
+async fn test_restart_with_sled_storage() {
+    let crypto = &OpenMlsRustCrypto::default();
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(MIXED_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .build();
+    let db_path = std::env::temp_dir().join(format!("ds-test-{}", uuid::Uuid::new_v4()));
+
+    let (credential_with_key_1, signer_1) =
+        generate_credential(b"Client1".to_vec(), SignatureScheme::from(ciphersuite));
+    let (credential_with_key_2, signer_2) =
+        generate_credential(b"Client2".to_vec(), SignatureScheme::from(ciphersuite));
+    let key_package_1 = generate_key_package(
+        ciphersuite,
+        credential_with_key_1.clone(),
+        Extensions::empty(),
+        crypto,
+        &signer_1,
+    );
+    let key_package_2 = generate_key_package(
+        ciphersuite,
+        credential_with_key_2,
+        Extensions::empty(),
+        crypto,
+        &signer_2,
+    );
+
+    let mut group = MlsGroup::new(
+        crypto,
+        &signer_1,
+        &mls_group_create_config,
+        credential_with_key_1,
+    )
+    .expect("An unexpected error occurred.");
+
+    let send_message = |msg: MlsMessageOut| {
+        signed_post(
+            "/send/message",
+            &GroupMessage::new(msg.into(), b"Client1")
+                .tls_serialize_detached()
+                .unwrap(),
+            b"Client1",
+            &signer_1,
+        )
+        .to_request()
+    };
+
+    // === Populate the DS ===
+    let commit = {
+        let data = web::Data::new(DsData::new(SledStorage::open(&db_path).unwrap()));
+        let app = test::init_service(
+            App::new()
+                .app_data(data.clone())
+                .service(register_client)
+                .service(create_group)
+                .service(msg_send),
+        )
+        .await;
+
+        // Register both clients.
+        for (client_name, key_package, signer) in [
+            ("Client1", &key_package_1, &signer_1),
+            ("Client2", &key_package_2, &signer_2),
+        ] {
+            let client_info = ClientInfo::new(
+                client_name.to_string(),
+                vec![(
+                    key_package
+                        .hash_ref(crypto.crypto())
+                        .unwrap()
+                        .as_slice()
+                        .to_vec(),
+                    key_package.clone().into(),
+                )],
+            );
+            let req = signed_post(
+                "/clients/register",
+                &client_info.tls_serialize_detached().unwrap(),
+                client_name.as_bytes(),
+                signer,
+            )
+            .to_request();
+            let response = test::call_service(&app, req).await;
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        // Register the group.
+        let group_info_and_tree = GroupInfoAndTree::new(
+            group
+                .export_group_info(crypto.crypto(), &signer_1, false)
+                .unwrap()
+                .into(),
+            group.export_ratchet_tree().into(),
+        );
+        let req = signed_post(
+            "/groups/create",
+            &group_info_and_tree.tls_serialize_detached().unwrap(),
+            b"Client1",
+            &signer_1,
+        )
+        .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Client1 adds Client2 and sends a message to the group.
+        let (commit, _welcome, _group_info) = group
+            .add_members(crypto, &signer_1, &[key_package_2])
+            .expect("Could not add member to group.");
+        let response = test::call_service(&app, send_message(commit.clone())).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        group
+            .merge_pending_commit(crypto)
+            .expect("error merging pending commit");
+
+        let message = group
+            .create_message(crypto, &signer_1, b"Before the restart")
+            .unwrap();
+        let response = test::call_service(&app, send_message(message)).await;
+        assert_eq!(response.status(), StatusCode::OK);
+
+        commit
+    };
+
+    // === Restart the DS ===
+    let data = web::Data::new(DsData::new(SledStorage::open(&db_path).unwrap()));
+    let app = test::init_service(
+        App::new()
+            .app_data(data.clone())
+            .service(list_clients)
+            .service(get_key_packages)
+            .service(msg_recv)
+            .service(msg_send),
+    )
+    .await;
+
+    // The clients are still registered.
+    let req = test::TestRequest::with_uri("/clients/list").to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let client_list =
+        TlsVecU32::<ClientInfo>::tls_deserialize(&mut bytes.as_ref()).expect("Invalid client list");
+    assert_eq!(client_list.len(), 2);
+
+    // The key packages of Client2 are still published.
+    let path =
+        "/clients/key_packages/".to_owned() + &base64::encode_config(b"Client2", base64::URL_SAFE);
+    let req = signed_get(&path, b"Client2", &signer_2).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let key_packages = ClientKeyPackages::tls_deserialize(&mut bytes.as_ref())
+        .expect("Invalid key package response");
+    assert_eq!(key_packages.0.len(), 1);
+
+    // The message for Client2 is still queued.
+    let path = "/recv/".to_owned() + &base64::encode_config(b"Client2", base64::URL_SAFE);
+    let req = signed_get(&path, b"Client2", &signer_2).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let messages = TlsVecU16::<DeliveredMessage>::tls_deserialize(&mut bytes.as_ref())
+        .expect("Invalid message list");
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].sequence_number, Some(0));
+
+    // The DS still knows the accepted commit and the epoch of the group.
+    let response = test::call_service(&app, send_message(commit.clone())).await;
+    assert_eq!(response.status(), StatusCode::CONFLICT);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let response = GroupMessageResponse::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(response, GroupMessageResponse::StaleEpoch(commit.into()));
+
+    // The sequence numbers continue where they stopped.
+    let message = group
+        .create_message(crypto, &signer_1, b"After the restart")
+        .unwrap();
+    let response = test::call_service(&app, send_message(message)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let response = GroupMessageResponse::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(response, GroupMessageResponse::Accepted(Some(1)));
+
+    drop(app);
+    drop(data);
+    let _ = std::fs::remove_dir_all(db_path);
+}
+
+#[actix_rt::test]
+async fn test_authentication() {
+    let crypto = &OpenMlsRustCrypto::default();
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let data = web::Data::new(DsData::default().with_admin_token("secret".to_string()));
+    let app = test::init_service(
+        App::new()
+            .app_data(data.clone())
+            .service(register_client)
+            .service(list_clients)
+            .service(msg_recv)
+            .service(reset),
+    )
+    .await;
+
+    let mut clients = Vec::new();
+    for client_name in ["Client1", "Client2"] {
+        let (credential_with_key, signer) = generate_credential(
+            client_name.as_bytes().to_vec(),
+            SignatureScheme::from(ciphersuite),
+        );
+        let key_package = generate_key_package(
+            ciphersuite,
+            credential_with_key,
+            Extensions::empty(),
+            crypto,
+            &signer,
+        );
+        let client_info = ClientInfo::new(
+            client_name.to_string(),
+            vec![(
+                key_package
+                    .hash_ref(crypto.crypto())
+                    .unwrap()
+                    .as_slice()
+                    .to_vec(),
+                key_package.into(),
+            )],
+        );
+        clients.push((client_info, signer));
+    }
+    let (client1, signer_1) = &clients[0];
+    let (client2, signer_2) = &clients[1];
+    let client_info = client1.tls_serialize_detached().unwrap();
+
+    // Registering requires a signature with the key of the client's key
+    // package.
+    let req = test::TestRequest::post()
+        .uri("/clients/register")
+        .set_payload(Bytes::copy_from_slice(&client_info))
+        .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let req = signed_post("/clients/register", &client_info, client1.id(), signer_2).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let req = signed_post("/clients/register", &client_info, client1.id(), signer_1).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let req = signed_post(
+        "/clients/register",
+        &client2.tls_serialize_detached().unwrap(),
+        client2.id(),
+        signer_2,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // A signature doesn't cover other requests.
+    let path = "/recv/".to_owned() + &base64::encode_config(client1.id(), base64::URL_SAFE);
+    let req = signed_get("/clients/list", client1.id(), signer_1)
+        .uri(&path)
+        .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    // Clients can only receive their own messages.
+    let req = signed_get(&path, client2.id(), signer_2).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let req = signed_get(&path, client1.id(), signer_1).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Only the admin can reset the server.
+    let req = signed_get("/reset", client1.id(), signer_1).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let req = test::TestRequest::with_uri("/reset")
+        .insert_header((ADMIN_TOKEN_HEADER, "wrong"))
+        .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let req = test::TestRequest::with_uri("/reset")
+        .insert_header((ADMIN_TOKEN_HEADER, "secret"))
+        .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let req = test::TestRequest::with_uri("/clients/list").to_request();
+    let response = test::call_service(&app, req).await;
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let client_list =
+        TlsVecU32::<ClientInfo>::tls_deserialize(&mut bytes.as_ref()).expect("Invalid client list");
+    assert!(client_list.is_empty());
+}
+
+#[actix_rt::test]
+async fn test_key_package_inventory() {
+    let crypto = &OpenMlsRustCrypto::default();
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let data = web::Data::new(DsData::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(data.clone())
+            .service(register_client)
+            .service(publish_key_packages)
+            .service(key_package_inventory)
+            .service(consume_key_package),
+    )
+    .await;
+
+    let (credential_with_key, signer) =
+        generate_credential(b"Client1".to_vec(), SignatureScheme::from(ciphersuite));
+    let client_id = credential_with_key.credential.identity().to_vec();
+    let build_key_package = |last_resort: bool, lifetime: Option<Lifetime>| {
+        let mut builder = KeyPackage::builder();
+        if last_resort {
+            builder = builder
+                .key_package_extensions(Extensions::single(Extension::LastResort(
+                    LastResortExtension::default(),
+                )))
+                .leaf_node_capabilities(Capabilities::new(
+                    None,
+                    None,
+                    Some(&[ExtensionType::LastResort]),
+                    None,
+                    None,
+                ));
+        }
+        if let Some(lifetime) = lifetime {
+            builder = builder.key_package_lifetime(lifetime);
+        }
+        builder
+            .build(
+                CryptoConfig::with_default_version(ciphersuite),
+                crypto,
+                &signer,
+                credential_with_key.clone(),
+            )
+            .unwrap()
+    };
+    let key_packages = |key_packages: &[&KeyPackage]| {
+        ClientKeyPackages(
+            key_packages
+                .iter()
+                .map(|kp| {
+                    (
+                        kp.hash_ref(crypto.crypto())
+                            .unwrap()
+                            .as_slice()
+                            .to_vec()
+                            .into(),
+                        KeyPackageIn::from((*kp).clone()),
+                    )
+                })
+                .collect::<Vec<(TlsByteVecU8, KeyPackageIn)>>()
+                .into(),
+        )
+        .tls_serialize_detached()
+        .unwrap()
+    };
+    let inventory_path = "/clients/key_packages/".to_owned()
+        + &base64::encode_config(&client_id, base64::URL_SAFE)
+        + "/inventory";
+    let publish_path =
+        "/clients/key_packages/".to_owned() + &base64::encode_config(&client_id, base64::URL_SAFE);
+    let consume_path =
+        "/clients/key_package/".to_owned() + &base64::encode_config(&client_id, base64::URL_SAFE);
+
+    // Register with a last-resort key package.
+    let last_resort_key_package = build_key_package(true, None);
+    let client_info = ClientInfo::new(
+        "Client1".to_string(),
+        vec![(
+            last_resort_key_package
+                .hash_ref(crypto.crypto())
+                .unwrap()
+                .as_slice()
+                .to_vec(),
+            last_resort_key_package.clone().into(),
+        )],
+    );
+    let req = signed_post(
+        "/clients/register",
+        &client_info.tls_serialize_detached().unwrap(),
+        &client_id,
+        &signer,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Publish another key package.
+    let key_package = build_key_package(false, None);
+    let req = signed_post(
+        &publish_path,
+        &key_packages(&[&key_package]),
+        &client_id,
+        &signer,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let inventory = KeyPackageInventory::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(
+        inventory,
+        KeyPackageInventory {
+            key_packages: 1,
+            last_resort_key_packages: 1
+        }
+    );
+
+    // Duplicate, expired and foreign key packages are rejected.
+    let expired_key_package = build_key_package(false, Some(Lifetime::new(0)));
+    let (other_credential_with_key, other_signer) =
+        generate_credential(b"Client2".to_vec(), SignatureScheme::from(ciphersuite));
+    let other_key_package = generate_key_package(
+        ciphersuite,
+        other_credential_with_key,
+        Extensions::empty(),
+        crypto,
+        &other_signer,
+    );
+    for invalid_key_package in [&key_package, &expired_key_package, &other_key_package] {
+        let req = signed_post(
+            &publish_path,
+            &key_packages(&[invalid_key_package]),
+            &client_id,
+            &signer,
+        )
+        .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    // The regular key package is handed out first and only once.
+    let req = signed_get(&consume_path, &client_id, &signer).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let consumed = KeyPackageIn::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(consumed, KeyPackageIn::from(key_package));
+
+    // The last-resort key package is handed out afterwards and kept.
+    for _ in 0..2 {
+        let req = signed_get(&consume_path, &client_id, &signer).to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = response.into_body().try_into_bytes().unwrap();
+        let consumed = KeyPackageIn::tls_deserialize(&mut bytes.as_ref()).unwrap();
+        assert_eq!(
+            consumed,
+            KeyPackageIn::from(last_resort_key_package.clone())
+        );
+    }
+
+    let req = signed_get(&inventory_path, &client_id, &signer).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let inventory = KeyPackageInventory::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(
+        inventory,
+        KeyPackageInventory {
+            key_packages: 0,
+            last_resort_key_packages: 1
+        }
+    );
+
+    // The client authenticates with the signature key it registered with,
+    // even if it has no key packages left.
+    {
+        let mut storage = data.storage.lock().unwrap();
+        let mut client_info = storage.client(&client_id).unwrap().unwrap();
+        client_info.key_packages = ClientKeyPackages::default();
+        storage.store_client(&client_info).unwrap();
+    }
+    let req = signed_get(&inventory_path, &client_id, &signer).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[actix_rt::test]
+async fn test_push_messages() {
+    let crypto = &OpenMlsRustCrypto::default();
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let data = web::Data::new(DsData::default());
+    let server = {
+        let data = data.clone();
+        actix_test::start(move || App::new().app_data(data.clone()).service(push_messages))
+    };
+    let ds_url = format!("ws://{}", server.addr());
+
+    // Client1 invites Client2 and sends two application messages.
+    let (credential_with_key_1, signer_1) =
+        generate_credential(b"Client1".to_vec(), SignatureScheme::from(ciphersuite));
+    let (credential_with_key_2, signer_2) =
+        generate_credential(b"Client2".to_vec(), SignatureScheme::from(ciphersuite));
+    let client_id = credential_with_key_2.credential.identity().to_vec();
+    let key_package = generate_key_package(
+        ciphersuite,
+        credential_with_key_2,
+        Extensions::empty(),
+        crypto,
+        &signer_2,
+    );
+    let mut group = MlsGroup::new(
+        crypto,
+        &signer_1,
+        &MlsGroupCreateConfig::default(),
+        credential_with_key_1,
+    )
+    .expect("An unexpected error occurred.");
+    let (_commit, welcome, _group_info) = group
+        .add_members(crypto, &signer_1, std::slice::from_ref(&key_package))
+        .expect("Could not add members to group.");
+    group
+        .merge_pending_commit(crypto)
+        .expect("error merging pending commit");
+    let welcome = DeliveredMessage::new(welcome.into(), None);
+    let messages: Vec<DeliveredMessage> = [b"Hello", b"World"]
+        .iter()
+        .enumerate()
+        .map(|(sequence_number, message)| {
+            DeliveredMessage::new(
+                group
+                    .create_message(crypto, &signer_1, *message)
+                    .unwrap()
+                    .into(),
+                Some(sequence_number as u64),
+            )
+        })
+        .collect();
+
+    // Client2 has the welcome and the first message queued.
+    let mut client_info = ClientInfo::new(
+        "Client2".to_string(),
+        vec![(
+            key_package
+                .hash_ref(crypto.crypto())
+                .unwrap()
+                .as_slice()
+                .to_vec(),
+            key_package.into(),
+        )],
+    );
+    client_info.welcome_queue.push(welcome.msg.clone());
+    client_info.msgs.push(messages[0].clone());
+    data.storage
+        .lock()
+        .unwrap()
+        .store_client(&client_info)
+        .unwrap();
+
+    // Only Client2 can connect.
+    assert!(push::PushClient::connect(&ds_url, &client_id, &signer_1).is_err());
+
+    // The queued messages are pushed right away.
+    let mut push_client = push::PushClient::connect(&ds_url, &client_id, &signer_2).unwrap();
+    let pushed = push_client.next_message().unwrap();
+    assert_eq!(pushed.delivery_id, DeliveryId::Welcome(0));
+    assert_eq!(pushed.message, welcome);
+    let pushed = push_client.next_message().unwrap();
+    assert_eq!(pushed.delivery_id, DeliveryId::Message(0));
+    assert_eq!(pushed.message, messages[0]);
+    push_client.acknowledge(DeliveryId::Message(0)).unwrap();
+    push_client.close().unwrap();
+
+    // Only the unacknowledged welcome is pushed again.
+    let mut push_client = push::PushClient::connect(&ds_url, &client_id, &signer_2).unwrap();
+    let pushed = push_client.next_message().unwrap();
+    assert_eq!(pushed.delivery_id, DeliveryId::Welcome(0));
+    push_client.acknowledge(DeliveryId::Welcome(0)).unwrap();
+
+    // New messages are pushed as they arrive.
+    {
+        let mut storage = data.storage.lock().unwrap();
+        let mut client_info = storage.client(&client_id).unwrap().unwrap();
+        client_info.msgs.push(messages[1].clone());
+        storage.store_client(&client_info).unwrap();
+    }
+    data.notify(&client_id);
+    let pushed = push_client.next_message().unwrap();
+    assert_eq!(pushed.delivery_id, DeliveryId::Message(1));
+    assert_eq!(pushed.message, messages[1]);
+    push_client.acknowledge(DeliveryId::Message(1)).unwrap();
+    push_client.close().unwrap();
+
+    // All messages were acknowledged and are deleted.
+    let client_info = data
+        .storage
+        .lock()
+        .unwrap()
+        .client(&client_id)
+        .unwrap()
+        .unwrap();
+    assert!(client_info.welcome_queue.is_empty());
+    assert!(client_info.msgs.is_empty());
+    assert_eq!(client_info.delivered_welcomes, 1);
+    assert_eq!(client_info.delivered_msgs, 2);
+}
+
+#[test]
+fn test_notifications() {
+    let data = DsData::default();
+    let mut client1_session1 = data.subscribe(b"Client1").unwrap();
+    let client1_session2 = data.subscribe(b"Client1").unwrap();
+    let client2_session = data.subscribe(b"Client2").unwrap();
+
+    // Only the sessions of the client messages are queued for are notified.
+    data.notify(b"Client1");
+    assert!(client1_session1.has_changed().unwrap());
+    assert!(client1_session2.has_changed().unwrap());
+    assert!(!client2_session.has_changed().unwrap());
+
+    // Notifications that weren't seen yet are coalesced.
+    data.notify(b"Client1");
+    client1_session1.mark_unchanged();
+    assert!(!client1_session1.has_changed().unwrap());
+
+    // The notifier of a client is dropped with its last session.
+    data.unsubscribe(b"Client1", client1_session1);
+    assert!(data
+        .notifications
+        .lock()
+        .unwrap()
+        .contains_key(&b"Client1"[..]));
+    data.unsubscribe(b"Client1", client1_session2);
+    assert!(!data
+        .notifications
+        .lock()
+        .unwrap()
+        .contains_key(&b"Client1"[..]));
+    data.unsubscribe(b"Client2", client2_session);
+    assert!(data.notifications.lock().unwrap().is_empty());
+}
+
+#[actix_rt::test]
+async fn test_external_join() {
+    let crypto = &OpenMlsRustCrypto::default();
+    let ciphersuite = Ciphersuite::MLS_128_DHKEMX25519_AES128GCM_SHA256_Ed25519;
+    let data = web::Data::new(DsData::default());
+    let app = test::init_service(
+        App::new()
+            .app_data(data.clone())
+            .service(register_client)
+            .service(create_group)
+            .service(publish_group_info)
+            .service(get_group_info)
+            .service(msg_send)
+            .service(msg_recv),
+    )
+    .await;
+
+    // Register Client1 and Client2.
+    let mut credentials_with_key = Vec::new();
+    let mut signers = Vec::new();
+    for client_name in ["Client1", "Client2"] {
+        let (credential_with_key, signer) = generate_credential(
+            client_name.as_bytes().to_vec(),
+            SignatureScheme::from(ciphersuite),
+        );
+        let key_package = generate_key_package(
+            ciphersuite,
+            credential_with_key.clone(),
+            Extensions::empty(),
+            crypto,
+            &signer,
+        );
+        let client_info = ClientInfo::new(
+            client_name.to_string(),
+            vec![(
+                key_package
+                    .hash_ref(crypto.crypto())
+                    .unwrap()
+                    .as_slice()
+                    .to_vec(),
+                key_package.into(),
+            )],
+        );
+        let req = signed_post(
+            "/clients/register",
+            &client_info.tls_serialize_detached().unwrap(),
+            credential_with_key.credential.identity(),
+            &signer,
+        )
+        .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        credentials_with_key.push(credential_with_key);
+        signers.push(signer);
+    }
+    let client_id_1 = credentials_with_key[0].credential.identity().to_vec();
+    let client_id_2 = credentials_with_key[1].credential.identity().to_vec();
+    let (signer_1, signer_2) = (&signers[0], &signers[1]);
+
+    // Client1 creates a group and registers it with the DS.
+    let mut group = MlsGroup::new_with_group_id(
+        crypto,
+        signer_1,
+        &MlsGroupCreateConfig::builder()
+            .wire_format_policy(MIXED_PLAINTEXT_WIRE_FORMAT_POLICY)
+            .build(),
+        GroupId::from_slice(b"ExternalJoinGroup"),
+        credentials_with_key[0].clone(),
+    )
+    .expect("An unexpected error occurred.");
+    let initial_group_info = group
+        .export_group_info(crypto.crypto(), signer_1, false)
+        .unwrap();
+    let group_info_and_tree = GroupInfoAndTree::new(
+        initial_group_info.clone().into(),
+        group.export_ratchet_tree().into(),
+    );
+    let req = signed_post(
+        "/groups/create",
+        &group_info_and_tree.tls_serialize_detached().unwrap(),
+        &client_id_1,
+        signer_1,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Client2 gets the group info and the ratchet tree of the group.
+    let group_info_path = "/groups/".to_owned()
+        + &base64::encode_config(b"ExternalJoinGroup", base64::URL_SAFE)
+        + "/group_info";
+    let req = signed_get(&group_info_path, &client_id_2, signer_2).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let group_info_and_tree = GroupInfoAndTree::tls_deserialize(&mut bytes.as_ref()).unwrap();
+
+    // Client2 joins the group with an external commit.
+    let (mut group_on_client2, external_commit, _group_info) = MlsGroup::join_by_external_commit(
+        crypto,
+        signer_2,
+        Some(group_info_and_tree.ratchet_tree),
+        group_info_and_tree
+            .group_info
+            .try_into_verifiable_group_info()
+            .unwrap(),
+        &MlsGroupJoinConfig::builder()
+            .wire_format_policy(MIXED_PLAINTEXT_WIRE_FORMAT_POLICY)
+            .build(),
+        &[],
+        credentials_with_key[1].clone(),
+    )
+    .expect("Error joining the group with an external commit.");
+    let req = signed_post(
+        "/send/message",
+        &GroupMessage::new(external_commit.into(), &client_id_2)
+            .tls_serialize_detached()
+            .unwrap(),
+        &client_id_2,
+        signer_2,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    group_on_client2
+        .merge_pending_commit(crypto)
+        .expect("error merging pending commit");
+
+    // The group info of the previous epoch isn't handed out anymore and can't
+    // be uploaded again.
+    let req = signed_get(&group_info_path, &client_id_2, signer_2).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let req = signed_post(
+        &group_info_path,
+        &initial_group_info.tls_serialize_detached().unwrap(),
+        &client_id_1,
+        signer_1,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // Client1 processes the external commit.
+    let path = "/recv/".to_owned() + &base64::encode_config(&client_id_1, base64::URL_SAFE);
+    let req = signed_get(&path, &client_id_1, signer_1).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let messages = TlsVecU16::<DeliveredMessage>::tls_deserialize(&mut bytes.as_ref())
+        .unwrap()
+        .into_vec();
+    assert_eq!(messages.len(), 1);
+    let protocol_message: ProtocolMessage = match messages[0].msg.clone().extract() {
+        MlsMessageBodyIn::PublicMessage(message) => message.into(),
+        _ => panic!("Unexpected message type."),
+    };
+    match group
+        .process_message(crypto, protocol_message)
+        .unwrap()
+        .into_content()
+    {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+            group.merge_staged_commit(crypto, *staged_commit).unwrap()
+        }
+        _ => panic!("Expected a commit."),
+    }
+    assert_eq!(group.members().count(), 2);
+
+    // Client2 uploads the group info for the new epoch.
+    let group_info = group_on_client2
+        .export_group_info(crypto.crypto(), signer_2, false)
+        .unwrap();
+    let req = signed_post(
+        &group_info_path,
+        &group_info.tls_serialize_detached().unwrap(),
+        &client_id_2,
+        signer_2,
+    )
+    .to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let req = signed_get(&group_info_path, &client_id_1, signer_1).to_request();
+    let response = test::call_service(&app, req).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = response.into_body().try_into_bytes().unwrap();
+    let group_info_and_tree = GroupInfoAndTree::tls_deserialize(&mut bytes.as_ref()).unwrap();
+    assert_eq!(group_info_and_tree.group_info, group_info.into());
 }
@@ -1,7 +1,45 @@
 #![no_main]
 use libfuzzer_sys::fuzz_target;
-use openmls::prelude::*;
+use openmls::{
+    framing::errors::{DecodeLimitError, MlsMessageDecodeError},
+    prelude::*,
+};
 
 fuzz_target!(|data: &[u8]| {
-    let _ = MlsMessageIn::tls_deserialize_exact(data);
+    let default_result = MlsMessageIn::tls_deserialize_exact(data);
+
+    let limits = DecodeLimits::default()
+        .with_max_message_size(1 << 12)
+        .with_max_tree_size(8)
+        .with_max_proposals(4)
+        .with_max_extensions(4)
+        .with_max_welcome_secrets(4);
+    let no_limits = DecodeLimits::default()
+        .with_max_message_size(usize::MAX)
+        .with_max_tree_size(u32::MAX)
+        .with_max_proposals(usize::MAX)
+        .with_max_extensions(usize::MAX)
+        .with_max_welcome_secrets(usize::MAX);
+
+    match MlsMessageIn::tls_deserialize_with_limits(data, &limits) {
+        Ok(message) => {
+            assert!(data.len() <= limits.max_message_size());
+            // Everything that is accepted under stricter limits is also
+            // accepted under the default limits.
+            assert_eq!(default_result.as_ref().ok(), Some(&message));
+            if let MlsMessageBodyIn::Welcome(welcome) = message.extract() {
+                assert!(welcome.secrets().len() <= limits.max_welcome_secrets());
+            }
+        }
+        Err(MlsMessageDecodeError::LimitExceeded(DecodeLimitError::MessageTooLarge)) => {
+            assert!(data.len() > limits.max_message_size());
+        }
+        Err(MlsMessageDecodeError::LimitExceeded(_)) => {
+            // The message is well-formed and only exceeds the limits.
+            assert!(MlsMessageIn::tls_deserialize_with_limits(data, &no_limits).is_ok());
+        }
+        Err(MlsMessageDecodeError::Codec(_)) => {
+            assert!(default_result.is_err());
+        }
+    }
 });
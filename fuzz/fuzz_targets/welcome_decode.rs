@@ -5,4 +5,17 @@ use openmls::prelude::*;
 
 fuzz_target!(|data: &[u8]| {
     let _ = Welcome::tls_deserialize(&mut &data[..]);
+
+    // Welcome messages are wrapped in an `MlsMessageIn` on the wire.
+    let mut message = ProtocolVersion::Mls10.tls_serialize_detached().unwrap();
+    message.extend_from_slice(&WireFormat::Welcome.tls_serialize_detached().unwrap());
+    message.extend_from_slice(data);
+
+    let limits = DecodeLimits::default().with_max_welcome_secrets(2);
+    if let Ok(message) = MlsMessageIn::tls_deserialize_with_limits(&message, &limits) {
+        let welcome = message
+            .try_into_welcome()
+            .expect("an MlsMessageIn with a welcome wire format contains a welcome");
+        assert!(welcome.secrets().len() <= limits.max_welcome_secrets());
+    }
 });
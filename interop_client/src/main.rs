@@ -15,13 +15,17 @@ use mls_interop_proto::mls_client;
 use openmls::{
     ciphersuite::HpkePrivateKey,
     credentials::{Credential, CredentialType, CredentialWithKey},
+    extensions::{Extension, Extensions, ExternalSender, SenderExtensionIndex},
     framing::{MlsMessageBodyIn, MlsMessageIn, MlsMessageOut, ProcessedMessageContent},
     group::{
-        GroupEpoch, GroupId, MlsGroup, MlsGroupCreateConfig, MlsGroupJoinConfig, WireFormatPolicy,
-        PURE_CIPHERTEXT_WIRE_FORMAT_POLICY, PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
+        GroupEpoch, GroupId, MlsGroup, MlsGroupCreateConfig, MlsGroupJoinConfig, ProposalStore,
+        WireFormatPolicy, PURE_CIPHERTEXT_WIRE_FORMAT_POLICY, PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
     },
     key_packages::KeyPackage,
-    prelude::{config::CryptoConfig, Capabilities, ExtensionType, SenderRatchetConfiguration},
+    messages::{external_proposals::ExternalProposal, proposals::ReInitProposal},
+    prelude::{
+        config::CryptoConfig, Capabilities, ExtensionType, PublicGroup, SenderRatchetConfiguration,
+    },
     schedule::{psk::ResumptionPskUsage, ExternalPsk, PreSharedKeyId, Psk},
     treesync::{
         test_utils::{read_keys_from_key_store, write_keys_from_key_store},
@@ -37,7 +41,7 @@ use openmls_traits::{
     types::{Ciphersuite, HpkeKeyPair},
     OpenMlsProvider,
 };
-use tls_codec::{Deserialize, Serialize};
+use tls_codec::{Deserialize, Serialize, VLBytes};
 use tonic::{async_trait, transport::Server, Code, Request, Response, Status};
 use tracing::{debug, error, info, instrument, trace, Span};
 use tracing_subscriber::EnvFilter;
@@ -72,6 +76,13 @@ type PendingState = (
     OpenMlsRustCrypto,
 );
 
+/// An external signer, i.e. a party outside of any group that can send
+/// proposals to groups that list it in their `ExternalSendersExtension`.
+pub struct ExternalSigner {
+    external_sender: ExternalSender,
+    signature_keys: SignatureKeyPair,
+}
+
 /// This is the main state struct of the interop client. It keeps track of the
 /// individual MLS clients, as well as pending key packages that it was told to
 /// create. It also contains a transaction id map, that maps the `u32`
@@ -80,6 +91,7 @@ pub struct MlsClientImpl {
     groups: Mutex<Vec<InteropGroup>>,
     pending_state: Mutex<HashMap<Vec<u8>, PendingState>>,
     transaction_id_map: Mutex<HashMap<u32, Vec<u8>>>, // Indirection, linking to pending key packages
+    external_signers: Mutex<Vec<ExternalSigner>>,
 }
 
 impl MlsClientImpl {
@@ -89,6 +101,7 @@ impl MlsClientImpl {
             groups: Mutex::new(Vec::new()),
             pending_state: Mutex::new(HashMap::new()),
             transaction_id_map: Mutex::new(HashMap::new()),
+            external_signers: Mutex::new(Vec::new()),
         }
     }
 }
@@ -160,6 +173,23 @@ fn ratchet_tree_from_config(bytes: Vec<u8>) -> Option<RatchetTreeIn> {
     }
 }
 
+// A helper function translating the extensions in the protobuf to OpenMLS'
+// Extensions
+fn extensions_from_proto(extensions: &[mls_client::Extension]) -> Result<Extensions, Status> {
+    let extensions = extensions
+        .iter()
+        .map(|extension| {
+            let mut bytes = (extension.extension_type as u16).tls_serialize_detached()?;
+            bytes.append(
+                &mut VLBytes::new(extension.extension_data.clone()).tls_serialize_detached()?,
+            );
+            Extension::tls_deserialize_exact(bytes)
+        })
+        .collect::<Result<Vec<Extension>, tls_codec::Error>>()
+        .map_err(|_| Status::invalid_argument("Invalid extension"))?;
+    Extensions::from_vec(extensions).map_err(into_status)
+}
+
 fn bytes_to_string<B>(bytes: B) -> String
 where
     B: AsRef<[u8]>,
@@ -1477,11 +1507,38 @@ impl MlsClient for MlsClientImpl {
         todo!()
     }
 
+    #[instrument(skip_all)]
     async fn create_external_signer(
         &self,
-        _: Request<CreateExternalSignerRequest>,
+        request: Request<CreateExternalSignerRequest>,
     ) -> Result<Response<CreateExternalSignerResponse>, Status> {
-        todo!()
+        let request = request.get_ref();
+        info!(?request, "Request");
+
+        let ciphersuite = *to_ciphersuite(request.cipher_suite)?;
+        let credential = Credential::new(request.identity.clone(), CredentialType::Basic)
+            .map_err(into_status)?;
+        let signature_keys = SignatureKeyPair::new(ciphersuite.signature_algorithm())
+            .map_err(|_| Status::internal("failed to generate signature keys"))?;
+        let external_sender = ExternalSender::new(signature_keys.public().into(), credential);
+        let external_sender_bytes = external_sender
+            .tls_serialize_detached()
+            .map_err(|_| Status::aborted("failed to serialize external sender"))?;
+
+        let mut external_signers = self.external_signers.lock().unwrap();
+        let signer_id = external_signers.len() as u32;
+        external_signers.push(ExternalSigner {
+            external_sender,
+            signature_keys,
+        });
+
+        let response = CreateExternalSignerResponse {
+            signer_id,
+            external_sender: external_sender_bytes,
+        };
+
+        info!(?response, "Response");
+        Ok(Response::new(response))
     }
 
     async fn add_external_signer(
@@ -1491,11 +1548,118 @@ impl MlsClient for MlsClientImpl {
         todo!()
     }
 
+    #[instrument(skip_all)]
     async fn external_signer_proposal(
         &self,
-        _: Request<ExternalSignerProposalRequest>,
+        request: Request<ExternalSignerProposalRequest>,
     ) -> Result<Response<ProposalResponse>, Status> {
-        todo!()
+        let request = request.get_ref();
+        info!(?request, "Request");
+
+        let external_signers = self.external_signers.lock().unwrap();
+        let external_signer = external_signers
+            .get(request.signer_id as usize)
+            .ok_or_else(|| Status::new(Code::InvalidArgument, "unknown signer_id"))?;
+
+        let verifiable_group_info =
+            match MlsMessageIn::tls_deserialize(&mut request.group_info.as_slice())
+                .map_err(|_| Status::aborted("failed to deserialize group info"))?
+                .extract()
+            {
+                MlsMessageBodyIn::GroupInfo(verifiable_group_info) => verifiable_group_info,
+                _ => return Err(Status::invalid_argument("Message was not a group info")),
+            };
+        let ratchet_tree = ratchet_tree_from_config(request.ratchet_tree.clone())
+            .ok_or(Status::invalid_argument("Missing ratchet tree"))?;
+
+        // The external signer only needs the public state of the group to
+        // build the proposal.
+        let provider = OpenMlsRustCrypto::default();
+        let (public_group, _group_info) = PublicGroup::from_external(
+            provider.crypto(),
+            ratchet_tree,
+            verifiable_group_info,
+            ProposalStore::new(),
+        )
+        .map_err(into_status)?;
+        let group_id = public_group.group_id().clone();
+        let epoch = public_group.group_context().epoch();
+        let sender_index = public_group
+            .group_context()
+            .extensions()
+            .external_senders()
+            .and_then(|external_senders| {
+                external_senders
+                    .iter()
+                    .position(|external_sender| external_sender == &external_signer.external_sender)
+            })
+            .map(|index| SenderExtensionIndex::new(index as u32))
+            .ok_or(Status::invalid_argument(
+                "Signer is not an external sender of the group",
+            ))?;
+        let signer = &external_signer.signature_keys;
+
+        let description = request
+            .description
+            .as_ref()
+            .ok_or(Status::invalid_argument("Missing proposal description"))?;
+        let proposal_type = String::from_utf8_lossy(&description.proposal_type).to_string();
+        trace!(r#type = proposal_type, "Creating external proposal.");
+
+        let proposal = match proposal_type.as_ref() {
+            "add" => {
+                let key_package =
+                    MlsMessageIn::tls_deserialize_exact(&mut description.key_package.clone())
+                        .map_err(|_| Status::invalid_argument("Invalid key package"))?
+                        .into_keypackage()
+                        .ok_or(Status::invalid_argument("Message was not a key package"))?;
+
+                ExternalProposal::new_add(key_package, group_id, epoch, signer, sender_index)
+                    .map_err(into_status)?
+            }
+            "remove" => {
+                let removed = public_group
+                    .members()
+                    .find(|member| member.credential.identity() == description.removed_id)
+                    .map(|member| member.index)
+                    .ok_or(Status::invalid_argument("Unknown member"))?;
+
+                ExternalProposal::new_remove(removed, group_id, epoch, signer, sender_index)
+                    .map_err(into_status)?
+            }
+            "reinit" => {
+                let reinit = ReInitProposal::new(
+                    GroupId::from_slice(&description.group_id),
+                    ProtocolVersion::Mls10,
+                    *to_ciphersuite(description.cipher_suite)?,
+                    extensions_from_proto(&description.extensions)?,
+                );
+
+                ExternalProposal::new_reinit(reinit, group_id, epoch, signer, sender_index)
+                    .map_err(into_status)?
+            }
+            "groupContextExtensions" => ExternalProposal::new_group_context_extensions(
+                extensions_from_proto(&description.extensions)?,
+                group_id,
+                epoch,
+                signer,
+                sender_index,
+            )
+            .map_err(into_status)?,
+            "externalPSK" | "resumptionPSK" => {
+                return Err(Status::unimplemented(
+                    "PSK proposals from external senders are not supported",
+                ))
+            }
+            _ => return Err(Status::invalid_argument("Invalid proposal type")),
+        };
+
+        let proposal = proposal.to_bytes().unwrap();
+
+        let response = ProposalResponse { proposal };
+
+        info!(?response, "Response");
+        Ok(Response::new(response))
     }
 
     async fn free(&self, _request: Request<FreeRequest>) -> Result<Response<FreeResponse>, Status> {
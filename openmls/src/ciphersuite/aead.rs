@@ -1,15 +1,18 @@
 use tls_codec::SecretVLBytes;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use super::*;
 
 /// The default NONCE size in bytes.
 pub(crate) const NONCE_BYTES: usize = 12;
 
-/// AEAD keys holding the plain key value and the AEAD algorithm type.
-#[derive(Serialize, Deserialize)]
-#[cfg_attr(any(feature = "test-utils", test), derive(Clone, PartialEq, Eq))]
+/// AEAD keys holding the plain key value and the AEAD algorithm type. The key
+/// value is zeroized when the key is dropped.
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[cfg_attr(any(feature = "test-utils", test), derive(Clone))]
 #[cfg_attr(feature = "crypto-debug", derive(Debug))]
 pub struct AeadKey {
+    #[zeroize(skip)]
     aead_mode: AeadType,
     value: SecretVLBytes,
 }
@@ -24,8 +27,9 @@ impl core::fmt::Debug for AeadKey {
     }
 }
 
-/// AEAD Nonce
-#[derive(Clone, PartialEq, Serialize, Deserialize)]
+/// AEAD Nonce. The nonce is zeroized when it is dropped.
+#[derive(Serialize, Deserialize, Zeroize, ZeroizeOnDrop)]
+#[cfg_attr(any(feature = "test-utils", test), derive(Clone))]
 #[cfg_attr(feature = "crypto-debug", derive(Debug))]
 pub(crate) struct AeadNonce([u8; NONCE_BYTES]);
 
@@ -36,14 +40,47 @@ impl core::fmt::Debug for AeadNonce {
     }
 }
 
+impl ConstantTimeEq for AeadKey {
+    fn ct_eq(&self, other: &Self) -> bool {
+        self.aead_mode == other.aead_mode && self.value.as_slice().ct_eq(other.value.as_slice())
+    }
+}
+
+#[cfg(any(feature = "test-utils", test))]
+impl PartialEq for AeadKey {
+    // Constant time comparison.
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
+#[cfg(any(feature = "test-utils", test))]
+impl Eq for AeadKey {}
+
+impl ConstantTimeEq for AeadNonce {
+    fn ct_eq(&self, other: &Self) -> bool {
+        self.0.as_slice().ct_eq(other.0.as_slice())
+    }
+}
+
+#[cfg(any(feature = "test-utils", test))]
+impl PartialEq for AeadNonce {
+    // Constant time comparison.
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
 impl AeadKey {
     /// Create an `AeadKey` from a `Secret`. TODO: This function should
     /// disappear when tackling issue #103.
     pub(crate) fn from_secret(secret: Secret) -> Self {
         log::trace!("AeadKey::from_secret with {}", secret.ciphersuite);
+        // The `secret` is zeroized when it is dropped at the end of this
+        // function.
         AeadKey {
             aead_mode: secret.ciphersuite.aead_algorithm(),
-            value: secret.value,
+            value: secret.value.as_slice().into(),
         }
     }
 
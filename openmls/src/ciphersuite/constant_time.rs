@@ -0,0 +1,28 @@
+//! Constant-time equality.
+//!
+//! Comparing MACs, tags or secrets with a regular byte-wise comparison leaks
+//! the position of the first differing byte through timing. All such
+//! comparisons go through [`ConstantTimeEq`] instead.
+
+/// Equality that doesn't depend on the content of the compared values.
+///
+/// The length of the compared values is considered public.
+pub(crate) trait ConstantTimeEq {
+    /// Returns `true` if `self` and `other` are equal.
+    fn ct_eq(&self, other: &Self) -> bool;
+}
+
+impl ConstantTimeEq for [u8] {
+    #[inline(never)]
+    fn ct_eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        let mut diff = 0u8;
+        for (l, r) in self.iter().zip(other.iter()) {
+            diff |= l ^ r;
+        }
+        // Prevent the compiler from turning this into an early-exit comparison.
+        std::hint::black_box(diff) == 0
+    }
+}
@@ -10,10 +10,16 @@ pub(crate) struct Mac {
     pub(crate) mac_value: VLBytes,
 }
 
+impl ConstantTimeEq for Mac {
+    fn ct_eq(&self, other: &Mac) -> bool {
+        self.mac_value.as_slice().ct_eq(other.mac_value.as_slice())
+    }
+}
+
 impl PartialEq for Mac {
     // Constant time comparison.
     fn eq(&self, other: &Mac) -> bool {
-        equal_ct(self.mac_value.as_slice(), other.mac_value.as_slice())
+        self.ct_eq(other)
     }
 }
 
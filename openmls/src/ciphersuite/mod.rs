@@ -16,6 +16,7 @@ use std::hash::Hash;
 
 mod aead;
 mod codec;
+mod constant_time;
 pub(crate) mod hpke;
 mod kdf_label;
 mod mac;
@@ -29,6 +30,7 @@ pub mod signature;
 
 // Crate
 pub(crate) use aead::*;
+pub(crate) use constant_time::*;
 pub(crate) use mac::*;
 pub(crate) use reuse_guard::*;
 pub(crate) use secret::*;
@@ -44,14 +46,3 @@ const LABEL_PREFIX: &str = "MLS 1.0 ";
 /// A simple type for HPKE public keys using [`VLBytes`] for (de)serializing.
 pub type HpkePublicKey = VLBytes;
 pub use openmls_traits::types::HpkePrivateKey;
-
-/// Compare two byte slices in a way that's hopefully not optimised out by the
-/// compiler.
-#[inline(never)]
-fn equal_ct(a: &[u8], b: &[u8]) -> bool {
-    let mut diff = 0u8;
-    for (l, r) in a.iter().zip(b.iter()) {
-        diff |= l ^ r;
-    }
-    diff == 0
-}
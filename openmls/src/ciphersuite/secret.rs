@@ -1,19 +1,27 @@
 use std::fmt::{Debug, Formatter};
 
 use tls_codec::SecretVLBytes;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use super::{kdf_label::KdfLabel, *};
 
 /// A struct to contain secrets. This is to provide better visibility into where
 /// and how secrets are used and to avoid passing secrets in their raw
-/// representation.
+/// representation. The secret value is zeroized when the `Secret` is dropped.
+///
+/// Copies of a `Secret` are zeroized when they are dropped as well. They are
+/// only made where a secret has to outlive its owner, e.g. for the resumption
+/// PSKs handed out to the application, for PSKs loaded for a commit, or for
+/// the secrets shared with a [`PublicGroup`](crate::group::PublicGroup).
 ///
 /// Note: This has a hand-written `Debug` implementation.
 ///       Please update as well when changing this struct.
-#[derive(Clone, Serialize, Deserialize, Eq)]
+#[derive(Clone, Serialize, Deserialize, Eq, Zeroize, ZeroizeOnDrop)]
 pub(crate) struct Secret {
+    #[zeroize(skip)]
     pub(in crate::ciphersuite) ciphersuite: Ciphersuite,
     pub(in crate::ciphersuite) value: SecretVLBytes,
+    #[zeroize(skip)]
     pub(in crate::ciphersuite) mls_version: ProtocolVersion,
 }
 
@@ -41,9 +49,8 @@ impl Default for Secret {
     }
 }
 
-impl PartialEq for Secret {
-    // Constant time comparison.
-    fn eq(&self, other: &Secret) -> bool {
+impl ConstantTimeEq for Secret {
+    fn ct_eq(&self, other: &Secret) -> bool {
         // These values can be considered public and checked before the actual
         // comparison.
         if self.ciphersuite != other.ciphersuite
@@ -65,7 +72,14 @@ impl PartialEq for Secret {
             );
             return false;
         }
-        equal_ct(self.value.as_slice(), other.value.as_slice())
+        self.value.as_slice().ct_eq(other.value.as_slice())
+    }
+}
+
+impl PartialEq for Secret {
+    // Constant time comparison.
+    fn eq(&self, other: &Secret) -> bool {
+        self.ct_eq(other)
     }
 }
 
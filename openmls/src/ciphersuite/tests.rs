@@ -1,6 +1,7 @@
 //! Unit tests for the ciphersuites.
 
 mod test_ciphersuite;
+mod test_constant_time;
 mod test_secrets;
 
 // Test vector for basic crypto functionality
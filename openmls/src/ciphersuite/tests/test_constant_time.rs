@@ -0,0 +1,181 @@
+use std::path::Path;
+
+use crate::{
+    ciphersuite::{AeadKey, AeadNonce, Ciphersuite, ConstantTimeEq, Mac, Secret},
+    framing::MembershipTag,
+    messages::ConfirmationTag,
+    test_utils::*,
+    treesync::node::encryption_keys::EncryptionPrivateKey,
+};
+
+#[test]
+fn ct_eq_bytes() {
+    assert!(b"".as_slice().ct_eq(b""));
+    assert!(b"secret".as_slice().ct_eq(b"secret"));
+    assert!(!b"secret".as_slice().ct_eq(b"secreT"));
+    assert!(!b"secret".as_slice().ct_eq(b"Secret"));
+
+    // Prefixes of a value are not equal to the value.
+    assert!(!b"secret".as_slice().ct_eq(b"secre"));
+    assert!(!b"secret".as_slice().ct_eq(b""));
+    assert!(!b"".as_slice().ct_eq(b"secret"));
+}
+
+#[apply(ciphersuites_and_providers)]
+fn ct_eq_tags(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let secret = Secret::random(ciphersuite, provider.rand(), None).unwrap();
+    let mac = Mac::new(provider.crypto(), &secret, b"payload").unwrap();
+    let other_mac = Mac::new(provider.crypto(), &secret, b"other payload").unwrap();
+
+    let mut truncated_mac = mac.clone();
+    truncated_mac.mac_value.pop();
+    let empty_mac = Mac {
+        mac_value: Vec::new().into(),
+    };
+
+    assert!(mac.ct_eq(&mac.clone()));
+    for wrong_mac in [&other_mac, &truncated_mac, &empty_mac] {
+        assert!(!mac.ct_eq(wrong_mac));
+        assert!(!wrong_mac.ct_eq(&mac));
+
+        let confirmation_tag = ConfirmationTag(mac.clone());
+        assert!(!confirmation_tag.ct_eq(&ConfirmationTag(wrong_mac.clone())));
+
+        let membership_tag = MembershipTag(mac.clone());
+        assert!(!membership_tag.ct_eq(&MembershipTag(wrong_mac.clone())));
+    }
+
+    // Secrets of different length are never equal.
+    let mut truncated_secret = secret.clone();
+    truncated_secret.value = secret.as_slice()[..1].to_vec().into();
+    assert!(secret.ct_eq(&secret.clone()));
+    assert!(!secret.ct_eq(&truncated_secret));
+}
+
+// Secret values must only be compared in constant time. The types that hold
+// secret bytes implement `PartialEq` on top of `ConstantTimeEq`, such that
+// `==` and `ct_eq` always agree.
+#[apply(ciphersuites_and_providers)]
+fn partial_eq_is_constant_time(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    fn implements_ct_eq<T: ConstantTimeEq + PartialEq>() {}
+    implements_ct_eq::<Mac>();
+    implements_ct_eq::<Secret>();
+    implements_ct_eq::<MembershipTag>();
+    implements_ct_eq::<ConfirmationTag>();
+    implements_ct_eq::<AeadKey>();
+    implements_ct_eq::<AeadNonce>();
+    implements_ct_eq::<EncryptionPrivateKey>();
+
+    fn assert_eq_agrees<T: ConstantTimeEq + PartialEq>(a: &T, b: &T) {
+        assert_eq!(a == b, a.ct_eq(b));
+        assert_eq!(b == a, b.ct_eq(a));
+        assert_eq!(a != b, !a.ct_eq(b));
+    }
+
+    let secret = Secret::random(ciphersuite, provider.rand(), None).unwrap();
+    let other_secret = Secret::random(ciphersuite, provider.rand(), None).unwrap();
+    let mut truncated_secret = secret.clone();
+    truncated_secret.value = secret.as_slice()[..1].to_vec().into();
+
+    assert!(secret == secret.clone());
+    assert!(secret != other_secret);
+    assert!(secret != truncated_secret);
+    for other in [&secret.clone(), &other_secret, &truncated_secret] {
+        assert_eq_agrees(&secret, other);
+    }
+
+    let mac = Mac::new(provider.crypto(), &secret, b"payload").unwrap();
+    let other_mac = Mac::new(provider.crypto(), &secret, b"other payload").unwrap();
+    let mut truncated_mac = mac.clone();
+    truncated_mac.mac_value.pop();
+
+    assert!(mac == mac.clone());
+    for other in [mac.clone(), other_mac, truncated_mac] {
+        assert_eq_agrees(&mac, &other);
+        assert_eq_agrees(
+            &ConfirmationTag(mac.clone()),
+            &ConfirmationTag(other.clone()),
+        );
+        assert_eq_agrees(&MembershipTag(mac.clone()), &MembershipTag(other));
+    }
+}
+
+/// The types of fields that hold secret bytes directly.
+const SECRET_BYTES: &[&str] = &["SecretVLBytes", "[u8; NONCE_BYTES]"];
+
+/// Returns the attributes and the definition of every struct in `source`.
+fn structs(source: &str) -> Vec<(String, String)> {
+    let lines = source.lines().collect::<Vec<_>>();
+    let mut structs = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_start();
+        if !(trimmed.starts_with("struct ") || trimmed.contains(" struct "))
+            || trimmed.starts_with("//")
+        {
+            continue;
+        }
+        // The definition ends with the first line that closes it.
+        let end = lines[index..]
+            .iter()
+            .position(|line| {
+                line.trim_end().ends_with(';')
+                    || line.trim() == "}"
+                    || line.trim_end().ends_with("{}")
+            })
+            .map_or(lines.len(), |end| index + end + 1);
+        let definition = lines[index..end].join("\n");
+        // The attributes and doc comments of the type end at the previous
+        // empty line.
+        let mut attributes = lines[..index]
+            .iter()
+            .rev()
+            .take_while(|line| !line.trim().is_empty() && line.trim() != "}")
+            .filter(|line| !line.trim_start().starts_with("//"))
+            .copied()
+            .collect::<Vec<_>>();
+        attributes.reverse();
+        structs.push((attributes.join("\n"), definition));
+    }
+    structs
+}
+
+fn rust_sources(directory: &Path, sources: &mut Vec<(String, String)>) {
+    for entry in std::fs::read_dir(directory).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            rust_sources(&path, sources);
+        } else if path.extension().is_some_and(|extension| extension == "rs") {
+            let source = std::fs::read_to_string(&path).unwrap();
+            sources.push((path.display().to_string(), source));
+        }
+    }
+}
+
+// A derived `PartialEq` compares secret bytes with an early exit. Types that
+// hold secret bytes directly therefore must implement `PartialEq` on top of
+// `ConstantTimeEq`, also for tests. Types that wrap those types may derive
+// `PartialEq`, since it compares the secret fields in constant time.
+#[test]
+fn no_derived_partial_eq_for_secret_bytes() {
+    // The secret types of the traits crate are checked as well.
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let mut sources = Vec::new();
+    rust_sources(&manifest_dir.join("src"), &mut sources);
+    rust_sources(&manifest_dir.join("../traits/src"), &mut sources);
+
+    let mut checked = 0;
+    for (path, source) in sources {
+        for (attributes, definition) in structs(&source) {
+            if !SECRET_BYTES.iter().any(|field| definition.contains(field)) {
+                continue;
+            }
+            checked += 1;
+            assert!(
+                !attributes.contains("PartialEq"),
+                "A struct holding secret bytes in {path} derives PartialEq:\n{attributes}\n{definition}"
+            );
+        }
+    }
+    // The check found the secret types.
+    assert!(checked >= 4);
+}
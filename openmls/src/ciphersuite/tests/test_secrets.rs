@@ -1,7 +1,10 @@
+use openmls_basic_credential::SignatureKeyPair;
 use openmls_rust_crypto::OpenMlsRustCrypto;
+use openmls_traits::types::ExporterSecret;
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::{
-    ciphersuite::{Ciphersuite, Secret},
+    ciphersuite::{AeadKey, AeadNonce, Ciphersuite, HpkePrivateKey, Secret},
     test_utils::*,
     versions::ProtocolVersion,
 };
@@ -31,3 +34,23 @@ fn secret_incompatible(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider
     // This must panic because the two secrets have incompatible MLS versions.
     let _default_extracted = default_secret.hkdf_extract(provider.crypto(), &draft_secret);
 }
+
+#[apply(ciphersuites_and_providers)]
+fn secrets_zeroize_on_drop(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    fn zeroize_on_drop<T: ZeroizeOnDrop>() {}
+    zeroize_on_drop::<Secret>();
+    zeroize_on_drop::<AeadKey>();
+    zeroize_on_drop::<AeadNonce>();
+    zeroize_on_drop::<HpkePrivateKey>();
+    zeroize_on_drop::<ExporterSecret>();
+    zeroize_on_drop::<SignatureKeyPair>();
+
+    // Zeroizing a secret clears the value, but keeps the ciphersuite and
+    // version.
+    let mut secret =
+        Secret::random(ciphersuite, provider.rand(), None).expect("Not enough randomness.");
+    secret.zeroize();
+    assert!(secret.as_slice().is_empty());
+    assert_eq!(secret.ciphersuite(), ciphersuite);
+    assert_eq!(secret.version(), ProtocolVersion::default());
+}
@@ -405,7 +405,11 @@ fn last_resort_extension(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvid
         .expect("error encoding key package with last resort extension");
     let decoded_kp = KeyPackageIn::tls_deserialize(&mut encoded_kp.as_slice())
         .expect("error decoding key package with last resort extension")
-        .validate(provider.crypto(), ProtocolVersion::default())
+        .validate(
+            provider.crypto(),
+            provider.time(),
+            ProtocolVersion::default(),
+        )
         .expect("error validating key package with last resort extension");
     assert!(decoded_kp.last_resort());
 
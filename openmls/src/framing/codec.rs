@@ -1,5 +1,5 @@
 use std::io::{Read, Write};
-use tls_codec::{Deserialize, Serialize, Size};
+use tls_codec::{Deserialize, DeserializeBytes, Serialize, Size};
 
 use crate::versions::ProtocolVersion;
 
@@ -64,7 +64,56 @@ impl Deserialize for MlsMessageIn {
     fn tls_deserialize<R: Read>(bytes: &mut R) -> Result<Self, tls_codec::Error> {
         let version = ProtocolVersion::tls_deserialize(bytes)?;
         let body = MlsMessageBodyIn::tls_deserialize(bytes)?;
+        let message = Self::new_checked(version, body)?;
 
+        // Enforce the default decode limits. The size of the input isn't
+        // known up front, so it is checked once the message is decoded.
+        let limits = DecodeLimits::default();
+        limits
+            .check_message_size(message.tls_serialized_len())
+            .and_then(|_| message.check_decode_limits(&limits))
+            .map_err(|e| tls_codec::Error::DecodingError(e.to_string()))?;
+
+        Ok(message)
+    }
+}
+
+impl DeserializeBytes for MlsMessageIn {
+    fn tls_deserialize_bytes(bytes: &[u8]) -> Result<(Self, &[u8]), tls_codec::Error> {
+        // Enforce the default decode limits.
+        Self::tls_deserialize_bytes_limited(bytes, &DecodeLimits::default()).map_err(|e| match e {
+            MlsMessageDecodeError::Codec(e) => e,
+            MlsMessageDecodeError::LimitExceeded(e) => {
+                tls_codec::Error::DecodingError(e.to_string())
+            }
+        })
+    }
+}
+
+impl MlsMessageIn {
+    /// Deserialize an [`MlsMessageIn`] from the beginning of `bytes` while
+    /// enforcing the given [`DecodeLimits`].
+    ///
+    /// Every length prefix is checked against the remaining input before
+    /// anything is allocated, such that a message can't claim more memory
+    /// than the size of the input.
+    pub(super) fn tls_deserialize_bytes_limited<'a>(
+        bytes: &'a [u8],
+        limits: &DecodeLimits,
+    ) -> Result<(Self, &'a [u8]), MlsMessageDecodeError> {
+        let (version, remainder) = ProtocolVersion::tls_deserialize_bytes(bytes)?;
+        let (body, remainder) = MlsMessageBodyIn::tls_deserialize_bytes(remainder)?;
+        let message = Self::new_checked(version, body)?;
+
+        limits.check_message_size(bytes.len() - remainder.len())?;
+        message.check_decode_limits(limits)?;
+        Ok((message, remainder))
+    }
+
+    fn new_checked(
+        version: ProtocolVersion,
+        body: MlsMessageBodyIn,
+    ) -> Result<Self, tls_codec::Error> {
         // KeyPackage version must match MlsMessage version.
         if let MlsMessageBodyIn::KeyPackage(key_package) = &body {
             if !key_package.version_is_supported(version) {
@@ -2,7 +2,7 @@
 //!
 //! This module contains errors related to message framing operations.
 
-use crate::error::LibraryError;
+use crate::{error::LibraryError, framing::WireFormat, versions::ProtocolVersion};
 use thiserror::Error;
 
 // === Public ===
@@ -65,3 +65,47 @@ pub enum ProtocolMessageError {
     #[error("Wrong wire format")]
     WrongWireFormat,
 }
+
+/// Error when unpacking the body of an [`MlsMessageIn`](super::MlsMessageIn).
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum MlsMessageBodyError {
+    /// The message uses a protocol version that is not supported.
+    #[error("The message uses an unsupported protocol version: {0}")]
+    UnsupportedVersion(ProtocolVersion),
+    /// The message has a wire format that can't be used here.
+    #[error("The message has an unexpected wire format: {0:?}")]
+    UnexpectedWireFormat(WireFormat),
+}
+
+/// Error when a decoded message exceeds the
+/// [`DecodeLimits`](super::DecodeLimits).
+#[derive(Error, Debug, PartialEq, Eq, Clone)]
+pub enum DecodeLimitError {
+    /// The serialized message is larger than allowed.
+    #[error("The serialized message is larger than allowed.")]
+    MessageTooLarge,
+    /// The ratchet tree has more leaves than allowed.
+    #[error("The ratchet tree has more leaves than allowed.")]
+    TreeTooLarge,
+    /// The commit contains more proposals than allowed.
+    #[error("The commit contains more proposals than allowed.")]
+    TooManyProposals,
+    /// An extension list contains more extensions than allowed.
+    #[error("An extension list contains more extensions than allowed.")]
+    TooManyExtensions,
+    /// The welcome contains more encrypted group secrets than allowed.
+    #[error("The welcome contains more encrypted group secrets than allowed.")]
+    TooManyWelcomeSecrets,
+}
+
+/// Error when decoding an [`MlsMessageIn`](super::MlsMessageIn) with
+/// [`DecodeLimits`](super::DecodeLimits).
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum MlsMessageDecodeError {
+    /// See [`DecodeLimitError`] for more details.
+    #[error(transparent)]
+    LimitExceeded(#[from] DecodeLimitError),
+    /// The message could not be decoded.
+    #[error("The message could not be decoded: {0:?}")]
+    Codec(#[from] tls_codec::Error),
+}
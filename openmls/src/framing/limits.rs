@@ -0,0 +1,198 @@
+//! # Decode limits
+//!
+//! This module contains the [`DecodeLimits`] that bound the resources an
+//! untrusted MLS message may claim. The TLS codec only bounds vectors by the
+//! size of their length prefix, so a peer could otherwise send messages with
+//! huge ratchet trees, extension lists or proposal vectors.
+//!
+//! The limits are checked in two places:
+//!  - when decoding an [`MlsMessageIn`](super::MlsMessageIn), either with the
+//!    default limits through `tls_deserialize` or with custom limits through
+//!    [`MlsMessageIn::tls_deserialize_with_limits()`](super::MlsMessageIn::tls_deserialize_with_limits),
+//!  - when processing a message in an [`MlsGroup`](crate::group::MlsGroup),
+//!    where the limits of the group's configuration also cover the content of
+//!    decrypted messages.
+//!
+//! When decoding from a byte slice, input larger than the size limit is
+//! rejected up front and every length prefix is checked against the remaining
+//! input before anything is allocated. When decoding from a reader, the size
+//! of the input isn't known up front and the size limit is only checked once
+//! the message is decoded, so untrusted input should be decoded from a byte
+//! slice. The counts are checked as soon as the message is decoded.
+
+use serde::{Deserialize, Serialize};
+
+use super::errors::DecodeLimitError;
+use crate::extensions::{Extension, Extensions};
+
+/// Default maximum size of a serialized message in bytes (16 MiB).
+const DEFAULT_MAX_MESSAGE_SIZE: usize = 1 << 24;
+/// Default maximum number of leaves of a ratchet tree.
+const DEFAULT_MAX_TREE_SIZE: u32 = 1 << 16;
+/// Default maximum number of proposals in a commit.
+const DEFAULT_MAX_PROPOSALS: usize = 1 << 12;
+/// Default maximum number of extensions in a single extension list.
+const DEFAULT_MAX_EXTENSIONS: usize = 1 << 8;
+/// Default maximum number of encrypted group secrets in a welcome.
+const DEFAULT_MAX_WELCOME_SECRETS: usize = 1 << 12;
+
+fn default_max_welcome_secrets() -> usize {
+    DEFAULT_MAX_WELCOME_SECRETS
+}
+
+/// Limits that are enforced when decoding and processing untrusted messages.
+///
+/// The limits can be set in the [`MlsGroupJoinConfig`] of a group, or passed
+/// to [`MlsMessageIn::tls_deserialize_with_limits()`] directly.
+///
+/// | Limit | Default |
+/// |-------|---------|
+/// | `max_message_size` | 16 MiB |
+/// | `max_tree_size` | 65536 leaves |
+/// | `max_proposals` | 4096 proposals |
+/// | `max_extensions` | 256 extensions |
+/// | `max_welcome_secrets` | 4096 encrypted group secrets |
+///
+/// [`MlsGroupJoinConfig`]: crate::group::MlsGroupJoinConfig
+/// [`MlsMessageIn::tls_deserialize_with_limits()`]: super::MlsMessageIn::tls_deserialize_with_limits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodeLimits {
+    max_message_size: usize,
+    max_tree_size: u32,
+    max_proposals: usize,
+    max_extensions: usize,
+    #[serde(default = "default_max_welcome_secrets")]
+    max_welcome_secrets: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_message_size: DEFAULT_MAX_MESSAGE_SIZE,
+            max_tree_size: DEFAULT_MAX_TREE_SIZE,
+            max_proposals: DEFAULT_MAX_PROPOSALS,
+            max_extensions: DEFAULT_MAX_EXTENSIONS,
+            max_welcome_secrets: DEFAULT_MAX_WELCOME_SECRETS,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Set the maximum size of a serialized message in bytes.
+    pub fn with_max_message_size(mut self, max_message_size: usize) -> Self {
+        self.max_message_size = max_message_size;
+        self
+    }
+
+    /// Set the maximum number of leaves of a ratchet tree.
+    pub fn with_max_tree_size(mut self, max_tree_size: u32) -> Self {
+        self.max_tree_size = max_tree_size;
+        self
+    }
+
+    /// Set the maximum number of proposals in a commit.
+    pub fn with_max_proposals(mut self, max_proposals: usize) -> Self {
+        self.max_proposals = max_proposals;
+        self
+    }
+
+    /// Set the maximum number of extensions in a single extension list.
+    pub fn with_max_extensions(mut self, max_extensions: usize) -> Self {
+        self.max_extensions = max_extensions;
+        self
+    }
+
+    /// Set the maximum number of encrypted group secrets in a welcome, i.e.
+    /// the number of members a welcome can add.
+    pub fn with_max_welcome_secrets(mut self, max_welcome_secrets: usize) -> Self {
+        self.max_welcome_secrets = max_welcome_secrets;
+        self
+    }
+
+    /// Get the maximum size of a serialized message in bytes.
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size
+    }
+
+    /// Get the maximum number of leaves of a ratchet tree.
+    pub fn max_tree_size(&self) -> u32 {
+        self.max_tree_size
+    }
+
+    /// Get the maximum number of proposals in a commit.
+    pub fn max_proposals(&self) -> usize {
+        self.max_proposals
+    }
+
+    /// Get the maximum number of extensions in a single extension list.
+    pub fn max_extensions(&self) -> usize {
+        self.max_extensions
+    }
+
+    /// Get the maximum number of encrypted group secrets in a welcome.
+    pub fn max_welcome_secrets(&self) -> usize {
+        self.max_welcome_secrets
+    }
+
+    /// Check the size of a serialized message.
+    pub(crate) fn check_message_size(&self, message_size: usize) -> Result<(), DecodeLimitError> {
+        if message_size > self.max_message_size {
+            return Err(DecodeLimitError::MessageTooLarge);
+        }
+        Ok(())
+    }
+
+    /// Check the number of nodes of a ratchet tree.
+    pub(crate) fn check_tree_size(&self, node_count: usize) -> Result<(), DecodeLimitError> {
+        // A tree with `n` leaves has `2n - 1` nodes.
+        let leaf_count = node_count / 2 + 1;
+        if leaf_count > self.max_tree_size as usize {
+            return Err(DecodeLimitError::TreeTooLarge);
+        }
+        Ok(())
+    }
+
+    /// Check the number of proposals of a commit.
+    pub(crate) fn check_proposals(&self, proposal_count: usize) -> Result<(), DecodeLimitError> {
+        if proposal_count > self.max_proposals {
+            return Err(DecodeLimitError::TooManyProposals);
+        }
+        Ok(())
+    }
+
+    /// Check the number of encrypted group secrets of a welcome.
+    pub(crate) fn check_welcome_secrets(
+        &self,
+        secrets_count: usize,
+    ) -> Result<(), DecodeLimitError> {
+        if secrets_count > self.max_welcome_secrets {
+            return Err(DecodeLimitError::TooManyWelcomeSecrets);
+        }
+        Ok(())
+    }
+}
+
+/// Structural checks of decoded, but otherwise unvalidated, input against the
+/// [`DecodeLimits`].
+///
+/// The check descends into all nested structures that carry extension lists,
+/// proposals or ratchet trees.
+pub(crate) trait CheckDecodeLimits {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError>;
+}
+
+impl CheckDecodeLimits for Extensions {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        if self.iter().count() > limits.max_extensions {
+            return Err(DecodeLimitError::TooManyExtensions);
+        }
+        for extension in self.iter() {
+            if let Extension::RatchetTree(ratchet_tree_extension) = extension {
+                ratchet_tree_extension
+                    .ratchet_tree()
+                    .check_decode_limits(limits)?;
+            }
+        }
+        Ok(())
+    }
+}
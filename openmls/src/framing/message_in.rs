@@ -93,10 +93,10 @@ pub enum MlsMessageBodyIn {
     KeyPackage(KeyPackageIn),
 }
 
-impl MlsMessageIn {
+impl MlsMessageBodyIn {
     /// Returns the wire format.
     pub fn wire_format(&self) -> WireFormat {
-        match self.body {
+        match self {
             MlsMessageBodyIn::PrivateMessage(_) => WireFormat::PrivateMessage,
             MlsMessageBodyIn::PublicMessage(_) => WireFormat::PublicMessage,
             MlsMessageBodyIn::Welcome(_) => WireFormat::Welcome,
@@ -104,6 +104,13 @@ impl MlsMessageIn {
             MlsMessageBodyIn::KeyPackage(_) => WireFormat::KeyPackage,
         }
     }
+}
+
+impl MlsMessageIn {
+    /// Returns the wire format.
+    pub fn wire_format(&self) -> WireFormat {
+        self.body.wire_format()
+    }
 
     /// Extract the content of an [`MlsMessageIn`] after deserialization for use
     /// with the [`MlsGroup`] API.
@@ -116,6 +123,75 @@ impl MlsMessageIn {
         self.try_into()
     }
 
+    /// Returns the protocol version of the message.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Deserialize an [`MlsMessageIn`] from `bytes` while enforcing the given
+    /// [`DecodeLimits`].
+    ///
+    /// Input larger than the maximum message size is rejected before
+    /// decoding, and length prefixes that claim more than the remaining input
+    /// are rejected before anything is allocated. The number of proposals,
+    /// the number of extensions, the number of welcome secrets and the size
+    /// of ratchet trees are checked as soon as the message is decoded, as far
+    /// as they are visible without decrypting the message. The bytes must
+    /// contain exactly one message.
+    ///
+    /// Note that decoding through [`tls_codec::DeserializeBytes`] enforces
+    /// the default [`DecodeLimits`] in the same way. Decoding through
+    /// [`tls_codec::Deserialize`] from a reader enforces them only once the
+    /// message is decoded, since the size of the input isn't known up front.
+    pub fn tls_deserialize_with_limits(
+        bytes: &[u8],
+        limits: &DecodeLimits,
+    ) -> Result<Self, MlsMessageDecodeError> {
+        limits.check_message_size(bytes.len())?;
+        let (message, remainder) = Self::tls_deserialize_bytes_limited(bytes, limits)?;
+        if !remainder.is_empty() {
+            return Err(tls_codec::Error::TrailingData.into());
+        }
+        Ok(message)
+    }
+
+    /// Try to convert the message into a [`Welcome`].
+    ///
+    /// Returns an error if the message doesn't use MLS 1.0 or if it doesn't
+    /// contain a [`Welcome`].
+    pub fn try_into_welcome(self) -> Result<Welcome, MlsMessageBodyError> {
+        match self.into_supported_body()? {
+            MlsMessageBodyIn::Welcome(welcome) => Ok(welcome),
+            body => Err(MlsMessageBodyError::UnexpectedWireFormat(
+                body.wire_format(),
+            )),
+        }
+    }
+
+    /// Try to convert the message into a [`VerifiableGroupInfo`].
+    ///
+    /// Returns an error if the message doesn't use MLS 1.0 or if it doesn't
+    /// contain a group info.
+    pub fn try_into_verifiable_group_info(
+        self,
+    ) -> Result<VerifiableGroupInfo, MlsMessageBodyError> {
+        match self.into_supported_body()? {
+            MlsMessageBodyIn::GroupInfo(group_info) => Ok(group_info),
+            body => Err(MlsMessageBodyError::UnexpectedWireFormat(
+                body.wire_format(),
+            )),
+        }
+    }
+
+    /// Returns the body of the message if it uses a supported protocol
+    /// version.
+    pub(crate) fn into_supported_body(self) -> Result<MlsMessageBodyIn, MlsMessageBodyError> {
+        if self.version != ProtocolVersion::Mls10 {
+            return Err(MlsMessageBodyError::UnsupportedVersion(self.version));
+        }
+        Ok(self.body)
+    }
+
     #[cfg(any(test, feature = "test-utils"))]
     pub fn into_keypackage(self) -> Option<crate::key_packages::KeyPackage> {
         match self.body {
@@ -172,6 +248,21 @@ impl MlsMessageIn {
     }
 }
 
+impl CheckDecodeLimits for MlsMessageIn {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        match &self.body {
+            MlsMessageBodyIn::PublicMessage(public_message) => {
+                public_message.content.body.check_decode_limits(limits)
+            }
+            // The content of private messages is checked after decryption.
+            MlsMessageBodyIn::PrivateMessage(_) => Ok(()),
+            MlsMessageBodyIn::Welcome(welcome) => welcome.check_decode_limits(limits),
+            MlsMessageBodyIn::GroupInfo(group_info) => group_info.check_decode_limits(limits),
+            MlsMessageBodyIn::KeyPackage(key_package) => key_package.check_decode_limits(limits),
+        }
+    }
+}
+
 /// Enum containing a message for use with `process_message` and an
 /// [`MlsGroup`]. Both [`PublicMessage`] and [`PrivateMessage`] implement
 /// [`Into<ProtocolMessage>`].
@@ -234,6 +325,14 @@ impl ProtocolMessage {
     pub fn is_handshake_message(&self) -> bool {
         self.content_type().is_handshake_message()
     }
+
+    /// Returns the length of the serialized message.
+    pub(crate) fn tls_serialized_len(&self) -> usize {
+        match self {
+            ProtocolMessage::PrivateMessage(ref m) => m.tls_serialized_len(),
+            ProtocolMessage::PublicMessage(ref m) => m.tls_serialized_len(),
+        }
+    }
 }
 
 impl From<PrivateMessageIn> for ProtocolMessage {
@@ -9,7 +9,7 @@
 
 use std::io::Read;
 
-use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use openmls_traits::{crypto::OpenMlsCrypto, time::TimeProvider, types::Ciphersuite};
 use tls_codec::Serialize as TlsSerializeTrait;
 
 use super::{mls_auth_content::*, mls_content_in::*, *};
@@ -48,6 +48,7 @@ impl AuthenticatedContentIn {
         self,
         ciphersuite: Ciphersuite,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         sender_context: Option<SenderContext>,
         protocol_version: ProtocolVersion,
     ) -> Result<AuthenticatedContent, ValidationError> {
@@ -56,6 +57,7 @@ impl AuthenticatedContentIn {
             content: self.content.validate(
                 ciphersuite,
                 crypto,
+                time,
                 sender_context,
                 protocol_version,
             )?,
@@ -187,6 +189,12 @@ impl VerifiableAuthenticatedContentIn {
     }
 }
 
+impl CheckDecodeLimits for VerifiableAuthenticatedContentIn {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        self.tbs.content.body.check_decode_limits(limits)
+    }
+}
+
 impl Verifiable for VerifiableAuthenticatedContentIn {
     type VerifiedStruct = AuthenticatedContentIn;
 
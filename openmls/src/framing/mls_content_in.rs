@@ -13,12 +13,13 @@ use crate::{
 use std::io::{Read, Write};
 
 use super::{
+    errors::DecodeLimitError,
     mls_auth_content_in::AuthenticatedContentIn,
     mls_content::{framed_content_tbs_serialized, FramedContent, FramedContentBody},
-    ContentType, Sender, WireFormat,
+    CheckDecodeLimits, ContentType, DecodeLimits, Sender, WireFormat,
 };
 
-use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use openmls_traits::{crypto::OpenMlsCrypto, time::TimeProvider, types::Ciphersuite};
 use serde::{Deserialize, Serialize};
 use tls_codec::{
     Deserialize as TlsDeserializeTrait, Serialize as TlsSerializeTrait, Size, TlsDeserialize,
@@ -61,6 +62,7 @@ impl FramedContentIn {
         self,
         ciphersuite: Ciphersuite,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         sender_context: Option<SenderContext>,
         protocol_version: ProtocolVersion,
     ) -> Result<FramedContent, ValidationError> {
@@ -69,9 +71,13 @@ impl FramedContentIn {
             epoch: self.epoch,
             sender: self.sender,
             authenticated_data: self.authenticated_data,
-            body: self
-                .body
-                .validate(ciphersuite, crypto, sender_context, protocol_version)?,
+            body: self.body.validate(
+                ciphersuite,
+                crypto,
+                time,
+                sender_context,
+                protocol_version,
+            )?,
         })
     }
 }
@@ -149,20 +155,28 @@ impl FramedContentBodyIn {
         self,
         ciphersuite: Ciphersuite,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         sender_context: Option<SenderContext>,
         protocol_version: ProtocolVersion,
     ) -> Result<FramedContentBody, ValidationError> {
         Ok(match self {
             FramedContentBodyIn::Application(bytes) => FramedContentBody::Application(bytes),
-            FramedContentBodyIn::Proposal(proposal_in) => FramedContentBody::Proposal(
-                proposal_in.validate(crypto, ciphersuite, sender_context, protocol_version)?,
-            ),
+            FramedContentBodyIn::Proposal(proposal_in) => {
+                FramedContentBody::Proposal(proposal_in.validate(
+                    crypto,
+                    time,
+                    ciphersuite,
+                    sender_context,
+                    protocol_version,
+                )?)
+            }
             FramedContentBodyIn::Commit(commit_in) => {
                 let sender_context = sender_context
                     .ok_or(LibraryError::custom("Forgot the commit sender context"))?;
                 FramedContentBody::Commit(commit_in.validate(
                     ciphersuite,
                     crypto,
+                    time,
                     sender_context,
                     protocol_version,
                 )?)
@@ -171,6 +185,16 @@ impl FramedContentBodyIn {
     }
 }
 
+impl CheckDecodeLimits for FramedContentBodyIn {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        match self {
+            FramedContentBodyIn::Application(_) => Ok(()),
+            FramedContentBodyIn::Proposal(proposal) => proposal.check_decode_limits(limits),
+            FramedContentBodyIn::Commit(commit) => commit.check_decode_limits(limits),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub(crate) struct FramedContentTbsIn {
     pub(super) version: ProtocolVersion,
@@ -53,6 +53,7 @@ use crate::{
 
 pub(crate) mod codec;
 
+pub(crate) mod limits;
 pub(crate) mod message_in;
 pub(crate) mod message_out;
 pub(crate) mod mls_auth_content;
@@ -78,11 +79,13 @@ pub(crate) use mls_content::*;
 pub(crate) use mls_content_in::*;
 
 // Crate
+pub(crate) use limits::CheckDecodeLimits;
 pub(crate) use sender::*;
 
 // Public
 pub mod errors;
 
+pub use limits::DecodeLimits;
 pub use message_in::*;
 pub use message_out::*;
 pub use private_message::*;
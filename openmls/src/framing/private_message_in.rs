@@ -13,7 +13,11 @@ use crate::{
     binary_tree::array_representation::LeafNodeIndex,
     error::LibraryError,
     framing::mls_content_in::FramedContentIn,
-    tree::{secret_tree::SecretType, sender_ratchet::SenderRatchetConfiguration},
+    schedule::SenderDataSecret,
+    tree::{
+        secret_tree::SecretType,
+        sender_ratchet::{RatchetKeyMaterial, SenderRatchetConfiguration},
+    },
 };
 
 use super::*;
@@ -52,16 +56,25 @@ impl PrivateMessageIn {
         message_secrets: &MessageSecrets,
         crypto: &impl OpenMlsCrypto,
         ciphersuite: Ciphersuite,
+    ) -> Result<MlsSenderData, MessageDecryptionError> {
+        self.sender_data_with_secret(message_secrets.sender_data_secret(), crypto, ciphersuite)
+    }
+
+    /// Decrypt the sender data from this [`PrivateMessageIn`] using the given
+    /// [`SenderDataSecret`].
+    pub(crate) fn sender_data_with_secret(
+        &self,
+        sender_data_secret: &SenderDataSecret,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
     ) -> Result<MlsSenderData, MessageDecryptionError> {
         log::debug!("Decrypting PrivateMessage");
         // Derive key from the key schedule using the ciphertext.
-        let sender_data_key = message_secrets
-            .sender_data_secret()
+        let sender_data_key = sender_data_secret
             .derive_aead_key(crypto, self.ciphertext.as_slice())
             .map_err(LibraryError::unexpected_crypto_error)?;
         // Derive initial nonce from the key schedule using the ciphertext.
-        let sender_data_nonce = message_secrets
-            .sender_data_secret()
+        let sender_data_nonce = sender_data_secret
             .derive_aead_nonce(ciphersuite, crypto, self.ciphertext.as_slice())
             .map_err(LibraryError::unexpected_crypto_error)?;
         // Serialize sender data AAD
@@ -172,6 +185,24 @@ impl PrivateMessageIn {
                 );
                 MessageDecryptionError::SecretTreeError(e)
             })?;
+        self.to_verifiable_content_with_key_material(
+            crypto,
+            (ratchet_key, ratchet_nonce),
+            sender_data,
+            message_secrets.serialized_context().to_vec(),
+        )
+    }
+
+    /// This function decrypts a [`PrivateMessage`] into a
+    /// [`VerifiableAuthenticatedContent`] using the given key material, which
+    /// must belong to the sender and generation in the `sender_data`.
+    pub(crate) fn to_verifiable_content_with_key_material(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        (ratchet_key, ratchet_nonce): RatchetKeyMaterial,
+        sender_data: MlsSenderData,
+        serialized_context: Vec<u8>,
+    ) -> Result<VerifiableAuthenticatedContentIn, MessageDecryptionError> {
         // Prepare the nonce by xoring with the reuse guard.
         let prepared_nonce = ratchet_nonce.xor_with_reuse_guard(&sender_data.reuse_guard);
         let private_message_content = self.decrypt(crypto, ratchet_key, &prepared_nonce)?;
@@ -193,7 +224,7 @@ impl PrivateMessageIn {
                 authenticated_data: self.authenticated_data.clone(),
                 body: private_message_content.content,
             },
-            Some(message_secrets.serialized_context().to_vec()),
+            Some(serialized_context),
             private_message_content.auth,
         );
         Ok(verifiable)
@@ -19,18 +19,23 @@ use crate::{error::LibraryError, versions::ProtocolVersion};
 
 /// Wrapper around a `Mac` used for type safety.
 #[derive(
-    Debug,
-    PartialEq,
-    Clone,
-    Serialize,
-    Deserialize,
-    TlsSerialize,
-    TlsDeserialize,
-    TlsDeserializeBytes,
-    TlsSize,
+    Debug, Clone, Serialize, Deserialize, TlsSerialize, TlsDeserialize, TlsDeserializeBytes, TlsSize,
 )]
 pub(crate) struct MembershipTag(pub(crate) Mac);
 
+impl ConstantTimeEq for MembershipTag {
+    fn ct_eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for MembershipTag {
+    // Constant time comparison.
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
 /// [`PublicMessage`] is a framing structure for MLS messages. It can contain
 /// Proposals, Commits and application messages.
 ///
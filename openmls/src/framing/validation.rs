@@ -23,7 +23,7 @@
 //! ```
 // TODO #106/#151: Update the above diagram
 
-use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use openmls_traits::{crypto::OpenMlsCrypto, time::TimeProvider, types::Ciphersuite};
 
 use crate::{
     binary_tree::LeafNodeIndex,
@@ -33,6 +33,7 @@ use crate::{
     group::{
         core_group::{proposals::QueuedProposal, staged_commit::StagedCommit},
         errors::ValidationError,
+        public_group::HandshakeObserverSecrets,
     },
     tree::sender_ratchet::SenderRatchetConfiguration,
     treesync::TreeSync,
@@ -121,6 +122,38 @@ impl DecryptedMessage {
         Self::from_verifiable_content(verifiable_content)
     }
 
+    /// Constructs a [DecryptedMessage] from a [PrivateMessage] containing a
+    /// handshake message, using the [`HandshakeObserverSecrets`] shared with
+    /// a [`PublicGroup`](crate::group::PublicGroup).
+    pub(crate) fn from_observed_ciphertext(
+        ciphertext: PrivateMessageIn,
+        crypto: &impl OpenMlsCrypto,
+        handshake_observer_secrets: &HandshakeObserverSecrets,
+        serialized_context: Vec<u8>,
+    ) -> Result<Self, ValidationError> {
+        let sender_data = ciphertext.sender_data_with_secret(
+            handshake_observer_secrets.sender_data_secret(),
+            crypto,
+            handshake_observer_secrets.ciphersuite(),
+        )?;
+        let key_material = handshake_observer_secrets
+            .key_material(crypto, sender_data.leaf_index, sender_data.generation)
+            .map_err(|e| {
+                log::error!(
+                    "  Ciphertext generation out of bounds {}\n\t{e:?}",
+                    sender_data.generation
+                );
+                MessageDecryptionError::SecretTreeError(e)
+            })?;
+        let verifiable_content = ciphertext.to_verifiable_content_with_key_material(
+            crypto,
+            key_material,
+            sender_data,
+            serialized_context,
+        )?;
+        Self::from_verifiable_content(verifiable_content)
+    }
+
     // Internal constructor function. Does the following checks:
     // - Confirmation tag must be present for Commit messages
     // - Membership tag must be present for member messages, if the original incoming message was not an PrivateMessage
@@ -272,14 +305,20 @@ impl UnverifiedMessage {
         self,
         ciphersuite: Ciphersuite,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         protocol_version: ProtocolVersion,
     ) -> Result<(AuthenticatedContent, Credential), ProcessMessageError> {
         let content: AuthenticatedContentIn = self
             .verifiable_content
             .verify(crypto, &self.sender_pk)
             .map_err(|_| ProcessMessageError::InvalidSignature)?;
-        let content =
-            content.validate(ciphersuite, crypto, self.sender_context, protocol_version)?;
+        let content = content.validate(
+            ciphersuite,
+            crypto,
+            time,
+            self.sender_context,
+            protocol_version,
+        )?;
         Ok((content, self.credential))
     }
 
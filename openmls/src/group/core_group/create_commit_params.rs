@@ -3,8 +3,8 @@
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    credentials::CredentialWithKey, framing::FramingParameters, group::ProposalStore,
-    messages::proposals::Proposal,
+    credentials::CredentialWithKey, extensions::Extensions, framing::FramingParameters,
+    group::ProposalStore, messages::proposals::Proposal,
 };
 
 #[cfg(doc)]
@@ -24,6 +24,7 @@ pub(crate) struct CreateCommitParams<'a> {
     force_self_update: bool,                        // Optional
     commit_type: CommitType,                        // Optional (default is `Member`)
     credential_with_key: Option<CredentialWithKey>, // Mandatory for external commits
+    leaf_node_extensions: Extensions,               // Optional (only for external commits)
 }
 
 pub(crate) struct TempBuilderCCPM0 {}
@@ -58,6 +59,7 @@ impl<'a> TempBuilderCCPM1<'a> {
                 force_self_update: true,
                 commit_type: CommitType::Member,
                 credential_with_key: None,
+                leaf_node_extensions: Extensions::empty(),
             },
         }
     }
@@ -81,6 +83,10 @@ impl<'a> CreateCommitParamsBuilder<'a> {
         self.ccp.credential_with_key = Some(credential_with_key);
         self
     }
+    pub(crate) fn leaf_node_extensions(mut self, leaf_node_extensions: Extensions) -> Self {
+        self.ccp.leaf_node_extensions = leaf_node_extensions;
+        self
+    }
     pub(crate) fn build(self) -> CreateCommitParams<'a> {
         self.ccp
     }
@@ -108,4 +114,7 @@ impl<'a> CreateCommitParams<'a> {
     pub(crate) fn take_credential_with_key(&mut self) -> Option<CredentialWithKey> {
         self.credential_with_key.take()
     }
+    pub(crate) fn take_leaf_node_extensions(&mut self) -> Extensions {
+        std::mem::take(&mut self.leaf_node_extensions)
+    }
 }
@@ -6,7 +6,7 @@
 //! error, will still return a `Result` since they may throw a `LibraryError`.
 
 // Private
-mod new_from_welcome;
+pub(crate) mod new_from_welcome;
 
 // Crate
 pub(crate) mod create_commit_params;
@@ -53,7 +53,9 @@ use super::{
         ValidationError,
     },
     group_context::*,
-    public_group::{diff::compute_path::PathComputationResult, PublicGroup},
+    public_group::{
+        diff::compute_path::PathComputationResult, HandshakeObserverSecrets, PublicGroup,
+    },
 };
 
 use crate::{
@@ -606,6 +608,136 @@ impl CoreGroup {
         self.message_secrets_store.resize(max_past_epochs);
     }
 
+    /// Deletes the message secrets and the resumption PSK of the past epoch
+    /// `epoch`. Returns `true` if any key material was deleted.
+    pub(crate) fn delete_past_epoch(&mut self, epoch: GroupEpoch) -> bool {
+        if epoch >= self.context().epoch() {
+            return false;
+        }
+        let deleted_message_secrets = self.message_secrets_store.delete_epoch(epoch);
+        let deleted_resumption_psk = self.resumption_psk_store.delete(epoch);
+        deleted_message_secrets || deleted_resumption_psk
+    }
+
+    /// Deletes the message secrets and the resumption PSKs of all past epochs
+    /// before `epoch`.
+    pub(crate) fn delete_past_epochs_before(&mut self, epoch: GroupEpoch) {
+        let epoch = epoch.min(self.context().epoch());
+        self.message_secrets_store.delete_epochs_before(epoch);
+        self.resumption_psk_store.delete_before(epoch);
+    }
+
+    /// Deletes the sender ratchets of the member at `leaf_index` in the given
+    /// `epoch`, which is either the current epoch or a past epoch in the
+    /// [`MessageSecretsStore`].
+    pub(crate) fn delete_sender_ratchets(
+        &mut self,
+        crypto: &impl OpenMlsCrypto,
+        epoch: GroupEpoch,
+        leaf_index: LeafNodeIndex,
+    ) -> Result<(), SecretTreeError> {
+        let ciphersuite = self.ciphersuite();
+        self.message_secrets_mut(epoch)?
+            .secret_tree_mut()
+            .delete_sender_ratchets(ciphersuite, crypto, leaf_index)
+    }
+
+    /// Returns the key material that is retained for past epochs and the
+    /// current epoch, oldest epoch first.
+    pub(crate) fn retained_epochs(&self) -> Vec<RetainedEpoch> {
+        let current_epoch = self.context().epoch();
+        // Resumption PSKs of past epochs may be kept longer than their message
+        // secrets.
+        let mut past_epochs: Vec<GroupEpoch> = self
+            .message_secrets_store
+            .past_epochs()
+            .map(|(epoch, _)| epoch)
+            .chain(
+                self.resumption_psk_store
+                    .epochs()
+                    .filter(|epoch| *epoch < current_epoch),
+            )
+            .collect();
+        past_epochs.sort();
+        past_epochs.dedup();
+
+        past_epochs
+            .into_iter()
+            .chain(std::iter::once(current_epoch))
+            .map(|epoch| {
+                let sender_ratchets =
+                    self.message_secrets_for_epoch(epoch)
+                        .ok()
+                        .map(|message_secrets| {
+                            message_secrets.secret_tree().retained_sender_ratchets()
+                        });
+                RetainedEpoch::new(
+                    epoch,
+                    sender_ratchets,
+                    self.resumption_psk_store.get(epoch).is_some(),
+                )
+            })
+            .collect()
+    }
+
+    /// Returns metrics on the keys for out-of-order messages that are
+    /// retained across the current and all past epochs.
+    pub(crate) fn key_retention_metrics(&self) -> KeyRetentionMetrics {
+        let (retained_keys, largest_ratchet_retention) = self
+            .message_secrets_store
+            .decryption_ratchets()
+            .map(|ratchet| ratchet.retained_keys())
+            .fold((0, 0), |(total, largest), retained_keys| {
+                (total + retained_keys, largest.max(retained_keys))
+            });
+        KeyRetentionMetrics::new(
+            retained_keys,
+            largest_ratchet_retention,
+            self.message_secrets_store.evicted_keys(),
+        )
+    }
+
+    /// Returns the [`HandshakeObserverSecrets`] of the current epoch. The
+    /// sender ratchets of all members are initialized if necessary. Members
+    /// whose sender ratchets were deleted are omitted.
+    pub(crate) fn handshake_observer_secrets(
+        &mut self,
+        crypto: &impl OpenMlsCrypto,
+    ) -> Result<HandshakeObserverSecrets, LibraryError> {
+        let ciphersuite = self.ciphersuite();
+        let leaf_indices: Vec<LeafNodeIndex> = self
+            .public_group
+            .members()
+            .map(|member| member.index)
+            .collect();
+        let group_id = self.group_id().clone();
+        let epoch = self.context().epoch();
+        let message_secrets = self.message_secrets_store.message_secrets_mut();
+        let mut handshake_ratchets = Vec::with_capacity(leaf_indices.len());
+        for leaf_index in leaf_indices {
+            match message_secrets.secret_tree_mut().handshake_ratchet_secret(
+                ciphersuite,
+                crypto,
+                leaf_index,
+            ) {
+                Ok(ratchet_secret) => handshake_ratchets.push((leaf_index, ratchet_secret)),
+                Err(SecretTreeError::DeletedSenderRatchets) => (),
+                Err(_) => {
+                    return Err(LibraryError::custom(
+                        "Unexpected error while deriving handshake ratchet secrets.",
+                    ))
+                }
+            }
+        }
+        Ok(HandshakeObserverSecrets::new(
+            group_id,
+            epoch,
+            ciphersuite,
+            message_secrets.sender_data_secret().copy(),
+            handshake_ratchets,
+        ))
+    }
+
     /// Get the message secrets. Either from the secrets store or from the group.
     pub(crate) fn message_secrets_mut(
         &mut self,
@@ -743,12 +875,21 @@ impl CoreGroup {
             }
         })?;
 
-        // TODO: #581 Filter proposals by support
-        // 11.2:
+        // 12.2:
         // Proposals with a non-default proposal type MUST NOT be included in a commit
         // unless the proposal type is supported by all the members of the group that
         // will process the Commit (i.e., not including any members being added
         // or removed by the Commit).
+        // Pending proposals that are not supported are excluded from the commit
+        // and reported via the staged commit.
+        let proposal_store_refs = params
+            .proposal_store()
+            .proposals()
+            .map(|queued_proposal| queued_proposal.proposal_reference())
+            .collect();
+        let (proposal_queue, unsupported_proposals) = self
+            .public_group
+            .filter_unsupported_proposals(proposal_queue, &proposal_store_refs);
 
         let proposal_reference_list = proposal_queue.commit_list();
 
@@ -758,10 +899,14 @@ impl CoreGroup {
         // ValSem102
         // ValSem103
         // ValSem104
+        // ValSem110
+        // ValSem115
         self.public_group
             .validate_key_uniqueness(&proposal_queue, None)?;
         // ValSem105
-        self.public_group.validate_add_proposals(&proposal_queue)?;
+        // ValSem116
+        self.public_group
+            .validate_add_proposals(&proposal_queue, provider.time())?;
         // ValSem106
         // ValSem109
         self.public_group.validate_capabilities(&proposal_queue)?;
@@ -773,11 +918,15 @@ impl CoreGroup {
             .validate_pre_shared_key_proposals(&proposal_queue)?;
         // Validate update proposals for member commits
         if let Sender::Member(sender_index) = &sender {
-            // ValSem110
             // ValSem111
             // ValSem112
-            self.public_group
-                .validate_update_proposals(&proposal_queue, *sender_index)?;
+            // ValSem113
+            // ValSem114
+            self.public_group.validate_update_proposals(
+                provider.crypto(),
+                &proposal_queue,
+                *sender_index,
+            )?;
         }
 
         // ValSem208
@@ -811,7 +960,8 @@ impl CoreGroup {
                     params.commit_type(),
                     signer,
                     params.take_credential_with_key(),
-                    apply_proposals_values.extensions.clone()
+                    apply_proposals_values.extensions.clone(),
+                    params.take_leaf_node_extensions(),
                 )?
             } else {
                 // If path is not needed, update the group context and return
@@ -998,7 +1148,9 @@ impl CoreGroup {
         let staged_commit = StagedCommit::new(
             proposal_queue,
             StagedCommitState::GroupMember(Box::new(staged_commit_state)),
-        );
+            None,
+        )
+        .with_unsupported_proposals(unsupported_proposals);
 
         Ok(CreateCommitResult {
             commit: authenticated_content,
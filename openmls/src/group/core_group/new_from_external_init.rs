@@ -1,8 +1,10 @@
 use crate::{
     binary_tree::array_representation::LeafNodeIndex,
+    ciphersuite::OpenMlsSignaturePublicKey,
     group::{
         core_group::create_commit_params::{CommitType, CreateCommitParams},
         errors::ExternalCommitError,
+        public_group::resync::{ResyncTarget, ResyncTbs},
     },
     messages::proposals::{ExternalInitProposal, Proposal},
 };
@@ -22,14 +24,16 @@ impl CoreGroup {
     /// Returns the new `CoreGroup` object, as well as the `PublicMessage`
     /// containing the commit.
     ///
-    /// Note: If there is a group member in the group with the same identity as us,
-    /// this will create a remove proposal.
+    /// If a [`ResyncTarget`] is given, the commit removes the targeted leaf.
+    /// Otherwise, if there is a group member in the group with the same
+    /// signature key as us, this will create a remove proposal.
     pub(crate) fn join_by_external_commit(
         provider: &impl OpenMlsProvider,
         signer: &impl Signer,
         mut params: CreateCommitParams,
         ratchet_tree: Option<RatchetTreeIn>,
         verifiable_group_info: VerifiableGroupInfo,
+        resync: Option<ResyncTarget>,
     ) -> Result<ExternalCommitResult, ExternalCommitError> {
         // Build the ratchet tree
 
@@ -90,17 +94,68 @@ impl CoreGroup {
 
         let mut inline_proposals = vec![external_init_proposal];
 
-        // If there is a group member in the group with the same identity as us,
-        // commit a remove proposal.
         let params_credential_with_key = params
             .take_credential_with_key()
             .ok_or(ExternalCommitError::MissingCredential)?;
-        if let Some(us) = public_group.members().find(|member| {
-            member.signature_key == params_credential_with_key.signature_key.as_slice()
-        }) {
-            let remove_proposal = Proposal::Remove(RemoveProposal { removed: us.index });
-            inline_proposals.push(remove_proposal);
+        let mut leaf_node_extensions = Extensions::empty();
+        let removed = match resync {
+            // If we resync, commit a remove proposal for the targeted leaf.
+            Some(ResyncTarget {
+                leaf_index,
+                old_key_signature,
+            }) => {
+                let old_leaf = public_group
+                    .leaf(leaf_index)
+                    .ok_or(ExternalCommitError::UnknownResyncLeaf)?;
+                // ValSem243
+                if old_leaf.credential().identity()
+                    != params_credential_with_key.credential.identity()
+                {
+                    return Err(ExternalCommitError::ResyncIdentityMismatch);
+                }
+                // If we use a different signature key than the old leaf, we
+                // have to prove ownership of the old leaf's signature key. We
+                // check the signature here, so that we don't send a commit that
+                // the other members are going to reject.
+                if old_leaf.signature_key() != &params_credential_with_key.signature_key {
+                    let old_key_signature =
+                        old_key_signature.ok_or(ExternalCommitError::MissingResyncProof)?;
+                    let old_signature_key = OpenMlsSignaturePublicKey::from_signature_key(
+                        old_leaf.signature_key().clone(),
+                        public_group.ciphersuite().signature_algorithm(),
+                    );
+                    ResyncTbs::new(
+                        public_group.group_id().clone(),
+                        group_context.epoch(),
+                        leaf_index,
+                        params_credential_with_key.signature_key.clone(),
+                    )
+                    .verify(
+                        provider.crypto(),
+                        &old_signature_key,
+                        old_key_signature.signature(),
+                    )
+                    .map_err(|_| ExternalCommitError::InvalidResyncSignature)?;
+                    leaf_node_extensions = Extensions::single(
+                        old_key_signature
+                            .into_extension()
+                            .map_err(LibraryError::missing_bound_check)?,
+                    );
+                }
+                Some(leaf_index)
+            }
+            // If there is a group member in the group with the same signature
+            // key as us, commit a remove proposal.
+            None => public_group
+                .members()
+                .find(|member| {
+                    member.signature_key == params_credential_with_key.signature_key.as_slice()
+                })
+                .map(|member| member.index),
         };
+        if let Some(removed) = removed {
+            inline_proposals.push(Proposal::Remove(RemoveProposal { removed }));
+        }
 
         let own_leaf_index = public_group.leftmost_free_index(inline_proposals.iter().map(Some))?;
 
@@ -120,6 +175,7 @@ impl CoreGroup {
             .inline_proposals(inline_proposals)
             .commit_type(CommitType::External)
             .credential_with_key(params_credential_with_key)
+            .leaf_node_extensions(leaf_node_extensions)
             .build();
 
         // Immediately create the commit to add ourselves to the group.
@@ -2,7 +2,7 @@ use log::debug;
 use openmls_traits::key_store::OpenMlsKeyStore;
 
 use crate::{
-    ciphersuite::hash_ref::HashReference,
+    ciphersuite::{hash_ref::HashReference, ConstantTimeEq},
     group::{core_group::*, errors::WelcomeError},
     schedule::psk::store::ResumptionPskStore,
     treesync::{
@@ -11,28 +11,110 @@ use crate::{
     },
 };
 
+/// The state of a group that was joined through a [`Welcome`] message, before
+/// any changes were made to the key store. See [`CoreGroup::stage_welcome()`].
+#[derive(Debug)]
+pub(crate) struct StagedCoreWelcome {
+    public_group: PublicGroup,
+    group_epoch_secrets: GroupEpochSecrets,
+    own_leaf_index: LeafNodeIndex,
+    use_ratchet_tree_extension: bool,
+    message_secrets_store: MessageSecretsStore,
+    resumption_psk_store: ResumptionPskStore,
+    welcome_sender_index: LeafNodeIndex,
+    leaf_keypair: EncryptionKeyPair,
+    path_keypairs: Vec<EncryptionKeyPair>,
+}
+
+impl StagedCoreWelcome {
+    /// Returns the [`PublicGroup`] of the group to be joined.
+    pub(crate) fn public_group(&self) -> &PublicGroup {
+        &self.public_group
+    }
+
+    /// Returns the leaf index of the member that created the [`Welcome`].
+    pub(crate) fn welcome_sender_index(&self) -> LeafNodeIndex {
+        self.welcome_sender_index
+    }
+
+    /// Returns our own leaf index in the group to be joined.
+    pub(crate) fn own_leaf_index(&self) -> LeafNodeIndex {
+        self.own_leaf_index
+    }
+
+    /// Turn the staged welcome into a [`CoreGroup`]. This stores the key pairs
+    /// of the new epoch and then deletes the leaf encryption key pair from the
+    /// key store.
+    pub(crate) fn into_core_group<KeyStore: OpenMlsKeyStore>(
+        self,
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+    ) -> Result<CoreGroup, WelcomeError<KeyStore::Error>> {
+        let group = CoreGroup {
+            public_group: self.public_group,
+            group_epoch_secrets: self.group_epoch_secrets,
+            own_leaf_index: self.own_leaf_index,
+            use_ratchet_tree_extension: self.use_ratchet_tree_extension,
+            message_secrets_store: self.message_secrets_store,
+            resumption_psk_store: self.resumption_psk_store,
+        };
+        let group_keypairs = vec![self.leaf_keypair]
+            .into_iter()
+            .chain(self.path_keypairs)
+            .collect::<Vec<_>>();
+        group
+            .store_epoch_keypairs(provider.key_store(), group_keypairs.as_slice())
+            .map_err(WelcomeError::KeyStoreError)?;
+
+        // The leaf key pair is only deleted once it's stored for the epoch,
+        // such that the welcome can be staged again if storing fails.
+        group_keypairs[0]
+            .delete_from_key_store(provider.key_store())
+            .map_err(|_| WelcomeError::NoMatchingEncryptionKey)?;
+
+        Ok(group)
+    }
+}
+
 impl CoreGroup {
     // Join a group from a welcome message
+    #[cfg(test)]
     pub fn new_from_welcome<KeyStore: OpenMlsKeyStore>(
         welcome: Welcome,
         ratchet_tree: Option<RatchetTreeIn>,
         key_package_bundle: KeyPackageBundle,
         provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
-        mut resumption_psk_store: ResumptionPskStore,
+        resumption_psk_store: ResumptionPskStore,
     ) -> Result<Self, WelcomeError<KeyStore::Error>> {
-        log::debug!("CoreGroup::new_from_welcome_internal");
+        Self::stage_welcome(
+            welcome,
+            ratchet_tree,
+            &key_package_bundle,
+            provider,
+            resumption_psk_store,
+        )?
+        .into_core_group(provider)
+    }
+
+    /// Decrypt and validate a [`Welcome`] message without making any changes
+    /// to the key store. The returned [`StagedCoreWelcome`] can be inspected
+    /// and turned into a [`CoreGroup`] with
+    /// [`StagedCoreWelcome::into_core_group()`].
+    pub(crate) fn stage_welcome<KeyStore: OpenMlsKeyStore>(
+        welcome: Welcome,
+        ratchet_tree: Option<RatchetTreeIn>,
+        key_package_bundle: &KeyPackageBundle,
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+        mut resumption_psk_store: ResumptionPskStore,
+    ) -> Result<StagedCoreWelcome, WelcomeError<KeyStore::Error>> {
+        log::debug!("CoreGroup::stage_welcome");
 
-        // Read the encryption key pair from the key store and delete it there.
-        // TODO #1207: Key store access happens as early as possible so it can
-        // be pulled up later more easily.
+        // Read the encryption key pair from the key store. It is only deleted
+        // when the staged welcome is turned into a group.
         let leaf_keypair = EncryptionKeyPair::read_from_key_store(
             provider,
             key_package_bundle.key_package.leaf_node().encryption_key(),
         )
         .ok_or(WelcomeError::NoMatchingEncryptionKey)?;
-        leaf_keypair
-            .delete_from_key_store(provider.key_store())
-            .map_err(|_| WelcomeError::NoMatchingEncryptionKey)?;
 
         let ciphersuite = welcome.ciphersuite();
 
@@ -160,8 +242,8 @@ impl CoreGroup {
             ))?;
 
         // If we got a path secret, derive the path (which also checks if the
-        // public keys match) and store the derived keys in the key store.
-        let group_keypairs = if let Some(path_secret) = path_secret_option {
+        // public keys match).
+        let path_keypairs = if let Some(path_secret) = path_secret_option {
             let (path_keypairs, _commit_secret) = public_group
                 .derive_path_secrets(
                     provider.crypto(),
@@ -176,12 +258,9 @@ impl CoreGroup {
                         WelcomeError::PublicTreeError(PublicTreeError::PublicKeyMismatch)
                     }
                 })?;
-            vec![leaf_keypair]
-                .into_iter()
-                .chain(path_keypairs)
-                .collect()
+            path_keypairs
         } else {
-            vec![leaf_keypair]
+            vec![]
         };
 
         let (group_epoch_secrets, message_secrets) = {
@@ -215,7 +294,7 @@ impl CoreGroup {
             .map_err(LibraryError::unexpected_crypto_error)?;
 
         // Verify confirmation tag
-        if &confirmation_tag != public_group.confirmation_tag() {
+        if !confirmation_tag.ct_eq(public_group.confirmation_tag()) {
             log::error!("Confirmation tag mismatch");
             log_crypto!(trace, "  Got:      {:x?}", confirmation_tag);
             log_crypto!(trace, "  Expected: {:x?}", public_group.confirmation_tag());
@@ -229,19 +308,17 @@ impl CoreGroup {
         let resumption_psk = group_epoch_secrets.resumption_psk();
         resumption_psk_store.add(public_group.group_context().epoch(), resumption_psk.clone());
 
-        let group = CoreGroup {
+        Ok(StagedCoreWelcome {
             public_group,
             group_epoch_secrets,
             own_leaf_index,
             use_ratchet_tree_extension: enable_ratchet_tree_extension,
             message_secrets_store,
             resumption_psk_store,
-        };
-        group
-            .store_epoch_keypairs(provider.key_store(), group_keypairs.as_slice())
-            .map_err(WelcomeError::KeyStoreError)?;
-
-        Ok(group)
+            welcome_sender_index,
+            leaf_keypair,
+            path_keypairs,
+        })
     }
 
     // Helper functions
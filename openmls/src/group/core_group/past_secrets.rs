@@ -1,6 +1,6 @@
 use std::collections::VecDeque;
 
-use crate::schedule::message_secrets::MessageSecrets;
+use crate::{schedule::message_secrets::MessageSecrets, tree::sender_ratchet::DecryptionRatchet};
 
 use super::*;
 
@@ -26,6 +26,10 @@ pub(crate) struct MessageSecretsStore {
     past_epoch_trees: VecDeque<EpochTree>,
     // The message secrets of the current epoch.
     message_secrets: MessageSecrets,
+    // The number of keys for out-of-order messages that were evicted to stay
+    // within the key retention budget.
+    #[serde(default)]
+    evicted_keys: u64,
 }
 
 #[cfg(not(feature = "crypto-debug"))]
@@ -35,6 +39,7 @@ impl core::fmt::Debug for MessageSecretsStore {
             .field("max_epochs", &"***")
             .field("past_epoch_trees", &"***")
             .field("message_secrets", &"***")
+            .field("evicted_keys", &self.evicted_keys)
             .finish()
     }
 }
@@ -47,6 +52,7 @@ impl MessageSecretsStore {
             max_epochs,
             past_epoch_trees: VecDeque::new(),
             message_secrets,
+            evicted_keys: 0,
         }
     }
 
@@ -91,6 +97,32 @@ impl MessageSecretsStore {
         );
     }
 
+    /// Delete the message secrets and leaves of the past epoch `group_epoch`.
+    /// Returns `true` if the epoch was in the store and `false` otherwise.
+    pub(crate) fn delete_epoch(&mut self, group_epoch: impl Into<GroupEpoch>) -> bool {
+        let epoch = group_epoch.into().as_u64();
+        let old_len = self.past_epoch_trees.len();
+        self.past_epoch_trees
+            .retain(|epoch_tree| epoch_tree.epoch != epoch);
+        self.past_epoch_trees.len() != old_len
+    }
+
+    /// Delete the message secrets and leaves of all past epochs before
+    /// `group_epoch`.
+    pub(crate) fn delete_epochs_before(&mut self, group_epoch: impl Into<GroupEpoch>) {
+        let epoch = group_epoch.into().as_u64();
+        self.past_epoch_trees
+            .retain(|epoch_tree| epoch_tree.epoch >= epoch);
+    }
+
+    /// Returns an iterator over the past epochs in the store and their
+    /// message secrets.
+    pub(crate) fn past_epochs(&self) -> impl Iterator<Item = (GroupEpoch, &MessageSecrets)> {
+        self.past_epoch_trees
+            .iter()
+            .map(|epoch_tree| (epoch_tree.epoch.into(), &epoch_tree.message_secrets))
+    }
+
     /// Get a mutable reference to a secret tree for a given epoch `group_epoch`.
     /// If no message secrets are found for that epoch, `None` is returned.
     pub(crate) fn secrets_for_epoch_mut(
@@ -162,6 +194,90 @@ impl MessageSecretsStore {
         })
     }
 
+    /// Returns an iterator over the decryption ratchets of the current and all
+    /// past epochs.
+    pub(crate) fn decryption_ratchets(&self) -> impl Iterator<Item = &DecryptionRatchet> {
+        self.past_epoch_trees
+            .iter()
+            .map(|epoch_tree| &epoch_tree.message_secrets)
+            .chain(std::iter::once(&self.message_secrets))
+            .flat_map(|message_secrets| message_secrets.secret_tree().decryption_ratchets())
+    }
+
+    /// Returns the number of keys for out-of-order messages that were evicted
+    /// to stay within the key retention budget.
+    pub(crate) fn evicted_keys(&self) -> u64 {
+        self.evicted_keys
+    }
+
+    /// Returns the number of keys for out-of-order messages that are retained
+    /// across the current and all past epochs.
+    pub(crate) fn retained_keys(&self) -> usize {
+        self.message_secrets().secret_tree().retained_keys()
+            + self
+                .past_epoch_trees
+                .iter()
+                .map(|epoch_tree| epoch_tree.message_secrets.secret_tree().retained_keys())
+                .sum::<usize>()
+    }
+
+    /// Evict keys for out-of-order messages until at most
+    /// `maximum_retained_keys` keys are retained across the decryption
+    /// ratchets of the current and all past epochs.
+    ///
+    /// Keys are evicted from the ratchets that retain the most keys first, by
+    /// limiting all ratchets to the largest number of keys per ratchet that
+    /// keeps the total within the budget. Within a ratchet, the oldest keys
+    /// are evicted first. This way, a sender that makes the group retain many
+    /// keys can't cause the keys of other senders to be evicted.
+    ///
+    /// The secret trees keep count of their retained keys, so that checking
+    /// the budget only takes one step per epoch. The ratchets are only
+    /// scanned if the budget is exceeded.
+    ///
+    /// Returns the number of evicted keys.
+    pub(crate) fn enforce_key_retention_budget(&mut self, maximum_retained_keys: usize) -> usize {
+        if self.retained_keys() <= maximum_retained_keys {
+            return 0;
+        }
+        let retained_keys: Vec<usize> = self
+            .decryption_ratchets()
+            .map(|ratchet| ratchet.retained_keys())
+            .collect();
+        let total = |cap: usize| -> usize {
+            retained_keys
+                .iter()
+                .map(|&retained| retained.min(cap))
+                .sum()
+        };
+
+        // Find the largest cap per ratchet that keeps the total within the
+        // budget. A cap of `0` always does.
+        let mut lower = 0;
+        let mut upper = retained_keys.iter().copied().max().unwrap_or_default();
+        while lower < upper {
+            let cap = lower + (upper - lower).div_ceil(2);
+            if total(cap) <= maximum_retained_keys {
+                lower = cap;
+            } else {
+                upper = cap - 1;
+            }
+        }
+
+        let evicted: usize = self
+            .past_epoch_trees
+            .iter_mut()
+            .map(|epoch_tree| &mut epoch_tree.message_secrets)
+            .chain(std::iter::once(&mut self.message_secrets))
+            .map(|message_secrets| message_secrets.secret_tree_mut().evict_retained_keys(lower))
+            .sum();
+        if evicted > 0 {
+            log::debug!("Evicted {evicted} keys for out-of-order messages.");
+        }
+        self.evicted_keys = self.evicted_keys.saturating_add(evicted as u64);
+        evicted
+    }
+
     /// Get a mutable reference to the message secrets of the current epoch.
     pub(crate) fn message_secrets_mut(&mut self) -> &mut MessageSecrets {
         &mut self.message_secrets
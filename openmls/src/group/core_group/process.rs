@@ -1,7 +1,7 @@
 use core_group::proposals::QueuedProposal;
 
 use crate::{
-    framing::mls_content::FramedContentBody,
+    framing::{mls_content::FramedContentBody, CheckDecodeLimits, DecodeLimits},
     group::{
         errors::{MergeCommitError, StageCommitError, ValidationError},
         mls_group::errors::ProcessMessageError,
@@ -49,8 +49,12 @@ impl CoreGroup {
         // Checks the following semantic validation:
         //  - ValSem010
         //  - ValSem246 (as part of ValSem010)
-        let (content, credential) =
-            unverified_message.verify(self.ciphersuite(), provider.crypto(), self.version())?;
+        let (content, credential) = unverified_message.verify(
+            self.ciphersuite(),
+            provider.crypto(),
+            provider.time(),
+            self.version(),
+        )?;
 
         match content.sender() {
             Sender::Member(_) | Sender::NewMemberCommit | Sender::NewMemberProposal => {
@@ -103,28 +107,27 @@ impl CoreGroup {
                     FramedContentBody::Application(_) => {
                         Err(ProcessMessageError::UnauthorizedExternalApplicationMessage)
                     }
-                    FramedContentBody::Proposal(Proposal::Remove(_)) => {
-                        let content = ProcessedMessageContent::ProposalMessage(Box::new(
-                            QueuedProposal::from_authenticated_content_by_ref(
-                                self.ciphersuite(),
-                                provider.crypto(),
-                                content,
-                            )?,
-                        ));
+                    FramedContentBody::Proposal(_) => {
+                        let proposal = QueuedProposal::from_authenticated_content_by_ref(
+                            self.ciphersuite(),
+                            provider.crypto(),
+                            content,
+                        )?;
+                        self.public_group
+                            .validate_external_proposal(&proposal, provider.time())?;
                         Ok(ProcessedMessage::new(
                             self.group_id().clone(),
                             self.context().epoch(),
                             sender,
                             data,
-                            content,
+                            ProcessedMessageContent::ProposalMessage(Box::new(proposal)),
                             credential,
                         ))
                     }
-                    // TODO #151/#106
-                    FramedContentBody::Proposal(_) => {
-                        Err(ProcessMessageError::UnsupportedProposalType)
-                    }
-                    FramedContentBody::Commit(_) => unimplemented!(),
+                    // A commit cannot be issued by a preconfigured sender.
+                    FramedContentBody::Commit(_) => Err(ProcessMessageError::InvalidCommit(
+                        StageCommitError::SenderTypeExternal,
+                    )),
                 }
             }
         }
@@ -171,18 +174,35 @@ impl CoreGroup {
         provider: &impl OpenMlsProvider,
         message: impl Into<ProtocolMessage>,
         sender_ratchet_configuration: &SenderRatchetConfiguration,
+        decode_limits: &DecodeLimits,
         proposal_store: &ProposalStore,
         own_leaf_nodes: &[LeafNode],
     ) -> Result<ProcessedMessage, ProcessMessageError> {
         let message: ProtocolMessage = message.into();
 
+        decode_limits.check_message_size(message.tls_serialized_len())?;
+
         // Checks the following semantic validation:
         //  - ValSem002
         //  - ValSem003
         //  - ValSem006
         //  - ValSem007 MembershipTag presence
-        let decrypted_message =
-            self.decrypt_message(provider.crypto(), message, sender_ratchet_configuration)?;
+        let decrypted_message = self
+            .decrypt_message(provider.crypto(), message, sender_ratchet_configuration)
+            .map_err(|e| match e {
+                // The key material for this generation was already used to
+                // decrypt a message.
+                ValidationError::UnableToDecrypt(MessageDecryptionError::SecretTreeError(
+                    SecretTreeError::SecretReuseError,
+                )) => ProcessMessageError::ReplayedMessage,
+                e => e.into(),
+            })?;
+
+        // The content of private messages can only be checked after
+        // decryption.
+        decrypted_message
+            .verifiable_content()
+            .check_decode_limits(decode_limits)?;
 
         let unverified_message = self
             .public_group
@@ -252,12 +272,18 @@ impl CoreGroup {
             }
             ProtocolMessage::PrivateMessage(ciphertext) => {
                 // If the message is older than the current epoch, we need to fetch the correct secret tree first
-                DecryptedMessage::from_inbound_ciphertext(
+                let decrypted_message = DecryptedMessage::from_inbound_ciphertext(
                     ciphertext,
                     crypto,
                     self,
                     sender_ratchet_configuration,
-                )
+                );
+                // Decryption may have left keys for out-of-order messages
+                // behind, even if it failed.
+                self.message_secrets_store.enforce_key_retention_budget(
+                    sender_ratchet_configuration.maximum_retained_keys(),
+                );
+                decrypted_message
             }
         }
     }
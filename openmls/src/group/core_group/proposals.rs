@@ -8,6 +8,7 @@ use crate::{
     binary_tree::array_representation::LeafNodeIndex,
     ciphersuite::hash_ref::ProposalRef,
     error::LibraryError,
+    extensions::ExtensionType,
     framing::{mls_auth_content::AuthenticatedContent, mls_content::FramedContentBody, Sender},
     group::errors::*,
     messages::proposals::{
@@ -572,6 +573,53 @@ impl ProposalQueue {
     }
 }
 
+/// A proposal from the [`ProposalStore`] that was not included in a commit,
+/// because it is not supported by all members that will process the commit.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct UnsupportedProposal {
+    queued_proposal: QueuedProposal,
+    reason: UnsupportedProposalReason,
+}
+
+impl UnsupportedProposal {
+    pub(crate) fn new(queued_proposal: QueuedProposal, reason: UnsupportedProposalReason) -> Self {
+        Self {
+            queued_proposal,
+            reason,
+        }
+    }
+
+    /// Returns the unsupported [`QueuedProposal`].
+    pub fn queued_proposal(&self) -> &QueuedProposal {
+        &self.queued_proposal
+    }
+
+    /// Returns the reason why the proposal is not supported.
+    pub fn reason(&self) -> &UnsupportedProposalReason {
+        &self.reason
+    }
+}
+
+/// The reason why an [`UnsupportedProposal`] was not included in a commit.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum UnsupportedProposalReason {
+    /// The member at the given leaf index doesn't support the proposal type.
+    UnsupportedProposalType {
+        /// The type of the proposal.
+        proposal_type: ProposalType,
+        /// The index of the member that doesn't support the proposal type.
+        member: LeafNodeIndex,
+    },
+    /// The member at the given leaf index doesn't support an extension of a
+    /// group context extensions proposal.
+    UnsupportedExtension {
+        /// The type of the unsupported extension.
+        extension_type: ExtensionType,
+        /// The index of the member that doesn't support the extension.
+        member: LeafNodeIndex,
+    },
+}
+
 /// A queued Add proposal
 #[derive(PartialEq, Debug)]
 pub struct QueuedAddProposal<'a> {
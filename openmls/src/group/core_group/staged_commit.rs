@@ -6,8 +6,8 @@ use public_group::diff::{apply_proposals::ApplyProposalsValues, StagedPublicGrou
 
 use super::{super::errors::*, proposals::ProposalStore, *};
 use crate::{
-    framing::mls_auth_content::AuthenticatedContent,
-    treesync::node::encryption_keys::EncryptionKeyPair,
+    ciphersuite::ConstantTimeEq, framing::mls_auth_content::AuthenticatedContent,
+    group::public_group::ExternalResync, treesync::node::encryption_keys::EncryptionKeyPair,
 };
 
 impl CoreGroup {
@@ -132,9 +132,18 @@ impl CoreGroup {
 
         let ciphersuite = self.ciphersuite();
 
-        let (commit, proposal_queue, sender_index) =
-            self.public_group
-                .validate_commit(mls_content, proposal_store, provider.crypto())?;
+        let (commit, proposal_queue, sender_index) = self.public_group.validate_commit(
+            mls_content,
+            proposal_store,
+            provider.crypto(),
+            provider.time(),
+        )?;
+        let external_resync = self.public_group.external_resync(
+            provider.crypto(),
+            mls_content.sender(),
+            commit,
+            &proposal_queue,
+        )?;
 
         // Create the provisional public group state (including the tree and
         // group context) and apply proposals.
@@ -149,6 +158,7 @@ impl CoreGroup {
             return Ok(StagedCommit::new(
                 proposal_queue,
                 StagedCommitState::PublicState(Box::new(staged_diff)),
+                external_resync,
             ));
         }
 
@@ -272,7 +282,7 @@ impl CoreGroup {
                 diff.group_context().confirmed_transcript_hash(),
             )
             .map_err(LibraryError::unexpected_crypto_error)?;
-        if &own_confirmation_tag != received_confirmation_tag {
+        if !own_confirmation_tag.ct_eq(received_confirmation_tag) {
             log::error!("Confirmation tag mismatch");
             log_crypto!(trace, "  Got:      {:x?}", received_confirmation_tag);
             log_crypto!(trace, "  Expected: {:x?}", own_confirmation_tag);
@@ -295,7 +305,11 @@ impl CoreGroup {
                 update_path_leaf_node,
             )));
 
-        Ok(StagedCommit::new(proposal_queue, staged_commit_state))
+        Ok(StagedCommit::new(
+            proposal_queue,
+            staged_commit_state,
+            external_resync,
+        ))
     }
 
     /// Merges a [StagedCommit] into the group state and optionally return a [`SecretTree`]
@@ -332,11 +346,13 @@ impl CoreGroup {
                 // correlated s.t. there is no divergence between key material
                 // and group state.
 
-                let leaf_keypair = if let Some(keypair) = &state.new_leaf_keypair_option {
-                    vec![keypair.clone()]
-                } else {
-                    vec![]
-                };
+                // The new leaf keypair is moved into the epoch keypairs. Only
+                // its public key is needed to delete it from the key store
+                // afterwards, so we don't clone the private key.
+                let new_leaf_public_key = state
+                    .new_leaf_keypair_option
+                    .as_ref()
+                    .map(|keypair| keypair.public_key().clone());
 
                 // Figure out which keys we need in the new epoch.
                 let new_owned_encryption_keys = self
@@ -346,7 +362,7 @@ impl CoreGroup {
                 let epoch_keypairs: Vec<EncryptionKeyPair> = old_epoch_keypairs
                     .into_iter()
                     .chain(state.new_keypairs)
-                    .chain(leaf_keypair)
+                    .chain(state.new_leaf_keypair_option)
                     .filter(|keypair| new_owned_encryption_keys.contains(keypair.public_key()))
                     .collect();
                 // We should have private keys for all owned encryption keys.
@@ -364,10 +380,12 @@ impl CoreGroup {
                 // Delete the old keys.
                 self.delete_previous_epoch_keypairs(provider.key_store())
                     .map_err(MergeCommitError::KeyStoreError)?;
-                if let Some(keypair) = state.new_leaf_keypair_option {
-                    keypair
-                        .delete_from_key_store(provider.key_store())
-                        .map_err(MergeCommitError::KeyStoreError)?;
+                if let Some(public_key) = new_leaf_public_key {
+                    EncryptionKeyPair::delete_from_key_store_by_public_key(
+                        provider.key_store(),
+                        &public_key,
+                    )
+                    .map_err(MergeCommitError::KeyStoreError)?;
                 }
 
                 Ok(Some(message_secrets))
@@ -409,18 +427,37 @@ pub(crate) enum StagedCommitState {
 pub struct StagedCommit {
     staged_proposal_queue: ProposalQueue,
     state: StagedCommitState,
+    #[serde(default)]
+    external_resync: Option<ExternalResync>,
+    #[serde(default)]
+    unsupported_proposals: Vec<UnsupportedProposal>,
 }
 
 impl StagedCommit {
     /// Create a new [`StagedCommit`] from the provisional group state created
     /// during the commit process.
-    pub(crate) fn new(staged_proposal_queue: ProposalQueue, state: StagedCommitState) -> Self {
+    pub(crate) fn new(
+        staged_proposal_queue: ProposalQueue,
+        state: StagedCommitState,
+        external_resync: Option<ExternalResync>,
+    ) -> Self {
         StagedCommit {
             staged_proposal_queue,
             state,
+            external_resync,
+            unsupported_proposals: Vec::new(),
         }
     }
 
+    /// Set the pending proposals that were not included in the commit.
+    pub(crate) fn with_unsupported_proposals(
+        mut self,
+        unsupported_proposals: Vec<UnsupportedProposal>,
+    ) -> Self {
+        self.unsupported_proposals = unsupported_proposals;
+        self
+    }
+
     /// Returns the Add proposals that are covered by the Commit message as in iterator over [QueuedAddProposal].
     pub fn add_proposals(&self) -> impl Iterator<Item = QueuedAddProposal> {
         self.staged_proposal_queue.add_proposals()
@@ -441,6 +478,13 @@ impl StagedCommit {
         self.staged_proposal_queue.psk_proposals()
     }
 
+    /// Returns the pending proposals that were not included in the commit,
+    /// because they are not supported by all members that will process it.
+    /// This is only populated for commits created by this client.
+    pub fn unsupported_proposals(&self) -> &[UnsupportedProposal] {
+        &self.unsupported_proposals
+    }
+
     /// Returns an iterator over all [`QueuedProposal`]s.
     pub(crate) fn queued_proposals(&self) -> impl Iterator<Item = &QueuedProposal> {
         self.staged_proposal_queue.queued_proposals()
@@ -503,6 +547,19 @@ impl StagedCommit {
             )
     }
 
+    /// Returns the [`ExternalResync`] if this is an external commit that
+    /// replaces a prior leaf of the group, i.e. a member rejoining after losing
+    /// its state.
+    pub fn external_resync(&self) -> Option<&ExternalResync> {
+        self.external_resync.as_ref()
+    }
+
+    /// Returns `true` if this is an external commit that replaces a prior leaf
+    /// of the group and `false` otherwise.
+    pub fn is_resync(&self) -> bool {
+        self.external_resync.is_some()
+    }
+
     /// Returns `true` if the member was removed through a proposal covered by this Commit message
     /// and `false` otherwise.
     pub fn self_removed(&self) -> bool {
@@ -50,6 +50,7 @@ fn test_external_init(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider)
         params,
         None,
         verifiable_group_info,
+        None,
     )
     .expect("Error initializing group externally.");
 
@@ -128,6 +129,7 @@ fn test_external_init(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider)
         params,
         Some(ratchet_tree.into()),
         verifiable_group_info,
+        None,
     )
     .expect("Error initializing group externally.");
 
@@ -215,6 +217,7 @@ fn test_external_init_single_member_group(
         params,
         Some(ratchet_tree.into()),
         verifiable_group_info,
+        None,
     )
     .expect("Error initializing group externally.");
 
@@ -278,7 +281,8 @@ fn test_external_init_broken_signature(ciphersuite: Ciphersuite, provider: &impl
             &charlie_signer,
             params,
             None,
-            verifiable_group_info
+            verifiable_group_info,
+            None,
         )
         .expect_err("Signature was corrupted. This should have failed.")
     );
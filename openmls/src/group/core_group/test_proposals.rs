@@ -44,7 +44,7 @@ fn proposal_queue_functions(ciphersuite: Ciphersuite, provider: &impl OpenMlsPro
     let alice_update_key_package = alice_update_key_package_bundle.key_package();
     let kpi = KeyPackageIn::from(alice_update_key_package.clone());
     assert!(kpi
-        .validate(provider.crypto(), ProtocolVersion::Mls10)
+        .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
         .is_ok());
 
     let group_context = GroupContext::new(
@@ -188,7 +188,7 @@ fn proposal_queue_order(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvide
     let alice_update_key_package = alice_update_key_package_bundle.key_package();
     let kpi = KeyPackageIn::from(alice_update_key_package.clone());
     assert!(kpi
-        .validate(provider.crypto(), ProtocolVersion::Mls10)
+        .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
         .is_ok());
 
     let group_context = GroupContext::new(
@@ -121,6 +121,18 @@ pub enum ExternalCommitError {
     /// Credential is missing from external commit.
     #[error("Credential is missing from external commit.")]
     MissingCredential,
+    /// The leaf to be replaced in a resync is not part of the group.
+    #[error("The leaf to be replaced in a resync is not part of the group.")]
+    UnknownResyncLeaf,
+    /// The leaf to be replaced in a resync has a different identity.
+    #[error("The leaf to be replaced in a resync has a different identity.")]
+    ResyncIdentityMismatch,
+    /// The leaf to be replaced in a resync has a different signature key.
+    #[error("The leaf to be replaced in a resync has a different signature key.")]
+    MissingResyncProof,
+    /// The resync signature is invalid.
+    #[error("The resync signature is invalid.")]
+    InvalidResyncSignature,
 }
 
 /// Stage Commit error
@@ -360,6 +372,12 @@ pub enum ProposalValidationError {
     /// The Commit includes update proposals from the committer.
     #[error("The Commit includes update proposals from the committer.")]
     CommitterIncludedOwnUpdate,
+    /// The leaf node in an update proposal doesn't have the source `update`.
+    #[error("The leaf node in an update proposal doesn't have the source `update`.")]
+    InvalidLeafNodeSource,
+    /// The signature of the leaf node in an update proposal is invalid.
+    #[error("The signature of the leaf node in an update proposal is invalid.")]
+    InvalidLeafNodeSignature,
     /// The capabilities of the add proposal are insufficient for this group.
     #[error("The capabilities of the add proposal are insufficient for this group.")]
     InsufficientCapabilities,
@@ -368,6 +386,12 @@ pub enum ProposalValidationError {
         "The add proposal's ciphersuite or protocol version do not match the ones in the group context."
     )]
     InvalidAddProposalCiphersuiteOrVersion,
+    /// The lifetime of the key package in an add proposal is not valid.
+    #[error("The lifetime of the key package in an add proposal is not valid.")]
+    InvalidAddProposalLifetime,
+    /// The protocol version of a ReInit proposal is lower than the one of the group.
+    #[error("The protocol version of a ReInit proposal is lower than the one of the group.")]
+    InvalidReInitProposalVersion,
     /// See [`PskError`] for more details.
     #[error(transparent)]
     Psk(#[from] PskError),
@@ -405,6 +429,9 @@ pub enum ExternalCommitValidationError {
     /// External commit contains referenced proposal
     #[error("Found a referenced proposal in an External Commit.")]
     ReferencedProposal,
+    /// The resync signature in the path leaf node is invalid.
+    #[error("The resync signature in the path leaf node is invalid.")]
+    InvalidResyncSignature,
 }
 
 /// Create add proposal error
@@ -506,6 +533,17 @@ pub enum CreateGroupContextExtProposalError {
     LeafNodeValidation(#[from] LeafNodeValidationError),
 }
 
+/// Create ReInit proposal error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum ProposeReInitError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The protocol version of the new group is a pre-RFC draft version.
+    #[error("The protocol version of the new group is a pre-RFC draft version.")]
+    UnsupportedProtocolVersion,
+}
+
 /// Error merging a commit.
 #[derive(Error, Debug, PartialEq, Clone)]
 pub enum MergeCommitError<KeyStoreError> {
@@ -110,6 +110,7 @@ impl MlsGroupBuilder {
             group,
             proposal_store: ProposalStore::new(),
             own_leaf_nodes: vec![],
+            dropped_proposals: vec![],
             aad: vec![],
             group_state: MlsGroupState::Operational,
             state_changed: InnerState::Changed,
@@ -29,8 +29,11 @@
 
 use super::*;
 use crate::{
-    extensions::errors::InvalidExtensionError, group::config::CryptoConfig, key_packages::Lifetime,
-    tree::sender_ratchet::SenderRatchetConfiguration, treesync::node::leaf_node::Capabilities,
+    extensions::errors::InvalidExtensionError,
+    group::{config::CryptoConfig, public_group::ExternalResync},
+    key_packages::Lifetime,
+    tree::sender_ratchet::SenderRatchetConfiguration,
+    treesync::node::leaf_node::Capabilities,
 };
 use serde::{Deserialize, Serialize};
 
@@ -54,6 +57,12 @@ pub struct MlsGroupJoinConfig {
     pub(crate) use_ratchet_tree_extension: bool,
     /// Sender ratchet configuration
     pub(crate) sender_ratchet_configuration: SenderRatchetConfiguration,
+    /// Defines which external commits that replace a prior leaf are accepted
+    #[serde(default)]
+    pub(crate) resync_policy: ResyncPolicy,
+    /// Limits for decoding and processing incoming messages
+    #[serde(default)]
+    pub(crate) decode_limits: DecodeLimits,
 }
 
 impl MlsGroupJoinConfig {
@@ -76,6 +85,16 @@ impl MlsGroupJoinConfig {
     pub fn sender_ratchet_configuration(&self) -> &SenderRatchetConfiguration {
         &self.sender_ratchet_configuration
     }
+
+    /// Returns the [`ResyncPolicy`] set in this  [`MlsGroupJoinConfig`].
+    pub fn resync_policy(&self) -> ResyncPolicy {
+        self.resync_policy
+    }
+
+    /// Returns the [`DecodeLimits`] set in this  [`MlsGroupJoinConfig`].
+    pub fn decode_limits(&self) -> &DecodeLimits {
+        &self.decode_limits
+    }
 }
 
 /// Specifies configuration for the creation of an [`MlsGroup`]. Refer to the
@@ -150,6 +169,18 @@ impl MlsGroupJoinConfigBuilder {
         self
     }
 
+    /// Sets the `resync_policy` property of the [`MlsGroupJoinConfig`].
+    pub fn resync_policy(mut self, resync_policy: ResyncPolicy) -> Self {
+        self.join_config.resync_policy = resync_policy;
+        self
+    }
+
+    /// Sets the `decode_limits` property of the [`MlsGroupJoinConfig`].
+    pub fn decode_limits(mut self, decode_limits: DecodeLimits) -> Self {
+        self.join_config.decode_limits = decode_limits;
+        self
+    }
+
     /// Finalizes the builder and returns an [`MlsGroupJoinConfig`].
     pub fn build(self) -> MlsGroupJoinConfig {
         self.join_config
@@ -192,6 +223,16 @@ impl MlsGroupCreateConfig {
         &self.join_config.sender_ratchet_configuration
     }
 
+    /// Returns the [`MlsGroupCreateConfig`] resync policy.
+    pub fn resync_policy(&self) -> ResyncPolicy {
+        self.join_config.resync_policy
+    }
+
+    /// Returns the [`MlsGroupCreateConfig`] decode limits.
+    pub fn decode_limits(&self) -> &DecodeLimits {
+        &self.join_config.decode_limits
+    }
+
     /// Returns the [`Extensions`] set as the initial group context.
     /// This does not contain the initial group context extensions
     /// added from builder calls to `external_senders` or `required_capabilities`.
@@ -295,6 +336,20 @@ impl MlsGroupCreateConfigBuilder {
         self
     }
 
+    /// Sets the `resync_policy` property of the MlsGroupCreateConfig.
+    /// See [`ResyncPolicy`] for more information.
+    pub fn resync_policy(mut self, resync_policy: ResyncPolicy) -> Self {
+        self.config.join_config.resync_policy = resync_policy;
+        self
+    }
+
+    /// Sets the `decode_limits` property of the MlsGroupCreateConfig.
+    /// See [`DecodeLimits`] for more information.
+    pub fn decode_limits(mut self, decode_limits: DecodeLimits) -> Self {
+        self.config.join_config.decode_limits = decode_limits;
+        self
+    }
+
     /// Sets the `lifetime` property of the MlsGroupCreateConfig.
     pub fn lifetime(mut self, lifetime: Lifetime) -> Self {
         self.config.lifetime = lifetime;
@@ -345,6 +400,36 @@ impl MlsGroupCreateConfigBuilder {
     }
 }
 
+/// Defines which external commits that replace a prior leaf of the group are
+/// accepted when processing incoming messages. See
+/// [`StagedCommit::external_resync()`] for how to inspect such commits.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResyncPolicy {
+    /// Accept all resync commits. Note that resync commits without a proof
+    /// only guarantee that the joiner has the same credential identity as the
+    /// removed member.
+    #[default]
+    AcceptAll,
+    /// Only accept resync commits in which the joiner proved ownership of the
+    /// removed leaf, either by using the same signature key or through a
+    /// signature by the removed leaf's signature key.
+    RequireProof,
+    /// Reject all resync commits.
+    RejectAll,
+}
+
+impl ResyncPolicy {
+    /// Returns `true` if the given [`ExternalResync`] is acceptable under this
+    /// policy.
+    pub fn accepts(&self, resync: &ExternalResync) -> bool {
+        match self {
+            ResyncPolicy::AcceptAll => true,
+            ResyncPolicy::RequireProof => resync.is_proven(),
+            ResyncPolicy::RejectAll => false,
+        }
+    }
+}
+
 /// Defines what wire format is acceptable for incoming handshake messages.
 /// Note that application messages must always be encrypted.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -1,12 +1,19 @@
 use openmls_traits::signatures::Signer;
 
+#[cfg(doc)]
+use crate::group::{public_group::RESYNC_SIGNATURE_EXTENSION_TYPE, StagedCommit};
+
 use super::{builder::MlsGroupBuilder, *};
 use crate::{
-    ciphersuite::HpkePrivateKey,
+    ciphersuite::{signable::Signable, HpkePrivateKey},
     credentials::CredentialWithKey,
+    framing::errors::MlsMessageBodyError,
     group::{
-        core_group::create_commit_params::CreateCommitParams,
-        errors::{ExternalCommitError, WelcomeError},
+        core_group::{
+            create_commit_params::CreateCommitParams, new_from_welcome::StagedCoreWelcome,
+        },
+        errors::{ExternalCommitError, JoinError, WelcomeError},
+        public_group::resync::{ResyncTarget, ResyncTbs},
     },
     messages::group_info::{GroupInfo, VerifiableGroupInfo},
     schedule::psk::store::ResumptionPskStore,
@@ -63,68 +70,65 @@ impl MlsGroup {
     /// Creates a new group from a [`Welcome`] message. Returns an error
     /// ([`WelcomeError::NoMatchingKeyPackage`]) if no [`KeyPackage`]
     /// can be found.
-    // TODO: #1326 This should take an MlsMessage rather than a Welcome message.
+    ///
+    /// To inspect the group before joining it, use
+    /// [`StagedWelcome::new_from_welcome()`] instead. To join a group from an
+    /// [`MlsMessageIn`] without unpacking it first, use
+    /// [`MlsGroup::join_from_message()`].
     pub fn new_from_welcome<KeyStore: OpenMlsKeyStore>(
         provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
         mls_group_config: &MlsGroupJoinConfig,
         welcome: Welcome,
         ratchet_tree: Option<RatchetTreeIn>,
     ) -> Result<Self, WelcomeError<KeyStore::Error>> {
-        let resumption_psk_store =
-            ResumptionPskStore::new(mls_group_config.number_of_resumption_psks);
-        let (key_package, _) = welcome
-            .secrets()
-            .iter()
-            .find_map(|egs| {
-                let hash_ref = egs.new_member().as_slice().to_vec();
-                provider
-                    .key_store()
-                    .read(&hash_ref)
-                    .map(|kp: KeyPackage| (kp, hash_ref))
-            })
-            .ok_or(WelcomeError::NoMatchingKeyPackage)?;
-
-        // TODO #751
-        let private_key = provider
-            .key_store()
-            .read::<HpkePrivateKey>(key_package.hpke_init_key().as_slice())
-            .ok_or(WelcomeError::NoMatchingKeyPackage)?;
-        let key_package_bundle = KeyPackageBundle {
-            key_package,
-            private_key,
-        };
+        StagedWelcome::new_from_welcome(provider, mls_group_config, welcome, ratchet_tree)?
+            .into_group(provider)
+    }
 
-        // Delete the [`KeyPackage`] and the corresponding private key from the
-        // key store, but only if it doesn't have a last resort extension.
-        if !key_package_bundle.key_package().last_resort() {
-            key_package_bundle
-                .key_package
-                .delete(provider)
-                .map_err(WelcomeError::KeyStoreError)?;
-        } else {
-            log::debug!("Key package has last resort extension, not deleting");
+    /// Join a group from an [`MlsMessageIn`] that either contains a
+    /// [`Welcome`] or a group info.
+    ///
+    /// If the message contains a [`Welcome`], the group is joined as in
+    /// [`MlsGroup::new_from_welcome()`] and `signer`, `aad` and
+    /// `credential_with_key` are ignored. If it contains a group info, the
+    /// group is joined through an External Commit as in
+    /// [`MlsGroup::join_by_external_commit()`]. In this case, the commit (and
+    /// the group info if the group is configured to create one) is returned
+    /// alongside the group and the commit has to be merged once the DS accepted
+    /// it.
+    ///
+    /// Returns a [`JoinError::MlsMessageBody`] if the message doesn't use MLS
+    /// 1.0 or contains neither a [`Welcome`] nor a group info.
+    #[allow(clippy::type_complexity)]
+    pub fn join_from_message<KeyStore: OpenMlsKeyStore>(
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+        signer: &impl Signer,
+        message: MlsMessageIn,
+        ratchet_tree: Option<RatchetTreeIn>,
+        mls_group_config: &MlsGroupJoinConfig,
+        aad: &[u8],
+        credential_with_key: CredentialWithKey,
+    ) -> Result<(Self, Option<MlsMessageOut>, Option<GroupInfo>), JoinError<KeyStore::Error>> {
+        match message.into_supported_body()? {
+            MlsMessageBodyIn::Welcome(welcome) => {
+                let group =
+                    Self::new_from_welcome(provider, mls_group_config, welcome, ratchet_tree)?;
+                Ok((group, None, None))
+            }
+            MlsMessageBodyIn::GroupInfo(verifiable_group_info) => {
+                let (group, commit, group_info) = Self::join_by_external_commit(
+                    provider,
+                    signer,
+                    ratchet_tree,
+                    verifiable_group_info,
+                    mls_group_config,
+                    aad,
+                    credential_with_key,
+                )?;
+                Ok((group, Some(commit), group_info))
+            }
+            body => Err(MlsMessageBodyError::UnexpectedWireFormat(body.wire_format()).into()),
         }
-
-        let mut group = CoreGroup::new_from_welcome(
-            welcome,
-            ratchet_tree,
-            key_package_bundle,
-            provider,
-            resumption_psk_store,
-        )?;
-        group.set_max_past_epochs(mls_group_config.max_past_epochs);
-
-        let mls_group = MlsGroup {
-            mls_group_config: mls_group_config.clone(),
-            group,
-            proposal_store: ProposalStore::new(),
-            own_leaf_nodes: vec![],
-            aad: vec![],
-            group_state: MlsGroupState::Operational,
-            state_changed: InnerState::Changed,
-        };
-
-        Ok(mls_group)
     }
 
     /// Join an existing group through an External Commit.
@@ -139,8 +143,10 @@ impl MlsGroup {
     /// group info. For more information on the external init process,
     /// please see Section 11.2.1 in the MLS specification.
     ///
-    /// Note: If there is a group member in the group with the same identity as
-    /// us, this will create a remove proposal.
+    /// Note: If there is a group member in the group with the same signature
+    /// key as us, this will create a remove proposal. Use
+    /// [`MlsGroup::resync_by_external_commit()`] to choose the leaf that is
+    /// replaced explicitly.
     pub fn join_by_external_commit(
         provider: &impl OpenMlsProvider,
         signer: &impl Signer,
@@ -149,6 +155,124 @@ impl MlsGroup {
         mls_group_config: &MlsGroupJoinConfig,
         aad: &[u8],
         credential_with_key: CredentialWithKey,
+    ) -> Result<(Self, MlsMessageOut, Option<GroupInfo>), ExternalCommitError> {
+        Self::join_by_external_commit_internal(
+            provider,
+            signer,
+            ratchet_tree,
+            verifiable_group_info,
+            mls_group_config,
+            aad,
+            credential_with_key,
+            None,
+        )
+    }
+
+    /// Rejoin a group after losing the group state through an External
+    /// Commit that replaces our own prior leaf at `old_leaf_index` (see Section
+    /// 12.4.3.2 of the MLS specification).
+    ///
+    /// The credential identity and the signature key in `credential_with_key`
+    /// must be the same as the ones of the prior leaf. This proves to the other
+    /// members that the new leaf belongs to the owner of the prior one. If the
+    /// signature key of the prior leaf is still available but a new one
+    /// should be used, see
+    /// [`MlsGroup::resync_by_external_commit_with_old_signer()`].
+    ///
+    /// Other members can inspect the resync via
+    /// [`StagedCommit::external_resync()`] and accept or reject it with the
+    /// [`ResyncPolicy`] of their group.
+    ///
+    /// Apart from that, this function behaves like
+    /// [`MlsGroup::join_by_external_commit()`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn resync_by_external_commit(
+        provider: &impl OpenMlsProvider,
+        signer: &impl Signer,
+        ratchet_tree: Option<RatchetTreeIn>,
+        verifiable_group_info: VerifiableGroupInfo,
+        mls_group_config: &MlsGroupJoinConfig,
+        aad: &[u8],
+        credential_with_key: CredentialWithKey,
+        old_leaf_index: LeafNodeIndex,
+    ) -> Result<(Self, MlsMessageOut, Option<GroupInfo>), ExternalCommitError> {
+        let resync = ResyncTarget {
+            leaf_index: old_leaf_index,
+            old_key_signature: None,
+        };
+        Self::join_by_external_commit_internal(
+            provider,
+            signer,
+            ratchet_tree,
+            verifiable_group_info,
+            mls_group_config,
+            aad,
+            credential_with_key,
+            Some(resync),
+        )
+    }
+
+    /// Rejoin a group after losing the group state through an External
+    /// Commit that replaces our own prior leaf at `old_leaf_index`, using a new
+    /// signature key.
+    ///
+    /// The `old_signer` must hold the signature key of the prior leaf. It is
+    /// used to sign the new signature key, the group ID, the epoch and the
+    /// index of the prior leaf. The signature is attached to the new leaf node
+    /// as an extension of type [`RESYNC_SIGNATURE_EXTENSION_TYPE`], so that the
+    /// other members can verify that the new leaf belongs to the owner of the
+    /// prior one.
+    ///
+    /// Apart from that, this function behaves like
+    /// [`MlsGroup::resync_by_external_commit()`].
+    #[allow(clippy::too_many_arguments)]
+    pub fn resync_by_external_commit_with_old_signer(
+        provider: &impl OpenMlsProvider,
+        signer: &impl Signer,
+        ratchet_tree: Option<RatchetTreeIn>,
+        verifiable_group_info: VerifiableGroupInfo,
+        mls_group_config: &MlsGroupJoinConfig,
+        aad: &[u8],
+        credential_with_key: CredentialWithKey,
+        old_leaf_index: LeafNodeIndex,
+        old_signer: &impl Signer,
+    ) -> Result<(Self, MlsMessageOut, Option<GroupInfo>), ExternalCommitError> {
+        // The group ID and epoch are not verified yet. If they are wrong, the
+        // group info signature check will fail when joining.
+        let old_key_signature = ResyncTbs::new(
+            verifiable_group_info.group_id().clone(),
+            verifiable_group_info.epoch(),
+            old_leaf_index,
+            credential_with_key.signature_key.clone(),
+        )
+        .sign(old_signer)
+        .map_err(|_| ExternalCommitError::InvalidResyncSignature)?;
+        let resync = ResyncTarget {
+            leaf_index: old_leaf_index,
+            old_key_signature: Some(old_key_signature),
+        };
+        Self::join_by_external_commit_internal(
+            provider,
+            signer,
+            ratchet_tree,
+            verifiable_group_info,
+            mls_group_config,
+            aad,
+            credential_with_key,
+            Some(resync),
+        )
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn join_by_external_commit_internal(
+        provider: &impl OpenMlsProvider,
+        signer: &impl Signer,
+        ratchet_tree: Option<RatchetTreeIn>,
+        verifiable_group_info: VerifiableGroupInfo,
+        mls_group_config: &MlsGroupJoinConfig,
+        aad: &[u8],
+        credential_with_key: CredentialWithKey,
+        resync: Option<ResyncTarget>,
     ) -> Result<(Self, MlsMessageOut, Option<GroupInfo>), ExternalCommitError> {
         // Prepare the commit parameters
         let framing_parameters = FramingParameters::new(aad, WireFormat::PublicMessage);
@@ -165,6 +289,7 @@ impl MlsGroup {
             params,
             ratchet_tree,
             verifiable_group_info,
+            resync,
         )?;
         group.set_max_past_epochs(mls_group_config.max_past_epochs);
 
@@ -173,6 +298,7 @@ impl MlsGroup {
             group,
             proposal_store: ProposalStore::new(),
             own_leaf_nodes: vec![],
+            dropped_proposals: vec![],
             aad: vec![],
             group_state: MlsGroupState::PendingCommit(Box::new(PendingCommitState::External(
                 create_commit_result.staged_commit,
@@ -189,3 +315,138 @@ impl MlsGroup {
         ))
     }
 }
+
+/// A [`Welcome`] message that was decrypted and validated, but not yet used to
+/// join the group. It allows inspecting the group, e.g. its members, its
+/// [`GroupContext`] and the member that sent the [`Welcome`], before deciding
+/// whether to join.
+///
+/// Creating a [`StagedWelcome`] doesn't make any changes to the key store. The
+/// group is joined with [`StagedWelcome::into_group()`], which also deletes
+/// the [`KeyPackage`] used for the join (unless it is a last resort key
+/// package). Dropping a [`StagedWelcome`] declines the invitation and keeps
+/// the [`KeyPackage`] in the key store.
+#[derive(Debug)]
+pub struct StagedWelcome {
+    mls_group_config: MlsGroupJoinConfig,
+    group: StagedCoreWelcome,
+    key_package_bundle: KeyPackageBundle,
+}
+
+impl StagedWelcome {
+    /// Decrypts and validates a [`Welcome`] message. Returns an error
+    /// ([`WelcomeError::NoMatchingKeyPackage`]) if no [`KeyPackage`]
+    /// can be found.
+    pub fn new_from_welcome<KeyStore: OpenMlsKeyStore>(
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+        mls_group_config: &MlsGroupJoinConfig,
+        welcome: Welcome,
+        ratchet_tree: Option<RatchetTreeIn>,
+    ) -> Result<Self, WelcomeError<KeyStore::Error>> {
+        let resumption_psk_store =
+            ResumptionPskStore::new(mls_group_config.number_of_resumption_psks);
+        let key_package = welcome
+            .secrets()
+            .iter()
+            .find_map(|egs| {
+                let hash_ref = egs.new_member().as_slice().to_vec();
+                provider.key_store().read::<KeyPackage>(&hash_ref)
+            })
+            .ok_or(WelcomeError::NoMatchingKeyPackage)?;
+
+        // TODO #751
+        let private_key = provider
+            .key_store()
+            .read::<HpkePrivateKey>(key_package.hpke_init_key().as_slice())
+            .ok_or(WelcomeError::NoMatchingKeyPackage)?;
+        let key_package_bundle = KeyPackageBundle {
+            key_package,
+            private_key,
+        };
+
+        let group = CoreGroup::stage_welcome(
+            welcome,
+            ratchet_tree,
+            &key_package_bundle,
+            provider,
+            resumption_psk_store,
+        )?;
+
+        Ok(Self {
+            mls_group_config: mls_group_config.clone(),
+            group,
+            key_package_bundle,
+        })
+    }
+
+    /// Returns the [`GroupContext`] of the group to be joined.
+    pub fn group_context(&self) -> &GroupContext {
+        self.group.public_group().group_context()
+    }
+
+    /// Returns the ciphersuite of the group to be joined.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.group.public_group().ciphersuite()
+    }
+
+    /// Returns an iterator over the members of the group to be joined,
+    /// including ourselves.
+    pub fn members(&self) -> impl Iterator<Item = Member> + '_ {
+        self.group.public_group().members()
+    }
+
+    /// Returns our own leaf index in the group to be joined.
+    pub fn own_leaf_index(&self) -> LeafNodeIndex {
+        self.group.own_leaf_index()
+    }
+
+    /// Returns the leaf index of the member that sent the [`Welcome`].
+    pub fn welcome_sender_index(&self) -> LeafNodeIndex {
+        self.group.welcome_sender_index()
+    }
+
+    /// Returns the [`LeafNode`] of the member that sent the [`Welcome`].
+    pub fn welcome_sender(&self) -> Result<&LeafNode, LibraryError> {
+        self.group
+            .public_group()
+            .leaf(self.welcome_sender_index())
+            .ok_or_else(|| LibraryError::custom("Welcome sender not in the tree"))
+    }
+
+    /// Joins the group. This deletes the [`KeyPackage`] used for the join
+    /// from the key store, unless it has a last resort extension.
+    pub fn into_group<KeyStore: OpenMlsKeyStore>(
+        self,
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+    ) -> Result<MlsGroup, WelcomeError<KeyStore::Error>> {
+        let mut group = self.group.into_core_group(provider)?;
+        group.set_max_past_epochs(self.mls_group_config.max_past_epochs);
+
+        // Delete the [`KeyPackage`] and the corresponding private key from the
+        // key store, but only if it doesn't have a last resort extension.
+        // This happens only once the group was created and its keys are
+        // stored, such that the welcome can be staged and joined again if
+        // creating the group fails.
+        if !self.key_package_bundle.key_package().last_resort() {
+            self.key_package_bundle
+                .key_package
+                .delete(provider)
+                .map_err(WelcomeError::KeyStoreError)?;
+        } else {
+            log::debug!("Key package has last resort extension, not deleting");
+        }
+
+        let mls_group = MlsGroup {
+            mls_group_config: self.mls_group_config,
+            group,
+            proposal_store: ProposalStore::new(),
+            own_leaf_nodes: vec![],
+            dropped_proposals: vec![],
+            aad: vec![],
+            group_state: MlsGroupState::Operational,
+            state_changed: InnerState::Changed,
+        };
+
+        Ok(mls_group)
+    }
+}
@@ -10,12 +10,13 @@ use thiserror::Error;
 use crate::{
     error::LibraryError,
     extensions::errors::InvalidExtensionError,
+    framing::errors::{DecodeLimitError, MlsMessageBodyError},
     group::{
         errors::{
-            CreateAddProposalError, CreateCommitError, MergeCommitError, StageCommitError,
-            ValidationError,
+            CreateAddProposalError, CreateCommitError, ExternalCommitError, MergeCommitError,
+            ProposalValidationError, StageCommitError, ValidationError, WelcomeError,
         },
-        CreateGroupContextExtProposalError,
+        CreateGroupContextExtProposalError, GroupContextExtensionsProposalValidationError,
     },
     schedule::errors::PskError,
     treesync::errors::{LeafNodeValidationError, PublicTreeError},
@@ -44,6 +45,21 @@ pub enum NewGroupError<KeyStoreError> {
     InvalidExtensions(#[from] InvalidExtensionError),
 }
 
+/// Join error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum JoinError<KeyStoreError> {
+    /// The message can't be used to join a group. See [`MlsMessageBodyError`]
+    /// for more details.
+    #[error(transparent)]
+    MlsMessageBody(#[from] MlsMessageBodyError),
+    /// See [`WelcomeError`] for more details.
+    #[error(transparent)]
+    Welcome(#[from] WelcomeError<KeyStoreError>),
+    /// See [`ExternalCommitError`] for more details.
+    #[error(transparent)]
+    ExternalCommit(#[from] ExternalCommitError),
+}
+
 /// EmptyInput error
 #[derive(Error, Debug, PartialEq, Eq, Clone)]
 pub enum EmptyInputError {
@@ -98,6 +114,9 @@ pub enum ProcessMessageError {
     /// The message's wire format is incompatible with the group's wire format policy.
     #[error("The message's wire format is incompatible with the group's wire format policy.")]
     IncompatibleWireFormat,
+    /// No handshake observer secrets were added for the epoch of the message.
+    #[error("No handshake observer secrets were added for the epoch of the message.")]
+    MissingHandshakeObserverSecrets,
     /// See [`ValidationError`] for more details.
     #[error(transparent)]
     ValidationError(#[from] ValidationError),
@@ -116,6 +135,21 @@ pub enum ProcessMessageError {
     /// The proposal is invalid for the Sender of type [External](crate::prelude::Sender::External)
     #[error("The proposal is invalid for the Sender of type External")]
     UnsupportedProposalType,
+    /// See [`ProposalValidationError`] for more details.
+    #[error(transparent)]
+    InvalidProposal(#[from] ProposalValidationError),
+    /// See [`GroupContextExtensionsProposalValidationError`] for more details.
+    #[error(transparent)]
+    InvalidGroupContextExtensionsProposal(#[from] GroupContextExtensionsProposalValidationError),
+    /// The resync commit is not allowed by the group's resync policy.
+    #[error("The resync commit is not allowed by the group's resync policy.")]
+    RejectedResync,
+    /// The message was already processed before.
+    #[error("The message was already processed before.")]
+    ReplayedMessage,
+    /// See [`DecodeLimitError`] for more details.
+    #[error(transparent)]
+    DecodeLimitExceeded(#[from] DecodeLimitError),
 }
 
 /// Create message error
@@ -197,6 +231,32 @@ pub enum RemoveMembersError<KeyStoreError> {
     UnknownMember,
 }
 
+/// Update external senders error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum UpdateExternalSendersError<KeyStoreError> {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// See [`CreateCommitError`] for more details.
+    #[error(transparent)]
+    CreateCommitError(#[from] CreateCommitError<KeyStoreError>),
+    /// See [`MlsGroupStateError`] for more details.
+    #[error(transparent)]
+    GroupStateError(#[from] MlsGroupStateError),
+    /// The external sender is already part of the group's external senders.
+    #[error("The external sender is already part of the group's external senders.")]
+    DuplicateExternalSender,
+    /// The external sender can not be found.
+    #[error("The external sender can not be found.")]
+    UnknownExternalSender,
+    /// A GroupContextExtensions proposal is pending. A commit can only contain
+    /// one of them.
+    #[error(
+        "A GroupContextExtensions proposal is pending. A commit can only contain one of them."
+    )]
+    PendingGroupContextExtensionsProposal,
+}
+
 /// Leave group error
 #[derive(Error, Debug, PartialEq, Clone)]
 pub enum LeaveGroupError {
@@ -282,6 +342,23 @@ pub enum ExportSecretError {
     GroupStateError(#[from] MlsGroupStateError),
 }
 
+/// Wipe sender ratchets error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum WipeSenderRatchetsError {
+    /// See [`LibraryError`] for more details.
+    #[error(transparent)]
+    LibraryError(#[from] LibraryError),
+    /// The message secrets of the epoch are not retained.
+    #[error("The message secrets of the epoch are not retained.")]
+    EpochNotRetained,
+    /// The leaf index is not part of the tree of the epoch.
+    #[error("The leaf index is not part of the tree of the epoch.")]
+    UnknownSender,
+    /// The own sender ratchets of the current epoch are needed to send messages.
+    #[error("The own sender ratchets of the current epoch are needed to send messages.")]
+    OwnSenderRatchets,
+}
+
 /// Propose PSK error
 #[derive(Error, Debug, PartialEq, Clone)]
 pub enum ProposePskError {
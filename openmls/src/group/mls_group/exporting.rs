@@ -35,6 +35,35 @@ impl MlsGroup {
         }
     }
 
+    /// Exports the [`HandshakeObserverSecrets`] of the current epoch, which
+    /// allow a [`PublicGroup`] to decrypt the handshake messages of this epoch
+    /// but not its application messages. See the
+    /// [`handshake_observer`](crate::group::public_group::handshake_observer)
+    /// module for details.
+    ///
+    /// The secrets should be exported at the beginning of the epoch. Handshake
+    /// messages that this member sent or decrypted before the export can't be
+    /// decrypted with them.
+    ///
+    /// Returns [`ExportSecretError::GroupStateError(MlsGroupStateError::UseAfterEviction)`](MlsGroupStateError::UseAfterEviction)
+    /// if the group is not active.
+    pub fn export_handshake_observer_secrets(
+        &mut self,
+        crypto: &impl OpenMlsCrypto,
+    ) -> Result<HandshakeObserverSecrets, ExportSecretError> {
+        if !self.is_active() {
+            return Err(ExportSecretError::GroupStateError(
+                MlsGroupStateError::UseAfterEviction,
+            ));
+        }
+        let handshake_observer_secrets = self.group.handshake_observer_secrets(crypto)?;
+
+        // The sender ratchets might have been initialized.
+        self.flag_state_change();
+
+        Ok(handshake_observer_secrets)
+    }
+
     /// Returns the epoch authenticator of the current epoch.
     pub fn epoch_authenticator(&self) -> &EpochAuthenticator {
         self.group.epoch_authenticator()
@@ -0,0 +1,306 @@
+//! MLS group external senders
+//!
+//! This module contains operations to manage the [`ExternalSendersExtension`]
+//! of a group.
+
+use core_group::create_commit_params::CreateCommitParams;
+use openmls_traits::signatures::Signer;
+
+use super::{errors::UpdateExternalSendersError, *};
+use crate::{
+    ciphersuite::signature::SignaturePublicKey,
+    extensions::{Extension, ExtensionType, ExternalSender, ExternalSendersExtension},
+    messages::group_info::GroupInfo,
+};
+
+impl MlsGroup {
+    /// Returns the external senders of the group, i.e. the entries of the
+    /// group's [`ExternalSendersExtension`]. The position of an external sender
+    /// in the returned slice is its
+    /// [`SenderExtensionIndex`](crate::extensions::SenderExtensionIndex).
+    pub fn external_senders(&self) -> &[ExternalSender] {
+        self.group
+            .context()
+            .extensions()
+            .external_senders()
+            .map(|external_senders| external_senders.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Adds an external sender to the group.
+    ///
+    /// The external sender is appended to the group's
+    /// [`ExternalSendersExtension`], so the
+    /// [`SenderExtensionIndex`](crate::extensions::SenderExtensionIndex) of existing
+    /// external senders does not change.
+    ///
+    /// This operation results in a Commit with a `path` that contains a
+    /// GroupContextExtensions proposal as well as all pending proposals.
+    ///
+    /// If successful, it returns a tuple of [`MlsMessageOut`] (containing the
+    /// commit), an optional [`MlsMessageOut`] (containing the [`Welcome`]) and
+    /// the [GroupInfo].
+    /// The [Welcome] is [Some] when the queue of pending proposals contained
+    /// add proposals.
+    /// The [GroupInfo] is [Some] if the group has the `use_ratchet_tree_extension` flag set.
+    ///
+    /// Returns an error if there is a pending commit, if a GroupContextExtensions
+    /// proposal is pending or if an external sender with the same signature key
+    /// is already part of the group.
+    // FIXME: #1217
+    #[allow(clippy::type_complexity)]
+    pub fn add_external_sender<KeyStore: OpenMlsKeyStore>(
+        &mut self,
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+        signer: &impl Signer,
+        external_sender: ExternalSender,
+    ) -> Result<
+        (MlsMessageOut, Option<MlsMessageOut>, Option<GroupInfo>),
+        UpdateExternalSendersError<KeyStore::Error>,
+    > {
+        self.is_operational()?;
+        self.no_pending_group_context_extensions_proposal()?;
+
+        if self
+            .external_sender_index(external_sender.signature_key())
+            .is_some()
+        {
+            return Err(UpdateExternalSendersError::DuplicateExternalSender);
+        }
+
+        let mut external_senders = self.external_senders().to_vec();
+        external_senders.push(external_sender);
+
+        self.commit_external_senders(provider, signer, external_senders)
+    }
+
+    /// Replaces the external sender with the given signature key, e.g. to
+    /// rotate its signature key.
+    ///
+    /// The new external sender takes the position of the old one in the
+    /// group's [`ExternalSendersExtension`], so the
+    /// [`SenderExtensionIndex`](crate::extensions::SenderExtensionIndex) of all
+    /// external senders stays the same.
+    ///
+    /// See [`MlsGroup::add_external_sender`] for the returned messages.
+    ///
+    /// Returns an error if there is a pending commit, if a GroupContextExtensions
+    /// proposal is pending, if there is no external sender with the given
+    /// signature key or if an external sender with the new signature key is
+    /// already part of the group.
+    // FIXME: #1217
+    #[allow(clippy::type_complexity)]
+    pub fn replace_external_sender<KeyStore: OpenMlsKeyStore>(
+        &mut self,
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+        signer: &impl Signer,
+        signature_key: &SignaturePublicKey,
+        external_sender: ExternalSender,
+    ) -> Result<
+        (MlsMessageOut, Option<MlsMessageOut>, Option<GroupInfo>),
+        UpdateExternalSendersError<KeyStore::Error>,
+    > {
+        self.is_operational()?;
+        self.no_pending_group_context_extensions_proposal()?;
+
+        let index = self
+            .external_sender_index(signature_key)
+            .ok_or(UpdateExternalSendersError::UnknownExternalSender)?;
+        if external_sender.signature_key() != signature_key
+            && self
+                .external_sender_index(external_sender.signature_key())
+                .is_some()
+        {
+            return Err(UpdateExternalSendersError::DuplicateExternalSender);
+        }
+
+        // Pending proposals of the old external sender would be attributed to
+        // the new one.
+        self.warn_about_external_sender_indices(&[index], |sender_index| sender_index == index);
+
+        let mut external_senders = self.external_senders().to_vec();
+        external_senders[index] = external_sender;
+
+        self.commit_external_senders(provider, signer, external_senders)
+    }
+
+    /// Removes the external sender with the given signature key from the
+    /// group.
+    ///
+    /// The [`SenderExtensionIndex`](crate::extensions::SenderExtensionIndex) of all
+    /// external senders following the removed one in the group's
+    /// [`ExternalSendersExtension`] is decremented.
+    /// A warning is logged for every pending external proposal that
+    /// references an index that is removed or shifted.
+    ///
+    /// See [`MlsGroup::add_external_sender`] for the returned messages.
+    ///
+    /// Returns an error if there is a pending commit, if a GroupContextExtensions
+    /// proposal is pending or if there is no external sender with the given
+    /// signature key.
+    // FIXME: #1217
+    #[allow(clippy::type_complexity)]
+    pub fn remove_external_sender_by_signature_key<KeyStore: OpenMlsKeyStore>(
+        &mut self,
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+        signer: &impl Signer,
+        signature_key: &SignaturePublicKey,
+    ) -> Result<
+        (MlsMessageOut, Option<MlsMessageOut>, Option<GroupInfo>),
+        UpdateExternalSendersError<KeyStore::Error>,
+    > {
+        self.is_operational()?;
+        self.no_pending_group_context_extensions_proposal()?;
+
+        let index = self
+            .external_sender_index(signature_key)
+            .ok_or(UpdateExternalSendersError::UnknownExternalSender)?;
+        self.warn_about_external_sender_indices(&[index], |sender_index| sender_index >= index);
+
+        let mut external_senders = self.external_senders().to_vec();
+        external_senders.remove(index);
+
+        self.commit_external_senders(provider, signer, external_senders)
+    }
+
+    /// Removes all external senders with the given credential from the group.
+    ///
+    /// See [`MlsGroup::remove_external_sender_by_signature_key`] for details.
+    // FIXME: #1217
+    #[allow(clippy::type_complexity)]
+    pub fn remove_external_sender_by_credential<KeyStore: OpenMlsKeyStore>(
+        &mut self,
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+        signer: &impl Signer,
+        credential: &Credential,
+    ) -> Result<
+        (MlsMessageOut, Option<MlsMessageOut>, Option<GroupInfo>),
+        UpdateExternalSendersError<KeyStore::Error>,
+    > {
+        self.is_operational()?;
+        self.no_pending_group_context_extensions_proposal()?;
+
+        let indices: Vec<usize> = self
+            .external_senders()
+            .iter()
+            .enumerate()
+            .filter(|(_, external_sender)| external_sender.credential() == credential)
+            .map(|(index, _)| index)
+            .collect();
+        let first_index = *indices
+            .first()
+            .ok_or(UpdateExternalSendersError::UnknownExternalSender)?;
+        self.warn_about_external_sender_indices(&indices, |sender_index| {
+            sender_index >= first_index
+        });
+
+        let external_senders = self
+            .external_senders()
+            .iter()
+            .filter(|external_sender| external_sender.credential() != credential)
+            .cloned()
+            .collect();
+
+        self.commit_external_senders(provider, signer, external_senders)
+    }
+
+    /// Returns the position of the external sender with the given signature
+    /// key in the group's [`ExternalSendersExtension`].
+    fn external_sender_index(&self, signature_key: &SignaturePublicKey) -> Option<usize> {
+        self.external_senders()
+            .iter()
+            .position(|external_sender| external_sender.signature_key() == signature_key)
+    }
+
+    /// Returns an error if a GroupContextExtensions proposal is pending, since
+    /// it would be committed together with the one that updates the external
+    /// senders.
+    fn no_pending_group_context_extensions_proposal<KeyStoreError>(
+        &self,
+    ) -> Result<(), UpdateExternalSendersError<KeyStoreError>> {
+        if self.proposal_store.proposals().any(|queued_proposal| {
+            matches!(
+                queued_proposal.proposal(),
+                Proposal::GroupContextExtensions(_)
+            )
+        }) {
+            return Err(UpdateExternalSendersError::PendingGroupContextExtensionsProposal);
+        }
+        Ok(())
+    }
+
+    /// Logs a warning for every pending external proposal whose sender index
+    /// is affected by a change of the external senders at `indices`.
+    fn warn_about_external_sender_indices(
+        &self,
+        indices: &[usize],
+        affected: impl Fn(usize) -> bool,
+    ) {
+        for queued_proposal in self.proposal_store.proposals() {
+            if let Sender::External(sender_index) = queued_proposal.sender() {
+                if affected(sender_index.index()) {
+                    log::warn!(
+                        "Pending external proposal {:?} references external sender {}, \
+                         which changes when the external senders at {:?} are updated.",
+                        queued_proposal.proposal_reference(),
+                        sender_index.index(),
+                        indices,
+                    );
+                }
+            }
+        }
+    }
+
+    /// Creates a Commit that sets the group's [`ExternalSendersExtension`] to
+    /// the given external senders.
+    #[allow(clippy::type_complexity)]
+    fn commit_external_senders<KeyStore: OpenMlsKeyStore>(
+        &mut self,
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+        signer: &impl Signer,
+        external_senders: ExternalSendersExtension,
+    ) -> Result<
+        (MlsMessageOut, Option<MlsMessageOut>, Option<GroupInfo>),
+        UpdateExternalSendersError<KeyStore::Error>,
+    > {
+        let mut extensions = self.group.context().extensions().clone();
+        if external_senders.is_empty() {
+            extensions.remove(ExtensionType::ExternalSenders);
+        } else {
+            extensions.add_or_replace(Extension::ExternalSenders(external_senders));
+        }
+
+        let inline_proposals = vec![Proposal::GroupContextExtensions(
+            GroupContextExtensionProposal::new(extensions),
+        )];
+
+        // Create Commit over all proposals
+        let params = CreateCommitParams::builder()
+            .framing_parameters(self.framing_parameters())
+            .proposal_store(&self.proposal_store)
+            .inline_proposals(inline_proposals)
+            .build();
+        let create_commit_result = self.group.create_commit(params, provider, signer)?;
+
+        // Convert PublicMessage messages to MLSMessage and encrypt them if required by
+        // the configuration
+        let mls_message = self.content_to_mls_message(create_commit_result.commit, provider)?;
+
+        // Set the current group state to [`MlsGroupState::PendingCommit`],
+        // storing the current [`StagedCommit`] from the commit results
+        self.group_state = MlsGroupState::PendingCommit(Box::new(PendingCommitState::Member(
+            create_commit_result.staged_commit,
+        )));
+
+        // Since the state of the group might be changed, arm the state flag
+        self.flag_state_change();
+
+        Ok((
+            mls_message,
+            create_commit_result
+                .welcome_option
+                .map(|w| MlsMessageOut::from_welcome(w, self.group.version())),
+            create_commit_result.group_info,
+        ))
+    }
+}
@@ -205,6 +205,30 @@ impl MlsGroup {
             .leaf(leaf_index)
             .map(|leaf| leaf.credential())
     }
+
+    /// Returns a list of [`Member`]s whose leaf node lifetime has expired at
+    /// the current time of the provider's [`TimeProvider`].
+    ///
+    /// Only leaf nodes that were created from a key package carry a lifetime.
+    /// Members that have replaced their leaf node since joining, through an
+    /// update proposal or a commit with a path, are never reported as
+    /// expired. Applications can use this to decide which members to remove
+    /// or to ask for an update.
+    ///
+    /// [`TimeProvider`]: openmls_traits::time::TimeProvider
+    pub fn expired_members(&self, provider: &impl OpenMlsProvider) -> Vec<Member> {
+        let public_group = self.group.public_group();
+        public_group
+            .members()
+            .filter(|member| {
+                public_group
+                    .leaf(member.index)
+                    .and_then(|leaf| leaf.life_time())
+                    .map(|life_time| life_time.is_expired(provider.time()))
+                    .unwrap_or(false)
+            })
+            .collect()
+    }
 }
 
 /// Helper `enum` that classifies the kind of remove operation. This can be used to
@@ -22,8 +22,13 @@ mod application;
 mod builder;
 mod creation;
 mod exporting;
+mod external_senders;
+mod retention;
 mod updates;
 
+pub use creation::StagedWelcome;
+pub use retention::{KeyRetentionMetrics, RetainedEpoch, RetainedRatchet, RetainedSenderRatchets};
+
 use config::*;
 use errors::*;
 
@@ -161,6 +166,11 @@ pub struct MlsGroup {
     // are needed in case an update proposal is committed by another group
     // member. The vector is emptied after every epoch change.
     own_leaf_nodes: Vec<LeafNode>,
+    // Own proposals that were pending when the last commit was merged, but
+    // that were not covered by it and are still valid in the new epoch. They
+    // can be re-issued with `reissue_dropped_proposals()`. The vector is
+    // replaced after every epoch change.
+    dropped_proposals: Vec<QueuedProposal>,
     // The AAD that is used for all outgoing handshake messages. The AAD can be set through
     // `set_aad()`.
     aad: Vec<u8>,
@@ -258,6 +268,17 @@ impl MlsGroup {
         self.proposal_store.proposals()
     }
 
+    /// Returns an `Iterator` over own proposals that were pending when the
+    /// last commit was merged, but that were not covered by that commit.
+    ///
+    /// Only proposals that can be re-issued in the current epoch are
+    /// returned, i.e. Add proposals for clients that are not yet part of the
+    /// group and Remove proposals for members that are still in the group. See
+    /// [`MlsGroup::reissue_dropped_proposals()`] for more information.
+    pub fn dropped_proposals(&self) -> impl Iterator<Item = &QueuedProposal> {
+        self.dropped_proposals.iter()
+    }
+
     /// Returns a reference to the [`StagedCommit`] of the most recently created
     /// commit. If there was no commit created in this epoch, either because
     /// this commit or another commit was merged, it returns `None`.
@@ -405,6 +426,16 @@ impl MlsGroup {
         self.group.print_ratchet_tree(message)
     }
 
+    /// Returns the sender data secret of the given epoch, if the message
+    /// secrets of the epoch are retained.
+    #[cfg(any(feature = "test-utils", test))]
+    pub fn sender_data_secret(&self, epoch: GroupEpoch) -> Option<&[u8]> {
+        self.group
+            .message_secrets_for_epoch(epoch)
+            .ok()
+            .map(|message_secrets| message_secrets.sender_data_secret().as_slice())
+    }
+
     /// Returns the underlying [CoreGroup].
     #[cfg(test)]
     pub(crate) fn group(&self) -> &CoreGroup {
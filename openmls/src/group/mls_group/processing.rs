@@ -20,6 +20,12 @@ impl MlsGroup {
     /// and semantic validation of the message. It returns a [ProcessedMessage]
     /// enum.
     ///
+    /// External commits that replace a prior leaf of the group are checked
+    /// against the group's [`ResyncPolicy`].
+    ///
+    /// Messages that exceed the group's [`DecodeLimits`] are rejected. The
+    /// content of encrypted messages is checked after decryption.
+    ///
     /// # Errors:
     /// Returns an [`ProcessMessageError`] when the validation checks fail
     /// with the exact reason of the failure.
@@ -54,13 +60,29 @@ impl MlsGroup {
         // Parse the message
         let sender_ratchet_configuration =
             self.configuration().sender_ratchet_configuration().clone();
-        self.group.process_message(
+        let decode_limits = *self.configuration().decode_limits();
+        let processed_message = self.group.process_message(
             provider,
             message,
             &sender_ratchet_configuration,
+            &decode_limits,
             &self.proposal_store,
             &self.own_leaf_nodes,
-        )
+        )?;
+
+        // Check that external commits replacing a prior leaf are acceptable
+        // under the resync policy
+        if let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+            processed_message.content()
+        {
+            if let Some(resync) = staged_commit.external_resync() {
+                if !self.configuration().resync_policy().accepts(resync) {
+                    return Err(ProcessMessageError::RejectedResync);
+                }
+            }
+        }
+
+        Ok(processed_message)
     }
 
     /// Stores a standalone proposal in the internal [ProposalStore]
@@ -76,6 +98,10 @@ impl MlsGroup {
     /// currently stored in the group's [ProposalStore]. The Commit message is
     /// created even if there are no valid pending proposals.
     ///
+    /// Pending proposals that are not supported by all members that will
+    /// process the Commit are not included. They can be inspected via
+    /// [`StagedCommit::unsupported_proposals()`] on the pending commit.
+    ///
     /// Returns an error if there is a pending commit. Otherwise it returns a
     /// tuple of `Commit, Option<Welcome>, Option<GroupInfo>`, where `Commit`
     /// and `Welcome` are MlsMessages of the type [`MlsMessageOut`].
@@ -136,10 +162,21 @@ impl MlsGroup {
         // Since the state of the group might be changed, arm the state flag
         self.flag_state_change();
 
+        // Collect our own pending proposals that are not covered by the
+        // commit before the proposal store is emptied
+        let uncommitted_proposals = self.uncommitted_own_proposals(&staged_commit);
+
         // Merge staged commit
         self.group
             .merge_staged_commit(provider, staged_commit, &mut self.proposal_store)?;
 
+        // Keep the uncommitted proposals that can be re-issued in the new epoch
+        self.dropped_proposals = if self.is_active() {
+            self.reissuable_proposals(uncommitted_proposals)
+        } else {
+            vec![]
+        };
+
         // Extract and store the resumption psk for the current epoch
         let resumption_psk = self.group.group_epoch_secrets().resumption_psk();
         self.group
@@ -8,13 +8,13 @@ use super::{
 };
 use crate::{
     binary_tree::LeafNodeIndex,
-    ciphersuite::hash_ref::ProposalRef,
+    ciphersuite::{hash_ref::ProposalRef, signature::SignaturePublicKey},
     credentials::Credential,
     extensions::Extensions,
-    framing::MlsMessageOut,
-    group::{errors::CreateAddProposalError, GroupId, QueuedProposal},
+    framing::{MlsMessageOut, Sender},
+    group::{errors::CreateAddProposalError, GroupId, QueuedProposal, StagedCommit},
     key_packages::KeyPackage,
-    messages::proposals::ProposalOrRefType,
+    messages::proposals::{Proposal, ProposalOrRefType},
     prelude::LibraryError,
     schedule::PreSharedKeyId,
     treesync::LeafNode,
@@ -319,6 +319,117 @@ impl MlsGroup {
         }
     }
 
+    /// Re-creates the own proposals that were pending when the last commit was
+    /// merged, but that were not covered by that commit (see
+    /// [`MlsGroup::dropped_proposals()`]).
+    ///
+    /// Add proposals are re-created with the same [`KeyPackage`] and Remove
+    /// proposals are re-created for the same leaf index. Both are signed anew
+    /// for the current epoch and use the same [`ProposalOrRefType`] as the
+    /// original proposal. The new proposals are stored in the internal
+    /// proposal store and the dropped proposals are cleared.
+    ///
+    /// Returns an error if there is a pending commit.
+    pub fn reissue_dropped_proposals<KeyStore: OpenMlsKeyStore>(
+        &mut self,
+        provider: &impl OpenMlsProvider<KeyStoreProvider = KeyStore>,
+        signer: &impl Signer,
+    ) -> Result<Vec<(MlsMessageOut, ProposalRef)>, ProposalError<KeyStore::Error>> {
+        self.is_operational()?;
+
+        let proposals = self
+            .dropped_proposals
+            .iter()
+            .filter_map(|queued_proposal| {
+                let propose = match queued_proposal.proposal() {
+                    Proposal::Add(add_proposal) => Propose::Add(add_proposal.key_package().clone()),
+                    Proposal::Remove(remove_proposal) => {
+                        Propose::Remove(remove_proposal.removed().u32())
+                    }
+                    _ => return None,
+                };
+                Some((propose, queued_proposal.proposal_or_ref_type()))
+            })
+            .collect::<Vec<_>>();
+
+        let mut reissued_proposals = Vec::with_capacity(proposals.len());
+        for (propose, ref_or_value) in proposals {
+            reissued_proposals.push(self.propose(provider, signer, propose, ref_or_value)?);
+        }
+        self.dropped_proposals.clear();
+
+        // Since the state of the group might be changed, arm the state flag
+        self.flag_state_change();
+
+        Ok(reissued_proposals)
+    }
+
+    /// Returns the own proposals from the proposal store that are not covered
+    /// by the given [`StagedCommit`]. Remove proposals are returned together
+    /// with the signature key of the member they target in the current epoch.
+    pub(super) fn uncommitted_own_proposals(
+        &self,
+        staged_commit: &StagedCommit,
+    ) -> Vec<(QueuedProposal, Option<SignaturePublicKey>)> {
+        let own_sender = Sender::Member(self.own_leaf_index());
+        self.proposal_store
+            .proposals()
+            .filter(|queued_proposal| queued_proposal.sender() == &own_sender)
+            .filter(|queued_proposal| {
+                !staged_commit.queued_proposals().any(|committed_proposal| {
+                    committed_proposal.proposal_reference() == queued_proposal.proposal_reference()
+                })
+            })
+            .map(|queued_proposal| {
+                let removed_signature_key = match queued_proposal.proposal() {
+                    Proposal::Remove(remove_proposal) => self
+                        .group
+                        .public_group()
+                        .leaf(remove_proposal.removed())
+                        .map(|leaf| leaf.signature_key().clone()),
+                    _ => None,
+                };
+                (queued_proposal.clone(), removed_signature_key)
+            })
+            .collect()
+    }
+
+    /// Filters the given uncommitted proposals for the ones that can be
+    /// re-issued in the current epoch:
+    ///  - Add proposals for clients that are not part of the group yet
+    ///  - Remove proposals for members that are still part of the group
+    ///
+    /// All other proposals are discarded.
+    pub(super) fn reissuable_proposals(
+        &self,
+        uncommitted_proposals: Vec<(QueuedProposal, Option<SignaturePublicKey>)>,
+    ) -> Vec<QueuedProposal> {
+        let public_group = self.group.public_group();
+        uncommitted_proposals
+            .into_iter()
+            .filter(
+                |(queued_proposal, removed_signature_key)| match queued_proposal.proposal() {
+                    Proposal::Add(add_proposal) => {
+                        let leaf_node = add_proposal.key_package().leaf_node();
+                        !public_group.members().any(|member| {
+                            member.signature_key == leaf_node.signature_key().as_slice()
+                                || member.encryption_key == leaf_node.encryption_key().as_slice()
+                        })
+                    }
+                    // The leaf might have been vacated and filled by another
+                    // client in the meantime, so we check that it still holds
+                    // the same signature key.
+                    Proposal::Remove(remove_proposal) => public_group
+                        .leaf(remove_proposal.removed())
+                        .map(|leaf| Some(leaf.signature_key()) == removed_signature_key.as_ref())
+                        .unwrap_or(false),
+                    _ => false,
+                },
+            )
+            .map(|(queued_proposal, _)| queued_proposal)
+            .collect()
+    }
+
     #[cfg(test)]
     pub fn propose_group_context_extensions(
         &mut self,
@@ -0,0 +1,248 @@
+//! MLS group key retention
+//!
+//! This module contains the operations that delete retained key material of
+//! past epochs and that report which key material is retained. See the
+//! forward secrecy chapter of the book for more details.
+
+use openmls_traits::crypto::OpenMlsCrypto;
+
+use super::{errors::WipeSenderRatchetsError, *};
+use crate::tree::secret_tree::SecretTreeError;
+
+impl MlsGroup {
+    /// Deletes the message secrets, the member list and the resumption PSK of
+    /// the past epoch `epoch`. Messages of this epoch can't be decrypted
+    /// afterwards. The key material of the current epoch is never deleted.
+    ///
+    /// Returns `true` if any key material was deleted and `false` otherwise.
+    ///
+    /// The group state must be persisted with [`MlsGroup::save()`] afterwards
+    /// to also delete the key material from the stored state.
+    pub fn purge_past_epoch(&mut self, epoch: GroupEpoch) -> bool {
+        let purged = self.group.delete_past_epoch(epoch);
+        if purged {
+            self.flag_state_change();
+        }
+        purged
+    }
+
+    /// Deletes the message secrets, the member lists and the resumption PSKs
+    /// of all past epochs that are more than `max_age` epochs older than the
+    /// current epoch. A `max_age` of `0` deletes the key material of all past
+    /// epochs.
+    ///
+    /// The group state must be persisted with [`MlsGroup::save()`] afterwards
+    /// to also delete the key material from the stored state.
+    pub fn purge_past_epochs_older_than(&mut self, max_age: u64) {
+        let oldest_epoch = self.epoch().as_u64().saturating_sub(max_age);
+        self.group.delete_past_epochs_before(oldest_epoch.into());
+        self.flag_state_change();
+    }
+
+    /// Deletes the handshake and application sender ratchets of the member at
+    /// `leaf_index` in the given `epoch`, including the key material that is
+    /// kept for out-of-order messages. Messages of that member in that epoch
+    /// can't be decrypted afterwards, which allows applications to delete key
+    /// material as soon as they processed all messages of a sender.
+    ///
+    /// The own sender ratchets can only be deleted in past epochs, because
+    /// they are needed to send messages in the current epoch.
+    ///
+    /// The group state must be persisted with [`MlsGroup::save()`] afterwards
+    /// to also delete the key material from the stored state.
+    pub fn wipe_sender_ratchets(
+        &mut self,
+        crypto: &impl OpenMlsCrypto,
+        epoch: GroupEpoch,
+        leaf_index: LeafNodeIndex,
+    ) -> Result<(), WipeSenderRatchetsError> {
+        if epoch > self.epoch() {
+            return Err(WipeSenderRatchetsError::EpochNotRetained);
+        }
+        if epoch == self.epoch() && leaf_index == self.own_leaf_index() {
+            return Err(WipeSenderRatchetsError::OwnSenderRatchets);
+        }
+
+        self.group
+            .delete_sender_ratchets(crypto, epoch, leaf_index)
+            .map_err(|e| match e {
+                SecretTreeError::TooDistantInThePast => WipeSenderRatchetsError::EpochNotRetained,
+                SecretTreeError::IndexOutOfBounds => WipeSenderRatchetsError::UnknownSender,
+                _ => LibraryError::custom("Unexpected error while deleting sender ratchets").into(),
+            })?;
+        self.flag_state_change();
+        Ok(())
+    }
+
+    /// Returns the key material that is retained for past epochs and for the
+    /// current epoch, oldest epoch first. The current epoch is always the
+    /// last entry.
+    pub fn retained_epochs(&self) -> Vec<RetainedEpoch> {
+        self.group.retained_epochs()
+    }
+
+    /// Returns metrics on the keys for out-of-order messages that are retained
+    /// across the current and all past epochs.
+    ///
+    /// The number of retained keys is bounded by the `maximum_retained_keys`
+    /// of the
+    /// [`SenderRatchetConfiguration`](crate::prelude::SenderRatchetConfiguration).
+    pub fn key_retention_metrics(&self) -> KeyRetentionMetrics {
+        self.group.key_retention_metrics()
+    }
+}
+
+/// Metrics on the keys for out-of-order messages that are retained by an
+/// [`MlsGroup`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyRetentionMetrics {
+    retained_keys: usize,
+    largest_ratchet_retention: usize,
+    evicted_keys: u64,
+}
+
+impl KeyRetentionMetrics {
+    pub(crate) fn new(
+        retained_keys: usize,
+        largest_ratchet_retention: usize,
+        evicted_keys: u64,
+    ) -> Self {
+        Self {
+            retained_keys,
+            largest_ratchet_retention,
+            evicted_keys,
+        }
+    }
+
+    /// Returns the number of retained keys across all senders and epochs.
+    pub fn retained_keys(&self) -> usize {
+        self.retained_keys
+    }
+
+    /// Returns the largest number of keys retained by a single sender
+    /// ratchet.
+    pub fn largest_ratchet_retention(&self) -> usize {
+        self.largest_ratchet_retention
+    }
+
+    /// Returns the number of keys that were evicted to stay within the key
+    /// retention budget since the group was created.
+    pub fn evicted_keys(&self) -> u64 {
+        self.evicted_keys
+    }
+}
+
+/// The key material of an epoch that is retained by an [`MlsGroup`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetainedEpoch {
+    epoch: GroupEpoch,
+    sender_ratchets: Option<Vec<RetainedSenderRatchets>>,
+    resumption_psk: bool,
+}
+
+impl RetainedEpoch {
+    pub(crate) fn new(
+        epoch: GroupEpoch,
+        sender_ratchets: Option<Vec<RetainedSenderRatchets>>,
+        resumption_psk: bool,
+    ) -> Self {
+        Self {
+            epoch,
+            sender_ratchets,
+            resumption_psk,
+        }
+    }
+
+    /// Returns the epoch.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    /// Returns `true` if the message secrets of the epoch are retained, i.e.
+    /// if messages of this epoch can still be decrypted.
+    pub fn has_message_secrets(&self) -> bool {
+        self.sender_ratchets.is_some()
+    }
+
+    /// Returns the sender ratchets that were initialized in this epoch, or
+    /// `None` if the message secrets of the epoch are not retained.
+    ///
+    /// The sender ratchets of a member are initialized when the first message
+    /// of that member is sent or received. Members without initialized
+    /// sender ratchets are not included.
+    pub fn sender_ratchets(&self) -> Option<&[RetainedSenderRatchets]> {
+        self.sender_ratchets.as_deref()
+    }
+
+    /// Returns `true` if the resumption PSK of the epoch is retained.
+    pub fn has_resumption_psk(&self) -> bool {
+        self.resumption_psk
+    }
+}
+
+/// The sender ratchets of a member that are retained in an epoch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetainedSenderRatchets {
+    leaf_index: LeafNodeIndex,
+    handshake: RetainedRatchet,
+    application: RetainedRatchet,
+}
+
+impl RetainedSenderRatchets {
+    pub(crate) fn new(
+        leaf_index: LeafNodeIndex,
+        handshake: RetainedRatchet,
+        application: RetainedRatchet,
+    ) -> Self {
+        Self {
+            leaf_index,
+            handshake,
+            application,
+        }
+    }
+
+    /// Returns the leaf index of the member.
+    pub fn leaf_index(&self) -> LeafNodeIndex {
+        self.leaf_index
+    }
+
+    /// Returns the ratchet for handshake messages.
+    pub fn handshake(&self) -> &RetainedRatchet {
+        &self.handshake
+    }
+
+    /// Returns the ratchet for application messages.
+    pub fn application(&self) -> &RetainedRatchet {
+        &self.application
+    }
+}
+
+/// The state of a sender ratchet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetainedRatchet {
+    generation: u32,
+    retained_generations: Vec<u32>,
+}
+
+impl RetainedRatchet {
+    pub(crate) fn new(generation: u32, retained_generations: Vec<u32>) -> Self {
+        Self {
+            generation,
+            retained_generations,
+        }
+    }
+
+    /// Returns the generation of the next key material of the ratchet.
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+
+    /// Returns the past generations, oldest first, for which key material is
+    /// still retained to decrypt out-of-order messages. The number of these
+    /// generations is bounded by the `out_of_order_tolerance` and the
+    /// `maximum_retained_keys` of the
+    /// [`SenderRatchetConfiguration`](crate::prelude::SenderRatchetConfiguration).
+    pub fn retained_generations(&self) -> &[u32] {
+        &self.retained_generations
+    }
+}
@@ -21,6 +21,8 @@ pub struct SerializedMlsGroup {
     group: CoreGroup,
     proposal_store: ProposalStore,
     own_leaf_nodes: Vec<LeafNode>,
+    #[serde(default)]
+    dropped_proposals: Vec<QueuedProposal>,
     aad: Vec<u8>,
     resumption_psk_store: ResumptionPskStore,
     group_state: MlsGroupState,
@@ -34,6 +36,7 @@ impl Into<MlsGroup> for SerializedMlsGroup {
             group: self.group,
             proposal_store: self.proposal_store,
             own_leaf_nodes: self.own_leaf_nodes,
+            dropped_proposals: self.dropped_proposals,
             aad: self.aad,
             group_state: self.group_state,
             state_changed: InnerState::Persisted,
@@ -50,11 +53,12 @@ impl Serialize for MlsGroup {
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("SerializedMlsGroup", 6)?;
+        let mut state = serializer.serialize_struct("SerializedMlsGroup", 8)?;
         state.serialize_field("mls_group_config", &self.mls_group_config)?;
         state.serialize_field("group", &self.group)?;
         state.serialize_field("proposal_store", &self.proposal_store)?;
         state.serialize_field("own_leaf_nodes", &self.own_leaf_nodes)?;
+        state.serialize_field("dropped_proposals", &self.dropped_proposals)?;
         state.serialize_field("aad", &self.aad)?;
         state.serialize_field("resumption_psk_store", &self.group.resumption_psk_store)?;
         state.serialize_field("group_state", &self.group_state)?;
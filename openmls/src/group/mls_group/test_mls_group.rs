@@ -1,7 +1,7 @@
 use core_group::test_core_group::setup_client;
 use openmls_rust_crypto::OpenMlsRustCrypto;
 use openmls_traits::{key_store::OpenMlsKeyStore, OpenMlsProvider};
-use tls_codec::{Deserialize, Serialize};
+use tls_codec::{Deserialize, DeserializeBytes, Serialize};
 
 use crate::{
     binary_tree::LeafNodeIndex,
@@ -17,6 +17,7 @@ use crate::{
     },
     test_utils::*,
     tree::sender_ratchet::SenderRatchetConfiguration,
+    versions::ProtocolVersion,
 };
 
 #[apply(ciphersuites_and_providers)]
@@ -1097,6 +1098,119 @@ fn group_context_extensions_proposal(ciphersuite: Ciphersuite, provider: &impl O
     //       constructing commits does not permit it. See #1476
 }
 
+// Test that pending proposals that are not supported by all members are not
+// committed and reported as unsupported.
+#[apply(ciphersuites_and_providers)]
+fn unsupported_pending_proposals(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    // === Alice creates a group and supports an unknown extension, Bob doesn't ===
+    let capabilities = Capabilities::new(
+        None,
+        None,
+        Some(&[ExtensionType::Unknown(0xff00)]),
+        None,
+        None,
+    );
+    let mut alice_group = MlsGroup::builder()
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .with_capabilities(capabilities)
+        .build(provider, &alice_signer, alice_credential_with_key)
+        .expect("error creating group using builder");
+
+    let (_commit, welcome, _group_info) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .expect("Could not add member to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    let mut bob_group = MlsGroup::new_from_welcome(
+        provider,
+        alice_group.configuration(),
+        welcome.into_welcome().expect("Unexpected message type."),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .expect("Error creating group from Welcome");
+
+    // === Alice has a pending proposal for the unknown extension ===
+    let extensions = Extensions::from_vec(vec![
+        Extension::Unknown(0xff00, UnknownExtension(vec![0, 1, 2, 3])),
+        Extension::RequiredCapabilities(RequiredCapabilitiesExtension::new(
+            &[ExtensionType::Unknown(0xff00)],
+            &[],
+            &[],
+        )),
+    ])
+    .expect("error creating group context extensions");
+    let gce_proposal =
+        Proposal::GroupContextExtensions(GroupContextExtensionProposal::new(extensions));
+    let queued_proposal = QueuedProposal::from_proposal_and_sender(
+        ciphersuite,
+        provider.crypto(),
+        gce_proposal.clone(),
+        &Sender::build_member(alice_group.own_leaf_index()),
+    )
+    .expect("error creating queued proposal");
+    alice_group.store_pending_proposal(queued_proposal);
+
+    // === Committing drops the proposal, since Bob doesn't support the extension ===
+    let (commit, _welcome, _group_info) = alice_group
+        .commit_to_pending_proposals(provider, &alice_signer)
+        .expect("failed to commit to pending proposals");
+
+    let unsupported_proposals = alice_group
+        .pending_commit()
+        .expect("no pending commit")
+        .unsupported_proposals();
+    assert_eq!(unsupported_proposals.len(), 1);
+    assert_eq!(
+        unsupported_proposals[0].queued_proposal().proposal(),
+        &gce_proposal
+    );
+    assert_eq!(
+        unsupported_proposals[0].reason(),
+        &UnsupportedProposalReason::UnsupportedExtension {
+            extension_type: ExtensionType::Unknown(0xff00),
+            member: bob_group.own_leaf_index(),
+        }
+    );
+
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    // === Bob can process the commit ===
+    let processed_message = bob_group
+        .process_message(
+            provider,
+            commit
+                .into_protocol_message()
+                .expect("Unexpected message type."),
+        )
+        .expect("error processing commit");
+    match processed_message.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => bob_group
+            .merge_staged_commit(provider, *staged_commit)
+            .expect("error merging staged commit"),
+        _ => panic!("Expected a staged commit."),
+    }
+
+    // The group context extensions are unchanged.
+    assert_eq!(
+        alice_group.export_group_context(),
+        bob_group.export_group_context()
+    );
+    assert!(alice_group
+        .export_group_context()
+        .extensions()
+        .iter()
+        .all(|extension| extension.extension_type() != ExtensionType::Unknown(0xff00)));
+}
+
 // Test that the builder pattern accurately configures the new group.
 #[apply(ciphersuites_and_providers)]
 fn builder_pattern(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
@@ -1295,3 +1409,1272 @@ fn unknown_extensions(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider)
     )
     .expect("Error creating group from Welcome");
 }
+
+// Test that own proposals that are not covered by a commit from another member
+// are returned after the merge and can be re-issued in the new epoch, unless
+// they have become invalid.
+#[apply(ciphersuites_and_providers)]
+fn reissue_dropped_proposals(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    // Define the MlsGroup configuration
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let (_, welcome, _) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(alice_group.dropped_proposals().count(), 0);
+
+    let mut bob_group = MlsGroup::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .unwrap();
+
+    // === Alice proposes to add Charlie, but Bob commits an update first ===
+    alice_group
+        .propose_add_member(provider, &alice_signer, charlie_kpb.key_package())
+        .unwrap();
+
+    let (commit, _, _) = bob_group.self_update(provider, &bob_signer).unwrap();
+    bob_group.merge_pending_commit(provider).unwrap();
+
+    let processed_message = alice_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    match processed_message.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => alice_group
+            .merge_staged_commit(provider, *staged_commit)
+            .unwrap(),
+        _ => unreachable!("Expected a StagedCommit."),
+    }
+
+    // The Add proposal was dropped and can be re-issued
+    assert!(alice_group.pending_proposals().next().is_none());
+    let dropped_proposals = alice_group.dropped_proposals().collect::<Vec<_>>();
+    assert_eq!(dropped_proposals.len(), 1);
+    assert!(matches!(dropped_proposals[0].proposal(), Proposal::Add(_)));
+
+    let reissued_proposals = alice_group
+        .reissue_dropped_proposals(provider, &alice_signer)
+        .unwrap();
+    assert_eq!(reissued_proposals.len(), 1);
+    assert_eq!(alice_group.pending_proposals().count(), 1);
+    assert_eq!(alice_group.dropped_proposals().count(), 0);
+
+    // Bob receives the re-issued proposal
+    let (proposal, _) = reissued_proposals.into_iter().next().unwrap();
+    let processed_message = bob_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .unwrap();
+    match processed_message.into_content() {
+        ProcessedMessageContent::ProposalMessage(proposal) => {
+            bob_group.store_pending_proposal(*proposal)
+        }
+        _ => unreachable!("Expected a Proposal."),
+    }
+
+    // Alice commits to the re-issued proposal, which adds Charlie
+    let (commit, welcome, _) = alice_group
+        .commit_to_pending_proposals(provider, &alice_signer)
+        .unwrap();
+    assert!(welcome.is_some());
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(alice_group.members().count(), 3);
+
+    let processed_message = bob_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    match processed_message.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => {
+            bob_group
+                .merge_staged_commit(provider, *staged_commit)
+                .unwrap();
+        }
+        _ => unreachable!("Expected a StagedCommit."),
+    }
+
+    // === Alice proposes to remove Charlie, but Bob removes Charlie first ===
+    let charlie_index = alice_group
+        .members()
+        .find(|member| member.credential.identity() == b"Charlie")
+        .map(|member| member.index)
+        .unwrap();
+    alice_group
+        .propose_remove_member(provider, &alice_signer, charlie_index)
+        .unwrap();
+
+    let (commit, _, _) = bob_group
+        .remove_members(provider, &bob_signer, &[charlie_index])
+        .unwrap();
+    bob_group.merge_pending_commit(provider).unwrap();
+
+    let processed_message = alice_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    match processed_message.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => alice_group
+            .merge_staged_commit(provider, *staged_commit)
+            .unwrap(),
+        _ => unreachable!("Expected a StagedCommit."),
+    }
+
+    // The Remove proposal is no longer valid and is not returned
+    assert_eq!(alice_group.members().count(), 2);
+    assert_eq!(alice_group.dropped_proposals().count(), 0);
+}
+
+#[apply(ciphersuites_and_providers)]
+fn resync_by_external_commit(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    // Bob's credential after losing his state, with a new signature key
+    let (bob_new_credential_with_key, _bob_new_kpb, bob_new_signer, _bob_new_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    // Define the MlsGroup configuration
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let (_, welcome, _) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let bob_group = MlsGroup::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .unwrap();
+    let bob_leaf_index = bob_group.own_leaf_index();
+
+    // === Bob loses his state and rejoins with a new signature key ===
+    // Without a proof of ownership of the old leaf, Bob can't replace it.
+    let verifiable_group_info = alice_group
+        .export_group_info(provider.crypto(), &alice_signer, true)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let result = MlsGroup::resync_by_external_commit(
+        provider,
+        &bob_new_signer,
+        None,
+        verifiable_group_info,
+        mls_group_create_config.join_config(),
+        &[],
+        bob_new_credential_with_key.clone(),
+        bob_leaf_index,
+    );
+    assert_eq!(result.err(), Some(ExternalCommitError::MissingResyncProof));
+
+    // With a signature by the old signature key, he can.
+    let verifiable_group_info = alice_group
+        .export_group_info(provider.crypto(), &alice_signer, true)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let (mut bob_group, commit, _) = MlsGroup::resync_by_external_commit_with_old_signer(
+        provider,
+        &bob_new_signer,
+        None,
+        verifiable_group_info,
+        mls_group_create_config.join_config(),
+        &[],
+        bob_new_credential_with_key.clone(),
+        bob_leaf_index,
+        &bob_signer,
+    )
+    .unwrap();
+
+    // Alice rejects the resync if her policy doesn't allow it.
+    let mut alice_config = alice_group.configuration().clone();
+    alice_config.resync_policy = ResyncPolicy::RejectAll;
+    alice_group.set_configuration(&alice_config);
+
+    let err = alice_group
+        .process_message(provider, commit.clone().into_protocol_message().unwrap())
+        .expect_err("Resync commit was accepted despite the resync policy.");
+    assert_eq!(err, ProcessMessageError::RejectedResync);
+
+    // Alice accepts the resync if a proof is required.
+    alice_config.resync_policy = ResyncPolicy::RequireProof;
+    alice_group.set_configuration(&alice_config);
+
+    let processed_message = alice_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    let staged_commit = match processed_message.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => staged_commit,
+        _ => unreachable!("Expected a StagedCommit."),
+    };
+    assert!(staged_commit.is_resync());
+    let resync = staged_commit.external_resync().unwrap();
+    assert_eq!(resync.removed(), bob_leaf_index);
+    assert_eq!(resync.proof(), Some(ResyncProof::OldKeySignature));
+
+    alice_group
+        .merge_staged_commit(provider, *staged_commit)
+        .unwrap();
+    bob_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(alice_group.members().count(), 2);
+    assert_eq!(bob_group.own_leaf_index(), bob_leaf_index);
+
+    // === Bob loses his state again and rejoins with the same signature key ===
+    let verifiable_group_info = alice_group
+        .export_group_info(provider.crypto(), &alice_signer, true)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let (_bob_group, commit, _) = MlsGroup::resync_by_external_commit(
+        provider,
+        &bob_new_signer,
+        None,
+        verifiable_group_info,
+        mls_group_create_config.join_config(),
+        &[],
+        bob_new_credential_with_key,
+        bob_leaf_index,
+    )
+    .unwrap();
+
+    let processed_message = alice_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    let staged_commit = match processed_message.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => staged_commit,
+        _ => unreachable!("Expected a StagedCommit."),
+    };
+    let resync = staged_commit.external_resync().unwrap();
+    assert_eq!(resync.removed(), bob_leaf_index);
+    assert_eq!(resync.proof(), Some(ResyncProof::SignatureKey));
+}
+
+#[apply(ciphersuites_and_providers)]
+fn staged_welcome(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    // Define the MlsGroup configuration
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id.clone(),
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let (_, welcome, _) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    let welcome = welcome.into_welcome().unwrap();
+
+    // === Bob inspects the group before joining ===
+    let staged_welcome = StagedWelcome::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.clone(),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .unwrap();
+
+    assert_eq!(staged_welcome.group_context().group_id(), &group_id);
+    assert_eq!(staged_welcome.ciphersuite(), ciphersuite);
+    assert_eq!(staged_welcome.members().count(), 2);
+    assert_eq!(staged_welcome.welcome_sender_index(), LeafNodeIndex::new(0));
+    assert_eq!(
+        staged_welcome
+            .welcome_sender()
+            .unwrap()
+            .credential()
+            .identity(),
+        b"Alice"
+    );
+    assert_eq!(staged_welcome.own_leaf_index(), LeafNodeIndex::new(1));
+
+    // Declining the invitation keeps the key package, so Bob can stage the
+    // Welcome again.
+    drop(staged_welcome);
+    let staged_welcome = StagedWelcome::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.clone(),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .unwrap();
+
+    // === Bob joins the group ===
+    let bob_group = staged_welcome.into_group(provider).unwrap();
+    assert_eq!(bob_group.epoch(), alice_group.epoch());
+    assert_eq!(bob_group.members().count(), 2);
+
+    // The key package was consumed by joining the group.
+    let err = StagedWelcome::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome,
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .expect_err("The key package was not deleted when joining the group.");
+    assert_eq!(err, WelcomeError::NoMatchingKeyPackage);
+}
+
+#[apply(ciphersuites_and_providers)]
+fn join_from_message(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (bob_credential_with_key, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (charlie_credential_with_key, _charlie_kpb, charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    // Define the MlsGroup configuration
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let (commit, welcome, _) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    // Messages that don't contain a Welcome or a group info are rejected.
+    let commit =
+        MlsMessageIn::tls_deserialize_exact(commit.tls_serialize_detached().unwrap()).unwrap();
+    let err = MlsGroup::join_from_message(
+        provider,
+        &bob_signer,
+        commit,
+        Some(alice_group.export_ratchet_tree().into()),
+        mls_group_create_config.join_config(),
+        &[],
+        bob_credential_with_key.clone(),
+    )
+    .expect_err("Joined a group from a commit.");
+    assert_eq!(
+        err,
+        JoinError::MlsMessageBody(MlsMessageBodyError::UnexpectedWireFormat(
+            WireFormat::PublicMessage
+        ))
+    );
+
+    // Messages with an unsupported protocol version are rejected.
+    let mut welcome =
+        MlsMessageIn::tls_deserialize_exact(welcome.tls_serialize_detached().unwrap()).unwrap();
+    welcome.version = ProtocolVersion::Mls10Draft11;
+    let err = MlsGroup::join_from_message(
+        provider,
+        &bob_signer,
+        welcome.clone(),
+        Some(alice_group.export_ratchet_tree().into()),
+        mls_group_create_config.join_config(),
+        &[],
+        bob_credential_with_key.clone(),
+    )
+    .expect_err("Joined a group with an unsupported protocol version.");
+    assert_eq!(
+        err,
+        JoinError::MlsMessageBody(MlsMessageBodyError::UnsupportedVersion(
+            ProtocolVersion::Mls10Draft11
+        ))
+    );
+
+    // === Bob joins the group from the Welcome ===
+    welcome.version = ProtocolVersion::Mls10;
+    let (bob_group, commit, group_info) = MlsGroup::join_from_message(
+        provider,
+        &bob_signer,
+        welcome,
+        Some(alice_group.export_ratchet_tree().into()),
+        mls_group_create_config.join_config(),
+        &[],
+        bob_credential_with_key,
+    )
+    .unwrap();
+    assert!(commit.is_none());
+    assert!(group_info.is_none());
+    assert_eq!(bob_group.epoch(), alice_group.epoch());
+
+    // === Charlie joins the group from a group info ===
+    let group_info = alice_group
+        .export_group_info(provider.crypto(), &alice_signer, true)
+        .unwrap();
+    let group_info =
+        MlsMessageIn::tls_deserialize_exact(group_info.tls_serialize_detached().unwrap()).unwrap();
+    let (mut charlie_group, commit, _) = MlsGroup::join_from_message(
+        provider,
+        &charlie_signer,
+        group_info,
+        None,
+        mls_group_create_config.join_config(),
+        &[],
+        charlie_credential_with_key,
+    )
+    .unwrap();
+    let commit = commit.expect("No commit was returned for the external join.");
+
+    let processed_message = alice_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    match processed_message.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => alice_group
+            .merge_staged_commit(provider, *staged_commit)
+            .unwrap(),
+        _ => panic!("Expected a commit."),
+    }
+    charlie_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(charlie_group.epoch(), alice_group.epoch());
+    assert_eq!(charlie_group.members().count(), 3);
+}
+
+#[apply(ciphersuites)]
+fn expired_members(ciphersuite: Ciphersuite) {
+    // We need a provider with a clock we can control.
+    let provider = &OpenMlsRustCrypto::default();
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    // Define the MlsGroup configuration
+    let mls_group_create_config = MlsGroupCreateConfig::test_default(ciphersuite);
+
+    // === Alice creates a group and adds Bob and Charlie ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[
+                bob_kpb.key_package().clone(),
+                charlie_kpb.key_package().clone(),
+            ],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    // Alice replaces her leaf node, which doesn't carry a lifetime anymore.
+    alice_group.self_update(provider, &alice_signer).unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert!(alice_group.own_leaf().unwrap().life_time().is_none());
+
+    // Nobody has expired yet.
+    assert!(alice_group.expired_members(provider).is_empty());
+
+    // Once the key packages of Bob and Charlie expire, their leaf nodes do as
+    // well.
+    let not_after = charlie_kpb
+        .key_package()
+        .leaf_node()
+        .life_time()
+        .unwrap()
+        .not_after()
+        .max(
+            bob_kpb
+                .key_package()
+                .leaf_node()
+                .life_time()
+                .unwrap()
+                .not_after(),
+        );
+    provider.time().set_time(not_after);
+
+    let expired_members = alice_group
+        .expired_members(provider)
+        .into_iter()
+        .map(|member| member.index)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        expired_members,
+        vec![LeafNodeIndex::new(1), LeafNodeIndex::new(2)]
+    );
+}
+
+// Replayed application messages are rejected, also after the group was saved
+// and loaded again, and for messages of past epochs.
+#[apply(ciphersuites_and_providers)]
+fn replayed_application_messages(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    // Bob keeps the secrets of one past epoch, but no secrets for out-of-order
+    // messages.
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .use_ratchet_tree_extension(true)
+        .max_past_epochs(1)
+        .sender_ratchet_configuration(SenderRatchetConfiguration::new(0, 1000))
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id.clone(),
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let (_, welcome, _) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let mut bob_group = MlsGroup::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        None,
+    )
+    .unwrap();
+
+    // === Bob processes a message only once ===
+    let message = alice_group
+        .create_message(provider, &alice_signer, b"Hello Bob")
+        .unwrap();
+    bob_group
+        .process_message(provider, message.clone().into_protocol_message().unwrap())
+        .expect("Error processing the message");
+
+    let err = bob_group
+        .process_message(provider, message.clone().into_protocol_message().unwrap())
+        .expect_err("Bob processed a replayed message.");
+    assert_eq!(err, ProcessMessageError::ReplayedMessage);
+
+    // === The replay is still detected after a restart ===
+    bob_group.save(provider.key_store()).unwrap();
+    let mut bob_group = MlsGroup::load(&group_id, provider.key_store()).unwrap();
+
+    let err = bob_group
+        .process_message(provider, message.into_protocol_message().unwrap())
+        .expect_err("Bob processed a replayed message after a restart.");
+    assert_eq!(err, ProcessMessageError::ReplayedMessage);
+
+    // === Messages from past epochs ===
+    let processed_message = alice_group
+        .create_message(provider, &alice_signer, b"Processed in epoch 1")
+        .unwrap();
+    let delayed_message = alice_group
+        .create_message(provider, &alice_signer, b"Delayed from epoch 1")
+        .unwrap();
+    bob_group
+        .process_message(
+            provider,
+            processed_message.clone().into_protocol_message().unwrap(),
+        )
+        .unwrap();
+
+    let (commit, _, _) = alice_group.self_update(provider, &alice_signer).unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    let processed_commit = bob_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    match processed_commit.into_content() {
+        ProcessedMessageContent::StagedCommitMessage(staged_commit) => bob_group
+            .merge_staged_commit(provider, *staged_commit)
+            .unwrap(),
+        _ => panic!("Expected a commit."),
+    }
+    assert_eq!(bob_group.epoch(), alice_group.epoch());
+
+    // A message from the past epoch that was processed before is a replay.
+    let err = bob_group
+        .process_message(provider, processed_message.into_protocol_message().unwrap())
+        .expect_err("Bob processed a replayed message from a past epoch.");
+    assert_eq!(err, ProcessMessageError::ReplayedMessage);
+
+    // A delayed message from the past epoch can be processed once.
+    bob_group
+        .process_message(
+            provider,
+            delayed_message.clone().into_protocol_message().unwrap(),
+        )
+        .expect("Error processing a delayed message from a past epoch.");
+
+    bob_group.save(provider.key_store()).unwrap();
+    let mut bob_group = MlsGroup::load(&group_id, provider.key_store()).unwrap();
+
+    let err = bob_group
+        .process_message(provider, delayed_message.into_protocol_message().unwrap())
+        .expect_err("Bob processed a replayed message from a past epoch after a restart.");
+    assert_eq!(err, ProcessMessageError::ReplayedMessage);
+}
+
+#[apply(ciphersuites_and_providers)]
+fn decode_limits(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, _charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+    let (_dave_credential_with_key, dave_kpb, _dave_signer, _dave_pk) =
+        setup_client("Dave", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // Bob only accepts commits with a single proposal.
+    let bob_limits = DecodeLimits::default().with_max_proposals(1);
+    let bob_create_config = MlsGroupCreateConfig::builder()
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .use_ratchet_tree_extension(true)
+        .decode_limits(bob_limits)
+        .build();
+    assert_eq!(bob_create_config.join_config().decode_limits(), &bob_limits);
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let (_, welcome, _) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let mut bob_group = MlsGroup::new_from_welcome(
+        provider,
+        bob_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        None,
+    )
+    .unwrap();
+
+    // === Alice adds Charlie and Dave in a single commit ===
+    let (commit, welcome, group_info) = alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[
+                charlie_kpb.key_package().clone(),
+                dave_kpb.key_package().clone(),
+            ],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    // The content of the commit is encrypted and can't be checked when
+    // decoding, only its size.
+    let commit_bytes = commit.tls_serialize_detached().unwrap();
+    let commit_in = MlsMessageIn::tls_deserialize_with_limits(&commit_bytes, &bob_limits)
+        .expect("Error decoding the commit.");
+    let err = MlsMessageIn::tls_deserialize_with_limits(
+        &commit_bytes,
+        &DecodeLimits::default().with_max_message_size(commit_bytes.len() - 1),
+    )
+    .expect_err("Decoded a message that is too large.");
+    assert_eq!(
+        err,
+        MlsMessageDecodeError::LimitExceeded(DecodeLimitError::MessageTooLarge)
+    );
+
+    // Bob rejects the commit after decryption.
+    let err = bob_group
+        .process_message(provider, commit_in.into_protocol_message().unwrap())
+        .expect_err("Bob processed a commit with too many proposals.");
+    assert_eq!(
+        err,
+        ProcessMessageError::DecodeLimitExceeded(DecodeLimitError::TooManyProposals)
+    );
+
+    // The welcome contains one set of secrets per new member. Their number
+    // has its own limit.
+    let welcome_bytes = welcome.tls_serialize_detached().unwrap();
+    MlsMessageIn::tls_deserialize_with_limits(&welcome_bytes, &bob_limits)
+        .expect("Error decoding the welcome.");
+    let err = MlsMessageIn::tls_deserialize_with_limits(
+        &welcome_bytes,
+        &DecodeLimits::default().with_max_welcome_secrets(1),
+    )
+    .expect_err("Decoded a welcome with too many secrets.");
+    assert_eq!(
+        err,
+        MlsMessageDecodeError::LimitExceeded(DecodeLimitError::TooManyWelcomeSecrets)
+    );
+
+    // The ratchet tree in the group info has four leaves.
+    let group_info_bytes = MlsMessageOut::from(group_info.unwrap())
+        .tls_serialize_detached()
+        .unwrap();
+    let err = MlsMessageIn::tls_deserialize_with_limits(
+        &group_info_bytes,
+        &DecodeLimits::default().with_max_tree_size(3),
+    )
+    .expect_err("Decoded a group info with a tree that is too large.");
+    assert_eq!(
+        err,
+        MlsMessageDecodeError::LimitExceeded(DecodeLimitError::TreeTooLarge)
+    );
+    MlsMessageIn::tls_deserialize_with_limits(
+        &group_info_bytes,
+        &DecodeLimits::default().with_max_tree_size(4),
+    )
+    .expect("Error decoding the group info.");
+
+    // The group info carries the ratchet tree in an extension.
+    let err = MlsMessageIn::tls_deserialize_with_limits(
+        &group_info_bytes,
+        &DecodeLimits::default().with_max_extensions(0),
+    )
+    .expect_err("Decoded a group info with too many extensions.");
+    assert_eq!(
+        err,
+        MlsMessageDecodeError::LimitExceeded(DecodeLimitError::TooManyExtensions)
+    );
+
+    // All messages can be decoded with the default limits.
+    for bytes in [commit_bytes, welcome_bytes, group_info_bytes] {
+        MlsMessageIn::tls_deserialize_exact(bytes).expect("Error decoding the message.");
+    }
+
+    // A message that claims more bytes than it contains is rejected before
+    // the claimed bytes are allocated.
+    let mut input = ProtocolVersion::Mls10.tls_serialize_detached().unwrap();
+    input.extend_from_slice(&WireFormat::PrivateMessage.tls_serialize_detached().unwrap());
+    // The group ID claims to be 2^30 - 1 bytes long.
+    input.extend_from_slice(&[0xbf, 0xff, 0xff, 0xff, 0, 0, 0, 0]);
+    let err = MlsMessageIn::tls_deserialize_with_limits(&input, &DecodeLimits::default())
+        .expect_err("Decoded a message that is too short.");
+    assert!(matches!(err, MlsMessageDecodeError::Codec(_)));
+    MlsMessageIn::tls_deserialize_exact_bytes(&input)
+        .expect_err("Decoded a message that is too short.");
+}
+
+#[apply(ciphersuites_and_providers)]
+fn forward_secrecy_controls(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .use_ratchet_tree_extension(true)
+        .max_past_epochs(3)
+        .number_of_resumption_psks(4)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id.clone(),
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+    let alice_index = alice_group.own_leaf_index();
+
+    let (_, welcome, _) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let mut bob_group = MlsGroup::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        None,
+    )
+    .unwrap();
+
+    // === Alice sends messages in three epochs, one of them is delayed ===
+    let mut delayed_messages = Vec::new();
+    for _ in 0..2 {
+        let message = alice_group
+            .create_message(provider, &alice_signer, b"Processed")
+            .unwrap();
+        bob_group
+            .process_message(provider, message.into_protocol_message().unwrap())
+            .unwrap();
+        let delayed_message = alice_group
+            .create_message(provider, &alice_signer, b"Delayed")
+            .unwrap();
+        delayed_messages.push((alice_group.epoch(), delayed_message));
+
+        let (commit, _, _) = alice_group.self_update(provider, &alice_signer).unwrap();
+        alice_group.merge_pending_commit(provider).unwrap();
+        let processed_commit = bob_group
+            .process_message(provider, commit.into_protocol_message().unwrap())
+            .unwrap();
+        match processed_commit.into_content() {
+            ProcessedMessageContent::StagedCommitMessage(staged_commit) => bob_group
+                .merge_staged_commit(provider, *staged_commit)
+                .unwrap(),
+            _ => panic!("Expected a commit."),
+        }
+    }
+    let (first_epoch, first_delayed_message) = delayed_messages.remove(0);
+    let (second_epoch, second_delayed_message) = delayed_messages.remove(0);
+
+    // === Bob reports the retained key material ===
+    let retained_epochs = bob_group.retained_epochs();
+    assert_eq!(
+        retained_epochs.last().map(|retained| retained.epoch()),
+        Some(bob_group.epoch())
+    );
+    let retained_first_epoch = retained_epochs
+        .iter()
+        .find(|retained| retained.epoch() == first_epoch)
+        .expect("The first epoch should be retained.");
+    let alice_ratchets = retained_first_epoch
+        .sender_ratchets()
+        .expect("The message secrets should be retained.")
+        .iter()
+        .find(|ratchets| ratchets.leaf_index() == alice_index)
+        .expect("Alice's ratchets should be initialized.");
+    assert_eq!(alice_ratchets.application().generation(), 1);
+
+    // === Bob wipes Alice's ratchets of the first epoch ===
+    bob_group.save(provider.key_store()).unwrap();
+    bob_group
+        .wipe_sender_ratchets(provider.crypto(), first_epoch, alice_index)
+        .unwrap();
+    assert_eq!(bob_group.state_changed(), InnerState::Changed);
+    assert!(bob_group
+        .retained_epochs()
+        .iter()
+        .find(|retained| retained.epoch() == first_epoch)
+        .and_then(|retained| retained.sender_ratchets())
+        .map(|ratchets| ratchets.is_empty())
+        .unwrap());
+    bob_group
+        .process_message(
+            provider,
+            first_delayed_message.into_protocol_message().unwrap(),
+        )
+        .expect_err("Bob decrypted a message with wiped ratchets.");
+
+    // === Bob purges the second epoch, also from the persisted state ===
+    assert!(bob_group.get_past_resumption_psk(second_epoch).is_some());
+    assert!(bob_group.purge_past_epoch(second_epoch));
+    assert!(!bob_group.purge_past_epoch(second_epoch));
+    assert!(!bob_group.purge_past_epoch(bob_group.epoch()));
+    bob_group.save(provider.key_store()).unwrap();
+    let mut bob_group = MlsGroup::load(&group_id, provider.key_store()).unwrap();
+
+    assert!(bob_group.get_past_resumption_psk(second_epoch).is_none());
+    assert!(!bob_group
+        .retained_epochs()
+        .iter()
+        .any(|retained| retained.epoch() == second_epoch));
+    bob_group
+        .process_message(
+            provider,
+            second_delayed_message.into_protocol_message().unwrap(),
+        )
+        .expect_err("Bob decrypted a message of a purged epoch.");
+
+    // === Bob purges all past epochs ===
+    bob_group.purge_past_epochs_older_than(0);
+    let retained_epochs = bob_group.retained_epochs();
+    assert_eq!(retained_epochs.len(), 1);
+    assert_eq!(retained_epochs[0].epoch(), bob_group.epoch());
+    assert!(retained_epochs[0].has_message_secrets());
+
+    // === Invalid wipes ===
+    let bob_index = bob_group.own_leaf_index();
+    let current_epoch = bob_group.epoch();
+    assert_eq!(
+        bob_group.wipe_sender_ratchets(provider.crypto(), current_epoch, bob_index),
+        Err(WipeSenderRatchetsError::OwnSenderRatchets)
+    );
+    assert_eq!(
+        bob_group.wipe_sender_ratchets(provider.crypto(), first_epoch, alice_index),
+        Err(WipeSenderRatchetsError::EpochNotRetained)
+    );
+    assert_eq!(
+        bob_group.wipe_sender_ratchets(
+            provider.crypto(),
+            (current_epoch.as_u64() + 1).into(),
+            alice_index
+        ),
+        Err(WipeSenderRatchetsError::EpochNotRetained)
+    );
+    assert_eq!(
+        bob_group.wipe_sender_ratchets(provider.crypto(), current_epoch, LeafNodeIndex::new(10)),
+        Err(WipeSenderRatchetsError::UnknownSender)
+    );
+}
+
+#[apply(ciphersuites_and_providers)]
+fn key_retention_budget(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential_with_key, charlie_kpb, charlie_signer, _charlie_pk) =
+        setup_client("Charlie", ciphersuite, provider);
+
+    // Keep up to 4 keys per sender, but only 4 keys in total.
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .use_ratchet_tree_extension(true)
+        .sender_ratchet_configuration(
+            SenderRatchetConfiguration::new(5, 1000).with_maximum_retained_keys(4),
+        )
+        .build();
+
+    // === Alice creates a group and adds Bob and Charlie ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let (_, welcome, _) = alice_group
+        .add_members(
+            provider,
+            &alice_signer,
+            &[
+                bob_kpb.key_package().clone(),
+                charlie_kpb.key_package().clone(),
+            ],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    let welcome = welcome.into_welcome().unwrap();
+
+    let mut bob_group = MlsGroup::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.clone(),
+        None,
+    )
+    .unwrap();
+    let mut charlie_group = MlsGroup::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome,
+        None,
+    )
+    .unwrap();
+
+    // === Bob only receives the last of Alice's messages ===
+    let mut alice_messages: Vec<MlsMessageOut> = (0..6)
+        .map(|_| {
+            alice_group
+                .create_message(provider, &alice_signer, b"Hi Bob")
+                .unwrap()
+        })
+        .collect();
+    bob_group
+        .process_message(
+            provider,
+            alice_messages
+                .pop()
+                .unwrap()
+                .into_protocol_message()
+                .unwrap(),
+        )
+        .unwrap();
+    let metrics = bob_group.key_retention_metrics();
+    assert_eq!(metrics.retained_keys(), 4);
+    assert_eq!(metrics.largest_ratchet_retention(), 4);
+    assert_eq!(metrics.evicted_keys(), 0);
+
+    // === Bob only receives the last of Charlie's messages ===
+    let mut charlie_messages: Vec<MlsMessageOut> = (0..3)
+        .map(|_| {
+            charlie_group
+                .create_message(provider, &charlie_signer, b"Hi Bob")
+                .unwrap()
+        })
+        .collect();
+    bob_group
+        .process_message(
+            provider,
+            charlie_messages
+                .pop()
+                .unwrap()
+                .into_protocol_message()
+                .unwrap(),
+        )
+        .unwrap();
+
+    // The oldest keys of Alice, who made Bob retain the most keys, are
+    // evicted, while Charlie's keys are kept.
+    let metrics = bob_group.key_retention_metrics();
+    assert_eq!(metrics.retained_keys(), 4);
+    assert_eq!(metrics.largest_ratchet_retention(), 2);
+    assert_eq!(metrics.evicted_keys(), 2);
+
+    for message in charlie_messages {
+        bob_group
+            .process_message(provider, message.into_protocol_message().unwrap())
+            .expect("Bob couldn't decrypt a message with a retained key.");
+    }
+    for (generation, message) in alice_messages.into_iter().enumerate().skip(1) {
+        let result = bob_group.process_message(provider, message.into_protocol_message().unwrap());
+        // The keys of generations 1 and 2 were evicted.
+        assert_eq!(result.is_ok(), generation > 2);
+    }
+    assert_eq!(bob_group.key_retention_metrics().retained_keys(), 0);
+}
+
+// Test that external senders can be added, replaced and removed and that the
+// indices of the remaining external senders shift accordingly.
+#[apply(ciphersuites_and_providers)]
+fn manage_external_senders(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    use crate::{
+        extensions::{ExternalSender, SenderExtensionIndex},
+        messages::external_proposals::ExternalProposal,
+    };
+
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential_with_key, bob_kpb, _bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+    let (ds1_credential_with_key, _ds1_kpb, _ds1_signer, _ds1_pk) =
+        setup_client("DS 1", ciphersuite, provider);
+    let (ds2_credential_with_key, _ds2_kpb, ds2_signer, _ds2_pk) =
+        setup_client("DS 2", ciphersuite, provider);
+    let (ds3_credential_with_key, _ds3_kpb, _ds3_signer, _ds3_pk) =
+        setup_client("DS 3", ciphersuite, provider);
+
+    let [ds1, ds2, ds3] = [
+        ds1_credential_with_key,
+        ds2_credential_with_key,
+        ds3_credential_with_key,
+    ]
+    .map(|credential_with_key| {
+        ExternalSender::new(
+            credential_with_key.signature_key,
+            credential_with_key.credential,
+        )
+    });
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    // === Alice creates a group and adds Bob ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id.clone(),
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let (_, welcome, _) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+
+    let mut bob_group = MlsGroup::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().unwrap(),
+        None,
+    )
+    .unwrap();
+    assert!(alice_group.external_senders().is_empty());
+
+    let mut merge_commit = |alice_group: &mut MlsGroup, commit: MlsMessageOut| {
+        alice_group.merge_pending_commit(provider).unwrap();
+        let processed_message = bob_group
+            .process_message(provider, commit.into_protocol_message().unwrap())
+            .unwrap();
+        let ProcessedMessageContent::StagedCommitMessage(staged_commit) =
+            processed_message.into_content()
+        else {
+            panic!("Expected a StagedCommit.");
+        };
+        bob_group
+            .merge_staged_commit(provider, *staged_commit)
+            .unwrap();
+        assert_eq!(alice_group.external_senders(), bob_group.external_senders());
+        bob_group.external_senders().to_vec()
+    };
+
+    // === Alice adds two external senders ===
+    let (commit, _, _) = alice_group
+        .add_external_sender(provider, &alice_signer, ds1.clone())
+        .unwrap();
+    merge_commit(&mut alice_group, commit);
+    let (commit, _, _) = alice_group
+        .add_external_sender(provider, &alice_signer, ds2.clone())
+        .unwrap();
+    let external_senders = merge_commit(&mut alice_group, commit);
+    assert_eq!(external_senders, vec![ds1.clone(), ds2.clone()]);
+
+    // Adding the same external sender twice fails
+    let error = alice_group
+        .add_external_sender(provider, &alice_signer, ds1.clone())
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        UpdateExternalSendersError::DuplicateExternalSender
+    ));
+
+    // === Alice rotates the first external sender, the second one keeps its index ===
+    let (commit, _, _) = alice_group
+        .replace_external_sender(provider, &alice_signer, ds1.signature_key(), ds3.clone())
+        .unwrap();
+    let external_senders = merge_commit(&mut alice_group, commit);
+    assert_eq!(external_senders, vec![ds3.clone(), ds2.clone()]);
+
+    let proposal = ExternalProposal::new_remove(
+        LeafNodeIndex::new(1),
+        group_id.clone(),
+        alice_group.epoch(),
+        &ds2_signer,
+        SenderExtensionIndex::new(1),
+    )
+    .unwrap();
+    alice_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .expect("the second external sender should keep its index");
+
+    // === Alice removes the first external sender, the second one shifts ===
+    let (commit, _, _) = alice_group
+        .remove_external_sender_by_credential(provider, &alice_signer, ds3.credential())
+        .unwrap();
+    let external_senders = merge_commit(&mut alice_group, commit);
+    assert_eq!(external_senders, vec![ds2.clone()]);
+
+    let proposal = ExternalProposal::new_remove(
+        LeafNodeIndex::new(1),
+        group_id,
+        alice_group.epoch(),
+        &ds2_signer,
+        SenderExtensionIndex::new(0),
+    )
+    .unwrap();
+    alice_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .expect("the second external sender should have shifted");
+
+    // Updating the external senders fails while a GroupContextExtensions
+    // proposal is pending, since a commit can only contain one of them
+    alice_group
+        .propose_group_context_extensions(
+            provider,
+            alice_group.group().context().extensions().clone(),
+            &alice_signer,
+        )
+        .unwrap();
+    let error = alice_group
+        .remove_external_sender_by_signature_key(provider, &alice_signer, ds2.signature_key())
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        UpdateExternalSendersError::PendingGroupContextExtensionsProposal
+    ));
+    alice_group.clear_pending_proposals();
+
+    // === Alice removes the last external sender ===
+    let (commit, _, _) = alice_group
+        .remove_external_sender_by_signature_key(provider, &alice_signer, ds2.signature_key())
+        .unwrap();
+    let external_senders = merge_commit(&mut alice_group, commit);
+    assert!(external_senders.is_empty());
+    assert!(alice_group
+        .group()
+        .context()
+        .extensions()
+        .external_senders()
+        .is_none());
+
+    let error = alice_group
+        .remove_external_sender_by_signature_key(provider, &alice_signer, ds2.signature_key())
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        UpdateExternalSendersError::UnknownExternalSender
+    ));
+}
@@ -16,7 +16,8 @@ use crate::{
     schedule::CommitSecret,
     treesync::{
         node::{
-            encryption_keys::EncryptionKeyPair, leaf_node::LeafNode,
+            encryption_keys::EncryptionKeyPair,
+            leaf_node::{Capabilities, LeafNode},
             parent_node::PlainUpdatePathNode,
         },
         treekem::UpdatePath,
@@ -46,6 +47,7 @@ impl<'a> PublicGroupDiff<'a> {
         signer: &impl Signer,
         credential_with_key: Option<CredentialWithKey>,
         extensions: Option<Extensions>,
+        leaf_node_extensions: Extensions,
     ) -> Result<PathComputationResult, CreateCommitError<KeyStore::Error>> {
         let version = self.group_context().protocol_version();
         let ciphersuite = self.group_context().ciphersuite();
@@ -55,21 +57,29 @@ impl<'a> PublicGroupDiff<'a> {
             // If this is an external commit we add a fresh leaf to the diff.
             // Generate a KeyPackageBundle to generate a payload from for later
             // path generation.
+            // The new leaf has to support its own (non-default) extensions.
+            let mut capabilities = Capabilities::default();
+            for extension in leaf_node_extensions.iter() {
+                capabilities.add_extension_type(extension.extension_type());
+            }
             let KeyPackageCreationResult {
                 key_package,
                 encryption_keypair,
                 // The KeyPackage is immediately put into the group. No need for
                 // the init key.
                 init_private_key: _,
-            } = KeyPackage::builder().build_without_key_storage(
-                CryptoConfig {
-                    ciphersuite,
-                    version,
-                },
-                provider,
-                signer,
-                credential_with_key.ok_or(CreateCommitError::MissingCredential)?,
-            )?;
+            } = KeyPackage::builder()
+                .leaf_node_capabilities(capabilities)
+                .leaf_node_extensions(leaf_node_extensions)
+                .build_without_key_storage(
+                    CryptoConfig {
+                        ciphersuite,
+                        version,
+                    },
+                    provider,
+                    signer,
+                    credential_with_key.ok_or(CreateCommitError::MissingCredential)?,
+                )?;
 
             let leaf_node: LeafNode = key_package.into();
             self.diff
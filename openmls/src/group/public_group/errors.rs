@@ -44,3 +44,17 @@ pub enum PublicGroupBuildError {
     #[error("Invalid extensions set in configuration")]
     InvalidExtensions(#[from] InvalidExtensionError),
 }
+
+/// Handshake observer error
+#[derive(Error, Debug, PartialEq, Clone)]
+pub enum HandshakeObserverError {
+    /// The secrets belong to a different group.
+    #[error("The secrets belong to a different group.")]
+    WrongGroupId,
+    /// The secrets belong to a past epoch.
+    #[error("The secrets belong to a past epoch.")]
+    PastEpoch,
+    /// Secrets for this epoch were added before.
+    #[error("Secrets for this epoch were added before.")]
+    DuplicateEpoch,
+}
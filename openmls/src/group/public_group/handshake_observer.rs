@@ -0,0 +1,137 @@
+//! # Handshake observers
+//!
+//! A [`PublicGroup`] can only follow a group based on the handshake messages,
+//! i.e. proposals and commits, that it can read. If a group encrypts its
+//! handshake messages, the members can opt in to share the keys for these
+//! messages with a designated observer, e.g. a delivery service that validates
+//! the membership of the group.
+//!
+//! To do so, a member exports the [`HandshakeObserverSecrets`] of the current
+//! epoch via [`MlsGroup::export_handshake_observer_secrets()`] and sends them
+//! to the observer over a confidential channel. The observer adds them to its
+//! [`PublicGroup`] via [`PublicGroup::add_handshake_observer_secrets()`],
+//! after which [`PublicGroup::process_message()`] also processes the
+//! encrypted handshake messages of that epoch. The secrets have to be shared
+//! again for every new epoch.
+//!
+//! The secrets don't contain any key material for application messages, so
+//! application data stays private. The observer does learn the sender and the
+//! generation of application messages though, since the secret used to
+//! encrypt the sender data is shared as well.
+//!
+//! The observer can't verify the secrets and thus has to trust the member that
+//! shared them: a malicious member could share secrets that make the observer
+//! accept a handshake message that the other members reject.
+
+use std::fmt::{Debug, Formatter};
+
+use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use serde::{Deserialize, Serialize};
+
+#[cfg(doc)]
+use crate::group::{MlsGroup, PublicGroup};
+use crate::{
+    binary_tree::LeafNodeIndex,
+    group::{GroupEpoch, GroupId},
+    schedule::SenderDataSecret,
+    tree::{
+        secret_tree::SecretTreeError,
+        sender_ratchet::{RatchetKeyMaterial, RatchetSecret, SenderRatchetConfiguration},
+    },
+};
+
+/// The secrets that allow a [`PublicGroup`] to decrypt the handshake messages
+/// of a group in a single epoch. See the [module documentation](self) for
+/// details.
+///
+/// Note: This has a hand-written `Debug` implementation that omits the secrets.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(test, derive(PartialEq, Clone))]
+pub struct HandshakeObserverSecrets {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    ciphersuite: Ciphersuite,
+    sender_data_secret: SenderDataSecret,
+    // The heads of the handshake sender ratchets of the members.
+    handshake_ratchets: Vec<(LeafNodeIndex, RatchetSecret)>,
+}
+
+impl Debug for HandshakeObserverSecrets {
+    fn fmt(&self, f: &mut Formatter) -> std::fmt::Result {
+        f.debug_struct("HandshakeObserverSecrets")
+            .field("group_id", &self.group_id)
+            .field("epoch", &self.epoch)
+            .field("ciphersuite", &self.ciphersuite)
+            .field(
+                "senders",
+                &self
+                    .handshake_ratchets
+                    .iter()
+                    .map(|(leaf_index, _)| leaf_index)
+                    .collect::<Vec<_>>(),
+            )
+            .finish_non_exhaustive()
+    }
+}
+
+impl HandshakeObserverSecrets {
+    pub(crate) fn new(
+        group_id: GroupId,
+        epoch: GroupEpoch,
+        ciphersuite: Ciphersuite,
+        sender_data_secret: SenderDataSecret,
+        handshake_ratchets: Vec<(LeafNodeIndex, RatchetSecret)>,
+    ) -> Self {
+        Self {
+            group_id,
+            epoch,
+            ciphersuite,
+            sender_data_secret,
+            handshake_ratchets,
+        }
+    }
+
+    /// Returns the group id of the group the secrets belong to.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the epoch the secrets belong to.
+    pub fn epoch(&self) -> GroupEpoch {
+        self.epoch
+    }
+
+    pub(crate) fn ciphersuite(&self) -> Ciphersuite {
+        self.ciphersuite
+    }
+
+    pub(crate) fn sender_data_secret(&self) -> &SenderDataSecret {
+        &self.sender_data_secret
+    }
+
+    /// Derive the key material for the handshake message of the given
+    /// `generation` sent by the member at `leaf_index`.
+    ///
+    /// Key material is derived from the shared ratchet heads without changing
+    /// them, so that messages can be processed in any order. Messages with a
+    /// generation further ahead of the shared ratchet head than the default
+    /// [`SenderRatchetConfiguration`] allows are rejected.
+    pub(crate) fn key_material(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        leaf_index: LeafNodeIndex,
+        generation: u32,
+    ) -> Result<RatchetKeyMaterial, SecretTreeError> {
+        let (_, ratchet_secret) = self
+            .handshake_ratchets
+            .iter()
+            .find(|(index, _)| *index == leaf_index)
+            .ok_or(SecretTreeError::IndexOutOfBounds)?;
+        ratchet_secret.key_material_for_generation(
+            crypto,
+            self.ciphersuite,
+            generation,
+            &SenderRatchetConfiguration::default(),
+        )
+    }
+}
@@ -21,9 +21,11 @@ use serde::{Deserialize, Serialize};
 
 use self::{
     diff::{PublicGroupDiff, StagedPublicGroupDiff},
-    errors::CreationFromExternalError,
+    errors::{CreationFromExternalError, HandshakeObserverError},
+};
+use super::{
+    GroupContext, GroupEpoch, GroupId, Member, ProposalStore, QueuedProposal, StagedCommit,
 };
-use super::{GroupContext, GroupId, Member, ProposalStore, QueuedProposal, StagedCommit};
 #[cfg(test)]
 use crate::treesync::{node::parent_node::PlainUpdatePathNode, treekem::UpdatePathNode};
 use crate::{
@@ -39,7 +41,7 @@ use crate::{
     },
     schedule::CommitSecret,
     treesync::{
-        errors::{DerivePathError, TreeSyncFromNodesError},
+        errors::DerivePathError,
         node::{
             encryption_keys::{EncryptionKey, EncryptionKeyPair},
             leaf_node::LeafNode,
@@ -54,12 +56,17 @@ use crate::{framing::PublicMessage, group::CoreGroup};
 pub(crate) mod builder;
 pub(crate) mod diff;
 pub mod errors;
+pub mod handshake_observer;
 pub mod process;
+pub(crate) mod resync;
 pub(crate) mod staged_commit;
 #[cfg(test)]
 mod tests;
 mod validation;
 
+pub use handshake_observer::HandshakeObserverSecrets;
+pub use resync::{ExternalResync, ResyncProof, RESYNC_SIGNATURE_EXTENSION_TYPE};
+
 /// This struct holds all public values of an MLS group.
 #[derive(Debug, Serialize, Deserialize)]
 #[cfg_attr(test, derive(PartialEq, Clone))]
@@ -70,6 +77,10 @@ pub struct PublicGroup {
     interim_transcript_hash: Vec<u8>,
     // Most recent confirmation tag. Kept here for verification purposes.
     confirmation_tag: ConfirmationTag,
+    // Secrets shared by the members to decrypt handshake messages, for the
+    // current and future epochs.
+    #[serde(default)]
+    handshake_observer_secrets: Vec<HandshakeObserverSecrets>,
 }
 
 impl PublicGroup {
@@ -97,6 +108,7 @@ impl PublicGroup {
             group_context,
             interim_transcript_hash,
             confirmation_tag: initial_confirmation_tag,
+            handshake_observer_secrets: Vec::new(),
         })
     }
 
@@ -114,13 +126,7 @@ impl PublicGroup {
         let ciphersuite = verifiable_group_info.ciphersuite();
 
         let group_id = verifiable_group_info.group_id();
-        let ratchet_tree = ratchet_tree
-            .into_verified(ciphersuite, crypto, group_id)
-            .map_err(|e| {
-                CreationFromExternalError::TreeSyncError(TreeSyncFromNodesError::RatchetTreeError(
-                    e,
-                ))
-            })?;
+        let ratchet_tree = ratchet_tree.into_verified(ciphersuite, crypto, group_id)?;
 
         // Create a RatchetTree from the given nodes. We have to do this before
         // verifying the group info, since we need to find the Credential to verify the
@@ -167,6 +173,7 @@ impl PublicGroup {
                 interim_transcript_hash,
                 confirmation_tag: group_info.confirmation_tag().clone(),
                 proposal_store,
+                handshake_observer_secrets: Vec::new(),
             },
             group_info,
         ))
@@ -238,6 +245,10 @@ impl PublicGroup {
         self.group_context = diff.group_context;
         self.interim_transcript_hash = diff.interim_transcript_hash;
         self.confirmation_tag = diff.confirmation_tag;
+        // The secrets of past epochs are not needed anymore.
+        let epoch = self.group_context.epoch();
+        self.handshake_observer_secrets
+            .retain(|secrets| secrets.epoch() >= epoch);
     }
 
     /// Derives [`EncryptionKeyPair`]s for the nodes in the shared direct path
@@ -284,6 +295,43 @@ impl PublicGroup {
     pub fn add_proposal(&mut self, proposal: QueuedProposal) {
         self.proposal_store.add(proposal)
     }
+
+    /// Add [`HandshakeObserverSecrets`] shared by a member, so that the
+    /// encrypted handshake messages of their epoch can be processed. See the
+    /// [`handshake_observer`] module for details.
+    ///
+    /// Secrets can be added ahead of time for a future epoch. They are
+    /// deleted once the group moves past their epoch.
+    ///
+    /// Returns an error if the secrets belong to a different group or a past
+    /// epoch, or if secrets for the same epoch were added before.
+    pub fn add_handshake_observer_secrets(
+        &mut self,
+        secrets: HandshakeObserverSecrets,
+    ) -> Result<(), HandshakeObserverError> {
+        if secrets.group_id() != self.group_id() {
+            return Err(HandshakeObserverError::WrongGroupId);
+        }
+        if secrets.epoch() < self.group_context.epoch() {
+            return Err(HandshakeObserverError::PastEpoch);
+        }
+        if self.handshake_observer_secrets(secrets.epoch()).is_some() {
+            return Err(HandshakeObserverError::DuplicateEpoch);
+        }
+        self.handshake_observer_secrets.push(secrets);
+        Ok(())
+    }
+
+    /// Returns the [`HandshakeObserverSecrets`] for the given epoch, if they
+    /// were added.
+    pub(crate) fn handshake_observer_secrets(
+        &self,
+        epoch: GroupEpoch,
+    ) -> Option<&HandshakeObserverSecrets> {
+        self.handshake_observer_secrets
+            .iter()
+            .find(|secrets| secrets.epoch() == epoch)
+    }
 }
 
 // Getters
@@ -1,4 +1,4 @@
-use openmls_traits::crypto::OpenMlsCrypto;
+use openmls_traits::{crypto::OpenMlsCrypto, time::TimeProvider, OpenMlsProvider};
 use tls_codec::Serialize;
 
 use crate::{
@@ -6,16 +6,16 @@ use crate::{
     credentials::CredentialWithKey,
     error::LibraryError,
     framing::{
-        mls_content::FramedContentBody, ApplicationMessage, DecryptedMessage, ProcessedMessage,
-        ProcessedMessageContent, ProtocolMessage, Sender, SenderContext, UnverifiedMessage,
+        mls_content::FramedContentBody, ApplicationMessage, ContentType, DecryptedMessage,
+        ProcessedMessage, ProcessedMessageContent, ProtocolMessage, Sender, SenderContext,
+        UnverifiedMessage,
     },
     group::{
         core_group::proposals::{ProposalStore, QueuedProposal},
-        errors::ValidationError,
+        errors::{StageCommitError, ValidationError},
         mls_group::errors::ProcessMessageError,
         past_secrets::MessageSecretsStore,
     },
-    messages::proposals::Proposal,
 };
 
 use super::PublicGroup;
@@ -57,7 +57,6 @@ impl PublicGroup {
         //  - ValSem245
         //  - Prepares ValSem246 by setting the right credential. The remainder
         //    of ValSem246 is validated as part of ValSem010.
-        // External senders are not supported yet #106/#151.
         let CredentialWithKey {
             credential,
             signature_key,
@@ -97,7 +96,15 @@ impl PublicGroup {
 
     /// This function is used to parse messages from the DS. It checks for
     /// syntactic errors and does semantic validation as well. It returns a
-    /// [ProcessedMessage] enum. Checks the following semantic validation:
+    /// [ProcessedMessage] enum.
+    ///
+    /// Handshake messages that are [PrivateMessage]s can only be processed if
+    /// a member shared the
+    /// [`HandshakeObserverSecrets`](super::HandshakeObserverSecrets) of their epoch (see
+    /// [`PublicGroup::add_handshake_observer_secrets()`]). Application
+    /// messages can never be processed.
+    ///
+    /// Checks the following semantic validation:
     ///  - ValSem002
     ///  - ValSem003
     ///  - ValSem004
@@ -132,7 +139,7 @@ impl PublicGroup {
     ///  - ValSem246 (as part of ValSem010)
     pub fn process_message(
         &self,
-        crypto: &impl OpenMlsCrypto,
+        provider: &impl OpenMlsProvider,
         message: impl Into<ProtocolMessage>,
     ) -> Result<ProcessedMessage, ProcessMessageError> {
         let protocol_message = message.into();
@@ -142,8 +149,23 @@ impl PublicGroup {
         self.validate_framing(&protocol_message)?;
 
         let decrypted_message = match protocol_message {
-            ProtocolMessage::PrivateMessage(_) => {
-                return Err(ProcessMessageError::IncompatibleWireFormat)
+            ProtocolMessage::PrivateMessage(private_message) => {
+                // The members can share the secrets for handshake messages,
+                // but never those for application messages.
+                if private_message.content_type() == ContentType::Application {
+                    return Err(ProcessMessageError::IncompatibleWireFormat);
+                }
+                let handshake_observer_secrets = self
+                    .handshake_observer_secrets(private_message.epoch())
+                    .ok_or(ProcessMessageError::MissingHandshakeObserverSecrets)?;
+                DecryptedMessage::from_observed_ciphertext(
+                    private_message,
+                    provider.crypto(),
+                    handshake_observer_secrets,
+                    self.group_context()
+                        .tls_serialize_detached()
+                        .map_err(LibraryError::missing_bound_check)?,
+                )?
             }
             ProtocolMessage::PublicMessage(public_message) => {
                 DecryptedMessage::from_inbound_public_message(
@@ -152,7 +174,7 @@ impl PublicGroup {
                     self.group_context()
                         .tls_serialize_detached()
                         .map_err(LibraryError::missing_bound_check)?,
-                    crypto,
+                    provider.crypto(),
                 )?
             }
         };
@@ -160,7 +182,12 @@ impl PublicGroup {
         let unverified_message = self
             .parse_message(decrypted_message, None)
             .map_err(ProcessMessageError::from)?;
-        self.process_unverified_message(crypto, unverified_message, &self.proposal_store)
+        self.process_unverified_message(
+            provider.crypto(),
+            provider.time(),
+            unverified_message,
+            &self.proposal_store,
+        )
     }
 }
 
@@ -195,6 +222,7 @@ impl PublicGroup {
     pub(crate) fn process_unverified_message(
         &self,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         unverified_message: UnverifiedMessage,
         proposal_store: &ProposalStore,
     ) -> Result<ProcessedMessage, ProcessMessageError> {
@@ -202,7 +230,7 @@ impl PublicGroup {
         //  - ValSem010
         //  - ValSem246 (as part of ValSem010)
         let (content, credential) =
-            unverified_message.verify(self.ciphersuite(), crypto, self.version())?;
+            unverified_message.verify(self.ciphersuite(), crypto, time, self.version())?;
 
         match content.sender() {
             Sender::Member(_) | Sender::NewMemberCommit | Sender::NewMemberProposal => {
@@ -228,7 +256,8 @@ impl PublicGroup {
                         }
                     }
                     FramedContentBody::Commit(_) => {
-                        let staged_commit = self.stage_commit(&content, proposal_store, crypto)?;
+                        let staged_commit =
+                            self.stage_commit(&content, proposal_store, crypto, time)?;
                         ProcessedMessageContent::StagedCommitMessage(Box::new(staged_commit))
                     }
                 };
@@ -249,28 +278,26 @@ impl PublicGroup {
                     FramedContentBody::Application(_) => {
                         Err(ProcessMessageError::UnauthorizedExternalApplicationMessage)
                     }
-                    FramedContentBody::Proposal(Proposal::Remove(_)) => {
-                        let content = ProcessedMessageContent::ProposalMessage(Box::new(
-                            QueuedProposal::from_authenticated_content_by_ref(
-                                self.ciphersuite(),
-                                crypto,
-                                content,
-                            )?,
-                        ));
+                    FramedContentBody::Proposal(_) => {
+                        let proposal = QueuedProposal::from_authenticated_content_by_ref(
+                            self.ciphersuite(),
+                            crypto,
+                            content,
+                        )?;
+                        self.validate_external_proposal(&proposal, time)?;
                         Ok(ProcessedMessage::new(
                             self.group_id().clone(),
                             self.group_context().epoch(),
                             sender,
                             data,
-                            content,
+                            ProcessedMessageContent::ProposalMessage(Box::new(proposal)),
                             credential,
                         ))
                     }
-                    // TODO #151/#106
-                    FramedContentBody::Proposal(_) => {
-                        Err(ProcessMessageError::UnsupportedProposalType)
-                    }
-                    FramedContentBody::Commit(_) => unimplemented!(),
+                    // A commit cannot be issued by a preconfigured sender.
+                    FramedContentBody::Commit(_) => Err(ProcessMessageError::InvalidCommit(
+                        StageCommitError::SenderTypeExternal,
+                    )),
                 }
             }
         }
@@ -0,0 +1,268 @@
+//! # External commit resync
+//!
+//! A client that lost its group state can rejoin the group through an external
+//! commit that removes its own, now unusable, leaf (see Section 12.4.3.2 of the
+//! MLS specification). This module contains the types used to describe such a
+//! resync and to prove that the joiner is entitled to replace the removed leaf.
+//!
+//! Ownership of the removed leaf is proven in one of two ways:
+//!  - the new leaf uses the same signature key as the removed leaf, or
+//!  - the new leaf carries a signature over a [`ResyncTbs`] created with the
+//!    signature key of the removed leaf. The signature is transported in a leaf
+//!    node extension of type [`RESYNC_SIGNATURE_EXTENSION_TYPE`].
+
+use openmls_traits::crypto::OpenMlsCrypto;
+use serde::{Deserialize, Serialize};
+use tls_codec::{
+    Deserialize as TlsDeserializeTrait, Serialize as TlsSerializeTrait, TlsSerialize, TlsSize,
+};
+
+use super::PublicGroup;
+use crate::{
+    binary_tree::LeafNodeIndex,
+    ciphersuite::{
+        signable::{Signable, SignedStruct},
+        OpenMlsSignaturePublicKey, SignContent, Signature, SignaturePublicKey,
+    },
+    extensions::{Extension, UnknownExtension},
+    framing::Sender,
+    group::{errors::ExternalCommitValidationError, GroupEpoch, GroupId, ProposalQueue},
+    messages::{
+        proposals::{Proposal, ProposalOrRefType, ProposalType},
+        Commit,
+    },
+    treesync::LeafNode,
+};
+
+/// Label for the signature over a [`ResyncTbs`].
+const RESYNC_SIGNATURE_LABEL: &str = "ExternalResync";
+
+/// The (private use) extension type of the leaf node extension that carries
+/// the signature of the removed leaf's signature key in a resync.
+pub const RESYNC_SIGNATURE_EXTENSION_TYPE: u16 = 0xff0e;
+
+/// How a resyncing client proved that it owns the leaf removed by its external
+/// commit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ResyncProof {
+    /// The new leaf uses the same signature key as the removed leaf.
+    SignatureKey,
+    /// The new leaf carries a valid signature by the signature key of the
+    /// removed leaf.
+    OldKeySignature,
+}
+
+/// Describes an external commit that removes a prior leaf of the group, i.e.
+/// a client rejoining the group after losing its state.
+///
+/// The removed leaf's credential identity is always identical to the one of
+/// the joiner (ValSem243). If no [`ResyncProof`] is present, the joiner did not
+/// prove that it controls the removed leaf and the application should decide
+/// whether it trusts the resync, e.g. through the
+/// [`ResyncPolicy`](crate::group::ResyncPolicy) of the group.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExternalResync {
+    removed: LeafNodeIndex,
+    proof: Option<ResyncProof>,
+}
+
+impl ExternalResync {
+    /// Returns the index of the leaf that was replaced by the joiner.
+    pub fn removed(&self) -> LeafNodeIndex {
+        self.removed
+    }
+
+    /// Returns how the joiner proved ownership of the removed leaf, if at all.
+    pub fn proof(&self) -> Option<ResyncProof> {
+        self.proof
+    }
+
+    /// Returns `true` if the joiner proved ownership of the removed leaf.
+    pub fn is_proven(&self) -> bool {
+        self.proof.is_some()
+    }
+}
+
+/// The prior leaf that a client replaces when rejoining through an external
+/// commit, together with an (optional) signature by the leaf's signature key.
+/// The signature is required if the client uses a new signature key.
+pub(crate) struct ResyncTarget {
+    pub(crate) leaf_index: LeafNodeIndex,
+    pub(crate) old_key_signature: Option<ResyncSignature>,
+}
+
+/// The content that is signed with the signature key of the removed leaf.
+///
+/// ```text
+/// struct {
+///     opaque group_id<V>;
+///     uint64 epoch;
+///     uint32 removed;
+///     SignaturePublicKey signature_key;
+/// } ResyncTBS;
+/// ```
+#[derive(Debug, TlsSerialize, TlsSize)]
+pub(crate) struct ResyncTbs {
+    group_id: GroupId,
+    epoch: GroupEpoch,
+    removed: LeafNodeIndex,
+    signature_key: SignaturePublicKey,
+}
+
+impl ResyncTbs {
+    pub(crate) fn new(
+        group_id: GroupId,
+        epoch: GroupEpoch,
+        removed: LeafNodeIndex,
+        signature_key: SignaturePublicKey,
+    ) -> Self {
+        Self {
+            group_id,
+            epoch,
+            removed,
+            signature_key,
+        }
+    }
+
+    /// Verify the given `signature` over this [`ResyncTbs`] with the signature
+    /// key of the removed leaf.
+    pub(crate) fn verify(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        signature_public_key: &OpenMlsSignaturePublicKey,
+        signature: &Signature,
+    ) -> Result<(), ExternalCommitValidationError> {
+        let payload = self
+            .tls_serialize_detached()
+            .map_err(|_| ExternalCommitValidationError::InvalidResyncSignature)?;
+        signature_public_key
+            .verify_with_label(
+                crypto,
+                signature,
+                &SignContent::new(RESYNC_SIGNATURE_LABEL, payload.into()),
+            )
+            .map_err(|_| ExternalCommitValidationError::InvalidResyncSignature)
+    }
+}
+
+/// The signature over a [`ResyncTbs`].
+pub(crate) struct ResyncSignature {
+    signature: Signature,
+}
+
+impl ResyncSignature {
+    /// Returns the leaf node extension carrying this signature.
+    pub(crate) fn into_extension(self) -> Result<Extension, tls_codec::Error> {
+        Ok(Extension::Unknown(
+            RESYNC_SIGNATURE_EXTENSION_TYPE,
+            UnknownExtension(self.signature.tls_serialize_detached()?),
+        ))
+    }
+
+    /// Returns the signature.
+    pub(crate) fn signature(&self) -> &Signature {
+        &self.signature
+    }
+}
+
+impl SignedStruct<ResyncTbs> for ResyncSignature {
+    fn from_payload(_payload: ResyncTbs, signature: Signature) -> Self {
+        Self { signature }
+    }
+}
+
+impl Signable for ResyncTbs {
+    type SignedOutput = ResyncSignature;
+
+    fn unsigned_payload(&self) -> Result<Vec<u8>, tls_codec::Error> {
+        self.tls_serialize_detached()
+    }
+
+    fn label(&self) -> &str {
+        RESYNC_SIGNATURE_LABEL
+    }
+}
+
+/// Returns the resync signature carried by the given leaf node, if any.
+fn resync_signature(
+    leaf_node: &LeafNode,
+) -> Result<Option<Signature>, ExternalCommitValidationError> {
+    leaf_node
+        .extensions()
+        .iter()
+        .find_map(|extension| match extension {
+            Extension::Unknown(RESYNC_SIGNATURE_EXTENSION_TYPE, UnknownExtension(bytes)) => {
+                Some(bytes)
+            }
+            _ => None,
+        })
+        .map(|bytes| {
+            Signature::tls_deserialize_exact(bytes)
+                .map_err(|_| ExternalCommitValidationError::InvalidResyncSignature)
+        })
+        .transpose()
+}
+
+impl PublicGroup {
+    /// Returns the [`ExternalResync`] of the given commit if it is an external
+    /// commit that removes a prior leaf of the group. The commit must have been
+    /// validated with `validate_commit()` before.
+    ///
+    /// Returns an error if the new leaf carries a resync signature that doesn't
+    /// verify under the signature key of the removed leaf.
+    pub(crate) fn external_resync(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        sender: &Sender,
+        commit: &Commit,
+        proposal_queue: &ProposalQueue,
+    ) -> Result<Option<ExternalResync>, ExternalCommitValidationError> {
+        if sender != &Sender::NewMemberCommit {
+            return Ok(None);
+        }
+
+        // External commits only contain inline proposals (ValSem244) and at
+        // most one Remove proposal.
+        let removed = match proposal_queue
+            .filtered_by_type(ProposalType::Remove)
+            .filter(|p| p.proposal_or_ref_type() == ProposalOrRefType::Proposal)
+            .find_map(|p| match p.proposal() {
+                Proposal::Remove(remove_proposal) => Some(remove_proposal.removed()),
+                _ => None,
+            }) {
+            Some(removed) => removed,
+            None => return Ok(None),
+        };
+
+        let removed_leaf = self
+            .treesync()
+            .leaf(removed)
+            .ok_or(ExternalCommitValidationError::UnknownMemberRemoval)?;
+        let new_leaf = commit
+            .path()
+            .as_ref()
+            .map(|path| path.leaf_node())
+            .ok_or(ExternalCommitValidationError::NoPath)?;
+
+        let proof = if new_leaf.signature_key() == removed_leaf.signature_key() {
+            Some(ResyncProof::SignatureKey)
+        } else if let Some(signature) = resync_signature(new_leaf)? {
+            let signature_public_key = OpenMlsSignaturePublicKey::from_signature_key(
+                removed_leaf.signature_key().clone(),
+                self.ciphersuite().signature_algorithm(),
+            );
+            ResyncTbs::new(
+                self.group_id().clone(),
+                self.group_context().epoch(),
+                removed,
+                new_leaf.signature_key().clone(),
+            )
+            .verify(crypto, &signature_public_key, &signature)?;
+            Some(ResyncProof::OldKeySignature)
+        } else {
+            None
+        };
+
+        Ok(Some(ExternalResync { removed, proof }))
+    }
+}
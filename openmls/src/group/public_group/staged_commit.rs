@@ -1,3 +1,5 @@
+use openmls_traits::time::TimeProvider;
+
 use super::{super::errors::*, *};
 use crate::{
     framing::{mls_auth_content::AuthenticatedContent, mls_content::FramedContentBody, Sender},
@@ -17,6 +19,7 @@ impl PublicGroup {
         mls_content: &'a AuthenticatedContent,
         proposal_store: &ProposalStore,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
     ) -> Result<(&'a Commit, ProposalQueue, LeafNodeIndex), StageCommitError> {
         let ciphersuite = self.ciphersuite();
 
@@ -78,9 +81,12 @@ impl PublicGroup {
         // ValSem102
         // ValSem103
         // ValSem104
+        // ValSem110
+        // ValSem115
         self.validate_key_uniqueness(&proposal_queue, Some(commit))?;
         // ValSem105
-        self.validate_add_proposals(&proposal_queue)?;
+        // ValSem116
+        self.validate_add_proposals(&proposal_queue, time)?;
         // ValSem106
         // ValSem109
         self.validate_capabilities(&proposal_queue)?;
@@ -97,10 +103,11 @@ impl PublicGroup {
 
         match sender {
             Sender::Member(leaf_index) => {
-                // ValSem110
                 // ValSem111
                 // ValSem112
-                self.validate_update_proposals(&proposal_queue, *leaf_index)?;
+                // ValSem113
+                // ValSem114
+                self.validate_update_proposals(crypto, &proposal_queue, *leaf_index)?;
             }
             Sender::External(_) => {
                 // A commit cannot be issued by a pre-configured sender.
@@ -166,6 +173,10 @@ impl PublicGroup {
     ///  - ValSem110
     ///  - ValSem111
     ///  - ValSem112
+    ///  - ValSem113
+    ///  - ValSem114
+    ///  - ValSem115
+    ///  - ValSem116
     ///  - ValSem200
     ///  - ValSem201
     ///  - ValSem202: Path must be the right length
@@ -188,17 +199,24 @@ impl PublicGroup {
         mls_content: &AuthenticatedContent,
         proposal_store: &ProposalStore,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
     ) -> Result<StagedCommit, StageCommitError> {
         let ciphersuite = self.ciphersuite();
 
         let (commit, proposal_queue, sender_index) =
-            self.validate_commit(mls_content, proposal_store, crypto)?;
+            self.validate_commit(mls_content, proposal_store, crypto, time)?;
+        let external_resync =
+            self.external_resync(crypto, mls_content.sender(), commit, &proposal_queue)?;
 
         let staged_diff = self.stage_diff(mls_content, &proposal_queue, sender_index, crypto)?;
 
         let staged_commit_state = StagedCommitState::PublicState(Box::new(staged_diff));
 
-        Ok(StagedCommit::new(proposal_queue, staged_commit_state))
+        Ok(StagedCommit::new(
+            proposal_queue,
+            staged_commit_state,
+            external_resync,
+        ))
     }
 
     fn stage_diff(
@@ -10,13 +10,14 @@ use crate::{
         ProcessedMessageContent, ProtocolMessage, Sender,
     },
     group::{
-        config::CryptoConfig, test_core_group::setup_client, GroupId, MlsGroup,
-        MlsGroupCreateConfig, ProposalStore, StagedCommit, PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
+        config::CryptoConfig, errors::ProcessMessageError, test_core_group::setup_client, GroupId,
+        MlsGroup, MlsGroupCreateConfig, ProposalStore, StagedCommit,
+        PURE_CIPHERTEXT_WIRE_FORMAT_POLICY, PURE_PLAINTEXT_WIRE_FORMAT_POLICY,
     },
     messages::proposals::Proposal,
 };
 
-use super::PublicGroup;
+use super::{errors::HandshakeObserverError, PublicGroup};
 
 #[apply(ciphersuites_and_providers)]
 fn public_group(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
@@ -75,7 +76,7 @@ fn public_group(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
         ProtocolMessage::PublicMessage(public_message) => public_message,
     };
     let processed_message = public_group
-        .process_message(provider.crypto(), public_message)
+        .process_message(provider, public_message)
         .unwrap();
 
     // Further inspection of the message can take place here ...
@@ -128,7 +129,7 @@ fn public_group(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
 
     // The public group processes
     let ppm = public_group
-        .process_message(provider.crypto(), into_public_message(queued_messages))
+        .process_message(provider, into_public_message(queued_messages))
         .unwrap();
     public_group.merge_commit(extract_staged_commit(ppm));
 
@@ -163,7 +164,7 @@ fn public_group(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
 
     // The public group processes
     let ppm = public_group
-        .process_message(provider.crypto(), into_public_message(queued_messages))
+        .process_message(provider, into_public_message(queued_messages))
         .unwrap();
     // We have to add the proposal to the public group's proposal store.
     match ppm.into_content() {
@@ -208,10 +209,7 @@ fn public_group(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
 
     // The public group processes
     let ppm = public_group
-        .process_message(
-            provider.crypto(),
-            into_public_message(queued_messages.clone()),
-        )
+        .process_message(provider, into_public_message(queued_messages.clone()))
         .unwrap();
     public_group.merge_commit(extract_staged_commit(ppm));
 
@@ -271,6 +269,160 @@ fn public_group(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
     );
 }
 
+// Test that a public group can follow a group with encrypted handshake
+// messages if the members share the handshake observer secrets.
+#[apply(ciphersuites_and_providers)]
+fn observe_encrypted_handshakes(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_pk) =
+        setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, bob_signer, _bob_pk) =
+        setup_client("Bob", ciphersuite, provider);
+
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(PURE_CIPHERTEXT_WIRE_FORMAT_POLICY)
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .build();
+
+    // === Alice creates a group and a public group tracks it ===
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let verifiable_group_info = alice_group
+        .export_group_info(provider.crypto(), &alice_signer, false)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let (mut public_group, _extensions) = PublicGroup::from_external(
+        provider.crypto(),
+        alice_group.export_ratchet_tree().into(),
+        verifiable_group_info,
+        ProposalStore::new(),
+    )
+    .unwrap();
+
+    // === Alice shares the secrets of the epoch and adds Bob ===
+    let handshake_observer_secrets = alice_group
+        .export_handshake_observer_secrets(provider.crypto())
+        .unwrap();
+    public_group
+        .add_handshake_observer_secrets(handshake_observer_secrets)
+        .unwrap();
+
+    let (message, welcome, _group_info) = alice_group
+        .add_members(provider, &alice_signer, &[bob_kpb.key_package().clone()])
+        .expect("Could not add member to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    let protocol_message = message.into_protocol_message().unwrap();
+    assert!(matches!(
+        protocol_message,
+        ProtocolMessage::PrivateMessage(_)
+    ));
+    let processed_message = public_group
+        .process_message(provider, protocol_message)
+        .unwrap();
+    public_group.merge_commit(extract_staged_commit(processed_message));
+
+    let mut bob_group = MlsGroup::new_from_welcome(
+        provider,
+        mls_group_create_config.join_config(),
+        welcome.into_welcome().expect("Unexpected message type."),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .expect("Error creating group from Welcome");
+
+    // === Without the secrets of the new epoch, Bob's commit can't be processed ===
+    let (commit, _welcome, _group_info) = bob_group.self_update(provider, &bob_signer).unwrap();
+    let error = public_group
+        .process_message(provider, commit.clone().into_protocol_message().unwrap())
+        .unwrap_err();
+    assert_eq!(error, ProcessMessageError::MissingHandshakeObserverSecrets);
+
+    // === Alice shares the secrets of the new epoch ===
+    let handshake_observer_secrets = alice_group
+        .export_handshake_observer_secrets(provider.crypto())
+        .unwrap();
+    public_group
+        .add_handshake_observer_secrets(handshake_observer_secrets)
+        .unwrap();
+    let handshake_observer_secrets = bob_group
+        .export_handshake_observer_secrets(provider.crypto())
+        .unwrap();
+    assert_eq!(
+        public_group.add_handshake_observer_secrets(handshake_observer_secrets),
+        Err(HandshakeObserverError::DuplicateEpoch)
+    );
+
+    let processed_message = public_group
+        .process_message(provider, commit.clone().into_protocol_message().unwrap())
+        .unwrap();
+    assert_eq!(
+        processed_message.sender(),
+        &Sender::Member(LeafNodeIndex::new(1))
+    );
+    public_group.merge_commit(extract_staged_commit(processed_message));
+
+    let processed_message = alice_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    alice_group
+        .merge_staged_commit(provider, extract_staged_commit(processed_message))
+        .unwrap();
+    bob_group.merge_pending_commit(provider).unwrap();
+
+    assert_eq!(
+        alice_group.export_ratchet_tree(),
+        public_group.export_ratchet_tree()
+    );
+    assert_eq!(
+        alice_group.export_group_context(),
+        public_group.group_context()
+    );
+
+    // === Application messages stay private ===
+    let handshake_observer_secrets = bob_group
+        .export_handshake_observer_secrets(provider.crypto())
+        .unwrap();
+    public_group
+        .add_handshake_observer_secrets(handshake_observer_secrets)
+        .unwrap();
+    let application_message = bob_group
+        .create_message(provider, &bob_signer, b"Hello Alice")
+        .unwrap();
+    let error = public_group
+        .process_message(
+            provider,
+            application_message.into_protocol_message().unwrap(),
+        )
+        .unwrap_err();
+    assert_eq!(error, ProcessMessageError::IncompatibleWireFormat);
+
+    // === Secrets of past epochs are rejected ===
+    let (commit, _welcome, _group_info) = alice_group.self_update(provider, &alice_signer).unwrap();
+    let stale_secrets = alice_group
+        .export_handshake_observer_secrets(provider.crypto())
+        .unwrap();
+    let processed_message = public_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .unwrap();
+    public_group.merge_commit(extract_staged_commit(processed_message));
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(
+        public_group.add_handshake_observer_secrets(stale_secrets),
+        Err(HandshakeObserverError::PastEpoch)
+    );
+}
+
 // A helper function
 fn into_public_message(message: MlsMessageOut) -> PublicMessageIn {
     match message.into_protocol_message().unwrap() {
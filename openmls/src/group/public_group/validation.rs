@@ -1,9 +1,9 @@
 //! This module contains validation functions for incoming messages
 //! as defined in <https://github.com/openmls/openmls/wiki/Message-validation>
 
-use std::collections::{BTreeSet, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
-use openmls_traits::types::VerifiableCiphersuite;
+use openmls_traits::{crypto::OpenMlsCrypto, time::TimeProvider, types::VerifiableCiphersuite};
 
 use super::PublicGroup;
 use crate::extensions::RequiredCapabilitiesExtension;
@@ -12,21 +12,23 @@ use crate::prelude::LibraryError;
 use crate::treesync::errors::LeafNodeValidationError;
 use crate::{
     binary_tree::array_representation::LeafNodeIndex,
+    ciphersuite::{hash_ref::ProposalRef, signable::Verifiable},
     framing::{
         mls_auth_content_in::VerifiableAuthenticatedContentIn, ContentType, ProtocolMessage,
         Sender, WireFormat,
     },
     group::{
         errors::{ExternalCommitValidationError, ProposalValidationError, ValidationError},
+        mls_group::errors::ProcessMessageError,
         past_secrets::MessageSecretsStore,
-        Member, ProposalQueue,
+        Member, ProposalQueue, QueuedProposal, UnsupportedProposal, UnsupportedProposalReason,
     },
     messages::{
         proposals::{Proposal, ProposalOrRefType, ProposalType},
         Commit,
     },
     schedule::errors::PskError,
-    treesync::node::leaf_node::LeafNode,
+    treesync::node::leaf_node::{LeafNode, LeafNodeIn, TreePosition, VerifiableLeafNode},
 };
 
 impl PublicGroup {
@@ -128,6 +130,7 @@ impl PublicGroup {
     ///  - ValSem103: Add Proposal: Encryption key in proposals must be unique among proposals & members
     ///  - ValSem104: Add Proposal: Init key and encryption key must be different
     ///  - ValSem110: Update Proposal: Encryption key must be unique among proposals & members
+    ///  - ValSem115: Update Proposal: Signature public key must be unique among proposals & members other than the sender
     ///  - ValSem206: Commit: Path leaf node encryption key must be unique among proposals & members
     ///  - ValSem207: Commit: Path encryption keys must be unique among proposals & members
     pub(crate) fn validate_key_uniqueness(
@@ -145,6 +148,16 @@ impl PublicGroup {
                 .map(|remove_proposal| remove_proposal.remove_proposal().removed),
         );
 
+        // Members that send an update replace their signature key, so it's
+        // fine for the update to reuse it.
+        let updating_members =
+            HashSet::<LeafNodeIndex>::from_iter(proposal_queue.update_proposals().filter_map(
+                |update_proposal| match update_proposal.sender() {
+                    Sender::Member(index) => Some(*index),
+                    _ => None,
+                },
+            ));
+
         // Initialize the sets with the current members, filtered by the
         // remove proposals.
         for Member {
@@ -155,21 +168,33 @@ impl PublicGroup {
         } in self.treesync().full_leave_members()
         {
             if !remove_proposals.contains(&index) {
-                signature_key_set.insert(signature_key);
+                if !updating_members.contains(&index) {
+                    signature_key_set.insert(signature_key);
+                }
                 encryption_key_set.insert(encryption_key);
             }
         }
 
-        // Collect signature keys from add proposals
-        let signature_keys = proposal_queue.add_proposals().map(|add_proposal| {
-            add_proposal
-                .add_proposal()
-                .key_package()
-                .leaf_node()
-                .signature_key()
-                .as_slice()
-                .to_vec()
-        });
+        // Collect signature keys from add proposals and update proposals
+        let signature_keys = proposal_queue
+            .add_proposals()
+            .map(|add_proposal| {
+                add_proposal
+                    .add_proposal()
+                    .key_package()
+                    .leaf_node()
+                    .signature_key()
+                    .as_slice()
+                    .to_vec()
+            })
+            .chain(proposal_queue.update_proposals().map(|update_proposal| {
+                update_proposal
+                    .update_proposal()
+                    .leaf_node()
+                    .signature_key()
+                    .as_slice()
+                    .to_vec()
+            }));
 
         // Collect encryption keys from add proposals, update proposals, the
         // commit leaf node and path keys
@@ -225,6 +250,7 @@ impl PublicGroup {
 
         // Validate uniqueness of signature keys
         //  - ValSem101
+        //  - ValSem115
         for signature_key in signature_keys {
             if !signature_key_set.insert(signature_key) {
                 return Err(ProposalValidationError::DuplicateSignatureKey);
@@ -291,7 +317,7 @@ impl PublicGroup {
                 _ => None,
             });
 
-        let mut group_leaf_nodes = self.treesync().full_leaves();
+        let group_leaf_nodes: Vec<&LeafNode> = self.treesync().full_leaves().collect();
 
         for leaf_node in leaf_nodes {
             // Check if the ciphersuite and the version of the group are
@@ -327,7 +353,7 @@ impl PublicGroup {
             }
 
             // Check that the credential type is supported by all members of the group.
-            if !group_leaf_nodes.all(|node| {
+            if !group_leaf_nodes.iter().all(|node| {
                 node.capabilities()
                     .contains_credential(&leaf_node.credential().credential_type())
             }) {
@@ -338,6 +364,7 @@ impl PublicGroup {
             // support for all the credential types currently in use by other
             // members.
             if !group_leaf_nodes
+                .iter()
                 .all(|node| capabilities.contains_credential(&node.credential().credential_type()))
             {
                 return Err(ProposalValidationError::InsufficientCapabilities);
@@ -348,9 +375,12 @@ impl PublicGroup {
 
     /// Validate Add proposals. This function implements the following checks:
     ///  - ValSem105: Add Proposal: Ciphersuite & protocol version must match the group
+    ///  - ValSem116: Add Proposal: The lifetime of the key package must be valid
+    ///               at the current time of the given [`TimeProvider`]
     pub(crate) fn validate_add_proposals(
         &self,
         proposal_queue: &ProposalQueue,
+        time: &impl TimeProvider,
     ) -> Result<(), ProposalValidationError> {
         let add_proposals = proposal_queue.add_proposals();
 
@@ -361,6 +391,77 @@ impl PublicGroup {
             {
                 return Err(ProposalValidationError::InvalidAddProposalCiphersuiteOrVersion);
             }
+
+            // ValSem116: Check that the key package has not expired since
+            // the proposal was received.
+            let lifetime_is_valid = add_proposal
+                .add_proposal()
+                .key_package()
+                .leaf_node()
+                .life_time()
+                .map(|life_time| life_time.is_valid(time))
+                .unwrap_or(false);
+            if !lifetime_is_valid {
+                return Err(ProposalValidationError::InvalidAddProposalLifetime);
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate a standalone proposal from a preconfigured external sender.
+    /// This function implements the following checks:
+    ///  - ValSem101-ValSem106, ValSem116 for Add proposals
+    ///  - ValSem117: ReInit Proposal: The protocol version must not be lower
+    ///               than the one of the group
+    ///  - ValSem208 and ValSem209 for GroupContextExtensions proposals
+    ///  - ValSem401-ValSem403 for PreSharedKey proposals
+    ///  - ValSem118: External Proposal: Only Add, Remove, PreSharedKey, ReInit
+    ///               and GroupContextExtensions proposals can be sent by
+    ///               preconfigured external senders
+    ///
+    /// Remove proposals are validated when they are committed.
+    pub(crate) fn validate_external_proposal(
+        &self,
+        queued_proposal: &QueuedProposal,
+        time: &impl TimeProvider,
+    ) -> Result<(), ProcessMessageError> {
+        let mut proposal_queue = ProposalQueue::default();
+        proposal_queue.add(queued_proposal.clone());
+        match queued_proposal.proposal() {
+            Proposal::Add(_) => {
+                // ValSem101
+                // ValSem102
+                // ValSem103
+                // ValSem104
+                self.validate_key_uniqueness(&proposal_queue, None)?;
+                // ValSem105
+                // ValSem116
+                self.validate_add_proposals(&proposal_queue, time)?;
+                // ValSem106
+                self.validate_capabilities(&proposal_queue)?;
+            }
+            Proposal::Remove(_) => {}
+            Proposal::PreSharedKey(_) => {
+                // ValSem401
+                // ValSem402
+                // ValSem403
+                self.validate_pre_shared_key_proposals(&proposal_queue)?;
+            }
+            Proposal::ReInit(reinit_proposal) => {
+                // ValSem117
+                if reinit_proposal.version < self.version() {
+                    return Err(ProposalValidationError::InvalidReInitProposalVersion.into());
+                }
+            }
+            Proposal::GroupContextExtensions(_) => {
+                // ValSem208
+                // ValSem209
+                self.validate_group_context_extensions_proposal(&proposal_queue)?;
+            }
+            // ValSem118
+            Proposal::Update(_) | Proposal::ExternalInit(_) | Proposal::AppAck(_) => {
+                return Err(ProcessMessageError::UnsupportedProposalType)
+            }
         }
         Ok(())
     }
@@ -395,9 +496,18 @@ impl PublicGroup {
     /// Validate Update proposals. This function implements the following checks:
     ///  - ValSem111: Update Proposal: The sender of a full Commit must not include own update proposals
     ///  - ValSem112: Update Proposal: The sender of a standalone update proposal must be of type member
-    /// TODO: #133 This validation must be updated according to Sec. 13.2
+    ///  - ValSem113: Update Proposal: The leaf node source must be `update`
+    ///  - ValSem114: Update Proposal: The leaf node signature must be valid for the sender's position in the tree
+    ///
+    /// The remaining checks of Section 13.2 of the MLS specification are
+    /// covered by ValSem109 (capabilities and credential types), ValSem110
+    /// (encryption key) and ValSem115 (signature key) in
+    /// [`Self::validate_capabilities()`] and [`Self::validate_key_uniqueness()`].
+    /// Leaf nodes with source `update` don't carry a lifetime, so no lifetime
+    /// check is required.
     pub(crate) fn validate_update_proposals(
         &self,
+        crypto: &impl OpenMlsCrypto,
         proposal_queue: &ProposalQueue,
         committer: LeafNodeIndex,
     ) -> Result<(), ProposalValidationError> {
@@ -407,15 +517,38 @@ impl PublicGroup {
         for update_proposal in update_proposals {
             // ValSem112
             // The sender of a standalone update proposal must be of type member
-            if let Sender::Member(sender_index) = update_proposal.sender() {
-                // ValSem111
-                // The sender of a full Commit must not include own update proposals
-                if committer == *sender_index {
-                    return Err(ProposalValidationError::CommitterIncludedOwnUpdate);
-                }
-            } else {
-                return Err(ProposalValidationError::UpdateFromNonMember);
+            let sender_index = match update_proposal.sender() {
+                Sender::Member(sender_index) => *sender_index,
+                _ => return Err(ProposalValidationError::UpdateFromNonMember),
+            };
+
+            // ValSem111
+            // The sender of a full Commit must not include own update proposals
+            if committer == sender_index {
+                return Err(ProposalValidationError::CommitterIncludedOwnUpdate);
             }
+
+            // ValSem113
+            // The leaf node source must be `update`
+            let mut leaf_node =
+                match LeafNodeIn::from(update_proposal.update_proposal().leaf_node().clone())
+                    .into_verifiable_leaf_node()
+                {
+                    VerifiableLeafNode::Update(leaf_node) => leaf_node,
+                    _ => return Err(ProposalValidationError::InvalidLeafNodeSource),
+                };
+
+            // ValSem114
+            // The leaf node signature must be valid for the sender's position
+            // in the tree
+            let signature_key = leaf_node
+                .signature_key()
+                .clone()
+                .into_signature_public_key_enriched(self.ciphersuite().signature_algorithm());
+            leaf_node.add_tree_position(TreePosition::new(self.group_id().clone(), sender_index));
+            leaf_node
+                .verify(crypto, &signature_key)
+                .map_err(|_| ProposalValidationError::InvalidLeafNodeSignature)?;
         }
         Ok(())
     }
@@ -589,4 +722,102 @@ impl PublicGroup {
         }
         Ok(())
     }
+
+    // === Capabilities ===
+
+    /// Removes the proposals that are not supported by all members that will
+    /// process the commit, i.e. all members that are not removed by the commit
+    /// (see Section 12.2 of RFC 9420). For members with an update proposal in
+    /// the queue, the capabilities of the new leaf node are used.
+    ///
+    /// Only proposals with a reference in `proposal_store_refs`, i.e. pending
+    /// proposals from the proposal store, are removed. Inline proposals are
+    /// left to the regular proposal validation.
+    ///
+    /// Returns the filtered [`ProposalQueue`] and the proposals that were
+    /// dropped.
+    pub(crate) fn filter_unsupported_proposals(
+        &self,
+        proposal_queue: ProposalQueue,
+        proposal_store_refs: &HashSet<ProposalRef>,
+    ) -> (ProposalQueue, Vec<UnsupportedProposal>) {
+        let removed_members: HashSet<LeafNodeIndex> = proposal_queue
+            .queued_proposals()
+            .filter_map(|queued_proposal| match queued_proposal.proposal() {
+                Proposal::Remove(remove_proposal) => Some(remove_proposal.removed()),
+                _ => None,
+            })
+            .collect();
+        let updated_leaf_nodes: HashMap<LeafNodeIndex, &LeafNode> = proposal_queue
+            .queued_proposals()
+            .filter_map(|queued_proposal| {
+                match (queued_proposal.proposal(), queued_proposal.sender()) {
+                    (Proposal::Update(update_proposal), Sender::Member(leaf_index)) => {
+                        Some((*leaf_index, update_proposal.leaf_node()))
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        let remaining_members: Vec<(LeafNodeIndex, &LeafNode)> = self
+            .treesync()
+            .full_leaves_with_index()
+            .filter(|(leaf_index, _)| !removed_members.contains(leaf_index))
+            .map(|(leaf_index, leaf_node)| {
+                let leaf_node = updated_leaf_nodes
+                    .get(&leaf_index)
+                    .copied()
+                    .unwrap_or(leaf_node);
+                (leaf_index, leaf_node)
+            })
+            .collect();
+
+        let mut filtered_queue = ProposalQueue::default();
+        let mut unsupported_proposals = Vec::new();
+        for queued_proposal in proposal_queue.queued_proposals() {
+            let drop_reason = if proposal_store_refs.contains(&queued_proposal.proposal_reference())
+            {
+                unsupported_by(&remaining_members, queued_proposal.proposal())
+            } else {
+                None
+            };
+            match drop_reason {
+                Some(reason) => unsupported_proposals
+                    .push(UnsupportedProposal::new(queued_proposal.clone(), reason)),
+                None => filtered_queue.add(queued_proposal.clone()),
+            }
+        }
+        (filtered_queue, unsupported_proposals)
+    }
+}
+
+/// Returns the reason why the given proposal is not supported by one of the
+/// given members, or `None` if all members support it.
+fn unsupported_by(
+    members: &[(LeafNodeIndex, &LeafNode)],
+    proposal: &Proposal,
+) -> Option<UnsupportedProposalReason> {
+    let proposal_type = proposal.proposal_type();
+    for &(member, leaf_node) in members {
+        if !leaf_node.capabilities().supports_proposal(&proposal_type) {
+            return Some(UnsupportedProposalReason::UnsupportedProposalType {
+                proposal_type,
+                member,
+            });
+        }
+        if let Proposal::GroupContextExtensions(gce_proposal) = proposal {
+            if let Some(extension_type) = gce_proposal
+                .extensions()
+                .iter()
+                .map(|extension| extension.extension_type())
+                .find(|extension_type| !leaf_node.supports_extension(extension_type))
+            {
+                return Some(UnsupportedProposalReason::UnsupportedExtension {
+                    extension_type,
+                    member,
+                });
+            }
+        }
+    }
+    None
 }
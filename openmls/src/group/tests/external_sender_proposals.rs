@@ -0,0 +1,436 @@
+use openmls_rust_crypto::OpenMlsRustCrypto;
+use rstest::*;
+use rstest_reuse::{self, *};
+
+use tls_codec::DeserializeBytes;
+
+use crate::{
+    ciphersuite::Secret,
+    credentials::CredentialType,
+    framing::{mls_auth_content::AuthenticatedContent, *},
+    group::{config::CryptoConfig, *},
+    messages::{external_proposals::*, proposals::*},
+    schedule::{errors::PskError, ExternalPsk, PreSharedKeyId, Psk},
+    versions::ProtocolVersion,
+};
+
+use openmls_traits::types::Ciphersuite;
+
+use super::utils::*;
+
+// Creates a group of Alice and Bob with the given external senders and
+// returns it together with Alice's signer.
+fn validation_test_setup(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+    external_senders: ExternalSendersExtension,
+) -> (MlsGroup, CredentialWithKeyAndSigner) {
+    let alice_credential_with_key =
+        generate_credential_with_key("Alice".into(), ciphersuite.signature_algorithm(), provider);
+
+    let mls_group_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .with_group_context_extensions(Extensions::single(Extension::ExternalSenders(
+            external_senders,
+        )))
+        .unwrap()
+        .build();
+
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_credential_with_key.signer,
+        &mls_group_config,
+        GroupId::from_slice(b"Test Group"),
+        alice_credential_with_key.credential_with_key.clone(),
+    )
+    .unwrap();
+
+    let bob_credential_with_key =
+        generate_credential_with_key("Bob".into(), ciphersuite.signature_algorithm(), provider);
+    let bob_key_package = generate_key_package(
+        ciphersuite,
+        Extensions::empty(),
+        provider,
+        bob_credential_with_key,
+    );
+    alice_group
+        .add_members(
+            provider,
+            &alice_credential_with_key.signer,
+            &[bob_key_package],
+        )
+        .expect("error adding Bob to group");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    (alice_group, alice_credential_with_key)
+}
+
+// Returns the credential of an external sender together with the
+// corresponding `ExternalSendersExtension`.
+fn external_sender(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+) -> (CredentialWithKeyAndSigner, ExternalSendersExtension) {
+    let ds_credential_with_key = generate_credential_with_key(
+        "admin-service".into(),
+        ciphersuite.signature_algorithm(),
+        provider,
+    );
+    let external_senders = vec![ExternalSender::new(
+        ds_credential_with_key
+            .credential_with_key
+            .signature_key
+            .clone(),
+        ds_credential_with_key
+            .credential_with_key
+            .credential
+            .clone(),
+    )];
+    (ds_credential_with_key, external_senders)
+}
+
+#[apply(ciphersuites_and_providers)]
+fn external_add_proposal_should_add_member(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+) {
+    let (ds_credential_with_key, external_senders) = external_sender(ciphersuite, provider);
+    let (mut alice_group, alice_credential) =
+        validation_test_setup(ciphersuite, provider, external_senders);
+
+    // A public group observing the group validates the proposal as well.
+    let verifiable_group_info = alice_group
+        .export_group_info(provider.crypto(), &alice_credential.signer, false)
+        .unwrap()
+        .into_verifiable_group_info()
+        .unwrap();
+    let (public_group, _extensions) = PublicGroup::from_external(
+        provider.crypto(),
+        alice_group.export_ratchet_tree().into(),
+        verifiable_group_info,
+        ProposalStore::new(),
+    )
+    .unwrap();
+
+    // The admin service wants to add Charlie
+    let charlie_credential_with_key = generate_credential_with_key(
+        "Charlie".into(),
+        ciphersuite.signature_algorithm(),
+        provider,
+    );
+    let charlie_key_package = generate_key_package(
+        ciphersuite,
+        Extensions::empty(),
+        provider,
+        charlie_credential_with_key,
+    );
+    let external_add_proposal = ExternalProposal::new_add(
+        charlie_key_package.clone(),
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &ds_credential_with_key.signer,
+        SenderExtensionIndex::new(0),
+    )
+    .unwrap();
+
+    let processed_message = public_group
+        .process_message(
+            provider,
+            external_add_proposal
+                .clone()
+                .into_protocol_message()
+                .unwrap(),
+        )
+        .unwrap();
+    assert!(matches!(
+        processed_message.into_content(),
+        ProcessedMessageContent::ProposalMessage(_)
+    ));
+
+    // Alice validates the proposal and commits it
+    let processed_message = alice_group
+        .process_message(
+            provider,
+            external_add_proposal.into_protocol_message().unwrap(),
+        )
+        .unwrap();
+    assert_eq!(
+        processed_message.sender(),
+        &Sender::External(SenderExtensionIndex::new(0))
+    );
+    let ProcessedMessageContent::ProposalMessage(add_proposal) = processed_message.into_content()
+    else {
+        panic!("Not an add proposal");
+    };
+    alice_group.store_pending_proposal(*add_proposal);
+    let (_commit, welcome, _group_info) = alice_group
+        .commit_to_pending_proposals(provider, &alice_credential.signer)
+        .unwrap();
+    assert!(welcome.is_some());
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert!(alice_group
+        .members()
+        .any(|member| member.credential.identity() == b"Charlie"));
+
+    // Adding Charlie again is rejected when the proposal is received
+    let invalid_external_add_proposal = ExternalProposal::new_add(
+        charlie_key_package,
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &ds_credential_with_key.signer,
+        SenderExtensionIndex::new(0),
+    )
+    .unwrap();
+    let error = alice_group
+        .process_message(
+            provider,
+            invalid_external_add_proposal
+                .into_protocol_message()
+                .unwrap(),
+        )
+        .unwrap_err();
+    assert_eq!(
+        error,
+        ProcessMessageError::InvalidProposal(ProposalValidationError::DuplicateSignatureKey)
+    );
+}
+
+#[apply(ciphersuites_and_providers)]
+fn external_group_context_extensions_proposal(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+) {
+    let (ds_credential_with_key, external_senders) = external_sender(ciphersuite, provider);
+    let (mut alice_group, alice_credential) =
+        validation_test_setup(ciphersuite, provider, external_senders.clone());
+
+    // The admin service requires an extension that isn't supported by the
+    // members
+    let unsupported_extensions = Extensions::from_vec(vec![
+        Extension::ExternalSenders(external_senders.clone()),
+        Extension::RequiredCapabilities(RequiredCapabilitiesExtension::new(
+            &[ExtensionType::Unknown(0xff00)],
+            &[],
+            &[],
+        )),
+    ])
+    .unwrap();
+    let invalid_proposal = ExternalProposal::new_group_context_extensions(
+        unsupported_extensions,
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &ds_credential_with_key.signer,
+        SenderExtensionIndex::new(0),
+    )
+    .unwrap();
+    let error = alice_group
+        .process_message(provider, invalid_proposal.into_protocol_message().unwrap())
+        .unwrap_err();
+    assert_eq!(
+        error,
+        ProcessMessageError::InvalidGroupContextExtensionsProposal(
+            GroupContextExtensionsProposalValidationError::RequiredExtensionNotSupportedByAllMembers
+        )
+    );
+
+    // The admin service updates the group policy
+    let required_capabilities =
+        RequiredCapabilitiesExtension::new(&[], &[], &[CredentialType::Basic]);
+    let new_extensions = Extensions::from_vec(vec![
+        Extension::ExternalSenders(external_senders),
+        Extension::RequiredCapabilities(required_capabilities.clone()),
+    ])
+    .unwrap();
+    let proposal = ExternalProposal::new_group_context_extensions(
+        new_extensions,
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &ds_credential_with_key.signer,
+        SenderExtensionIndex::new(0),
+    )
+    .unwrap();
+    let processed_message = alice_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(gce_proposal) = processed_message.into_content()
+    else {
+        panic!("Not a group context extensions proposal");
+    };
+    alice_group.store_pending_proposal(*gce_proposal);
+    alice_group
+        .commit_to_pending_proposals(provider, &alice_credential.signer)
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(
+        alice_group
+            .group()
+            .context()
+            .extensions()
+            .required_capabilities(),
+        Some(&required_capabilities)
+    );
+}
+
+#[apply(ciphersuites_and_providers)]
+fn external_reinit_proposal(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let (ds_credential_with_key, external_senders) = external_sender(ciphersuite, provider);
+    let (mut alice_group, _alice_credential) =
+        validation_test_setup(ciphersuite, provider, external_senders);
+
+    let reinit = ReInitProposal::new(
+        GroupId::from_slice(b"New Test Group"),
+        ProtocolVersion::Mls10,
+        ciphersuite,
+        Extensions::empty(),
+    );
+    let proposal = ExternalProposal::new_reinit(
+        reinit.clone(),
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &ds_credential_with_key.signer,
+        SenderExtensionIndex::new(0),
+    )
+    .unwrap();
+    let processed_message = alice_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(reinit_proposal) =
+        processed_message.into_content()
+    else {
+        panic!("Not a reinit proposal");
+    };
+    assert_eq!(reinit_proposal.proposal(), &Proposal::ReInit(reinit));
+}
+
+#[apply(ciphersuites_and_providers)]
+fn external_reinit_proposal_with_draft_version(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+) {
+    let (ds_credential_with_key, external_senders) = external_sender(ciphersuite, provider);
+    let (alice_group, _alice_credential) =
+        validation_test_setup(ciphersuite, provider, external_senders);
+
+    let reinit = ReInitProposal::new(
+        GroupId::from_slice(b"New Test Group"),
+        ProtocolVersion::Mls10Draft11,
+        ciphersuite,
+        Extensions::empty(),
+    );
+    let error = ExternalProposal::new_reinit(
+        reinit,
+        alice_group.group_id().clone(),
+        alice_group.epoch(),
+        &ds_credential_with_key.signer,
+        SenderExtensionIndex::new(0),
+    )
+    .unwrap_err();
+    assert_eq!(error, ProposeReInitError::UnsupportedProtocolVersion);
+}
+
+#[apply(ciphersuites_and_providers)]
+fn external_psk_proposal(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let (ds_credential_with_key, external_senders) = external_sender(ciphersuite, provider);
+    let (mut alice_group, alice_credential) =
+        validation_test_setup(ciphersuite, provider, external_senders);
+
+    let external_psk_proposal = |psk_id: PreSharedKeyId, alice_group: &MlsGroup| {
+        let proposal = AuthenticatedContent::new_external_proposal(
+            Proposal::PreSharedKey(PreSharedKeyProposal::new(psk_id)),
+            alice_group.group_id().clone(),
+            alice_group.epoch(),
+            &ds_credential_with_key.signer,
+            SenderExtensionIndex::new(0),
+        )
+        .unwrap();
+        MlsMessageOut::from(PublicMessage::from(proposal))
+            .into_protocol_message()
+            .unwrap()
+    };
+
+    // A PSK proposal with an invalid nonce is rejected when it is received
+    let psk = Psk::External(ExternalPsk::new(b"external psk".to_vec()));
+    let invalid_psk_id = PreSharedKeyId::new_with_nonce(psk.clone(), vec![0; 1]);
+    let error = alice_group
+        .process_message(
+            provider,
+            external_psk_proposal(invalid_psk_id, &alice_group),
+        )
+        .unwrap_err();
+    assert!(matches!(
+        error,
+        ProcessMessageError::InvalidProposal(ProposalValidationError::Psk(
+            PskError::NonceLengthMismatch { .. }
+        ))
+    ));
+
+    // A valid PSK proposal can be committed
+    let psk_id = PreSharedKeyId::new(ciphersuite, provider.rand(), psk).unwrap();
+    let secret = Secret::random(ciphersuite, provider.rand(), None).unwrap();
+    psk_id
+        .write_to_key_store(provider, ciphersuite, secret.as_slice())
+        .unwrap();
+    let processed_message = alice_group
+        .process_message(provider, external_psk_proposal(psk_id, &alice_group))
+        .unwrap();
+    let ProcessedMessageContent::ProposalMessage(psk_proposal) = processed_message.into_content()
+    else {
+        panic!("Not a PSK proposal");
+    };
+    alice_group.store_pending_proposal(*psk_proposal);
+    let epoch = alice_group.epoch();
+    alice_group
+        .commit_to_pending_proposals(provider, &alice_credential.signer)
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    assert_eq!(alice_group.epoch(), GroupEpoch::from(epoch.as_u64() + 1));
+}
+
+// ValSem118
+#[apply(ciphersuites_and_providers)]
+fn external_proposals_of_unsupported_types(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+) {
+    let (ds_credential_with_key, external_senders) = external_sender(ciphersuite, provider);
+    let (mut alice_group, _alice_credential) =
+        validation_test_setup(ciphersuite, provider, external_senders);
+
+    let update = Proposal::Update(UpdateProposal {
+        leaf_node: alice_group.own_leaf_node().unwrap().clone(),
+    });
+    let external_init = Proposal::ExternalInit(ExternalInitProposal::from(vec![0; 32]));
+    let app_ack = Proposal::AppAck(AppAckProposal::tls_deserialize_exact_bytes(&[0]).unwrap());
+
+    // Update proposals from external senders are already rejected when the
+    // sender is checked (ValSem112).
+    for (proposal, expected_error) in [
+        (
+            update,
+            ProcessMessageError::ValidationError(ValidationError::CommitterIncludedOwnUpdate),
+        ),
+        (external_init, ProcessMessageError::UnsupportedProposalType),
+        (app_ack, ProcessMessageError::UnsupportedProposalType),
+    ] {
+        let proposal = AuthenticatedContent::new_external_proposal(
+            proposal,
+            alice_group.group_id().clone(),
+            alice_group.epoch(),
+            &ds_credential_with_key.signer,
+            SenderExtensionIndex::new(0),
+        )
+        .unwrap();
+        let error = alice_group
+            .process_message(
+                provider,
+                MlsMessageOut::from(PublicMessage::from(proposal))
+                    .into_protocol_message()
+                    .unwrap(),
+            )
+            .unwrap_err();
+        assert_eq!(error, expected_error);
+    }
+}
@@ -5,6 +5,8 @@ mod external_add_proposal;
 #[cfg(test)]
 mod external_remove_proposal;
 #[cfg(test)]
+mod external_sender_proposals;
+#[cfg(test)]
 pub mod kat_messages;
 #[cfg(test)]
 pub mod kat_transcript_hashes;
@@ -29,6 +31,7 @@ mod test_remove_operation;
 #[cfg(test)]
 mod test_wire_format_policy;
 #[cfg(test)]
+#[cfg(test)]
 pub(crate) mod utils;
 
 pub(crate) mod tree_printing;
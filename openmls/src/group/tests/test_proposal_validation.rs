@@ -23,12 +23,20 @@ use crate::{
     group::{config::CryptoConfig, *},
     key_packages::{errors::*, *},
     messages::{
-        proposals::{AddProposal, Proposal, ProposalOrRef, RemoveProposal, UpdateProposal},
+        proposals::{
+            AddProposal, Proposal, ProposalOrRef, ProposalType, RemoveProposal, UpdateProposal,
+        },
         Commit, Welcome,
     },
     prelude::MlsMessageBodyIn,
     schedule::PreSharedKeyId,
-    treesync::{errors::ApplyUpdatePathError, node::leaf_node::Capabilities},
+    treesync::{
+        errors::ApplyUpdatePathError,
+        node::{
+            encryption_keys::EncryptionKey,
+            leaf_node::{Capabilities, LeafNode},
+        },
+    },
     versions::ProtocolVersion,
 };
 
@@ -1046,7 +1054,7 @@ fn test_valsem105(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
             generate_credential_with_key_and_key_package("Charlie".into(), ciphersuite, provider);
 
         let kpi = KeyPackageIn::from(charlie_key_package.clone());
-        kpi.validate(provider.crypto(), ProtocolVersion::Mls10)
+        kpi.validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
             .unwrap();
 
         // Let's just pick a ciphersuite that's not the one we're testing right now.
@@ -1990,6 +1998,422 @@ fn test_valsem112(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
         .expect("Unexpected error.");
 }
 
+/// Helper function that has Alice commit to an update proposal by Bob that
+/// contains the given `leaf_node`. Checks that Alice refuses to create the
+/// commit and that Bob rejects a commit that covers the proposal with the given
+/// `expected_error`.
+fn test_invalid_update_proposal(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+    setup: ProposalValidationTestSetup,
+    leaf_node: LeafNode,
+    expected_error: ProposalValidationError,
+) {
+    let ProposalValidationTestSetup {
+        mut alice_group,
+        alice_credential_with_key_and_signer,
+        mut bob_group,
+        ..
+    } = setup;
+
+    // Both Alice and Bob know about the update proposal.
+    let update_proposal = Proposal::Update(UpdateProposal { leaf_node });
+    let queued_proposal = QueuedProposal::from_proposal_and_sender(
+        ciphersuite,
+        provider.crypto(),
+        update_proposal.clone(),
+        &Sender::build_member(bob_group.own_leaf_index()),
+    )
+    .expect("error creating queued proposal");
+    alice_group.store_pending_proposal(queued_proposal.clone());
+    bob_group.store_pending_proposal(queued_proposal);
+
+    // Alice can't commit to the proposal.
+    let err = alice_group
+        .commit_to_pending_proposals(provider, &alice_credential_with_key_and_signer.signer)
+        .expect_err("no error while committing to an invalid update proposal");
+    assert_eq!(
+        err,
+        CommitToPendingProposalsError::CreateCommitError(
+            CreateCommitError::ProposalValidationError(expected_error.clone())
+        )
+    );
+
+    // We now have Alice create a commit and artificially add the proposal by
+    // reference.
+    alice_group.clear_pending_commit();
+    alice_group.clear_pending_proposals();
+
+    let serialized_update = alice_group
+        .self_update(provider, &alice_credential_with_key_and_signer.signer)
+        .expect("Error creating self-update")
+        .tls_serialize_detached()
+        .expect("Could not serialize message.");
+
+    let plaintext = MlsMessageIn::tls_deserialize(&mut serialized_update.as_slice())
+        .expect("Could not deserialize message.")
+        .into_plaintext()
+        .expect("Message was not a plaintext.");
+
+    let original_plaintext = plaintext.clone();
+
+    let verifiable_plaintext = insert_proposal_and_resign(
+        provider,
+        vec![ProposalOrRef::Reference(
+            ProposalRef::from_raw_proposal(ciphersuite, provider.crypto(), &update_proposal)
+                .expect("error creating hash reference"),
+        )],
+        plaintext,
+        &original_plaintext,
+        &alice_group,
+        &alice_credential_with_key_and_signer.signer,
+    );
+
+    // Have Bob process the resulting plaintext
+    let err = bob_group
+        .process_message(provider, ProtocolMessage::from(verifiable_plaintext))
+        .expect_err("Could process a commit covering an invalid update proposal.");
+
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::ProposalValidationError(
+            expected_error
+        ))
+    );
+}
+
+/// ValSem109
+/// Update Proposal:
+/// The leaf node must support the extensions it carries and the capabilities
+/// required by the group
+#[apply(ciphersuites_and_providers)]
+fn test_valsem109(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    // === The leaf node carries an extension it doesn't support ===
+    let setup = validation_test_setup(PURE_PLAINTEXT_WIRE_FORMAT_POLICY, ciphersuite, provider);
+
+    let mut update_leaf_node = setup
+        .bob_group
+        .own_leaf_node()
+        .expect("error getting own leaf node")
+        .clone();
+    update_leaf_node.set_extensions(Extensions::single(Extension::Unknown(
+        0xff00,
+        UnknownExtension(vec![0, 1, 2]),
+    )));
+    update_leaf_node
+        .update_and_re_sign(
+            fresh_encryption_key(ciphersuite, provider, &setup),
+            None,
+            setup.bob_group.group_id().clone(),
+            setup.bob_group.own_leaf_index(),
+            &setup.bob_credential_with_key_and_signer.signer,
+        )
+        .unwrap();
+
+    test_invalid_update_proposal(
+        ciphersuite,
+        provider,
+        setup,
+        update_leaf_node,
+        ProposalValidationError::InsufficientCapabilities,
+    );
+
+    // === The leaf node doesn't support a proposal the group requires ===
+    let capabilities =
+        Capabilities::new(None, None, None, Some(&[ProposalType::ExternalInit]), None);
+    let required_capabilities = Extensions::single(Extension::RequiredCapabilities(
+        RequiredCapabilitiesExtension::new(&[], &[ProposalType::ExternalInit], &[]),
+    ));
+    let alice_credential_with_key_and_signer =
+        generate_credential_with_key("Alice".into(), ciphersuite.signature_algorithm(), provider);
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .capabilities(capabilities.clone())
+        .with_group_context_extensions(required_capabilities)
+        .unwrap()
+        .build();
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_credential_with_key_and_signer.signer,
+        &mls_group_create_config,
+        alice_credential_with_key_and_signer
+            .credential_with_key
+            .clone(),
+    )
+    .unwrap();
+
+    let bob_credential_with_key_and_signer =
+        generate_credential_with_key("Bob".into(), ciphersuite.signature_algorithm(), provider);
+    let bob_key_package = KeyPackage::builder()
+        .leaf_node_capabilities(capabilities)
+        .build(
+            CryptoConfig::with_default_version(ciphersuite),
+            provider,
+            &bob_credential_with_key_and_signer.signer,
+            bob_credential_with_key_and_signer
+                .credential_with_key
+                .clone(),
+        )
+        .unwrap();
+    let (_message, welcome, _group_info) = alice_group
+        .add_members(
+            provider,
+            &alice_credential_with_key_and_signer.signer,
+            &[bob_key_package],
+        )
+        .unwrap();
+    alice_group.merge_pending_commit(provider).unwrap();
+    let bob_group = MlsGroup::new_from_welcome(
+        provider,
+        &MlsGroupJoinConfig::builder()
+            .wire_format_policy(PURE_PLAINTEXT_WIRE_FORMAT_POLICY)
+            .build(),
+        welcome.into_welcome().unwrap(),
+        Some(alice_group.export_ratchet_tree().into()),
+    )
+    .unwrap();
+    let setup = ProposalValidationTestSetup {
+        alice_group,
+        alice_credential_with_key_and_signer,
+        bob_group,
+        bob_credential_with_key_and_signer,
+    };
+
+    let mut update_leaf_node = setup
+        .bob_group
+        .own_leaf_node()
+        .expect("error getting own leaf node")
+        .clone();
+    *update_leaf_node.capabilities_mut() = Capabilities::new(None, None, None, None, None);
+    update_leaf_node
+        .update_and_re_sign(
+            fresh_encryption_key(ciphersuite, provider, &setup),
+            None,
+            setup.bob_group.group_id().clone(),
+            setup.bob_group.own_leaf_index(),
+            &setup.bob_credential_with_key_and_signer.signer,
+        )
+        .unwrap();
+
+    test_invalid_update_proposal(
+        ciphersuite,
+        provider,
+        setup,
+        update_leaf_node,
+        ProposalValidationError::InsufficientCapabilities,
+    );
+}
+
+/// Returns a fresh encryption key for an update of Bob's leaf node.
+fn fresh_encryption_key(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+    setup: &ProposalValidationTestSetup,
+) -> EncryptionKey {
+    generate_key_package(
+        ciphersuite,
+        Extensions::empty(),
+        provider,
+        setup.bob_credential_with_key_and_signer.clone(),
+    )
+    .leaf_node()
+    .encryption_key()
+    .clone()
+}
+
+/// ValSem113
+/// Update Proposal:
+/// The leaf node source must be `update`
+#[apply(ciphersuites_and_providers)]
+fn test_valsem113(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let setup = validation_test_setup(PURE_PLAINTEXT_WIRE_FORMAT_POLICY, ciphersuite, provider);
+
+    // Bob's update contains the leaf node of a fresh key package, i.e. its
+    // source is `key_package`.
+    let key_package = generate_key_package(
+        ciphersuite,
+        Extensions::empty(),
+        provider,
+        setup.bob_credential_with_key_and_signer.clone(),
+    );
+
+    test_invalid_update_proposal(
+        ciphersuite,
+        provider,
+        setup,
+        key_package.leaf_node().clone(),
+        ProposalValidationError::InvalidLeafNodeSource,
+    );
+}
+
+/// ValSem114
+/// Update Proposal:
+/// The leaf node signature must be valid for the sender's position in the tree
+#[apply(ciphersuites_and_providers)]
+fn test_valsem114(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let setup = validation_test_setup(PURE_PLAINTEXT_WIRE_FORMAT_POLICY, ciphersuite, provider);
+
+    let encryption_key = generate_key_package(
+        ciphersuite,
+        Extensions::empty(),
+        provider,
+        setup.bob_credential_with_key_and_signer.clone(),
+    )
+    .leaf_node()
+    .encryption_key()
+    .clone();
+
+    // Bob signs his new leaf node for Alice's position in the tree.
+    let mut update_leaf_node = setup
+        .bob_group
+        .own_leaf_node()
+        .expect("error getting own leaf node")
+        .clone();
+    update_leaf_node
+        .update_and_re_sign(
+            encryption_key,
+            None,
+            setup.bob_group.group_id().clone(),
+            setup.alice_group.own_leaf_index(),
+            &setup.bob_credential_with_key_and_signer.signer,
+        )
+        .unwrap();
+
+    test_invalid_update_proposal(
+        ciphersuite,
+        provider,
+        setup,
+        update_leaf_node,
+        ProposalValidationError::InvalidLeafNodeSignature,
+    );
+}
+
+/// ValSem115
+/// Update Proposal:
+/// Signature public key must be unique among proposals & members other than
+/// the sender
+#[apply(ciphersuites_and_providers)]
+fn test_valsem115(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let setup = validation_test_setup(PURE_PLAINTEXT_WIRE_FORMAT_POLICY, ciphersuite, provider);
+
+    let encryption_key = generate_key_package(
+        ciphersuite,
+        Extensions::empty(),
+        provider,
+        setup.bob_credential_with_key_and_signer.clone(),
+    )
+    .leaf_node()
+    .encryption_key()
+    .clone();
+
+    // Bob's new leaf node uses Alice's signature key. The leaf node is signed
+    // with Alice's signature key, so that only the uniqueness check fails.
+    let mut update_leaf_node = setup
+        .bob_group
+        .own_leaf_node()
+        .expect("error getting own leaf node")
+        .clone();
+    update_leaf_node.set_signature_key(
+        setup
+            .alice_credential_with_key_and_signer
+            .credential_with_key
+            .signature_key
+            .clone(),
+    );
+    update_leaf_node
+        .update_and_re_sign(
+            encryption_key,
+            None,
+            setup.bob_group.group_id().clone(),
+            setup.bob_group.own_leaf_index(),
+            &setup.alice_credential_with_key_and_signer.signer,
+        )
+        .unwrap();
+
+    test_invalid_update_proposal(
+        ciphersuite,
+        provider,
+        setup,
+        update_leaf_node,
+        ProposalValidationError::DuplicateSignatureKey,
+    );
+}
+
+/// ValSem116
+/// Add Proposal:
+/// The lifetime of the key package must be valid at the current time
+#[apply(ciphersuites)]
+fn test_valsem116(ciphersuite: Ciphersuite) {
+    // We need a provider with a clock we can control.
+    let provider = &OpenMlsRustCrypto::default();
+
+    let ProposalValidationTestSetup {
+        mut alice_group,
+        alice_credential_with_key_and_signer,
+        mut bob_group,
+        ..
+    } = validation_test_setup(PURE_PLAINTEXT_WIRE_FORMAT_POLICY, ciphersuite, provider);
+
+    let (_charlie_credential_with_key, charlie_key_package) =
+        generate_credential_with_key_and_key_package("Charlie".into(), ciphersuite, provider);
+    let not_after = charlie_key_package
+        .leaf_node()
+        .life_time()
+        .expect("key package without lifetime")
+        .not_after();
+
+    // Alice proposes to add Charlie while the key package is still valid and
+    // Bob stores the proposal.
+    let (proposal, _) = alice_group
+        .propose_add_member(
+            provider,
+            &alice_credential_with_key_and_signer.signer,
+            &charlie_key_package,
+        )
+        .unwrap();
+    let processed_message = bob_group
+        .process_message(provider, proposal.into_protocol_message().unwrap())
+        .unwrap();
+    match processed_message.into_content() {
+        ProcessedMessageContent::ProposalMessage(queued_proposal) => {
+            bob_group.store_pending_proposal(*queued_proposal)
+        }
+        _ => panic!("expected a proposal"),
+    }
+
+    let (commit, _welcome, _group_info) = alice_group
+        .commit_to_pending_proposals(provider, &alice_credential_with_key_and_signer.signer)
+        .unwrap();
+
+    // Charlie's key package expires before Bob gets to process the commit.
+    provider.time().set_time(not_after);
+
+    let err = bob_group
+        .process_message(provider, commit.into_protocol_message().unwrap())
+        .expect_err("Bob accepted an expired key package.");
+    assert_eq!(
+        err,
+        ProcessMessageError::InvalidCommit(StageCommitError::ProposalValidationError(
+            ProposalValidationError::InvalidAddProposalLifetime
+        ))
+    );
+
+    // Alice can't commit to the expired proposal anymore either.
+    alice_group.clear_pending_commit();
+    let err = alice_group
+        .commit_to_pending_proposals(provider, &alice_credential_with_key_and_signer.signer)
+        .expect_err("Alice committed to an expired key package.");
+    assert_eq!(
+        err,
+        CommitToPendingProposalsError::CreateCommitError(
+            CreateCommitError::ProposalValidationError(
+                ProposalValidationError::InvalidAddProposalLifetime
+            )
+        )
+    );
+}
+
 // --- PreSharedKey Proposals ---
 
 #[apply(ciphersuites_and_providers)]
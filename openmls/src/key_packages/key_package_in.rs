@@ -5,10 +5,11 @@ use crate::{
     ciphersuite::{signable::*, *},
     credentials::*,
     extensions::Extensions,
+    framing::{errors::DecodeLimitError, CheckDecodeLimits, DecodeLimits},
     treesync::node::leaf_node::{LeafNodeIn, VerifiableLeafNode},
     versions::ProtocolVersion,
 };
-use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use openmls_traits::{crypto::OpenMlsCrypto, time::TimeProvider, types::Ciphersuite};
 use serde::{Deserialize, Serialize};
 use tls_codec::{
     Serialize as TlsSerializeTrait, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize,
@@ -123,7 +124,8 @@ impl KeyPackageIn {
     /// * verify that the signature on this key package is valid
     /// * verify that the signature on the leaf node is valid
     /// * verify that all extensions are supported by the leaf node
-    /// * make sure that the lifetime is valid
+    /// * make sure that the lifetime is valid at the current time of the
+    ///   given [`TimeProvider`]
     /// * make sure that the init key and the encryption key are different
     /// * make sure that the protocol version is valid
     ///
@@ -132,6 +134,7 @@ impl KeyPackageIn {
     pub fn validate(
         self,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         protocol_version: ProtocolVersion,
     ) -> Result<KeyPackage, KeyPackageVerifyError> {
         // We first need to verify the LeafNode inside the KeyPackage
@@ -186,7 +189,7 @@ impl KeyPackageIn {
 
         // Ensure validity of the life time extension in the leaf node.
         if let Some(life_time) = key_package.payload.leaf_node.life_time() {
-            if !life_time.is_valid() {
+            if !life_time.is_valid(time) {
                 return Err(KeyPackageVerifyError::InvalidLifetime);
             }
         } else {
@@ -205,6 +208,13 @@ impl KeyPackageIn {
     }
 }
 
+impl CheckDecodeLimits for KeyPackageIn {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        self.payload.extensions.check_decode_limits(limits)?;
+        self.payload.leaf_node.check_decode_limits(limits)
+    }
+}
+
 #[cfg(any(feature = "test-utils", test))]
 impl From<KeyPackageTbsIn> for KeyPackageTbs {
     fn from(value: KeyPackageTbsIn) -> Self {
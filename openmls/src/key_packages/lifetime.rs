@@ -3,6 +3,7 @@ use fluvio_wasm_timer::{SystemTime, UNIX_EPOCH};
 #[cfg(not(target_arch = "wasm32"))]
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use openmls_traits::time::TimeProvider;
 use serde::{Deserialize, Serialize};
 use tls_codec::{TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize};
 
@@ -73,18 +74,23 @@ impl Lifetime {
         }
     }
 
-    /// Returns true if this lifetime is valid.
-    pub(crate) fn is_valid(&self) -> bool {
-        match SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map(|duration| duration.as_secs())
-        {
-            Ok(elapsed) => self.not_before < elapsed && elapsed < self.not_after,
-            Err(_) => {
-                log::error!("SystemTime before UNIX EPOCH.");
-                false
-            }
-        }
+    /// Returns true if this lifetime is valid at the current time of the given
+    /// [`TimeProvider`].
+    pub(crate) fn is_valid(&self, time: &impl TimeProvider) -> bool {
+        let now = time.now();
+        self.not_before < now && now < self.not_after
+    }
+
+    /// Returns true if this lifetime has expired at the current time of the
+    /// given [`TimeProvider`].
+    pub(crate) fn is_expired(&self, time: &impl TimeProvider) -> bool {
+        time.now() >= self.not_after
+    }
+
+    /// Returns the time (in seconds since the Unix epoch) after which this
+    /// lifetime is not valid anymore.
+    pub fn not_after(&self) -> u64 {
+        self.not_after
     }
 
     /// ValSem(openmls/annotations#32):
@@ -103,20 +109,27 @@ impl Default for Lifetime {
 
 #[cfg(test)]
 mod tests {
+    use openmls_rust_crypto::OpenMlsRustCrypto;
+    use openmls_traits::OpenMlsProvider;
     use tls_codec::{Deserialize, Serialize};
 
     use super::Lifetime;
 
     #[test]
     fn lifetime() {
+        let provider = OpenMlsRustCrypto::default();
+
         // A freshly created extensions must be valid.
         let ext = Lifetime::default();
-        assert!(ext.is_valid());
+        assert!(ext.is_valid(provider.time()));
+        assert!(!ext.is_expired(provider.time()));
 
-        // An extension without lifetime is invalid (waiting for 1 second).
+        // An extension without lifetime is invalid (advancing the clock by 1
+        // second).
         let ext = Lifetime::new(0);
-        std::thread::sleep(std::time::Duration::from_secs(1));
-        assert!(!ext.is_valid());
+        provider.time().advance(1);
+        assert!(!ext.is_valid(provider.time()));
+        assert!(ext.is_expired(provider.time()));
 
         // Test (de)serializing invalid extension
         let serialized = ext
@@ -124,6 +137,6 @@ mod tests {
             .expect("error encoding life time extension");
         let ext_deserialized = Lifetime::tls_deserialize(&mut serialized.as_slice())
             .expect("Error deserializing lifetime");
-        assert!(!ext_deserialized.is_valid());
+        assert!(!ext_deserialized.is_valid(provider.time()));
     }
 }
@@ -87,7 +87,7 @@
 //!     .expect("Could not deserialize KeyPackage");
 //!
 //! let key_package = key_package_in
-//!     .validate(provider.crypto(), ProtocolVersion::Mls10)
+//!     .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
 //!     .expect("Invalid KeyPackage");
 //! ```
 //!
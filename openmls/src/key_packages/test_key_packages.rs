@@ -1,6 +1,7 @@
 use crate::test_utils::*;
 use openmls_basic_credential::SignatureKeyPair;
 use openmls_rust_crypto::OpenMlsRustCrypto;
+use openmls_traits::time::TimeProvider;
 use tls_codec::Deserialize;
 
 use crate::{extensions::*, key_packages::*};
@@ -38,7 +39,7 @@ fn generate_key_package(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvide
 
     let kpi = KeyPackageIn::from(key_package);
     assert!(kpi
-        .validate(provider.crypto(), ProtocolVersion::Mls10)
+        .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
         .is_ok());
 }
 
@@ -85,7 +86,7 @@ fn application_id_extension(ciphersuite: Ciphersuite, provider: &impl OpenMlsPro
 
     let kpi = KeyPackageIn::from(key_package.clone());
     assert!(kpi
-        .validate(provider.crypto(), ProtocolVersion::Mls10)
+        .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
         .is_ok());
 
     // Check ID
@@ -119,7 +120,7 @@ fn key_package_validation(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvi
 
     let key_package_in = KeyPackageIn::tls_deserialize(&mut encoded.as_slice()).unwrap();
     let err = key_package_in
-        .validate(provider.crypto(), ProtocolVersion::Mls10)
+        .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
         .unwrap_err();
 
     // Expect an invalid protocol version error
@@ -138,9 +139,52 @@ fn key_package_validation(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvi
 
     let key_package_in = KeyPackageIn::tls_deserialize(&mut encoded.as_slice()).unwrap();
     let err = key_package_in
-        .validate(provider.crypto(), ProtocolVersion::Mls10)
+        .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
         .unwrap_err();
 
     // Expect an invalid init/encryption key error
     assert_eq!(err, KeyPackageVerifyError::InitKeyEqualsEncryptionKey);
 }
+
+/// Test that the lifetime of a key package is checked against the time of the
+/// provider.
+#[apply(ciphersuites)]
+fn key_package_lifetime(ciphersuite: Ciphersuite) {
+    // We need a provider with a clock we can control.
+    let provider = &OpenMlsRustCrypto::default();
+
+    let (key_package, _, _) = key_package(ciphersuite, provider);
+    let not_after = key_package
+        .leaf_node()
+        .life_time()
+        .expect("key package without lifetime")
+        .not_after();
+    let encoded = key_package
+        .tls_serialize_detached()
+        .expect("An unexpected error occurred.");
+
+    // The key package is valid right before it expires ...
+    provider.time().set_time(not_after - 1);
+    KeyPackageIn::tls_deserialize(&mut encoded.as_slice())
+        .unwrap()
+        .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
+        .expect("valid key package was rejected");
+
+    // ... but not when it expired.
+    provider.time().set_time(not_after);
+    let err = KeyPackageIn::tls_deserialize(&mut encoded.as_slice())
+        .unwrap()
+        .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
+        .unwrap_err();
+    assert_eq!(err, KeyPackageVerifyError::InvalidLifetime);
+
+    // A key package from the future is not valid either.
+    provider.time().reset();
+    let now = provider.time().now();
+    provider.time().set_time(now - 2 * 60 * 60);
+    let err = KeyPackageIn::tls_deserialize(&mut encoded.as_slice())
+        .unwrap()
+        .validate(provider.crypto(), provider.time(), ProtocolVersion::Mls10)
+        .unwrap_err();
+    assert_eq!(err, KeyPackageVerifyError::InvalidLifetime);
+}
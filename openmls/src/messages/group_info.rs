@@ -15,7 +15,8 @@ use crate::{
         AeadKey, AeadNonce, Signature,
     },
     extensions::Extensions,
-    group::{GroupContext, GroupId},
+    framing::{errors::DecodeLimitError, CheckDecodeLimits, DecodeLimits},
+    group::{GroupContext, GroupEpoch, GroupId},
     messages::ConfirmationTag,
 };
 
@@ -114,6 +115,24 @@ impl VerifiableGroupInfo {
     pub(crate) fn group_id(&self) -> &GroupId {
         self.payload.group_context.group_id()
     }
+
+    /// Get (unverified) epoch of the verifiable group info.
+    ///
+    /// Note: This method should only be used when necessary to verify the group
+    /// info signature.
+    pub(crate) fn epoch(&self) -> GroupEpoch {
+        self.payload.group_context.epoch()
+    }
+}
+
+impl CheckDecodeLimits for VerifiableGroupInfo {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        self.payload
+            .group_context
+            .extensions()
+            .check_decode_limits(limits)?;
+        self.payload.extensions.check_decode_limits(limits)
+    }
 }
 
 #[cfg(test)]
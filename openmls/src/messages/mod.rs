@@ -5,6 +5,7 @@
 
 use openmls_traits::{
     crypto::OpenMlsCrypto,
+    time::TimeProvider,
     types::{Ciphersuite, HpkeCiphertext, HpkeKeyPair},
 };
 use serde::{Deserialize, Serialize};
@@ -17,7 +18,7 @@ use crate::{
     ciphersuite::{hash_ref::KeyPackageRef, *},
     credentials::CredentialWithKey,
     error::LibraryError,
-    framing::SenderContext,
+    framing::{errors::DecodeLimitError, CheckDecodeLimits, DecodeLimits, SenderContext},
     group::errors::ValidationError,
     schedule::{psk::PreSharedKeyId, JoinerSecret},
     treesync::{
@@ -95,6 +96,35 @@ impl Welcome {
         self.encrypted_group_info.as_slice()
     }
 
+    /// Splits this Welcome message into one Welcome message per new member.
+    ///
+    /// Each of the returned Welcome messages only contains the
+    /// [`EncryptedGroupSecrets`] of a single new member, identified by the
+    /// [`KeyPackageRef`] it is returned with, and shares the encrypted group
+    /// info with the other ones. This allows the DS to send each new member
+    /// only its own part of the Welcome message, without revealing the other
+    /// new members to it.
+    pub fn split(self) -> Vec<(KeyPackageRef, Welcome)> {
+        let Welcome {
+            cipher_suite,
+            secrets,
+            encrypted_group_info,
+        } = self;
+        secrets
+            .into_iter()
+            .map(|secret| {
+                (
+                    secret.new_member(),
+                    Welcome {
+                        cipher_suite,
+                        secrets: vec![secret],
+                        encrypted_group_info: encrypted_group_info.clone(),
+                    },
+                )
+            })
+            .collect()
+    }
+
     /// Set the welcome's encrypted group info.
     #[cfg(test)]
     pub fn set_encrypted_group_info(&mut self, encrypted_group_info: Vec<u8>) {
@@ -102,6 +132,14 @@ impl Welcome {
     }
 }
 
+impl CheckDecodeLimits for Welcome {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        // The group info is encrypted and can only be checked after
+        // decryption.
+        limits.check_welcome_secrets(self.secrets.len())
+    }
+}
+
 /// EncryptedGroupSecrets
 ///
 /// This is part of a [`Welcome`] message. It can be used to correlate the correct secrets with each new member.
@@ -205,13 +243,14 @@ impl CommitIn {
         self,
         ciphersuite: Ciphersuite,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         sender_context: SenderContext,
         protocol_version: ProtocolVersion,
     ) -> Result<Commit, ValidationError> {
         let proposals = self
             .proposals
             .into_iter()
-            .map(|p| p.validate(crypto, ciphersuite, protocol_version))
+            .map(|p| p.validate(crypto, time, ciphersuite, protocol_version))
             .collect::<Result<Vec<_>, _>>()?;
 
         let path = if let Some(path) = self.path {
@@ -253,6 +292,19 @@ impl CommitIn {
     }
 }
 
+impl CheckDecodeLimits for CommitIn {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        limits.check_proposals(self.proposals.len())?;
+        for proposal in self.proposals.iter() {
+            proposal.check_decode_limits(limits)?;
+        }
+        if let Some(path) = &self.path {
+            path.leaf_node().check_decode_limits(limits)?;
+        }
+        Ok(())
+    }
+}
+
 // The following `From` implementation( breaks abstraction layers and MUST
 // NOT be made available outside of tests or "test-utils".
 #[cfg(any(feature = "test-utils", test))]
@@ -277,18 +329,23 @@ impl From<Commit> for CommitIn {
 /// Confirmation tag field of PublicMessage. For type safety this is a wrapper
 /// around a `Mac`.
 #[derive(
-    Debug,
-    PartialEq,
-    Clone,
-    Serialize,
-    Deserialize,
-    TlsDeserialize,
-    TlsDeserializeBytes,
-    TlsSerialize,
-    TlsSize,
+    Debug, Clone, Serialize, Deserialize, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize,
 )]
 pub struct ConfirmationTag(pub(crate) Mac);
 
+impl ConstantTimeEq for ConfirmationTag {
+    fn ct_eq(&self, other: &Self) -> bool {
+        self.0.ct_eq(&other.0)
+    }
+}
+
+impl PartialEq for ConfirmationTag {
+    // Constant time comparison.
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
 /// PathSecret
 ///
 /// > 11.2.2. Welcoming New Members
@@ -403,6 +403,45 @@ pub struct ReInitProposal {
     pub(crate) extensions: Extensions,
 }
 
+impl ReInitProposal {
+    /// Create a new [`ReInitProposal`] for a new group with the given
+    /// `group_id`, protocol `version`, `ciphersuite` and group context
+    /// `extensions`.
+    pub fn new(
+        group_id: GroupId,
+        version: ProtocolVersion,
+        ciphersuite: Ciphersuite,
+        extensions: Extensions,
+    ) -> Self {
+        Self {
+            group_id,
+            version,
+            ciphersuite,
+            extensions,
+        }
+    }
+
+    /// Returns the group ID of the new group.
+    pub fn group_id(&self) -> &GroupId {
+        &self.group_id
+    }
+
+    /// Returns the protocol version of the new group.
+    pub fn version(&self) -> ProtocolVersion {
+        self.version
+    }
+
+    /// Returns the ciphersuite of the new group.
+    pub fn ciphersuite(&self) -> Ciphersuite {
+        self.ciphersuite
+    }
+
+    /// Returns the group context extensions of the new group.
+    pub fn extensions(&self) -> &Extensions {
+        &self.extensions
+    }
+}
+
 /// ExternalInit Proposal.
 ///
 /// An ExternalInit proposal is used by new members that want to join a group by using an external
@@ -494,13 +533,12 @@ pub struct GroupContextExtensionProposal {
 
 impl GroupContextExtensionProposal {
     /// Create a new [`GroupContextExtensionProposal`].
-    #[cfg(test)]
     pub(crate) fn new(extensions: Extensions) -> Self {
         Self { extensions }
     }
 
     /// Get the extensions of the proposal
-    pub(crate) fn extensions(&self) -> &Extensions {
+    pub fn extensions(&self) -> &Extensions {
         &self.extensions
     }
 }
@@ -8,14 +8,14 @@
 use crate::{
     ciphersuite::{hash_ref::ProposalRef, signable::Verifiable},
     credentials::CredentialWithKey,
-    framing::SenderContext,
+    framing::{errors::DecodeLimitError, CheckDecodeLimits, DecodeLimits, SenderContext},
     group::errors::ValidationError,
     key_packages::*,
     treesync::node::leaf_node::{LeafNodeIn, TreePosition, VerifiableLeafNode},
     versions::ProtocolVersion,
 };
 
-use openmls_traits::{crypto::OpenMlsCrypto, types::Ciphersuite};
+use openmls_traits::{crypto::OpenMlsCrypto, time::TimeProvider, types::Ciphersuite};
 use serde::{Deserialize, Serialize};
 use tls_codec::{TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize};
 
@@ -104,13 +104,14 @@ impl ProposalIn {
     pub(crate) fn validate(
         self,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         ciphersuite: Ciphersuite,
         sender_context: Option<SenderContext>,
         protocol_version: ProtocolVersion,
     ) -> Result<Proposal, ValidationError> {
         Ok(match self {
             ProposalIn::Add(add) => {
-                Proposal::Add(add.validate(crypto, protocol_version, ciphersuite)?)
+                Proposal::Add(add.validate(crypto, time, protocol_version, ciphersuite)?)
             }
             ProposalIn::Update(update) => {
                 let sender_context =
@@ -154,6 +155,27 @@ pub struct AddProposalIn {
     key_package: KeyPackageIn,
 }
 
+impl CheckDecodeLimits for ProposalIn {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        match self {
+            ProposalIn::Add(add_proposal) => add_proposal.key_package.check_decode_limits(limits),
+            ProposalIn::Update(update_proposal) => {
+                update_proposal.leaf_node.check_decode_limits(limits)
+            }
+            ProposalIn::ReInit(reinit_proposal) => {
+                reinit_proposal.extensions.check_decode_limits(limits)
+            }
+            ProposalIn::GroupContextExtensions(gce_proposal) => {
+                gce_proposal.extensions().check_decode_limits(limits)
+            }
+            ProposalIn::Remove(_)
+            | ProposalIn::PreSharedKey(_)
+            | ProposalIn::ExternalInit(_)
+            | ProposalIn::AppAck(_) => Ok(()),
+        }
+    }
+}
+
 impl AddProposalIn {
     pub(crate) fn unverified_credential(&self) -> CredentialWithKey {
         self.key_package.unverified_credential()
@@ -163,10 +185,11 @@ impl AddProposalIn {
     pub(crate) fn validate(
         self,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         protocol_version: ProtocolVersion,
         ciphersuite: Ciphersuite,
     ) -> Result<AddProposal, ValidationError> {
-        let key_package = self.key_package.validate(crypto, protocol_version)?;
+        let key_package = self.key_package.validate(crypto, time, protocol_version)?;
         // Verify that the ciphersuite is valid
         if key_package.ciphersuite() != ciphersuite {
             return Err(ValidationError::InvalidAddProposalCiphersuite);
@@ -263,18 +286,28 @@ impl ProposalOrRefIn {
     pub(crate) fn validate(
         self,
         crypto: &impl OpenMlsCrypto,
+        time: &impl TimeProvider,
         ciphersuite: Ciphersuite,
         protocol_version: ProtocolVersion,
     ) -> Result<ProposalOrRef, ValidationError> {
         Ok(match self {
             ProposalOrRefIn::Proposal(proposal_in) => ProposalOrRef::Proposal(
-                proposal_in.validate(crypto, ciphersuite, None, protocol_version)?,
+                proposal_in.validate(crypto, time, ciphersuite, None, protocol_version)?,
             ),
             ProposalOrRefIn::Reference(reference) => ProposalOrRef::Reference(reference),
         })
     }
 }
 
+impl CheckDecodeLimits for ProposalOrRefIn {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        match self {
+            ProposalOrRefIn::Proposal(proposal) => proposal.check_decode_limits(limits),
+            ProposalOrRefIn::Reference(_) => Ok(()),
+        }
+    }
+}
+
 // The following `From` implementation breaks abstraction layers and MUST
 // NOT be made available outside of tests or "test-utils".
 #[cfg(any(feature = "test-utils", test))]
@@ -325,3 +325,71 @@ fn invalid_welcomes() {
     let msg = Welcome::tls_deserialize(&mut bytes);
     assert!(msg.is_err());
 }
+
+#[apply(ciphersuites_and_providers)]
+fn split_welcome(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .use_ratchet_tree_extension(true)
+        .build();
+
+    let (alice_credential_with_key, _alice_kpb, alice_signer, _alice_signature_key) =
+        crate::group::test_core_group::setup_client("Alice", ciphersuite, provider);
+    let (_bob_credential, bob_kpb, _bob_signer, _bob_signature_key) =
+        crate::group::test_core_group::setup_client("Bob", ciphersuite, provider);
+    let (_charlie_credential, charlie_kpb, _charlie_signer, _charlie_signature_key) =
+        crate::group::test_core_group::setup_client("Charlie", ciphersuite, provider);
+
+    // === Alice creates a group and adds Bob and Charlie ===
+    let mut alice_group = MlsGroup::new(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        alice_credential_with_key,
+    )
+    .expect("An unexpected error occurred.");
+
+    let key_packages = [
+        bob_kpb.key_package().clone(),
+        charlie_kpb.key_package().clone(),
+    ];
+    let (_queued_message, welcome, _group_info) = alice_group
+        .add_members(provider, &alice_signer, &key_packages)
+        .expect("Could not add members to group.");
+    alice_group
+        .merge_pending_commit(provider)
+        .expect("error merging pending commit");
+
+    let welcome = welcome.into_welcome().expect("Unexpected message type.");
+    assert_eq!(welcome.secrets().len(), 2);
+
+    // === Split the Welcome message ===
+    let welcomes = welcome.clone().split();
+    assert_eq!(welcomes.len(), 2);
+    let key_package_refs = key_packages
+        .iter()
+        .map(|key_package| key_package.hash_ref(provider.crypto()).unwrap())
+        .collect::<Vec<_>>();
+    for (key_package_ref, split_welcome) in welcomes.iter() {
+        assert!(key_package_refs.contains(key_package_ref));
+        assert_eq!(split_welcome.secrets().len(), 1);
+        assert_eq!(&split_welcome.secrets()[0].new_member(), key_package_ref);
+        assert_eq!(
+            split_welcome.encrypted_group_info(),
+            welcome.encrypted_group_info()
+        );
+    }
+
+    // === Bob and Charlie join with their own Welcome messages ===
+    for (_key_package_ref, split_welcome) in welcomes {
+        let group = MlsGroup::new_from_welcome(
+            provider,
+            mls_group_create_config.join_config(),
+            split_welcome,
+            None,
+        )
+        .expect("Could not join the group with a split Welcome message.");
+        assert_eq!(group.epoch(), alice_group.epoch());
+        assert_eq!(group.members().count(), 3);
+    }
+}
@@ -4,7 +4,7 @@
 // MlsGroup
 pub use crate::group::{config::CryptoConfig, core_group::Member, ser::*, *};
 
-pub use crate::group::public_group::{errors::*, PublicGroup};
+pub use crate::group::public_group::{errors::*, HandshakeObserverSecrets, PublicGroup};
 
 // Ciphersuite
 pub use crate::ciphersuite::{hash_ref::KeyPackageRef, signable::*, signature::*, *};
@@ -885,8 +885,9 @@ fn ciphertext_sample(ciphersuite: Ciphersuite, ciphertext: &[u8]) -> &[u8] {
 
 /// A key that can be used to derive an `AeadKey` and an `AeadNonce`.
 #[derive(Serialize, Deserialize)]
+#[cfg_attr(any(feature = "test-utils", test), derive(Clone))]
 #[cfg_attr(test, derive(PartialEq))]
-#[cfg_attr(any(feature = "test-utils", test), derive(Debug, Clone))]
+#[cfg_attr(any(feature = "test-utils", test), derive(Debug))]
 pub(crate) struct SenderDataSecret {
     secret: Secret,
 }
@@ -898,6 +899,15 @@ impl SenderDataSecret {
         Ok(SenderDataSecret { secret })
     }
 
+    /// Returns a copy of this secret to share with a
+    /// [`PublicGroup`](crate::group::PublicGroup) through the
+    /// [`HandshakeObserverSecrets`](crate::group::HandshakeObserverSecrets).
+    pub(crate) fn copy(&self) -> Self {
+        Self {
+            secret: self.secret.clone(),
+        }
+    }
+
     /// Derive a new AEAD key from a `SenderDataSecret`.
     pub(crate) fn derive_aead_key(
         &self,
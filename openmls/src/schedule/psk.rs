@@ -390,7 +390,7 @@ impl<'a> PskLabel<'a> {
 
 /// This contains the `psk-secret` calculated from the PSKs contained in a
 /// Commit or a PreSharedKey proposal.
-#[derive(Clone)]
+#[cfg_attr(any(feature = "test-utils", test), derive(Clone))]
 pub struct PskSecret {
     secret: Secret,
 }
@@ -481,6 +481,8 @@ pub(crate) fn load_psks<'p>(
         match &psk_id.psk {
             Psk::Resumption(resumption) => {
                 if let Some(psk_bundle) = resumption_psk_store.get(resumption.psk_epoch()) {
+                    // The resumption PSK stays in the store, since it may be
+                    // used again in a later epoch.
                     psk_bundles.push((psk_id, psk_bundle.secret.clone()));
                 } else {
                     return Err(PskError::KeyNotFound);
@@ -550,5 +552,39 @@ pub mod store {
                 .find(|&(e, _s)| e == &epoch)
                 .map(|(_e, s)| s)
         }
+
+        /// Deletes the entry for a given epoch number. Returns `true` if
+        /// there was an entry and `false` otherwise.
+        pub(crate) fn delete(&mut self, epoch: GroupEpoch) -> bool {
+            self.retain(|e| e != epoch)
+        }
+
+        /// Deletes the entries of all epochs before the given epoch number.
+        pub(crate) fn delete_before(&mut self, epoch: GroupEpoch) {
+            self.retain(|e| e >= epoch);
+        }
+
+        /// Returns the epochs for which a resumption psk is stored.
+        pub(crate) fn epochs(&self) -> impl Iterator<Item = GroupEpoch> + '_ {
+            self.resumption_psk.iter().map(|(e, _s)| *e)
+        }
+
+        /// Keeps only the entries whose epoch matches the `predicate`. Returns
+        /// `true` if entries were deleted.
+        fn retain(&mut self, predicate: impl Fn(GroupEpoch) -> bool) -> bool {
+            if !self.resumption_psk.iter().any(|(e, _s)| !predicate(*e)) {
+                return false;
+            }
+            // Once the store is full, the entry after the cursor is the next
+            // one to be overwritten. Restore the insertion order before
+            // deleting entries, so that new entries are appended again.
+            if self.resumption_psk.len() == self.max_number_of_secrets {
+                let oldest = (self.cursor + 1) % self.resumption_psk.len();
+                self.resumption_psk.rotate_left(oldest);
+            }
+            self.resumption_psk.retain(|(e, _s)| predicate(*e));
+            self.cursor = self.resumption_psk.len();
+            true
+        }
     }
 }
@@ -12,6 +12,7 @@ use crate::{
         LeafNodeIndex,
     },
     framing::*,
+    group::{RetainedRatchet, RetainedSenderRatchets},
     schedule::*,
     tree::sender_ratchet::*,
 };
@@ -37,6 +38,9 @@ pub enum SecretTreeError {
     /// Ratchet generation has reached `u32::MAX`.
     #[error("Ratchet generation has reached `u32::MAX`.")]
     RatchetTooLong,
+    /// The sender ratchets of this leaf were deleted.
+    #[error("The sender ratchets of this leaf were deleted.")]
+    DeletedSenderRatchets,
     /// An unrecoverable error has occurred due to a bug in the implementation.
     #[error("An unrecoverable error has occurred due to a bug in the implementation.")]
     LibraryError,
@@ -106,6 +110,7 @@ pub(crate) struct SecretTreeNode {
 }
 
 #[derive(Serialize, Deserialize)]
+#[serde(from = "SecretTreeState")]
 #[cfg_attr(any(feature = "test-utils", test), derive(PartialEq, Clone))]
 #[cfg_attr(any(feature = "crypto-debug", test), derive(Debug))]
 pub(crate) struct SecretTree {
@@ -115,6 +120,41 @@ pub(crate) struct SecretTree {
     handshake_sender_ratchets: Vec<Option<SenderRatchet>>,
     application_sender_ratchets: Vec<Option<SenderRatchet>>,
     size: TreeSize,
+    // The number of keys for out-of-order messages retained by the decryption
+    // ratchets, kept up to date as they change.
+    #[serde(skip_serializing)]
+    retained_keys: usize,
+}
+
+/// The persisted state of a [`SecretTree`]. The number of retained keys is
+/// counted again when it is loaded, since older versions didn't persist it.
+#[derive(Deserialize)]
+struct SecretTreeState {
+    own_index: LeafNodeIndex,
+    leaf_nodes: Vec<Option<SecretTreeNode>>,
+    parent_nodes: Vec<Option<SecretTreeNode>>,
+    handshake_sender_ratchets: Vec<Option<SenderRatchet>>,
+    application_sender_ratchets: Vec<Option<SenderRatchet>>,
+    size: TreeSize,
+}
+
+impl From<SecretTreeState> for SecretTree {
+    fn from(state: SecretTreeState) -> Self {
+        let mut secret_tree = Self {
+            own_index: state.own_index,
+            leaf_nodes: state.leaf_nodes,
+            parent_nodes: state.parent_nodes,
+            handshake_sender_ratchets: state.handshake_sender_ratchets,
+            application_sender_ratchets: state.application_sender_ratchets,
+            size: state.size,
+            retained_keys: 0,
+        };
+        secret_tree.retained_keys = secret_tree
+            .decryption_ratchets()
+            .map(|ratchet| ratchet.retained_keys())
+            .sum();
+        secret_tree
+    }
 }
 
 impl SecretTree {
@@ -141,6 +181,7 @@ impl SecretTree {
             handshake_sender_ratchets,
             application_sender_ratchets,
             size,
+            retained_keys: 0,
         };
 
         // Set the encryption secret in the root node. We ignore the Result
@@ -198,16 +239,25 @@ impl SecretTree {
             // Collect empty nodes in the direct path until a non-empty node is
             // found
             let mut empty_nodes: Vec<ParentNodeIndex> = Vec::new();
+            let mut found_secret = false;
             let direct_path = direct_path(index, self.size);
             log::trace!("Direct path for node {index:?}: {:?}", direct_path);
             for parent_node in direct_path {
                 empty_nodes.push(parent_node);
                 // Stop if we find a non-empty node
                 if self.get_node(parent_node.into())?.is_some() {
+                    found_secret = true;
                     break;
                 }
             }
 
+            // If there is no secret left in the direct path, the sender
+            // ratchets of this leaf were deleted.
+            if !found_secret {
+                log::error!("The sender ratchets of {index:?} were deleted.");
+                return Err(SecretTreeError::DeletedSenderRatchets);
+            }
+
             // Invert direct path
             empty_nodes.reverse();
 
@@ -277,6 +327,122 @@ impl SecretTree {
         self.set_node(index.into(), None)
     }
 
+    /// Deletes the sender ratchets of the leaf at the given `index`, including
+    /// all retained key material. If the ratchets weren't initialized yet,
+    /// they are initialized first, so that the secret of the leaf is removed
+    /// from the tree while the secrets of the other leaves are kept. No key
+    /// material can be derived for the leaf afterwards.
+    ///
+    /// Returns an error if the index is out of bounds.
+    pub(crate) fn delete_sender_ratchets(
+        &mut self,
+        ciphersuite: Ciphersuite,
+        crypto: &impl OpenMlsCrypto,
+        index: LeafNodeIndex,
+    ) -> Result<(), SecretTreeError> {
+        match self.initialize_sender_ratchets(ciphersuite, crypto, index) {
+            // There is nothing left to initialize if the ratchets were
+            // deleted before.
+            Ok(()) | Err(SecretTreeError::DeletedSenderRatchets) => (),
+            Err(e) => return Err(e),
+        }
+        for sender_ratchets in [
+            &mut self.handshake_sender_ratchets,
+            &mut self.application_sender_ratchets,
+        ] {
+            let deleted = sender_ratchets
+                .get_mut(index.usize())
+                .ok_or(SecretTreeError::IndexOutOfBounds)?
+                .take();
+            if let Some(SenderRatchet::DecryptionRatchet(dec_ratchet)) = deleted {
+                self.retained_keys -= dec_ratchet.retained_keys();
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the generations of all initialized sender ratchets. Leaves
+    /// whose ratchets weren't initialized or were deleted are not included.
+    pub(crate) fn retained_sender_ratchets(&self) -> Vec<RetainedSenderRatchets> {
+        self.handshake_sender_ratchets
+            .iter()
+            .zip(self.application_sender_ratchets.iter())
+            .enumerate()
+            .filter_map(|(index, ratchets)| match ratchets {
+                (Some(handshake_ratchet), Some(application_ratchet)) => {
+                    Some(RetainedSenderRatchets::new(
+                        LeafNodeIndex::new(index as u32),
+                        RetainedRatchet::new(
+                            handshake_ratchet.generation(),
+                            handshake_ratchet.retained_generations(),
+                        ),
+                        RetainedRatchet::new(
+                            application_ratchet.generation(),
+                            application_ratchet.retained_generations(),
+                        ),
+                    ))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Returns an iterator over all initialized decryption ratchets.
+    pub(crate) fn decryption_ratchets(&self) -> impl Iterator<Item = &DecryptionRatchet> {
+        self.handshake_sender_ratchets
+            .iter()
+            .chain(self.application_sender_ratchets.iter())
+            .filter_map(|ratchet| match ratchet {
+                Some(SenderRatchet::DecryptionRatchet(dec_ratchet)) => Some(dec_ratchet),
+                _ => None,
+            })
+    }
+
+    /// Returns the number of keys for out-of-order messages that are retained
+    /// by the decryption ratchets.
+    pub(crate) fn retained_keys(&self) -> usize {
+        self.retained_keys
+    }
+
+    /// Evict the oldest retained keys of each decryption ratchet until it
+    /// retains at most `maximum` keys and return the number of evicted keys.
+    pub(crate) fn evict_retained_keys(&mut self, maximum: usize) -> usize {
+        let evicted: usize = self
+            .handshake_sender_ratchets
+            .iter_mut()
+            .chain(self.application_sender_ratchets.iter_mut())
+            .filter_map(|ratchet| match ratchet {
+                Some(SenderRatchet::DecryptionRatchet(dec_ratchet)) => Some(dec_ratchet),
+                _ => None,
+            })
+            .map(|dec_ratchet| dec_ratchet.evict_retained_keys(maximum))
+            .sum();
+        self.retained_keys -= evicted;
+        evicted
+    }
+
+    /// Returns a copy of the head of the handshake sender ratchet of the leaf
+    /// at the given `index`, initializing the sender ratchets if necessary.
+    /// The key material of all generations from the one of the returned
+    /// [`RatchetSecret`] onwards can be derived from it.
+    pub(crate) fn handshake_ratchet_secret(
+        &mut self,
+        ciphersuite: Ciphersuite,
+        crypto: &impl OpenMlsCrypto,
+        index: LeafNodeIndex,
+    ) -> Result<RatchetSecret, SecretTreeError> {
+        if self
+            .ratchet_opt(index, SecretType::HandshakeSecret)?
+            .is_none()
+        {
+            self.initialize_sender_ratchets(ciphersuite, crypto, index)?;
+        }
+        Ok(self
+            .ratchet_mut(index, SecretType::HandshakeSecret)?
+            .ratchet_secret()
+            .copy())
+    }
+
     /// Return RatchetSecrets for a given index and generation. This should be
     /// called when decrypting an PrivateMessage received from another member.
     /// Returns an error if index or generation are out of bound.
@@ -312,7 +478,17 @@ impl SecretTree {
             }
             SenderRatchet::DecryptionRatchet(dec_ratchet) => {
                 log::trace!("   getting secret for decryption");
-                dec_ratchet.secret_for_decryption(ciphersuite, crypto, generation, configuration)
+                // The ratchet may retain and drop keys, even if it fails.
+                let retained_before = dec_ratchet.retained_keys();
+                let result = dec_ratchet.secret_for_decryption(
+                    ciphersuite,
+                    crypto,
+                    generation,
+                    configuration,
+                );
+                let retained_after = dec_ratchet.retained_keys();
+                self.retained_keys = self.retained_keys - retained_before + retained_after;
+                result
             }
         }
     }
@@ -6,7 +6,7 @@
 //! error, will still return a `Result` since they may throw a `LibraryError`.
 
 use openmls_traits::crypto::OpenMlsCrypto;
-use std::collections::VecDeque;
+use std::{collections::VecDeque, ops::Range};
 
 use openmls_traits::types::Ciphersuite;
 
@@ -17,6 +17,27 @@ use super::*;
 
 /// The generation of a given [`SenderRatchet`].
 pub(crate) type Generation = u32;
+
+/// The default maximum number of keys for out-of-order messages that a group
+/// retains across all senders and epochs.
+const DEFAULT_MAXIMUM_RETAINED_KEYS: usize = 1 << 14;
+
+fn default_maximum_retained_keys() -> usize {
+    DEFAULT_MAXIMUM_RETAINED_KEYS
+}
+
+/// The default maximum number of key derivations that a single message can
+/// cause in a sender ratchet.
+const DEFAULT_MAXIMUM_DERIVATIONS_PER_MESSAGE: u32 = 1 << 12;
+
+fn default_maximum_derivations_per_message() -> u32 {
+    DEFAULT_MAXIMUM_DERIVATIONS_PER_MESSAGE
+}
+
+/// The number of 64-bit words of the window of generations for which a
+/// [`DecryptionRatchet`] remembers whether they were used.
+const UNUSED_GENERATIONS_WORDS: usize = 16;
+
 /// Stores the configuration parameters for `DecryptionRatchet`s.
 ///
 /// **Parameters**
@@ -29,10 +50,29 @@ pub(crate) type Generation = u32;
 ///  - maximum_forward_distance:
 /// This parameter defines how many incoming messages can be skipped. This is useful if the DS
 /// drops application messages. The default value is 1000.
+/// Key material is only derived for skipped generations within the
+/// `out_of_order_tolerance`, so that the work per message is bounded by one
+/// derivation per skipped generation and a few derivations per retained key.
+///  - maximum_retained_keys:
+/// This parameter bounds the number of keys for out-of-order messages that a
+/// group retains across all senders and all retained epochs. If the budget is
+/// exceeded, keys are evicted from the senders that retain the most keys
+/// first, so that a single sender can't evict the keys of all other senders.
+/// The default value is 16384.
+///  - maximum_derivations_per_message:
+/// This parameter bounds the number of key derivations that a single incoming
+/// message can cause in a sender ratchet. Skipping a generation takes one
+/// derivation, deriving the key material of a generation takes three. Messages
+/// that would take more derivations are rejected before any key material is
+/// derived. The default value is 4096.
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct SenderRatchetConfiguration {
     out_of_order_tolerance: Generation,
     maximum_forward_distance: Generation,
+    #[serde(default = "default_maximum_retained_keys")]
+    maximum_retained_keys: usize,
+    #[serde(default = "default_maximum_derivations_per_message")]
+    maximum_derivations_per_message: u32,
 }
 
 impl SenderRatchetConfiguration {
@@ -41,8 +81,28 @@ impl SenderRatchetConfiguration {
         Self {
             out_of_order_tolerance,
             maximum_forward_distance,
+            maximum_retained_keys: DEFAULT_MAXIMUM_RETAINED_KEYS,
+            maximum_derivations_per_message: DEFAULT_MAXIMUM_DERIVATIONS_PER_MESSAGE,
         }
     }
+
+    /// Set the maximum number of keys for out-of-order messages that a group
+    /// retains across all senders and epochs.
+    pub fn with_maximum_retained_keys(mut self, maximum_retained_keys: usize) -> Self {
+        self.maximum_retained_keys = maximum_retained_keys;
+        self
+    }
+
+    /// Set the maximum number of key derivations that a single message can
+    /// cause in a sender ratchet.
+    pub fn with_maximum_derivations_per_message(
+        mut self,
+        maximum_derivations_per_message: u32,
+    ) -> Self {
+        self.maximum_derivations_per_message = maximum_derivations_per_message;
+        self
+    }
+
     /// Get a reference to the sender ratchet configuration's out of order tolerance.
     pub fn out_of_order_tolerance(&self) -> Generation {
         self.out_of_order_tolerance
@@ -52,6 +112,37 @@ impl SenderRatchetConfiguration {
     pub fn maximum_forward_distance(&self) -> Generation {
         self.maximum_forward_distance
     }
+
+    /// Get the maximum number of keys for out-of-order messages that a group
+    /// retains across all senders and epochs.
+    pub fn maximum_retained_keys(&self) -> usize {
+        self.maximum_retained_keys
+    }
+
+    /// Get the maximum number of key derivations that a single message can
+    /// cause in a sender ratchet.
+    pub fn maximum_derivations_per_message(&self) -> u32 {
+        self.maximum_derivations_per_message
+    }
+
+    /// Returns an error if ratcheting `distance` generations forward takes
+    /// more key derivations than allowed, if the key material of the
+    /// `derived` newest skipped generations and of the generation the ratchet
+    /// arrives at is derived.
+    fn check_derivations(
+        &self,
+        distance: Generation,
+        derived: Generation,
+    ) -> Result<(), SecretTreeError> {
+        // One derivation of the next ratchet secret per generation and two
+        // more for the key and the nonce of every derived generation.
+        let derivations = distance as u64 + 1 + 2 * (derived as u64 + 1);
+        if derivations > self.maximum_derivations_per_message as u64 {
+            log::error!("  Ratcheting forward would take {derivations} key derivations.");
+            return Err(SecretTreeError::TooDistantInTheFuture);
+        }
+        Ok(())
+    }
 }
 
 impl Default for SenderRatchetConfiguration {
@@ -79,13 +170,31 @@ pub(crate) enum SenderRatchet {
 }
 
 impl SenderRatchet {
-    #[cfg(test)]
     pub(crate) fn generation(&self) -> Generation {
         match self {
             SenderRatchet::EncryptionRatchet(enc_ratchet) => enc_ratchet.generation(),
             SenderRatchet::DecryptionRatchet(dec_ratchet) => dec_ratchet.generation(),
         }
     }
+
+    /// Returns the generations before the ratchet head for which key
+    /// material is still retained. An encryption ratchet never retains key
+    /// material of past generations.
+    pub(crate) fn retained_generations(&self) -> Vec<Generation> {
+        match self {
+            SenderRatchet::EncryptionRatchet(_) => Vec::new(),
+            SenderRatchet::DecryptionRatchet(dec_ratchet) => dec_ratchet.retained_generations(),
+        }
+    }
+
+    /// Returns the head of the ratchet, i.e. the [`RatchetSecret`] of the
+    /// next generation that hasn't been used yet.
+    pub(crate) fn ratchet_secret(&self) -> &RatchetSecret {
+        match self {
+            SenderRatchet::EncryptionRatchet(enc_ratchet) => enc_ratchet,
+            SenderRatchet::DecryptionRatchet(dec_ratchet) => &dec_ratchet.ratchet_head,
+        }
+    }
 }
 
 /// The core of both types of [`SenderRatchet`]. It contains the current head of
@@ -114,6 +223,16 @@ impl RatchetSecret {
         self.generation
     }
 
+    /// Returns a copy of this [`RatchetSecret`]. The copy is used to share the
+    /// ratchet head with a [`PublicGroup`](crate::group::PublicGroup), or to
+    /// derive key material without advancing the ratchet itself.
+    pub(crate) fn copy(&self) -> Self {
+        Self {
+            secret: self.secret.clone(),
+            generation: self.generation,
+        }
+    }
+
     /// Consume this [`RatchetSecret`] to derive a pair of [`RatchetSecrets`],
     /// as well as the [`RatchetSecret`] of the next generation and return both.
     pub(crate) fn ratchet_forward(
@@ -157,22 +276,195 @@ impl RatchetSecret {
         ))
     }
 
+    /// Ratchet forward without deriving the key material of the current
+    /// generation. This is used for skipped generations whose key material
+    /// wouldn't be retained anyway.
+    fn skip_forward(
+        &mut self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+    ) -> Result<(), SecretTreeError> {
+        if self.generation == u32::MAX {
+            return Err(SecretTreeError::RatchetTooLong);
+        }
+        self.secret = derive_tree_secret(
+            &self.secret,
+            "secret",
+            self.generation,
+            ciphersuite.hash_length(),
+            crypto,
+        )?;
+        self.generation += 1;
+        Ok(())
+    }
+
+    /// Derive the key material of the given `generation` without changing
+    /// this [`RatchetSecret`]. Returns an error if the generation is lower
+    /// than the current one, or if it is further ahead of it than the
+    /// `maximum_forward_distance` or the `maximum_derivations_per_message` of
+    /// the [`SenderRatchetConfiguration`] allow.
+    pub(crate) fn key_material_for_generation(
+        &self,
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        generation: Generation,
+        configuration: &SenderRatchetConfiguration,
+    ) -> Result<RatchetKeyMaterial, SecretTreeError> {
+        if generation < self.generation {
+            return Err(SecretTreeError::TooDistantInThePast);
+        }
+        if generation - self.generation > configuration.maximum_forward_distance() {
+            return Err(SecretTreeError::TooDistantInTheFuture);
+        }
+        configuration.check_derivations(generation - self.generation, 0)?;
+        let mut ratchet_secret = self.copy();
+        for _ in self.generation..generation {
+            ratchet_secret.skip_forward(crypto, ciphersuite)?;
+        }
+        ratchet_secret
+            .ratchet_forward(crypto, ciphersuite)
+            .map(|(_, key_material)| key_material)
+    }
+
     #[cfg(test)]
     pub(crate) fn set_generation(&mut self, generation: Generation) {
         self.generation = generation
     }
 }
 
+/// The generations of a [`DecryptionRatchet`] that fell out of the
+/// out-of-order window without being used for decryption.
+///
+/// Only the newest generations are tracked, in a bitmap over a fixed window of
+/// `64 * UNUSED_GENERATIONS_WORDS` generations starting at the low-water mark
+/// `start`. Generations before the low-water mark are treated as used, such
+/// that the size of the state doesn't grow with the number of messages and
+/// replays are never accepted.
+///
+/// Generations before `tracked_since` were dropped before unused generations
+/// were tracked, e.g. by a ratchet that was persisted by an older version.
+/// Nothing is known about them.
+#[derive(Serialize, Deserialize, Default)]
+#[cfg_attr(any(feature = "test-utils", test), derive(PartialEq, Clone))]
+#[cfg_attr(any(feature = "crypto-debug", test), derive(Debug))]
+struct UnusedGenerations {
+    #[serde(default)]
+    tracked_since: Generation,
+    // A multiple of 64.
+    start: Generation,
+    // Bit `i % 64` of word `i / 64` is set if generation `start + i` is unused.
+    words: VecDeque<u64>,
+}
+
+impl UnusedGenerations {
+    /// Track the unused generations from the given generation on.
+    fn tracked_since(generation: Generation) -> Self {
+        Self {
+            tracked_since: generation,
+            start: generation - generation % 64,
+            words: VecDeque::new(),
+        }
+    }
+
+    /// Remember that the given generations were never used. The range has to
+    /// be newer than all generations remembered before. Generations that
+    /// don't fit into the window anymore are forgotten.
+    fn record(&mut self, generations: Range<Generation>) {
+        let window = (64 * UNUSED_GENERATIONS_WORDS) as Generation;
+        let first = generations
+            .start
+            .max(generations.end.saturating_sub(window));
+        for generation in first..generations.end {
+            self.insert(generation);
+        }
+    }
+
+    fn insert(&mut self, generation: Generation) {
+        if generation < self.start {
+            return;
+        }
+        // Start over if the whole window would be shifted out.
+        let word_start = generation - generation % 64;
+        if (word_start - self.start) / 64 >= 2 * UNUSED_GENERATIONS_WORDS as Generation {
+            self.words.clear();
+            self.start = word_start;
+        }
+        let mut word = ((generation - self.start) / 64) as usize;
+        while word >= self.words.len() {
+            self.words.push_back(0);
+            if self.words.len() > UNUSED_GENERATIONS_WORDS {
+                self.words.pop_front();
+                self.start += 64;
+                word -= 1;
+            }
+        }
+        self.words[word] |= 1 << ((generation - self.start) % 64);
+    }
+
+    /// Returns `true` if the generation is known to be unused and `false` if
+    /// it's used or older than the low-water mark. Returns `None` if the
+    /// generation was dropped before unused generations were tracked.
+    fn is_unused(&self, generation: Generation) -> Option<bool> {
+        if generation < self.tracked_since {
+            return None;
+        }
+        if generation < self.start {
+            return Some(false);
+        }
+        let offset = generation - self.start;
+        let word = self.words.get((offset / 64) as usize).copied().unwrap_or(0);
+        Some(word & (1 << (offset % 64)) != 0)
+    }
+}
+
 /// [`SenderRatchet`] used to derive key material for decryption. It keeps the
 /// [`RatchetKeyMaterial`] of epochs around until they are retrieved. This
 /// behaviour can be configured via the `out_of_order_tolerance` and
 /// `maximum_forward_distance` of the given [`SenderRatchetConfiguration`].
+///
+/// Every generation can be used for decryption at most once. To tell replayed
+/// messages apart from messages that arrive too late, the ratchet remembers
+/// which of the generations that fell out of the out-of-order window were
+/// never used. This is only remembered for the most recent generations. Older
+/// generations are reported as replays, since it can't be ruled out that they
+/// were used.
 #[derive(Serialize, Deserialize)]
+#[serde(from = "DecryptionRatchetState")]
 #[cfg_attr(any(feature = "test-utils", test), derive(PartialEq, Clone))]
 #[cfg_attr(any(feature = "crypto-debug", test), derive(Debug))]
 pub struct DecryptionRatchet {
     past_secrets: VecDeque<Option<RatchetKeyMaterial>>,
     ratchet_head: RatchetSecret,
+    unused_generations: UnusedGenerations,
+}
+
+/// The persisted state of a [`DecryptionRatchet`]. Ratchets persisted by
+/// older versions don't track unused generations.
+#[derive(Deserialize)]
+struct DecryptionRatchetState {
+    past_secrets: VecDeque<Option<RatchetKeyMaterial>>,
+    ratchet_head: RatchetSecret,
+    unused_generations: Option<UnusedGenerations>,
+}
+
+impl From<DecryptionRatchetState> for DecryptionRatchet {
+    fn from(state: DecryptionRatchetState) -> Self {
+        // Without tracked unused generations, nothing is known about the
+        // generations that were dropped before. The secrets still in the
+        // queue are tracked from now on.
+        let unused_generations = state.unused_generations.unwrap_or_else(|| {
+            let oldest_retained = state
+                .ratchet_head
+                .generation()
+                .saturating_sub(state.past_secrets.len() as Generation);
+            UnusedGenerations::tracked_since(oldest_retained)
+        });
+        Self {
+            past_secrets: state.past_secrets,
+            ratchet_head: state.ratchet_head,
+            unused_generations,
+        }
+    }
 }
 
 impl DecryptionRatchet {
@@ -181,14 +473,84 @@ impl DecryptionRatchet {
         Self {
             past_secrets: VecDeque::new(),
             ratchet_head: RatchetSecret::initial_ratchet_secret(secret),
+            unused_generations: UnusedGenerations::default(),
         }
     }
 
     /// Remove elements from the `past_secrets` queue until it is within the
-    /// bounds determined by the [`SenderRatchetConfiguration`].
+    /// bounds determined by the [`SenderRatchetConfiguration`]. The
+    /// generations of removed secrets that were never used are remembered.
     fn prune_past_secrets(&mut self, configuration: &SenderRatchetConfiguration) {
+        self.drop_past_secrets(configuration.out_of_order_tolerance() as usize);
+    }
+
+    /// Remove all but the `keep` newest elements from the `past_secrets`
+    /// queue and return the number of removed secrets that were never used.
+    /// Their generations are remembered.
+    fn drop_past_secrets(&mut self, keep: usize) -> usize {
+        let keep = keep.min(self.past_secrets.len());
+        let generation = self.generation();
+        // The secret at index `i` belongs to generation `generation - 1 - i`.
+        let unused_generations: Vec<Generation> = self
+            .past_secrets
+            .drain(keep..)
+            .enumerate()
+            .filter(|(_, secret)| secret.is_some())
+            .map(|(offset, _)| generation - 1 - (keep + offset) as Generation)
+            .collect();
+        let dropped = unused_generations.len();
+        // Dropped generations are always newer than the ones dropped before.
+        for unused_generation in unused_generations.into_iter().rev() {
+            self.unused_generations
+                .record(unused_generation..unused_generation + 1);
+        }
+        dropped
+    }
+
+    /// Returns the error for a past generation whose key material isn't
+    /// retained anymore: [`SecretTreeError::SecretReuseError`] if the
+    /// generation is known to be used and
+    /// [`SecretTreeError::TooDistantInThePast`] otherwise.
+    fn deleted_secret_error(&self, generation: Generation) -> SecretTreeError {
+        match self.unused_generations.is_unused(generation) {
+            Some(false) => {
+                log::error!("  Generation {generation} was already used for decryption.");
+                SecretTreeError::SecretReuseError
+            }
+            _ => {
+                log::error!("  Generation {generation} is too far in the past.");
+                SecretTreeError::TooDistantInThePast
+            }
+        }
+    }
+
+    /// Returns the number of keys for out-of-order messages that are
+    /// currently retained.
+    pub(crate) fn retained_keys(&self) -> usize {
         self.past_secrets
-            .truncate(configuration.out_of_order_tolerance() as usize)
+            .iter()
+            .filter(|secret| secret.is_some())
+            .count()
+    }
+
+    /// Evict the oldest retained keys until at most `maximum` keys are left
+    /// and return the number of evicted keys. Messages of evicted generations
+    /// can't be decrypted anymore.
+    pub(crate) fn evict_retained_keys(&mut self, maximum: usize) -> usize {
+        let mut retained_keys = self.retained_keys();
+        if retained_keys <= maximum {
+            return 0;
+        }
+        // Find the number of entries to keep such that only `maximum` keys
+        // are left in them.
+        let mut keep = self.past_secrets.len();
+        while retained_keys > maximum {
+            keep -= 1;
+            if self.past_secrets[keep].is_some() {
+                retained_keys -= 1;
+            }
+        }
+        self.drop_past_secrets(keep)
     }
 
     /// Get the generation of the ratchet head.
@@ -196,6 +558,22 @@ impl DecryptionRatchet {
         self.ratchet_head.generation()
     }
 
+    /// Returns the generations for which key material is still retained,
+    /// oldest first.
+    pub(crate) fn retained_generations(&self) -> Vec<Generation> {
+        let generation = self.generation();
+        // The secret at index `i` belongs to generation `generation - 1 - i`.
+        let mut retained_generations: Vec<Generation> = self
+            .past_secrets
+            .iter()
+            .enumerate()
+            .filter(|(_, secret)| secret.is_some())
+            .map(|(index, _)| generation - 1 - index as Generation)
+            .collect();
+        retained_generations.reverse();
+        retained_generations
+    }
+
     #[cfg(test)]
     pub(crate) fn ratchet_secret_mut(&mut self) -> &mut RatchetSecret {
         &mut self.ratchet_head
@@ -221,11 +599,35 @@ impl DecryptionRatchet {
         if generation < self.generation()
             && (self.generation() - generation) > configuration.out_of_order_tolerance()
         {
+            // The secret of this generation was deleted. If it was used
+            // before, this is a replay.
+            self.prune_past_secrets(configuration);
             log::error!("  Generation is too far in the past (broke out of order tolerance ({}) {generation} < {}).", configuration.out_of_order_tolerance(), self.generation());
-            return Err(SecretTreeError::TooDistantInThePast);
+            return Err(self.deleted_secret_error(generation));
         }
         // If generation is the one the ratchet is currently at or in the future
         if generation >= self.generation() {
+            // Key material of skipped generations that would immediately fall
+            // out of the out-of-order window is never derived. This bounds the
+            // work per message and leaves no key material of other generations
+            // behind.
+            let distance = generation - self.generation();
+            let skipped =
+                distance.saturating_sub(configuration.out_of_order_tolerance().saturating_sub(1));
+            // Reject the message before doing any work if it takes too many
+            // derivations.
+            configuration.check_derivations(distance, distance - skipped)?;
+            if skipped > 0 {
+                // All key material that is retained now is older than the
+                // skipped generations and thus outside of the window.
+                self.drop_past_secrets(0);
+                let first_skipped = self.generation();
+                for _ in 0..skipped {
+                    self.ratchet_head.skip_forward(crypto, ciphersuite)?;
+                }
+                self.unused_generations
+                    .record(first_skipped..self.generation());
+            }
             // Ratchet the chain forward as far as necessary
             for _ in 0..(generation - self.generation()) {
                 // Derive the key material
@@ -258,6 +660,11 @@ impl DecryptionRatchet {
                 log::error!("  Generation is too far in the past (not in the window).");
                 return Err(SecretTreeError::TooDistantInThePast);
             };
+            // Key material that isn't in the queue anymore was evicted. If
+            // it was used before, this is a replay.
+            if index >= self.past_secrets.len() {
+                return Err(self.deleted_secret_error(generation));
+            }
             // Get the relevant secrets from the past secrets queue.
             self.past_secrets
                 .get_mut(index)
@@ -378,7 +378,12 @@ pub fn run_test_vector(
                 .parse_message(decrypted_message, group.message_secrets_store())
                 .unwrap();
             let processed_message: AuthenticatedContent = processed_unverified_message
-                .verify(ciphersuite, provider.crypto(), ProtocolVersion::Mls10)
+                .verify(
+                    ciphersuite,
+                    provider.crypto(),
+                    provider.time(),
+                    ProtocolVersion::Mls10,
+                )
                 .unwrap()
                 .0;
             match processed_message.content().to_owned() {
@@ -411,6 +416,7 @@ pub fn run_test_vector(
                     provider,
                     proposal_priv.into_protocol_message().unwrap(),
                     &sender_ratchet_config,
+                    &DecodeLimits::default(),
                     &proposal_store,
                     &[],
                 )
@@ -514,7 +520,12 @@ pub fn run_test_vector(
                 .parse_message(decrypted_message, group.message_secrets_store())
                 .unwrap();
             let processed_message: AuthenticatedContent = processed_unverified_message
-                .verify(ciphersuite, provider.crypto(), ProtocolVersion::Mls10)
+                .verify(
+                    ciphersuite,
+                    provider.crypto(),
+                    provider.time(),
+                    ProtocolVersion::Mls10,
+                )
                 .unwrap()
                 .0;
             match processed_message.content().to_owned() {
@@ -557,7 +568,12 @@ pub fn run_test_vector(
                 .parse_message(decrypted_message, group.message_secrets_store())
                 .unwrap();
             let processed_message: AuthenticatedContent = processed_unverified_message
-                .verify(ciphersuite, provider.crypto(), ProtocolVersion::Mls10)
+                .verify(
+                    ciphersuite,
+                    provider.crypto(),
+                    provider.time(),
+                    ProtocolVersion::Mls10,
+                )
                 .unwrap()
                 .0;
             match processed_message.content().to_owned() {
@@ -659,6 +675,7 @@ pub fn run_test_vector(
                     provider,
                     application_priv.into_ciphertext().unwrap(),
                     &sender_ratchet_config,
+                    &DecodeLimits::default(),
                     &proposal_store,
                     &[],
                 )
@@ -260,3 +260,63 @@ fn secret_tree(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
         application_secret_nonce.as_slice()
     );
 }
+
+// Test that the secret tree keeps count of the keys retained by its decryption
+// ratchets.
+#[apply(ciphersuites_and_providers)]
+fn test_retained_keys(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let configuration = &SenderRatchetConfiguration::new(10, 1000);
+    let encryption_secret = EncryptionSecret::random(ciphersuite, provider.rand());
+    let mut secret_tree = SecretTree::new(
+        encryption_secret,
+        TreeSize::from_leaf_count(4u32),
+        LeafNodeIndex::new(3u32),
+    );
+    let decrypt = |secret_tree: &mut SecretTree, leaf_index: u32, generation: u32| {
+        let _ = secret_tree.secret_for_decryption(
+            ciphersuite,
+            provider.crypto(),
+            LeafNodeIndex::new(leaf_index),
+            SecretType::ApplicationSecret,
+            generation,
+            configuration,
+        );
+    };
+    let count = |secret_tree: &SecretTree| -> usize {
+        secret_tree
+            .decryption_ratchets()
+            .map(|ratchet| ratchet.retained_keys())
+            .sum()
+    };
+
+    // Skip generations of two senders and use some of the retained keys.
+    decrypt(&mut secret_tree, 0, 5);
+    decrypt(&mut secret_tree, 1, 3);
+    decrypt(&mut secret_tree, 0, 2);
+    assert_eq!(secret_tree.retained_keys(), 7);
+    assert_eq!(secret_tree.retained_keys(), count(&secret_tree));
+
+    // Failed decryptions don't change the count.
+    decrypt(&mut secret_tree, 0, 2);
+    decrypt(&mut secret_tree, 1, 2_000);
+    assert_eq!(secret_tree.retained_keys(), 7);
+
+    // Moving the window drops keys.
+    decrypt(&mut secret_tree, 0, 14);
+    assert_eq!(secret_tree.retained_keys(), count(&secret_tree));
+
+    // The count is restored when the tree is loaded.
+    let serialized = serde_json::to_vec(&secret_tree).expect("Error serializing the tree.");
+    let loaded: SecretTree =
+        serde_json::from_slice(&serialized).expect("Error deserializing the tree.");
+    assert_eq!(loaded.retained_keys(), secret_tree.retained_keys());
+
+    assert_eq!(secret_tree.evict_retained_keys(1), count(&loaded) - 2);
+    assert_eq!(secret_tree.retained_keys(), 2);
+
+    secret_tree
+        .delete_sender_ratchets(ciphersuite, provider.crypto(), LeafNodeIndex::new(1))
+        .expect("Error deleting the sender ratchets.");
+    assert_eq!(secret_tree.retained_keys(), 1);
+    assert_eq!(secret_tree.retained_keys(), count(&secret_tree));
+}
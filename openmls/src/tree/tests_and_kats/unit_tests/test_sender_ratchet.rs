@@ -58,7 +58,8 @@ fn test_out_of_order_generations(ciphersuite: Ciphersuite, provider: &impl OpenM
             .expect("Expected decryption secret.");
     }
 
-    // Check that secrets from before the window are not accessible anymore
+    // Check that secrets from before the window are not accessible anymore.
+    // They were used before, so this is a replay.
     let err = ratchet1
         .secret_for_decryption(
             ciphersuite,
@@ -68,7 +69,7 @@ fn test_out_of_order_generations(ciphersuite: Ciphersuite, provider: &impl OpenM
         )
         .expect_err("Expected error.");
 
-    assert_eq!(err, SecretTreeError::TooDistantInThePast);
+    assert_eq!(err, SecretTreeError::SecretReuseError);
 
     // All secrets within the window should have been deleted because of FS.
     for i in configuration.out_of_order_tolerance()..configuration.out_of_order_tolerance() * 2 {
@@ -133,6 +134,169 @@ fn test_forward_secrecy(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvide
     }
 }
 
+// Test that replays are told apart from messages that are too old, even
+// outside of the out-of-order window and after (de)serialization.
+#[apply(ciphersuites_and_providers)]
+fn test_replay_detection(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    // Don't keep any secrets for out-of-order messages.
+    let configuration = &SenderRatchetConfiguration::new(0, 1000);
+    let secret = Secret::random(ciphersuite, provider.rand(), ProtocolVersion::Mls10)
+        .expect("Not enough randomness.");
+    let mut ratchet = DecryptionRatchet::new(secret);
+
+    // Use generation 0 and skip generations 1 to 4.
+    for generation in [0, 5] {
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+            .expect("Error ratcheting forward.");
+    }
+
+    let check_generations = |ratchet: &mut DecryptionRatchet| {
+        for generation in 0..6 {
+            let err = ratchet
+                .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+                .expect_err("No error when trying to retrieve a past key.");
+            if generation == 0 || generation == 5 {
+                assert_eq!(err, SecretTreeError::SecretReuseError);
+            } else {
+                assert_eq!(err, SecretTreeError::TooDistantInThePast);
+            }
+        }
+    };
+    check_generations(&mut ratchet);
+
+    // The used generations are still known after a restart.
+    let serialized = serde_json::to_vec(&ratchet).expect("Error serializing the ratchet.");
+    let mut ratchet: DecryptionRatchet =
+        serde_json::from_slice(&serialized).expect("Error deserializing the ratchet.");
+    check_generations(&mut ratchet);
+
+    // Shrinking the window doesn't change the result either.
+    let configuration = &SenderRatchetConfiguration::default();
+    let secret = Secret::random(ciphersuite, provider.rand(), ProtocolVersion::Mls10)
+        .expect("Not enough randomness.");
+    let mut ratchet = DecryptionRatchet::new(secret);
+    for generation in [0, 2] {
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+            .expect("Error ratcheting forward.");
+    }
+    let configuration = &SenderRatchetConfiguration::new(0, 1000);
+    assert_eq!(
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), 0, configuration)
+            .expect_err("No error when trying to retrieve a used key."),
+        SecretTreeError::SecretReuseError
+    );
+    assert_eq!(
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), 1, configuration)
+            .expect_err("No error when trying to retrieve a key outside the window."),
+        SecretTreeError::TooDistantInThePast
+    );
+}
+
+// Test that the state for replay detection stays bounded and that generations
+// that are too old to tell are reported as replays.
+#[apply(ciphersuites_and_providers)]
+fn test_replay_detection_is_bounded(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let configuration = &SenderRatchetConfiguration::new(0, 1000);
+    let secret = Secret::random(ciphersuite, provider.rand(), ProtocolVersion::Mls10)
+        .expect("Not enough randomness.");
+    let mut ratchet = DecryptionRatchet::new(secret);
+
+    // Use every other generation, such that every other generation is
+    // remembered as unused.
+    let mut serialized_len = 0;
+    for generation in (0..10_000).step_by(2) {
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+            .expect("Error ratcheting forward.");
+        if generation == 5_000 {
+            serialized_len = serde_json::to_vec(&ratchet).unwrap().len();
+        }
+    }
+    // The state doesn't grow with the number of messages.
+    assert!(serde_json::to_vec(&ratchet).unwrap().len() <= serialized_len + 16);
+
+    // Recent generations can still be told apart.
+    for (generation, expected_error) in [
+        (9_998, SecretTreeError::SecretReuseError),
+        (9_997, SecretTreeError::TooDistantInThePast),
+        (9_100, SecretTreeError::SecretReuseError),
+        (9_101, SecretTreeError::TooDistantInThePast),
+    ] {
+        assert_eq!(
+            ratchet
+                .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+                .expect_err("No error when trying to retrieve a past key."),
+            expected_error
+        );
+    }
+
+    // Generations before the low-water mark are reported as replays, even if
+    // they weren't used.
+    for generation in [0, 1, 1_000] {
+        assert_eq!(
+            ratchet
+                .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+                .expect_err("No error when trying to retrieve a past key."),
+            SecretTreeError::SecretReuseError
+        );
+    }
+}
+
+// Test that ratchets persisted without unused generations only report replays
+// of generations that were dropped after loading them.
+#[apply(ciphersuites_and_providers)]
+fn test_replay_detection_of_old_state(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let configuration = &SenderRatchetConfiguration::new(5, 1000);
+    let secret = Secret::random(ciphersuite, provider.rand(), ProtocolVersion::Mls10)
+        .expect("Not enough randomness.");
+    let mut ratchet = DecryptionRatchet::new(secret);
+
+    // Generations 3 and 8 are never used. Generation 3 falls out of the
+    // window before the ratchet is persisted.
+    for generation in [0, 1, 2, 4, 5, 6, 7, 9] {
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+            .expect("Error ratcheting forward.");
+    }
+
+    // Persist the ratchet the way older versions did.
+    let mut state = serde_json::to_value(&ratchet).unwrap();
+    state
+        .as_object_mut()
+        .unwrap()
+        .remove("unused_generations")
+        .expect("No unused generations in the state.");
+    let mut ratchet: DecryptionRatchet = serde_json::from_value(state).unwrap();
+
+    // Move the window past all generations used so far.
+    for generation in 10..16 {
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+            .expect("Error ratcheting forward.");
+    }
+
+    for (generation, expected_error) in [
+        // Nothing is known about generations dropped before persisting.
+        (2, SecretTreeError::TooDistantInThePast),
+        (3, SecretTreeError::TooDistantInThePast),
+        // Generations dropped after loading are tracked.
+        (7, SecretTreeError::SecretReuseError),
+        (8, SecretTreeError::TooDistantInThePast),
+        (9, SecretTreeError::SecretReuseError),
+    ] {
+        assert_eq!(
+            ratchet
+                .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+                .expect_err("No error when trying to retrieve a past key."),
+            expected_error
+        );
+    }
+}
+
 // Test if a sender ratchet overflow is caught
 #[test]
 fn sender_ratchet_generation_overflow() {
@@ -150,3 +314,114 @@ fn sender_ratchet_generation_overflow() {
         .expect_err("no error exceeding generation u32::MAX");
     assert_eq!(err, SecretTreeError::RatchetTooLong)
 }
+
+// Test that skipping generations without deriving their key material yields
+// the same key material as ratcheting through all of them.
+#[apply(ciphersuites_and_providers)]
+fn test_skipped_generations(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let configuration = &SenderRatchetConfiguration::default();
+    let secret = Secret::random(ciphersuite, provider.rand(), ProtocolVersion::Mls10)
+        .expect("Not enough randomness.");
+    let mut skipping_ratchet = DecryptionRatchet::new(secret.clone());
+    let mut ratchet = RatchetSecret::initial_ratchet_secret(secret);
+    let key_material: Vec<_> = (0..=100)
+        .map(|_| {
+            ratchet
+                .ratchet_forward(provider.crypto(), ciphersuite)
+                .expect("Error ratcheting forward.")
+                .1
+        })
+        .collect();
+
+    let secrets = skipping_ratchet
+        .secret_for_decryption(ciphersuite, provider.crypto(), 100, configuration)
+        .expect("Error ratcheting forward.");
+    assert!(secrets == key_material[100]);
+
+    // Only the key material within the window was derived and retained.
+    let window = configuration.out_of_order_tolerance() - 1;
+    assert_eq!(skipping_ratchet.retained_keys(), window as usize);
+    for generation in 100 - window..100 {
+        let secrets = skipping_ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+            .expect("Error retrieving a retained key.");
+        assert!(secrets == key_material[generation as usize]);
+    }
+    assert_eq!(
+        skipping_ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), 50, configuration)
+            .expect_err("No error when trying to retrieve a skipped key."),
+        SecretTreeError::TooDistantInThePast
+    );
+}
+
+// Test that evicted keys can't be used anymore.
+#[apply(ciphersuites_and_providers)]
+fn test_evict_retained_keys(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let configuration = &SenderRatchetConfiguration::new(10, 1000);
+    let secret = Secret::random(ciphersuite, provider.rand(), ProtocolVersion::Mls10)
+        .expect("Not enough randomness.");
+    let mut ratchet = DecryptionRatchet::new(secret);
+
+    // Use generation 5 and retain the keys of generations 0 to 4 and 6 to 8.
+    for generation in [5, 9] {
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+            .expect("Error ratcheting forward.");
+    }
+    assert_eq!(ratchet.retained_keys(), 8);
+
+    assert_eq!(ratchet.evict_retained_keys(10), 0);
+    assert_eq!(ratchet.evict_retained_keys(3), 5);
+    assert_eq!(ratchet.retained_keys(), 3);
+    assert_eq!(ratchet.retained_generations(), vec![6, 7, 8]);
+
+    for generation in 0..5 {
+        assert_eq!(
+            ratchet
+                .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+                .expect_err("No error when trying to retrieve an evicted key."),
+            SecretTreeError::TooDistantInThePast
+        );
+    }
+    assert_eq!(
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), 5, configuration)
+            .expect_err("No error when trying to retrieve a used key."),
+        SecretTreeError::SecretReuseError
+    );
+    for generation in 6..9 {
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), generation, configuration)
+            .expect("Error retrieving a retained key.");
+    }
+}
+
+// Test that messages that take too many key derivations are rejected without
+// changing the ratchet.
+#[apply(ciphersuites_and_providers)]
+fn test_maximum_derivations_per_message(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let configuration =
+        &SenderRatchetConfiguration::new(5, 1000).with_maximum_derivations_per_message(100);
+    let secret = Secret::random(ciphersuite, provider.rand(), ProtocolVersion::Mls10)
+        .expect("Not enough randomness.");
+    let mut ratchet = DecryptionRatchet::new(secret);
+
+    // Skipping 96 generations and deriving the key material of 5 takes 111
+    // derivations.
+    assert_eq!(
+        ratchet
+            .secret_for_decryption(ciphersuite, provider.crypto(), 100, configuration)
+            .expect_err("No error when exceeding the maximum number of derivations."),
+        SecretTreeError::TooDistantInTheFuture
+    );
+    assert_eq!(ratchet.generation(), 0);
+    assert_eq!(ratchet.retained_keys(), 0);
+
+    // Skipping 85 generations and deriving the key material of 5 takes 100
+    // derivations.
+    ratchet
+        .secret_for_decryption(ciphersuite, provider.crypto(), 89, configuration)
+        .expect("Error ratcheting forward.");
+    assert_eq!(ratchet.generation(), 90);
+}
@@ -487,7 +487,8 @@ impl<'a> TreeSyncDiff<'a> {
                         for leaf_index in parent.unmerged_leaves() {
                             if !excluded_indices.contains(&leaf_index) {
                                 let leaf = self.diff.leaf(*leaf_index);
-                                // TODO #800: unmerged leaves should be checked
+                                // Unmerged leaves are verified to be non-blank
+                                // when the tree is imported.
                                 if let Some(leaf_node) = leaf.node() {
                                     resolution.push((
                                         TreeNodeIndex::Leaf(*leaf_index),
@@ -149,6 +149,22 @@ pub enum TreeSyncFromNodesError {
     /// See [`RatchetTreeError`] for more details.
     #[error(transparent)]
     RatchetTreeError(#[from] RatchetTreeError),
+    /// A parent node lists a blank leaf as unmerged leaf.
+    #[error("A parent node lists a blank leaf as unmerged leaf.")]
+    BlankUnmergedLeaf,
+    /// A parent node lists a leaf outside of its subtree as unmerged leaf.
+    #[error("A parent node lists a leaf outside of its subtree as unmerged leaf.")]
+    UnmergedLeafNotInSubtree,
+    /// An unmerged leaf is not listed as unmerged leaf by all non-blank nodes
+    /// between the leaf and the parent node.
+    #[error("An unmerged leaf is not listed as unmerged leaf by all non-blank nodes between the leaf and the parent node.")]
+    InconsistentUnmergedLeaves,
+    /// An HPKE encryption key appears more than once in the tree.
+    #[error("An HPKE encryption key appears more than once in the tree.")]
+    DuplicateEncryptionKey,
+    /// A signature key appears in more than one leaf of the tree.
+    #[error("A signature key appears in more than one leaf of the tree.")]
+    DuplicateSignatureKey,
 }
 
 /// TreeSync parent hash error
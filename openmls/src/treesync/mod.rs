@@ -24,6 +24,7 @@ use openmls_rust_crypto::OpenMlsRustCrypto;
 use rstest::*;
 #[cfg(test)]
 use rstest_reuse::apply;
+use std::collections::HashSet;
 #[cfg(any(feature = "test-utils", test))]
 use std::fmt;
 
@@ -57,13 +58,16 @@ use crate::{
 };
 use crate::{
     binary_tree::{
-        array_representation::{is_node_in_tree, tree::TreeNode, LeafNodeIndex, TreeSize},
+        array_representation::{
+            direct_path, is_node_in_tree, tree::TreeNode, LeafNodeIndex, TreeSize,
+        },
         MlsBinaryTree, MlsBinaryTreeError,
     },
     ciphersuite::{signable::Verifiable, Secret},
     credentials::CredentialWithKey,
     error::LibraryError,
     extensions::Extensions,
+    framing::{errors::DecodeLimitError, CheckDecodeLimits, DecodeLimits},
     group::{config::CryptoConfig, GroupId, Member},
     key_packages::Lifetime,
     messages::{PathSecret, PathSecretError},
@@ -230,15 +234,21 @@ impl RatchetTree {
 pub struct RatchetTreeIn(Vec<Option<NodeIn>>);
 
 impl RatchetTreeIn {
-    /// Create a new [`RatchetTreeIn`] from a vector of nodes after verifying
+    /// Create a new [`RatchetTree`] from a vector of nodes after verifying
     /// the nodes.
+    ///
+    /// Besides the individual nodes, this verifies the invariants spanning
+    /// multiple nodes, i.e. the consistency of unmerged leaves, the parent
+    /// hashes and the uniqueness of encryption and signature keys.
     pub fn into_verified(
         self,
         ciphersuite: Ciphersuite,
         crypto: &impl OpenMlsCrypto,
         group_id: &GroupId,
-    ) -> Result<RatchetTree, RatchetTreeError> {
-        RatchetTree::try_from_nodes(ciphersuite, crypto, self.0, group_id)
+    ) -> Result<RatchetTree, TreeSyncFromNodesError> {
+        let ratchet_tree = RatchetTree::try_from_nodes(ciphersuite, crypto, self.0, group_id)?;
+        TreeSync::verify_ratchet_tree(crypto, ciphersuite, &ratchet_tree)?;
+        Ok(ratchet_tree)
     }
 
     fn from_ratchet_tree(ratchet_tree: RatchetTree) -> Self {
@@ -256,6 +266,18 @@ impl RatchetTreeIn {
     }
 }
 
+impl CheckDecodeLimits for RatchetTreeIn {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        limits.check_tree_size(self.0.len())?;
+        for node in self.0.iter().flatten() {
+            if let NodeIn::LeafNode(leaf_node) = node {
+                leaf_node.check_decode_limits(limits)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 impl From<RatchetTree> for RatchetTreeIn {
     fn from(ratchet_tree: RatchetTree) -> Self {
         RatchetTreeIn::from_ratchet_tree(ratchet_tree)
@@ -431,14 +453,45 @@ impl TreeSync {
     }
 
     /// A helper function that generates a [`TreeSync`] instance from the given
-    /// slice of nodes. It verifies that the provided encryption key is present
-    /// in the tree and that the invariants documented in [`TreeSync`] hold.
+    /// [`RatchetTree`]. The tree must have been verified with
+    /// [`RatchetTreeIn::into_verified()`], which ensures that the invariants
+    /// documented in [`TreeSync`] hold.
     pub(crate) fn from_ratchet_tree(
         crypto: &impl OpenMlsCrypto,
         ciphersuite: Ciphersuite,
         ratchet_tree: RatchetTree,
     ) -> Result<Self, TreeSyncFromNodesError> {
-        // TODO #800: Unmerged leaves should be checked
+        let mut tree_sync = Self::from_nodes(ratchet_tree)?;
+        // Populate tree hash caches.
+        tree_sync.populate_parent_hashes(crypto, ciphersuite)?;
+        Ok(tree_sync)
+    }
+
+    /// Verify the unmerged leaves, the uniqueness of all keys and the parent
+    /// hashes of the given [`RatchetTree`].
+    fn verify_ratchet_tree(
+        crypto: &impl OpenMlsCrypto,
+        ciphersuite: Ciphersuite,
+        ratchet_tree: &RatchetTree,
+    ) -> Result<(), TreeSyncFromNodesError> {
+        let tree_sync = Self::from_nodes(ratchet_tree.clone())?;
+        // Verify the unmerged leaves and the uniqueness of all keys.
+        tree_sync.verify_unmerged_leaves()?;
+        tree_sync.verify_key_uniqueness()?;
+        // Verify all parent hashes.
+        tree_sync
+            .verify_parent_hashes(crypto, ciphersuite)
+            .map_err(|e| match e {
+                TreeSyncParentHashError::LibraryError(e) => e.into(),
+                TreeSyncParentHashError::InvalidParentHash => {
+                    PublicTreeError::InvalidParentHash.into()
+                }
+            })
+    }
+
+    /// Convert the nodes of the given [`RatchetTree`] into a [`TreeSync`]
+    /// instance without tree hashes.
+    fn from_nodes(ratchet_tree: RatchetTree) -> Result<Self, TreeSyncFromNodesError> {
         let mut ts_nodes: Vec<TreeNode<TreeSyncLeafNode, TreeSyncParentNode>> =
             Vec::with_capacity(ratchet_tree.0.len());
 
@@ -457,22 +510,76 @@ impl TreeSync {
             ts_nodes.push(ts_node_option);
         }
         let tree = MlsBinaryTree::new(ts_nodes).map_err(|_| PublicTreeError::MalformedTree)?;
-        let mut tree_sync = Self {
+        Ok(Self {
             tree,
             tree_hash: vec![],
-        };
-        // Verify all parent hashes.
-        tree_sync
-            .verify_parent_hashes(crypto, ciphersuite)
-            .map_err(|e| match e {
-                TreeSyncParentHashError::LibraryError(e) => e.into(),
-                TreeSyncParentHashError::InvalidParentHash => {
-                    TreeSyncFromNodesError::from(PublicTreeError::InvalidParentHash)
+        })
+    }
+
+    /// Verify that the unmerged leaves of all parent nodes are consistent
+    /// (see Section 7.9.2 of RFC 9420): Every unmerged leaf of a parent node
+    /// must be a non-blank leaf in the subtree of that parent node and every
+    /// non-blank node between the leaf and the parent node must list the leaf
+    /// as unmerged as well.
+    fn verify_unmerged_leaves(&self) -> Result<(), TreeSyncFromNodesError> {
+        let tree_size = self.tree.tree_size();
+        for (parent_index, parent) in self.tree.parents() {
+            let parent_node = match parent.node() {
+                Some(parent_node) => parent_node,
+                None => continue,
+            };
+            for &leaf_index in parent_node.unmerged_leaves() {
+                if !self.is_leaf_in_tree(leaf_index) {
+                    return Err(TreeSyncFromNodesError::UnmergedLeafNotInSubtree);
                 }
-            })?;
-        // Populate tree hash caches.
-        tree_sync.populate_parent_hashes(crypto, ciphersuite)?;
-        Ok(tree_sync)
+                if self.leaf(leaf_index).is_none() {
+                    return Err(TreeSyncFromNodesError::BlankUnmergedLeaf);
+                }
+                // The nodes between the leaf and the parent node are the ones
+                // preceding the parent node in the direct path of the leaf.
+                let direct_path = direct_path(leaf_index, tree_size);
+                let intermediate_nodes =
+                    match direct_path.iter().position(|&index| index == parent_index) {
+                        Some(position) => &direct_path[..position],
+                        None => return Err(TreeSyncFromNodesError::UnmergedLeafNotInSubtree),
+                    };
+                for &intermediate_index in intermediate_nodes {
+                    if let Some(intermediate_node) =
+                        self.tree.parent_by_index(intermediate_index).node()
+                    {
+                        if !intermediate_node.unmerged_leaves().contains(&leaf_index) {
+                            return Err(TreeSyncFromNodesError::InconsistentUnmergedLeaves);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Verify that no HPKE encryption key appears more than once in the tree
+    /// and that no signature key appears in more than one leaf.
+    fn verify_key_uniqueness(&self) -> Result<(), TreeSyncFromNodesError> {
+        let mut encryption_keys = HashSet::new();
+        let mut signature_keys = HashSet::new();
+        for leaf_node in self.full_leaves() {
+            if !encryption_keys.insert(leaf_node.encryption_key().as_slice()) {
+                return Err(TreeSyncFromNodesError::DuplicateEncryptionKey);
+            }
+            if !signature_keys.insert(leaf_node.signature_key().as_slice()) {
+                return Err(TreeSyncFromNodesError::DuplicateSignatureKey);
+            }
+        }
+        for parent_node in self
+            .tree
+            .parents()
+            .filter_map(|(_, tsn)| tsn.node().as_ref())
+        {
+            if !encryption_keys.insert(parent_node.encryption_key().as_slice()) {
+                return Err(TreeSyncFromNodesError::DuplicateEncryptionKey);
+            }
+        }
+        Ok(())
     }
 
     /// Find the `LeafNodeIndex` which a new leaf would have if it were added to the
@@ -538,6 +645,15 @@ impl TreeSync {
             .filter_map(|(_, tsn)| tsn.node().as_ref())
     }
 
+    /// Returns an iterator over the indices and nodes of all full leaves.
+    pub(crate) fn full_leaves_with_index(
+        &self,
+    ) -> impl Iterator<Item = (LeafNodeIndex, &LeafNode)> {
+        self.tree
+            .leaves()
+            .filter_map(|(index, tsn)| tsn.node().as_ref().map(|node| (index, node)))
+    }
+
     /// Returns the index of the last full leaf in the tree.
     fn rightmost_full_leaf(&self) -> LeafNodeIndex {
         let mut index = LeafNodeIndex::new(0);
@@ -10,7 +10,7 @@ use serde::{Deserialize, Serialize};
 use tls_codec::{TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize, VLBytes};
 
 use crate::{
-    ciphersuite::{hpke, HpkePrivateKey, HpkePublicKey, Secret},
+    ciphersuite::{hpke, ConstantTimeEq, HpkePrivateKey, HpkePublicKey, Secret},
     error::LibraryError,
     group::config::CryptoConfig,
     versions::ProtocolVersion,
@@ -79,11 +79,27 @@ impl EncryptionKey {
 #[derive(
     Clone, Serialize, Deserialize, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize,
 )]
-#[cfg_attr(test, derive(PartialEq, Eq))]
 pub(crate) struct EncryptionPrivateKey {
     key: HpkePrivateKey,
 }
 
+impl ConstantTimeEq for EncryptionPrivateKey {
+    fn ct_eq(&self, other: &Self) -> bool {
+        self.key.ct_eq(&other.key)
+    }
+}
+
+#[cfg(test)]
+impl PartialEq for EncryptionPrivateKey {
+    // Constant time comparison.
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other)
+    }
+}
+
+#[cfg(test)]
+impl Eq for EncryptionPrivateKey {}
+
 impl Debug for EncryptionPrivateKey {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let mut ds = f.debug_struct("EncryptionPrivateKey");
@@ -196,7 +212,19 @@ impl EncryptionKeyPair {
         &self,
         store: &KeyStore,
     ) -> Result<(), KeyStore::Error> {
-        store.delete::<Self>(&self.public_key().to_bytes_with_prefix())
+        Self::delete_from_key_store_by_public_key(store, self.public_key())
+    }
+
+    /// Delete the [`EncryptionKeyPair`] with the given `encryption_key` from
+    /// the key store of the `provider`. This function is meant to delete
+    /// standalone keypairs, not ones that are already in use with an MLS group.
+    ///
+    /// Returns a key store error if access to the key store fails.
+    pub(crate) fn delete_from_key_store_by_public_key<KeyStore: OpenMlsKeyStore>(
+        store: &KeyStore,
+        encryption_key: &EncryptionKey,
+    ) -> Result<(), KeyStore::Error> {
+        store.delete::<Self>(&encryption_key.to_bytes_with_prefix())
     }
 
     pub(crate) fn public_key(&self) -> &EncryptionKey {
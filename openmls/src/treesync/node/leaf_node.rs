@@ -21,6 +21,7 @@ use crate::{
     credentials::{Credential, CredentialWithKey},
     error::LibraryError,
     extensions::{ExtensionType, Extensions},
+    framing::{errors::DecodeLimitError, CheckDecodeLimits, DecodeLimits},
     group::{config::CryptoConfig, GroupId},
     key_packages::{KeyPackage, Lifetime},
     treesync::errors::PublicTreeError,
@@ -438,6 +439,12 @@ impl LeafNode {
         self.payload.signature_key = signature_key;
     }
 
+    /// Replace the extensions of the leaf node.
+    #[cfg(test)]
+    pub(crate) fn set_extensions(&mut self, extensions: Extensions) {
+        self.payload.extensions = extensions;
+    }
+
     /// Resign the node
     pub(crate) fn resign(
         &mut self,
@@ -728,6 +735,12 @@ impl LeafNodeIn {
     }
 }
 
+impl CheckDecodeLimits for LeafNodeIn {
+    fn check_decode_limits(&self, limits: &DecodeLimits) -> Result<(), DecodeLimitError> {
+        self.payload.extensions.check_decode_limits(limits)
+    }
+}
+
 impl From<LeafNode> for LeafNodeIn {
     fn from(leaf_node: LeafNode) -> Self {
         Self {
@@ -160,6 +160,12 @@ impl Capabilities {
         Ok(())
     }
 
+    /// Check if these [`Capabilities`] support the given proposal type. The
+    /// proposal types defined in RFC 9420 are always supported.
+    pub(crate) fn supports_proposal(&self, proposal_type: &ProposalType) -> bool {
+        proposal_type.is_supported() || self.proposals().contains(proposal_type)
+    }
+
     /// Check if these [`Capabilities`] contain all the extensions.
     pub(crate) fn contain_extensions(&self, extension: &Extensions) -> bool {
         extension
@@ -168,6 +174,14 @@ impl Capabilities {
             .all(|e| self.extensions().contains(&e))
     }
 
+    /// Add the given extension type to the list of supported extensions, if
+    /// it's not already in there.
+    pub(crate) fn add_extension_type(&mut self, extension_type: ExtensionType) {
+        if !self.extensions.contains(&extension_type) {
+            self.extensions.push(extension_type);
+        }
+    }
+
     /// Check if these [`Capabilities`] contain all the credentials.
     pub(crate) fn contains_credential(&self, credential_type: &CredentialType) -> bool {
         self.credentials().contains(credential_type)
@@ -11,6 +11,7 @@ use crate::{
 };
 
 mod test_diff;
+mod test_tree_validation;
 mod test_unmerged_leaves;
 
 /// Pathological example taken from ...
@@ -0,0 +1,291 @@
+use openmls_rust_crypto::OpenMlsRustCrypto;
+use openmls_traits::{types::Ciphersuite, OpenMlsProvider};
+use rstest::*;
+use rstest_reuse::apply;
+
+use crate::{
+    binary_tree::LeafNodeIndex,
+    credentials::{test_utils::new_credential, CredentialType},
+    group::{config::CryptoConfig, GroupId},
+    key_packages::KeyPackageBundle,
+    treesync::{
+        errors::{PublicTreeError, TreeSyncFromNodesError},
+        node::{
+            encryption_keys::{EncryptionKey, EncryptionKeyPair},
+            leaf_node::LeafNode,
+            parent_node::ParentNode,
+            Node,
+        },
+        RatchetTree, RatchetTreeIn,
+    },
+};
+
+/// Generate `n` leaf nodes with distinct encryption and signature keys.
+fn leaf_nodes(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+    n: usize,
+) -> Vec<LeafNode> {
+    (0..n)
+        .map(|i| {
+            let (credential_with_key, signer) = new_credential(
+                provider,
+                format!("leaf{i}").as_bytes(),
+                CredentialType::Basic,
+                ciphersuite.signature_algorithm(),
+            );
+            KeyPackageBundle::new(provider, &signer, ciphersuite, credential_with_key)
+                .key_package()
+                .leaf_node()
+                .clone()
+        })
+        .collect()
+}
+
+/// Generate a parent node with a fresh encryption key and the given unmerged
+/// leaves.
+fn parent_node(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+    unmerged_leaves: Vec<u32>,
+) -> ParentNode {
+    let encryption_key =
+        EncryptionKeyPair::random(provider, CryptoConfig::with_default_version(ciphersuite))
+            .unwrap()
+            .public_key()
+            .clone();
+    parent_node_with_key(encryption_key, unmerged_leaves)
+}
+
+fn parent_node_with_key(encryption_key: EncryptionKey, unmerged_leaves: Vec<u32>) -> ParentNode {
+    let mut parent_node = ParentNode::from(encryption_key);
+    parent_node.set_unmerged_leaves(
+        unmerged_leaves
+            .into_iter()
+            .map(LeafNodeIndex::new)
+            .collect(),
+    );
+    parent_node
+}
+
+/// Build a tree with four leaves and the given parent nodes. `None` leaves
+/// are blank.
+fn four_leaf_tree(leaves: [Option<LeafNode>; 4], parents: [Option<ParentNode>; 3]) -> RatchetTree {
+    let [l0, l1, l2, l3] = leaves;
+    let [p0, p1, p2] = parents;
+    RatchetTree::trimmed(vec![
+        l0.map(Node::LeafNode),
+        p0.map(Node::ParentNode),
+        l1.map(Node::LeafNode),
+        p1.map(Node::ParentNode),
+        l2.map(Node::LeafNode),
+        p2.map(Node::ParentNode),
+        l3.map(Node::LeafNode),
+    ])
+}
+
+fn import(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+    ratchet_tree: RatchetTree,
+) -> Result<RatchetTree, TreeSyncFromNodesError> {
+    RatchetTreeIn::from(ratchet_tree).into_verified(
+        ciphersuite,
+        provider.crypto(),
+        &GroupId::from_slice(b"Test Group"),
+    )
+}
+
+fn import_error(
+    ciphersuite: Ciphersuite,
+    provider: &impl OpenMlsProvider,
+    ratchet_tree: RatchetTree,
+) -> TreeSyncFromNodesError {
+    import(ciphersuite, provider, ratchet_tree).expect_err("importing a malformed tree succeeded")
+}
+
+// A parent node must not list a blank leaf as unmerged leaf.
+#[apply(ciphersuites_and_providers)]
+fn blank_unmerged_leaf(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let [l0, l1, _l2, l3]: [LeafNode; 4] = leaf_nodes(ciphersuite, provider, 4).try_into().unwrap();
+
+    let ratchet_tree = four_leaf_tree(
+        [Some(l0), Some(l1), None, Some(l3)],
+        [
+            None,
+            None,
+            Some(parent_node(ciphersuite, provider, vec![2])),
+        ],
+    );
+
+    assert_eq!(
+        import_error(ciphersuite, provider, ratchet_tree),
+        TreeSyncFromNodesError::BlankUnmergedLeaf
+    );
+}
+
+// A parent node must only list leaves in its own subtree as unmerged leaves.
+#[apply(ciphersuites_and_providers)]
+fn unmerged_leaf_not_in_subtree(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    // The leaf is in the tree, but not below the parent node.
+    let [l0, l1, l2, l3]: [LeafNode; 4] = leaf_nodes(ciphersuite, provider, 4).try_into().unwrap();
+    let ratchet_tree = four_leaf_tree(
+        [
+            Some(l0.clone()),
+            Some(l1.clone()),
+            Some(l2.clone()),
+            Some(l3.clone()),
+        ],
+        [
+            Some(parent_node(ciphersuite, provider, vec![3])),
+            None,
+            None,
+        ],
+    );
+    assert_eq!(
+        import_error(ciphersuite, provider, ratchet_tree),
+        TreeSyncFromNodesError::UnmergedLeafNotInSubtree
+    );
+
+    // The leaf is outside of the tree.
+    let ratchet_tree = four_leaf_tree(
+        [Some(l0), Some(l1), Some(l2), Some(l3)],
+        [
+            None,
+            Some(parent_node(ciphersuite, provider, vec![7])),
+            None,
+        ],
+    );
+    assert_eq!(
+        import_error(ciphersuite, provider, ratchet_tree),
+        TreeSyncFromNodesError::UnmergedLeafNotInSubtree
+    );
+}
+
+// Every non-blank node between an unmerged leaf and the parent node listing it
+// must list the leaf as well.
+#[apply(ciphersuites_and_providers)]
+fn inconsistent_unmerged_leaves(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let [l0, l1, l2, l3]: [LeafNode; 4] = leaf_nodes(ciphersuite, provider, 4).try_into().unwrap();
+
+    // The root lists leaf 0 as unmerged, but the non-blank node in between
+    // doesn't.
+    let ratchet_tree = four_leaf_tree(
+        [Some(l0), Some(l1), Some(l2), Some(l3)],
+        [
+            Some(parent_node(ciphersuite, provider, vec![])),
+            Some(parent_node(ciphersuite, provider, vec![0])),
+            None,
+        ],
+    );
+
+    assert_eq!(
+        import_error(ciphersuite, provider, ratchet_tree),
+        TreeSyncFromNodesError::InconsistentUnmergedLeaves
+    );
+}
+
+// No HPKE encryption key may appear twice in the tree.
+#[apply(ciphersuites_and_providers)]
+fn duplicate_encryption_key(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let [l0, l1, l2, l3]: [LeafNode; 4] = leaf_nodes(ciphersuite, provider, 4).try_into().unwrap();
+
+    // A parent node re-uses the encryption key of a leaf.
+    let duplicate_key = l0.encryption_key().clone();
+    let ratchet_tree = four_leaf_tree(
+        [
+            Some(l0.clone()),
+            Some(l1.clone()),
+            Some(l2.clone()),
+            Some(l3.clone()),
+        ],
+        [
+            None,
+            None,
+            Some(parent_node_with_key(duplicate_key, vec![])),
+        ],
+    );
+    assert_eq!(
+        import_error(ciphersuite, provider, ratchet_tree),
+        TreeSyncFromNodesError::DuplicateEncryptionKey
+    );
+
+    // Two parent nodes share the same encryption key.
+    let parent = parent_node(ciphersuite, provider, vec![]);
+    let ratchet_tree = four_leaf_tree(
+        [Some(l0), Some(l1), Some(l2), Some(l3)],
+        [Some(parent.clone()), None, Some(parent)],
+    );
+    assert_eq!(
+        import_error(ciphersuite, provider, ratchet_tree),
+        TreeSyncFromNodesError::DuplicateEncryptionKey
+    );
+}
+
+// No signature key may appear in more than one leaf.
+#[apply(ciphersuites_and_providers)]
+fn duplicate_signature_key(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let (credential_with_key, signer) = new_credential(
+        provider,
+        b"leaf0",
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    );
+
+    // Two key packages of the same client have different encryption keys, but
+    // the same signature key.
+    let l0 = KeyPackageBundle::new(provider, &signer, ciphersuite, credential_with_key.clone())
+        .key_package()
+        .leaf_node()
+        .clone();
+    let l1 = KeyPackageBundle::new(provider, &signer, ciphersuite, credential_with_key)
+        .key_package()
+        .leaf_node()
+        .clone();
+
+    let ratchet_tree = RatchetTree::trimmed(vec![
+        Some(Node::LeafNode(l0)),
+        None,
+        Some(Node::LeafNode(l1)),
+    ]);
+
+    assert_eq!(
+        import_error(ciphersuite, provider, ratchet_tree),
+        TreeSyncFromNodesError::DuplicateSignatureKey
+    );
+}
+
+// Every non-blank parent node must be covered by the parent hash of one of
+// its descendants.
+#[apply(ciphersuites_and_providers)]
+fn invalid_parent_hash(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let [l0, l1, l2, l3]: [LeafNode; 4] = leaf_nodes(ciphersuite, provider, 4).try_into().unwrap();
+
+    // The leaves come from key packages and therefore carry no parent hash.
+    let ratchet_tree = four_leaf_tree(
+        [Some(l0), Some(l1), Some(l2), Some(l3)],
+        [Some(parent_node(ciphersuite, provider, vec![])), None, None],
+    );
+
+    assert_eq!(
+        import_error(ciphersuite, provider, ratchet_tree),
+        TreeSyncFromNodesError::PublicTreeError(PublicTreeError::InvalidParentHash)
+    );
+}
+
+// Well-formed trees are accepted.
+#[apply(ciphersuites_and_providers)]
+fn valid_unmerged_leaves(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let [l0, l1, l2, l3]: [LeafNode; 4] = leaf_nodes(ciphersuite, provider, 4).try_into().unwrap();
+
+    // Leaf-only trees don't contain unmerged leaves or parent hashes.
+    let ratchet_tree = four_leaf_tree(
+        [Some(l0.clone()), Some(l1), None, Some(l3.clone())],
+        [None, None, None],
+    );
+    import(ciphersuite, provider, ratchet_tree).expect("importing a valid tree failed");
+
+    // Blank leaves are ignored by the key uniqueness check.
+    let ratchet_tree = four_leaf_tree([Some(l0), None, Some(l2), Some(l3)], [None, None, None]);
+    import(ciphersuite, provider, ratchet_tree).expect("importing a valid tree failed");
+}
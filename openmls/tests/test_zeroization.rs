@@ -0,0 +1,182 @@
+//! This test checks that the message secrets of past epochs are zeroized when
+//! they are dropped, including copies of them.
+//!
+//! To observe this, this test binary runs with an allocator that can scan
+//! memory for a secret before it is freed. It is a separate binary, such that
+//! the allocator doesn't affect any other tests.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use openmls::{
+    prelude::{config::CryptoConfig, test_utils::new_credential, *},
+    test_utils::*,
+    *,
+};
+use openmls_basic_credential::SignatureKeyPair;
+
+/// An allocator that checks freed memory for the secret set with
+/// [`freed_copies()`] on the current thread.
+struct ScanningAllocator;
+
+#[global_allocator]
+static ALLOCATOR: ScanningAllocator = ScanningAllocator;
+
+thread_local! {
+    static NEEDLE: Cell<Option<&'static [u8]>> = const { Cell::new(None) };
+    static FREED_COPIES: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for ScanningAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // The thread locals might already be destroyed when the thread exits.
+        if let Ok(Some(needle)) = NEEDLE.try_with(Cell::get) {
+            let freed = std::slice::from_raw_parts(ptr, layout.size());
+            if freed.windows(needle.len()).any(|window| window == needle) {
+                let _ = FREED_COPIES.try_with(|copies| copies.set(copies.get() + 1));
+            }
+        }
+        System.dealloc(ptr, layout)
+    }
+}
+
+/// Runs `f` and returns the number of allocations freed on the current thread
+/// that still contained the `secret`.
+fn freed_copies(secret: &[u8], f: impl FnOnce()) -> usize {
+    assert!(!secret.is_empty());
+    // The needle itself is never freed.
+    let needle: &'static [u8] = Box::leak(secret.to_vec().into_boxed_slice());
+    FREED_COPIES.with(|copies| copies.set(0));
+    NEEDLE.with(|current| current.set(Some(needle)));
+    f();
+    NEEDLE.with(|current| current.set(None));
+    FREED_COPIES.with(Cell::get)
+}
+
+fn self_update(group: &mut MlsGroup, provider: &impl OpenMlsProvider, signer: &SignatureKeyPair) {
+    group
+        .self_update(provider, signer)
+        .expect("Error creating a commit.");
+    group
+        .merge_pending_commit(provider)
+        .expect("Error merging the commit.");
+}
+
+#[apply(ciphersuites_and_providers)]
+fn past_message_secrets_are_zeroized(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let group_id = GroupId::from_slice(b"Test Group");
+    let (alice_credential, alice_signer) = new_credential(
+        provider,
+        b"Alice",
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    );
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .max_past_epochs(2)
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .build();
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        group_id.clone(),
+        alice_credential,
+    )
+    .expect("An unexpected error occurred.");
+
+    // === The first epoch becomes a past epoch ===
+    let past_epoch = alice_group.epoch();
+    self_update(&mut alice_group, provider, &alice_signer);
+    let past_sender_data_secret = alice_group
+        .sender_data_secret(past_epoch)
+        .expect("The past epoch should be retained.")
+        .to_vec();
+
+    // A plain copy of the secret is found when it is freed.
+    assert_eq!(
+        freed_copies(&past_sender_data_secret, || drop(
+            past_sender_data_secret.clone()
+        )),
+        1
+    );
+
+    // === Copies of the past message secrets are zeroized ===
+    alice_group
+        .save(provider.key_store())
+        .expect("Error saving the group.");
+    assert_eq!(
+        freed_copies(&past_sender_data_secret, || {
+            let loaded_group =
+                MlsGroup::load(&group_id, provider.key_store()).expect("Error loading the group.");
+            assert!(loaded_group.sender_data_secret(past_epoch).is_some());
+            drop(loaded_group);
+        }),
+        0
+    );
+
+    // === The past epoch falls out of the store ===
+    assert_eq!(
+        freed_copies(&past_sender_data_secret, || {
+            for _ in 0..3 {
+                self_update(&mut alice_group, provider, &alice_signer);
+            }
+        }),
+        0
+    );
+    assert!(alice_group.sender_data_secret(past_epoch).is_none());
+
+    // === The group is dropped ===
+    let sender_data_secret = alice_group
+        .sender_data_secret(alice_group.epoch())
+        .unwrap()
+        .to_vec();
+    assert_eq!(freed_copies(&sender_data_secret, || drop(alice_group)), 0);
+}
+
+#[apply(ciphersuites_and_providers)]
+fn purged_message_secrets_are_zeroized(ciphersuite: Ciphersuite, provider: &impl OpenMlsProvider) {
+    let (alice_credential, alice_signer) = new_credential(
+        provider,
+        b"Alice",
+        CredentialType::Basic,
+        ciphersuite.signature_algorithm(),
+    );
+    let mls_group_create_config = MlsGroupCreateConfig::builder()
+        .max_past_epochs(2)
+        .crypto_config(CryptoConfig::with_default_version(ciphersuite))
+        .build();
+    let mut alice_group = MlsGroup::new_with_group_id(
+        provider,
+        &alice_signer,
+        &mls_group_create_config,
+        GroupId::from_slice(b"Test Group"),
+        alice_credential,
+    )
+    .expect("An unexpected error occurred.");
+
+    let past_epoch = alice_group.epoch();
+    self_update(&mut alice_group, provider, &alice_signer);
+    let past_sender_data_secret = alice_group
+        .sender_data_secret(past_epoch)
+        .expect("The past epoch should be retained.")
+        .to_vec();
+
+    // === The past epoch is purged ===
+    assert_eq!(
+        freed_copies(&past_sender_data_secret, || {
+            assert!(alice_group.purge_past_epoch(past_epoch));
+        }),
+        0
+    );
+    assert!(alice_group.sender_data_secret(past_epoch).is_none());
+}
@@ -9,16 +9,21 @@ use openmls_traits::OpenMlsProvider;
 mod provider;
 pub use provider::*;
 
+mod time;
+pub use time::Clock;
+
 #[derive(Default, Debug)]
 pub struct OpenMlsRustCrypto {
     crypto: RustCrypto,
     key_store: MemoryKeyStore,
+    clock: Clock,
 }
 
 impl OpenMlsProvider for OpenMlsRustCrypto {
     type CryptoProvider = RustCrypto;
     type RandProvider = RustCrypto;
     type KeyStoreProvider = MemoryKeyStore;
+    type TimeProvider = Clock;
 
     fn crypto(&self) -> &Self::CryptoProvider {
         &self.crypto
@@ -31,4 +36,8 @@ impl OpenMlsProvider for OpenMlsRustCrypto {
     fn key_store(&self) -> &Self::KeyStoreProvider {
         &self.key_store
     }
+
+    fn time(&self) -> &Self::TimeProvider {
+        &self.clock
+    }
 }
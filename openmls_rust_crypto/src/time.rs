@@ -0,0 +1,67 @@
+#[cfg(feature = "test-utils")]
+use std::sync::{PoisonError, RwLock};
+
+#[cfg(target_arch = "wasm32")]
+use fluvio_wasm_timer::{SystemTime, UNIX_EPOCH};
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use openmls_traits::time::TimeProvider;
+
+/// A [`TimeProvider`] based on the system clock.
+///
+/// With the `test-utils` feature, the clock can be set to a fixed time to
+/// test time-dependent behaviour, e.g. the expiry of lifetimes,
+/// deterministically.
+///
+/// If the system clock is set to a time before the Unix epoch, [`Clock::now`]
+/// returns `0`. No lifetime is valid at that time, such that lifetimes are
+/// rejected rather than accepted based on a wrong clock.
+#[derive(Debug, Default)]
+pub struct Clock {
+    #[cfg(feature = "test-utils")]
+    mock_time: RwLock<Option<u64>>,
+}
+
+impl TimeProvider for Clock {
+    fn now(&self) -> u64 {
+        #[cfg(feature = "test-utils")]
+        if let Some(now) = *self
+            .mock_time
+            .read()
+            .unwrap_or_else(PoisonError::into_inner)
+        {
+            return now;
+        }
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl Clock {
+    /// Set the clock to the given time in seconds since the Unix epoch.
+    pub fn set_time(&self, now: u64) {
+        *self
+            .mock_time
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = Some(now);
+    }
+
+    /// Advance the clock by the given number of seconds. If the clock is not
+    /// set to a fixed time yet, it is fixed at the current system time first.
+    pub fn advance(&self, seconds: u64) {
+        let now = self.now();
+        self.set_time(now + seconds);
+    }
+
+    /// Reset the clock to the system time.
+    pub fn reset(&self) {
+        *self
+            .mock_time
+            .write()
+            .unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
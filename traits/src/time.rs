@@ -0,0 +1,15 @@
+//! # Time Source for OpenMLS
+//!
+//! The [`TimeProvider`] trait defines the functionality required by OpenMLS to
+//! get the current time, e.g. to validate the lifetimes of key packages and
+//! leaf nodes.
+
+pub trait TimeProvider {
+    /// Returns the current time in seconds since the Unix epoch
+    /// (1970-01-01T00:00:00Z).
+    ///
+    /// Implementations must not panic if the current time can't be
+    /// determined. Returning `0` in that case makes OpenMLS reject all
+    /// lifetimes.
+    fn now(&self) -> u64;
+}
@@ -7,16 +7,19 @@ pub mod crypto;
 pub mod key_store;
 pub mod random;
 pub mod signatures;
+pub mod time;
 pub mod types;
 
 /// The OpenMLS Crypto Provider Trait
 ///
 /// An implementation of this trait must be passed in to the public OpenMLS API
-/// to perform randomness generation, cryptographic operations, and key storage.
+/// to perform randomness generation, cryptographic operations, key storage,
+/// and to get the current time.
 pub trait OpenMlsProvider {
     type CryptoProvider: crypto::OpenMlsCrypto;
     type RandProvider: random::OpenMlsRand;
     type KeyStoreProvider: key_store::OpenMlsKeyStore;
+    type TimeProvider: time::TimeProvider;
 
     /// Get the crypto provider.
     fn crypto(&self) -> &Self::CryptoProvider;
@@ -26,4 +29,7 @@ pub trait OpenMlsProvider {
 
     /// Get the key store provider.
     fn key_store(&self) -> &Self::KeyStoreProvider;
+
+    /// Get the time provider.
+    fn time(&self) -> &Self::TimeProvider;
 }
@@ -8,6 +8,7 @@ use serde::{Deserialize, Serialize};
 use tls_codec::{
     SecretVLBytes, TlsDeserialize, TlsDeserializeBytes, TlsSerialize, TlsSize, VLBytes,
 };
+use zeroize::{Zeroize, ZeroizeOnDrop};
 
 use crate::key_store::{MlsEntity, MlsEntityId};
 
@@ -244,6 +245,8 @@ pub struct HpkeCiphertext {
 }
 
 /// A simple type for HPKE private keys.
+///
+/// The key is zeroized when it is dropped.
 #[derive(
     Debug,
     Clone,
@@ -253,11 +256,31 @@ pub struct HpkeCiphertext {
     TlsDeserialize,
     TlsDeserializeBytes,
     TlsSize,
+    Zeroize,
+    ZeroizeOnDrop,
 )]
-#[cfg_attr(feature = "test-utils", derive(PartialEq, Eq))]
 #[serde(transparent)]
 pub struct HpkePrivateKey(SecretVLBytes);
 
+#[cfg(feature = "test-utils")]
+impl PartialEq for HpkePrivateKey {
+    // Constant time comparison. The length of the keys is public.
+    fn eq(&self, other: &Self) -> bool {
+        let (key, other_key) = (self.0.as_slice(), other.0.as_slice());
+        if key.len() != other_key.len() {
+            return false;
+        }
+        let diff = key
+            .iter()
+            .zip(other_key.iter())
+            .fold(0u8, |diff, (l, r)| diff | (l ^ r));
+        std::hint::black_box(diff) == 0
+    }
+}
+
+#[cfg(feature = "test-utils")]
+impl Eq for HpkePrivateKey {}
+
 impl From<Vec<u8>> for HpkePrivateKey {
     fn from(bytes: Vec<u8>) -> Self {
         Self(bytes.into())
@@ -290,7 +313,11 @@ pub struct HpkeKeyPair {
 }
 
 pub type KemOutput = Vec<u8>;
-#[derive(Clone, Debug)]
+
+/// An HPKE exporter secret.
+///
+/// The secret is zeroized when it is dropped.
+#[derive(Clone, Debug, Zeroize, ZeroizeOnDrop)]
 pub struct ExporterSecret(SecretVLBytes);
 
 impl Deref for ExporterSecret {